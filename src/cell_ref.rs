@@ -0,0 +1,214 @@
+//! A1-style cell reference parsing.
+//!
+//! This module converts spreadsheet references such as `B7`, `$A$2` or
+//! `A2:L500` into zero-based row/column indices, and back again. Absolute
+//! anchors (`$`) are accepted and ignored, and column letters are
+//! case-insensitive.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::cell_ref::{CellRef, CellRange};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let cell = CellRef::parse("$B$7")?;
+//! assert_eq!((cell.row, cell.col), (6, 1));
+//! assert_eq!(cell.to_string(), "B7");
+//!
+//! let range = CellRange::parse("a2:l500")?;
+//! assert_eq!(range.start, CellRef { row: 1, col: 0 });
+//! assert_eq!(range.end, CellRef { row: 499, col: 11 });
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use std::fmt;
+
+/// A single cell position with zero-based row and column indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRef {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl CellRef {
+    /// Parses an A1-style reference such as `A1`, `$A$1`, `a1` or `AA10`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending token if the reference does not
+    /// consist of column letters followed by a positive row number.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::cell_ref::CellRef;
+    ///
+    /// assert_eq!(CellRef::parse("AA10").unwrap(), CellRef { row: 9, col: 26 });
+    /// assert!(CellRef::parse("1A").is_err());
+    /// ```
+    pub fn parse(reference: &str) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid cell reference '{}': expected a reference like A1 or $B$2", reference);
+
+        let token = reference.trim();
+        let rest = token.strip_prefix('$').unwrap_or(token);
+        let letters_end = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let (letters, rest) = rest.split_at(letters_end);
+        let digits = rest.strip_prefix('$').unwrap_or(rest);
+
+        if letters.is_empty() || digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let row: usize = digits.parse().map_err(|_| invalid())?;
+        if row == 0 {
+            return Err(invalid());
+        }
+
+        Ok(CellRef {
+            row: row - 1,
+            col: column_to_index(letters).ok_or_else(invalid)?,
+        })
+    }
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", index_to_column(self.col), self.row + 1)
+    }
+}
+
+/// A rectangular block of cells, inclusive on both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRange {
+    pub start: CellRef,
+    pub end: CellRef,
+}
+
+impl CellRange {
+    /// Parses a range such as `A2:F100` or `$A$2:$L$500`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either side is missing or malformed, or if the end
+    /// lies above or to the left of the start.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::cell_ref::CellRange;
+    ///
+    /// assert!(CellRange::parse("A2:F100").is_ok());
+    /// assert!(CellRange::parse("A2:").is_err());
+    /// assert!(CellRange::parse("F100:A2").is_err());
+    /// ```
+    pub fn parse(range: &str) -> Result<Self> {
+        let (start, end) = range
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Invalid range '{}': expected START:END, e.g. A2:F100", range))?;
+
+        let start = CellRef::parse(start)
+            .map_err(|e| anyhow::anyhow!("Invalid range '{}': {}", range, e))?;
+        let end = CellRef::parse(end)
+            .map_err(|e| anyhow::anyhow!("Invalid range '{}': {}", range, e))?;
+
+        if end.row < start.row || end.col < start.col {
+            anyhow::bail!("Invalid range '{}': end cell {} precedes start cell {}", range, end, start);
+        }
+
+        Ok(CellRange { start, end })
+    }
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.start, self.end)
+    }
+}
+
+/// Converts column letters to a zero-based index (`A` = 0, `Z` = 25, `AA` = 26).
+///
+/// Returns `None` for empty input, non-letters, or columns too large to index.
+pub fn column_to_index(letters: &str) -> Option<usize> {
+    if letters.is_empty() {
+        return None;
+    }
+
+    let mut index: usize = 0;
+    for c in letters.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        let digit = (c.to_ascii_uppercase() as u8 - b'A') as usize + 1;
+        index = index.checked_mul(26)?.checked_add(digit)?;
+    }
+    Some(index - 1)
+}
+
+/// Converts a zero-based column index to letters (0 = `A`, 26 = `AA`).
+pub fn index_to_column(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        letters.push((b'A' + rem as u8) as char);
+        n = (n - 1) / 26;
+    }
+    letters.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absolute_reference() {
+        assert_eq!(CellRef::parse("$A$1").unwrap(), CellRef { row: 0, col: 0 });
+        assert_eq!(CellRef::parse("$C7").unwrap(), CellRef { row: 6, col: 2 });
+        assert_eq!(CellRef::parse("C$7").unwrap(), CellRef { row: 6, col: 2 });
+    }
+
+    #[test]
+    fn test_lowercase_reference() {
+        assert_eq!(CellRef::parse("a1").unwrap(), CellRef { row: 0, col: 0 });
+    }
+
+    #[test]
+    fn test_multi_letter_column() {
+        assert_eq!(CellRef::parse("AA10").unwrap(), CellRef { row: 9, col: 26 });
+        assert_eq!(CellRef::parse("AA10").unwrap().to_string(), "AA10");
+        assert_eq!(index_to_column(701), "ZZ");
+        assert_eq!(column_to_index("ZZ"), Some(701));
+    }
+
+    #[test]
+    fn test_malformed_reference_names_token() {
+        for bad in ["1A", "A0", "A", "12", "A$$1", "A1B", ""] {
+            let err = CellRef::parse(bad).expect_err(bad);
+            assert!(err.to_string().contains(&format!("'{}'", bad)), "got: {}", err);
+        }
+    }
+
+    #[test]
+    fn test_range_with_anchors() {
+        let range = CellRange::parse("$A$2:$L$500").unwrap();
+        assert_eq!(range.start, CellRef { row: 1, col: 0 });
+        assert_eq!(range.end, CellRef { row: 499, col: 11 });
+        assert_eq!(range.to_string(), "A2:L500");
+    }
+
+    #[test]
+    fn test_malformed_ranges() {
+        let err = CellRange::parse("A2:").unwrap_err();
+        assert!(err.to_string().contains("'A2:'"), "got: {}", err);
+
+        let err = CellRange::parse("B2:1A").unwrap_err();
+        assert!(err.to_string().contains("'1A'"), "got: {}", err);
+
+        assert!(CellRange::parse("A2").is_err());
+        assert!(CellRange::parse("F100:A2").is_err());
+    }
+}
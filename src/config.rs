@@ -0,0 +1,134 @@
+//! The `excel-to-json.toml` config file.
+//!
+//! A config file holds default values for command-line options, so a team can
+//! share the flags it runs the tool with. Each top-level key is the long name
+//! of an option, with `-` or `_` between words; the value is what would follow
+//! the flag:
+//!
+//! ```toml
+//! sheet = ["Cascade Fields", "Line Type"]
+//! format = "csv"
+//! header-row = 2
+//! require = ["main_value", "minor_value"]
+//! legacy_warnings = true
+//! ```
+//!
+//! Arrays stand for a repeated option and `true` for a flag given without a
+//! value. Options given on the command line take precedence over the file,
+//! which takes precedence over the built-in defaults.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::config::{ConfigFile, ConfigValue};
+//!
+//! let config = ConfigFile::parse("format = \"csv\"\nheader_row = 2\n")?;
+//! assert_eq!(config.options[1], ("header-row".to_string(), ConfigValue::Values(vec!["2".to_string()])));
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Name of the config file looked up in the working directory.
+pub const CONFIG_FILE_NAME: &str = "excel-to-json.toml";
+
+/// The value of one option in the config file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    /// A boolean: whether to pass the flag
+    Flag(bool),
+    /// The option's values, one per occurrence of the flag
+    Values(Vec<String>),
+}
+
+/// The options of a config file, in file order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    /// Option long names (`header-row`) and their values
+    pub options: Vec<(String, ConfigValue)>,
+}
+
+impl ConfigFile {
+    /// Returns the config file in `dir`, if there is one.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        let path = dir.join(CONFIG_FILE_NAME);
+        path.is_file().then_some(path)
+    }
+
+    /// Reads and parses a config file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid config
+    /// file (see [`ConfigFile::parse`]).
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Parses the text of a config file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for invalid TOML, tables, and values other than
+    /// strings, numbers, booleans and arrays of strings and numbers.
+    pub fn parse(text: &str) -> Result<Self> {
+        let document: DocumentMut = text.parse()?;
+        let mut options = Vec::new();
+        for (key, item) in document.iter() {
+            let value = match item {
+                Item::Value(Value::Boolean(flag)) => ConfigValue::Flag(*flag.value()),
+                Item::Value(Value::Array(array)) => ConfigValue::Values(
+                    array.iter()
+                        .map(|value| scalar(value).with_context(|| format!("Unsupported value in '{}'", key)))
+                        .collect::<Result<_>>()?,
+                ),
+                Item::Value(value) => ConfigValue::Values(vec![
+                    scalar(value).with_context(|| format!("Unsupported value for '{}'", key))?,
+                ]),
+                _ => anyhow::bail!("'{}' must be a value, not a table", key),
+            };
+            options.push((key.replace('_', "-"), value));
+        }
+        Ok(ConfigFile { options })
+    }
+}
+
+/// Converts a string or number to the text it stands for on the command line.
+fn scalar(value: &Value) -> Result<String> {
+    match value {
+        Value::String(text) => Ok(text.value().clone()),
+        Value::Integer(number) => Ok(number.value().to_string()),
+        Value::Float(number) => Ok(number.value().to_string()),
+        other => anyhow::bail!("expected a string or number, found {}", other.type_name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_options_in_file_order() {
+        let config = ConfigFile::parse(
+            "sheet = [\"Cascade Fields\", \"Line Type\"]\nheader_row = 2\nall-sheets = false\nmax-memory-mb = 1.5\n",
+        ).unwrap();
+
+        assert_eq!(config.options, vec![
+            ("sheet".to_string(), ConfigValue::Values(vec!["Cascade Fields".to_string(), "Line Type".to_string()])),
+            ("header-row".to_string(), ConfigValue::Values(vec!["2".to_string()])),
+            ("all-sheets".to_string(), ConfigValue::Flag(false)),
+            ("max-memory-mb".to_string(), ConfigValue::Values(vec!["1.5".to_string()])),
+        ]);
+
+        assert!(ConfigFile::parse("format = ").is_err());
+        let table = ConfigFile::parse("[output]\nformat = \"csv\"\n").unwrap_err();
+        assert_eq!(table.to_string(), "'output' must be a value, not a table");
+        let nested = ConfigFile::parse("sheet = [true]\n").unwrap_err();
+        assert_eq!(format!("{:#}", nested), "Unsupported value in 'sheet': expected a string or number, found boolean");
+    }
+}
@@ -5,6 +5,10 @@
 //! crate for Excel file parsing and provides specialized handling for
 //! VLOOKUP formulas commonly found in cascade field data.
 //!
+//! `.xlsx` workbooks, binary `.xlsb` workbooks and OpenDocument `.ods`
+//! spreadsheets are supported; the format is chosen from the file extension,
+//! or from the content for workbooks held in memory (see [`WorkbookSource`]).
+//!
 //! # Example
 //!
 //! ```rust,no_run
@@ -21,12 +25,496 @@
 //! # }
 //! ```
 
+use crate::cell_ref::{self, CellRef};
+use crate::memory;
+use crate::models::{CascadeField, CellType, RowDiagnostic, RowWindow};
+use crate::hyperlink::{self, CellHyperlinks};
+use crate::number_format::{self, CellFormats};
 use anyhow::{Context, Result};
-use calamine::{open_workbook, Data, Reader, Xlsx};
+use calamine::{Data, Ods, Reader, Sheets, Xlsb, Xlsx};
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::{debug, info, warn};
 
+/// A single spreadsheet row with one optional string value per column.
+pub type RawRow = Vec<Option<String>>;
+
+/// The spreadsheet type of each cell in a [`RawRow`].
+pub type RowTypes = Vec<CellType>;
+
+/// VLOOKUP tables: sheet name → first-column key → row values by absolute column.
+type LookupTables = HashMap<String, HashMap<String, Vec<String>>>;
+
+/// Number of leading rows searched for [`ReaderOptions::expect_headers`].
+pub const HEADER_SCAN_ROWS: usize = 10;
+
+/// Everything read from a sheet: the header row and the non-empty data rows,
+/// with each row's cell types and 1-based spreadsheet row number.
+#[derive(Debug, Clone, Default)]
+pub struct SheetRows {
+    pub headers: Vec<String>,
+    pub rows: Vec<RawRow>,
+    pub types: Vec<RowTypes>,
+    pub row_numbers: Vec<usize>,
+    /// Notes about the sheet itself, such as the header row found with
+    /// [`ReaderOptions::expect_headers`], followed by a warning for each
+    /// error cell in the data rows
+    pub diagnostics: Vec<RowDiagnostic>,
+    /// The sheet has no cells at all, not even a header; see
+    /// [`SheetRowIter::is_sheet_empty`]
+    pub empty: bool,
+    /// The sheet's width before its blank trailing columns were trimmed; see
+    /// [`SheetRowIter::untrimmed_width`]
+    pub untrimmed_width: Option<usize>,
+}
+
+impl TryFrom<SheetRowIter> for SheetRows {
+    type Error = anyhow::Error;
+
+    /// Converts every remaining row of the iterator.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`CellError`] with
+    /// [`ReaderOptions::fail_on_cell_error`].
+    fn try_from(rows: SheetRowIter) -> Result<Self> {
+        let fail_on_cell_error = rows.fail_on_cell_error;
+        let mut sheet = SheetRows {
+            headers: rows.headers().to_vec(),
+            diagnostics: rows.diagnostics().to_vec(),
+            empty: rows.is_sheet_empty(),
+            untrimmed_width: rows.untrimmed_width(),
+            ..SheetRows::default()
+        };
+        for row in rows {
+            if let (true, Some(error)) = (fail_on_cell_error, row.cell_errors.first()) {
+                return Err(error.clone().into());
+            }
+            sheet.diagnostics.extend(row.cell_diagnostics());
+            sheet.rows.push(row.cells);
+            sheet.types.push(row.types);
+            sheet.row_numbers.push(row.row_number);
+        }
+        Ok(sheet)
+    }
+}
+
+/// A data cell holding an Excel error value such as `#REF!` or `#DIV/0!`.
+///
+/// Error cells are read as empty (or as their formula text). Each one is
+/// reported as a warning diagnostic naming the cell, and is returned as an
+/// error with [`ReaderOptions::fail_on_cell_error`]; find it with
+/// `error.chain()` and `downcast_ref`.
+///
+/// # Example
+///
+/// ```rust
+/// use calamine::CellErrorType;
+/// use excel_to_json::cell_ref::CellRef;
+/// use excel_to_json::excel_reader::CellError;
+///
+/// let error = CellError { cell: CellRef { row: 6, col: 1 }, error: CellErrorType::Ref };
+/// assert_eq!(error.to_string(), "Cell B7 holds the error #REF!");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellError {
+    /// Position of the cell in the sheet
+    pub cell: CellRef,
+    /// The Excel error value
+    pub error: calamine::CellErrorType,
+}
+
+impl std::fmt::Display for CellError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cell {} holds the error {}", self.cell, self.error)
+    }
+}
+
+impl std::error::Error for CellError {}
+
+/// Where a workbook is read from.
+///
+/// Cloning is cheap: in-memory workbooks share their bytes.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use excel_to_json::excel_reader::{ExcelReader, WorkbookSource};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// // `cat data.xlsx | my-tool`
+/// let source = WorkbookSource::stdin()?;
+/// for sheet in ExcelReader::from_source(source.clone(), String::new())?.get_sheet_names() {
+///     let mut reader = ExcelReader::from_source(source.clone(), sheet)?;
+///     println!("{} rows", reader.read_sheet()?.rows.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub enum WorkbookSource {
+    /// A file on disk; `.ods` files are opened as OpenDocument, `.xlsb` as
+    /// binary workbooks, others as xlsx
+    Path(PathBuf),
+    /// A whole workbook in memory; OpenDocument and xlsb are recognized from
+    /// the content
+    Bytes(Arc<[u8]>),
+}
+
+impl WorkbookSource {
+    /// Reads a workbook from standard input.
+    ///
+    /// xlsx, xlsb and ods files are zip archives, which can only be read with random
+    /// access, so the whole input is buffered in memory before it is opened.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if stdin cannot be read or is empty.
+    pub fn stdin() -> Result<Self> {
+        let mut bytes = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut bytes).context("Failed to read workbook from stdin")?;
+        if bytes.is_empty() {
+            anyhow::bail!("No workbook data on stdin");
+        }
+        info!("Read {} bytes from stdin", bytes.len());
+        Ok(WorkbookSource::Bytes(bytes.into()))
+    }
+
+    /// Explains why a workbook that failed to open cannot be read, when its
+    /// container gives it away.
+    ///
+    /// Password-protected xlsx files are not zip packages but OLE compound
+    /// files holding an `EncryptedPackage` stream. Decrypting them is not
+    /// supported. Other compound files are legacy `.xls` workbooks.
+    fn unsupported_container(&self) -> Option<&'static str> {
+        const COMPOUND_FILE_MAGIC: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+        
+        let bytes: std::borrow::Cow<[u8]> = match self {
+            WorkbookSource::Path(path) => std::fs::read(path).ok()?.into(),
+            WorkbookSource::Bytes(bytes) => bytes[..].into(),
+        };
+        if !bytes.starts_with(&COMPOUND_FILE_MAGIC) {
+            return None;
+        }
+        
+        // Directory entry names are UTF-16LE
+        let encrypted: Vec<u8> = "EncryptedPackage".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        if bytes.windows(encrypted.len()).any(|window| window == encrypted.as_slice()) {
+            Some("workbook is password-protected; decryption is not supported, remove the password in Excel and try again")
+        } else {
+            Some("legacy .xls workbooks are not supported; save the file as .xlsx")
+        }
+    }
+
+    /// Returns the spreadsheet format of the workbook.
+    ///
+    /// Files on disk are told apart by their extension. In memory, OpenDocument
+    /// and xlsb packages are recognized from their content; xlsb is a zip
+    /// package like xlsx, but stores its workbook part as `xl/workbook.bin`.
+    fn format(&self) -> WorkbookFormat {
+        match self {
+            WorkbookSource::Path(path) => match path.extension() {
+                Some(ext) if ext.eq_ignore_ascii_case("ods") => WorkbookFormat::Ods,
+                Some(ext) if ext.eq_ignore_ascii_case("xlsb") => WorkbookFormat::Xlsb,
+                _ => WorkbookFormat::Xlsx,
+            },
+            WorkbookSource::Bytes(bytes) => {
+                // An ODF package stores its `mimetype` entry first, uncompressed
+                let head = &bytes[..bytes.len().min(128)];
+                if head.windows(24).any(|window| window == b"opendocument.spreadsheet") {
+                    WorkbookFormat::Ods
+                } else if bytes.windows(15).any(|window| window == b"xl/workbook.bin") {
+                    WorkbookFormat::Xlsb
+                } else {
+                    WorkbookFormat::Xlsx
+                }
+            },
+        }
+    }
+}
+
+/// The spreadsheet formats a [`WorkbookSource`] can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WorkbookFormat {
+    Xlsx,
+    Xlsb,
+    Ods,
+}
+
+impl From<&str> for WorkbookSource {
+    fn from(path: &str) -> Self {
+        WorkbookSource::Path(PathBuf::from(path))
+    }
+}
+
+impl From<PathBuf> for WorkbookSource {
+    fn from(path: PathBuf) -> Self {
+        WorkbookSource::Path(path)
+    }
+}
+
+impl std::fmt::Display for WorkbookSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkbookSource::Path(path) => write!(f, "{}", path.display()),
+            WorkbookSource::Bytes(_) => write!(f, "<stdin>"),
+        }
+    }
+}
+
+/// A seekable reader holding workbook data.
+trait WorkbookRead: Read + Seek {}
+
+impl<T: Read + Seek> WorkbookRead for T {}
+
+/// Settings that adjust how [`ExcelReader`] converts cells.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use excel_to_json::excel_reader::{ExcelReader, ReaderOptions};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut reader = ExcelReader::new("data.xlsx", "Rates".to_string())?;
+/// reader.set_options(ReaderOptions {
+///     display_format: true,
+///     ..ReaderOptions::default()
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReaderOptions {
+    /// Render numeric cells with their Excel number format (`0.5` with a
+    /// `0%` format becomes `50%`), and text cells with its text section.
+    /// Cells whose format cannot be applied keep their raw value.
+    pub display_format: bool,
+    /// Columns whose numbers are kept as text, such as zero-padded part
+    /// numbers: header texts, compared ignoring case and surrounding spaces,
+    /// or cascade field names, which stand for their column in the default
+    /// layout. A number in these columns is written as its number format
+    /// displays it (`123` formatted `00000` stays `00123`), or otherwise
+    /// without `float_precision` rounding or `decimal_separator`, and its
+    /// type is text. Number formats are read from xlsx files only.
+    pub text_columns: Vec<String>,
+    /// Write each hyperlinked cell as its text followed by the link target,
+    /// `Widget <https://example.com/widget>` (see [`hyperlink::with_url`]).
+    /// Cells without a hyperlink are unchanged. xlsx only.
+    pub with_hyperlinks: bool,
+    /// 1-based spreadsheet row holding the headers. Rows above it (such as
+    /// title banners) are ignored. `None` uses the first non-empty row, or
+    /// the row found with `expect_headers`. Ignored when `no_header` is set.
+    pub header_row: Option<usize>,
+    /// Header texts identifying the header row when `header_row` is not set:
+    /// the first of the leading [`HEADER_SCAN_ROWS`] rows holding all of them,
+    /// ignoring case and surrounding spaces, is the header row. Its number is
+    /// reported as an info diagnostic; without a match the first row is used
+    /// and a warning diagnostic is reported instead.
+    pub expect_headers: Vec<String>,
+    /// The sheet has no header row: every row, including the first, is data.
+    /// Header-keyed columns are named `column_N`. Takes precedence over
+    /// `header_row`.
+    pub no_header: bool,
+    /// Copy the value of each merged region's top-left cell into every other
+    /// cell of the region, so vertically merged category columns repeat their
+    /// value on each row. xlsx only.
+    pub fill_merged: bool,
+    /// Keep rows whose cells are all empty instead of dropping them, so data
+    /// rows line up with the sheet's rows. Kept rows count toward `offset`
+    /// and `limit`.
+    pub keep_empty_rows: bool,
+    /// Keep columns that are empty in every row, header included, at the
+    /// right edge of the sheet. By default rows end at the last column
+    /// holding a value, so sheets padded with formatted blank cells out to
+    /// column ZZ do not produce rows of dozens of empty cells.
+    pub keep_blank_columns: bool,
+    /// Stop reading at the first fully empty row below the header, so
+    /// anything further down (totals, notes) is ignored. Takes precedence
+    /// over `keep_empty_rows`: the empty row ends the data and is not kept.
+    pub stop_at_blank: bool,
+    /// Number of rows at the bottom of the sheet (or of `range`), such as a
+    /// totals row, to ignore. Empty rows among them count too.
+    pub footer_rows: usize,
+    /// Number of leading data rows (non-empty rows below the header, unless
+    /// `keep_empty_rows` is set) to skip.
+    pub offset: usize,
+    /// Maximum number of data rows to read after `offset`; `None` reads all.
+    pub limit: Option<usize>,
+    /// Only read the cells inside this block. Its first row holds the
+    /// headers; cells outside it are ignored.
+    pub range: Option<cell_ref::CellRange>,
+    /// chrono strftime pattern (e.g. `%d/%m/%Y`) for date cells; `None`
+    /// writes ISO-8601. See [`format_excel_datetime`].
+    pub date_format: Option<String>,
+    /// Round non-integer numbers to this many decimals, e.g. `0.1 + 0.2` to
+    /// `0.30` with 2; `None` writes the shortest text that reads back as the
+    /// same number. See [`format_float`].
+    pub float_precision: Option<usize>,
+    /// Decimal separator of non-integer numbers, e.g. `,` for German locales;
+    /// `None` uses `.`
+    pub decimal_separator: Option<char>,
+    /// Stop reading at the first data cell holding an Excel error value
+    /// (see [`CellError`]) instead of reporting it as a warning.
+    pub fail_on_cell_error: bool,
+}
+
+impl ReaderOptions {
+    /// The data row window to report in the metadata; `None` when every data
+    /// row is read.
+    pub fn row_window(&self) -> Option<RowWindow> {
+        (self.offset > 0 || self.limit.is_some()).then_some(RowWindow {
+            offset: self.offset,
+            limit: self.limit,
+        })
+    }
+}
+
+/// Errors from [`ExcelReader`] that callers can act on.
+///
+/// They are returned inside [`anyhow::Error`]; find them with
+/// `error.chain()` and `downcast_ref`.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::excel_reader::{ExcelReader, ReaderError};
+///
+/// let mut reader = ExcelReader::new("resources/Cascade Sample.xlsx", "Nope".to_string()).unwrap();
+/// let error = reader.read_with_formulas().unwrap_err();
+/// match error.chain().find_map(|cause| cause.downcast_ref::<ReaderError>()) {
+///     Some(ReaderError::SheetNotFound { requested, available, suggestion }) => {
+///         assert_eq!(requested, "Nope");
+///         assert_eq!(available, &["Cascade Fields", "Line Type"]);
+///         assert_eq!(suggestion, &None);
+///     },
+///     other => panic!("expected SheetNotFound, got {:?}", other),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReaderError {
+    /// The requested sheet is not in the workbook
+    SheetNotFound {
+        /// The sheet name that was asked for
+        requested: String,
+        /// The workbook's sheets, in workbook order
+        available: Vec<String>,
+        /// The sheet the name is a near miss of; see [`closest_sheet_name`]
+        suggestion: Option<String>,
+    },
+    /// A sheet name pattern matches none of the workbook's sheets
+    NoSheetMatches {
+        /// The pattern, as given
+        pattern: String,
+        /// The workbook's sheets, in workbook order
+        available: Vec<String>,
+    },
+    /// A sheet index is past the workbook's last sheet
+    SheetIndexOutOfRange {
+        /// The 0-based index that was asked for
+        index: usize,
+        /// The workbook's sheets, in workbook order
+        available: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for ReaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReaderError::SheetNotFound { requested, available, suggestion } => {
+                write!(f, "Sheet '{}' not found", requested)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, "; did you mean '{}'?", suggestion)?;
+                }
+                write!(f, " Available sheets: {:?}", available)
+            },
+            ReaderError::NoSheetMatches { pattern, available } => {
+                write!(f, "No sheet matches '{}'. Available sheets: {:?}", pattern, available)
+            },
+            ReaderError::SheetIndexOutOfRange { index, available } => {
+                write!(f, "Sheet index {} is out of range; the workbook has {} sheet(s): {:?}", index, available.len(), available)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ReaderError {}
+
+/// Finds the sheet a mistyped name most likely refers to.
+///
+/// Names are compared case-insensitively by Levenshtein distance. The closest
+/// sheet is returned if its distance is at most a third of the requested
+/// name's length (at least 1), so unrelated names get no suggestion; ties go
+/// to the earlier sheet.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::excel_reader::closest_sheet_name;
+///
+/// let sheets = vec!["Cascade Fields".to_string(), "Line Type".to_string()];
+/// assert_eq!(closest_sheet_name("Cascaed Fields", &sheets), Some("Cascade Fields"));
+/// assert_eq!(closest_sheet_name("line type", &sheets), Some("Line Type"));
+/// assert_eq!(closest_sheet_name("Revenue", &sheets), None);
+/// ```
+pub fn closest_sheet_name<'a>(requested: &str, available: &'a [String]) -> Option<&'a str> {
+    let requested = requested.to_lowercase();
+    let max_distance = (requested.chars().count() / 3).max(1);
+    available.iter()
+        .map(|name| (levenshtein(&requested, &name.to_lowercase()), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name.as_str())
+}
+
+/// Finds the workbook sheet a requested name refers to.
+///
+/// An exact match is preferred; failing that, the first sheet whose name
+/// equals the request when surrounding whitespace is ignored, and then the
+/// first that also ignores case.
+///
+/// # Returns
+///
+/// The canonical sheet name, or `None` if no sheet matches
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::excel_reader::resolve_sheet_name;
+///
+/// let sheets = vec!["Data ".to_string(), "data".to_string(), "Summary".to_string()];
+/// assert_eq!(resolve_sheet_name("data", &sheets), Some("data"));
+/// assert_eq!(resolve_sheet_name("Data", &sheets), Some("Data "));
+/// assert_eq!(resolve_sheet_name(" SUMMARY", &sheets), Some("Summary"));
+/// assert_eq!(resolve_sheet_name("Totals", &sheets), None);
+/// ```
+pub fn resolve_sheet_name<'a>(requested: &str, available: &'a [String]) -> Option<&'a str> {
+    let trimmed = requested.trim();
+    available.iter()
+        .find(|name| *name == requested)
+        .or_else(|| available.iter().find(|name| name.trim() == trimmed))
+        .or_else(|| available.iter().find(|name| name.trim().to_lowercase() == trimmed.to_lowercase()))
+        .map(String::as_str)
+}
+
+/// Number of single-character insertions, deletions and substitutions
+/// turning `a` into `b`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 /// Reads and processes Excel files with support for formula evaluation.
 ///
 /// The `ExcelReader` struct provides methods to read Excel worksheets,
@@ -53,20 +541,25 @@ use tracing::{debug, info, warn};
 /// # }
 /// ```
 pub struct ExcelReader {
-    workbook: Xlsx<std::io::BufReader<std::fs::File>>,
+    workbook: Sheets<Box<dyn WorkbookRead>>,
+    source: WorkbookSource,
     sheet_name: String,
+    options: ReaderOptions,
 }
 
 impl ExcelReader {
     /// Creates a new ExcelReader for the specified file.
     ///
     /// Opens an Excel file and prepares it for reading. The reader maintains
-    /// a reference to the workbook and the target sheet name.
+    /// a reference to the workbook and the target sheet name. Files ending in
+    /// `.ods` are opened as OpenDocument spreadsheets, `.xlsb` as binary
+    /// workbooks, anything else as xlsx.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to the Excel file to open
-    /// * `sheet_name` - Name of the worksheet to process
+    /// * `path` - Path to the Excel (`.xlsx`, `.xlsb`) or OpenDocument (`.ods`) file
+    /// * `sheet_name` - Name of the worksheet to process, matched as by
+    ///   [`resolve_sheet_name`]
     ///
     /// # Returns
     ///
@@ -95,19 +588,99 @@ impl ExcelReader {
     /// Returns an error if:
     /// - The file does not exist
     /// - The file is not a valid Excel file
+    /// - The file is password-protected (decryption is not supported)
     /// - The file cannot be read due to permissions
     pub fn new<P: AsRef<Path>>(path: P, sheet_name: String) -> Result<Self> {
-        let workbook: Xlsx<_> = open_workbook(path.as_ref())
-            .with_context(|| format!("Failed to open Excel file: {:?}", path.as_ref()))?;
+        Self::from_source(WorkbookSource::Path(path.as_ref().to_path_buf()), sheet_name)
+    }
+
+    /// Creates a reader for a workbook held in memory, such as one piped in
+    /// through stdin.
+    ///
+    /// OpenDocument spreadsheets and xlsb workbooks are recognized from their
+    /// content; anything else is opened as xlsx. The bytes are shared rather
+    /// than copied, but the workbook is opened anew by every reader created
+    /// from them (see [`from_source`](Self::from_source)).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use excel_to_json::excel_reader::ExcelReader;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let bytes = std::fs::read("data.xlsx")?;
+    /// let mut reader = ExcelReader::from_bytes(bytes, "Cascade Fields".to_string())?;
+    /// let sheet = reader.read_sheet()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid xlsx, xlsb or ods workbook.
+    pub fn from_bytes(bytes: impl Into<Arc<[u8]>>, sheet_name: String) -> Result<Self> {
+        Self::from_source(WorkbookSource::Bytes(bytes.into()), sheet_name)
+    }
+
+    /// Creates a reader for a workbook on disk or in memory.
+    ///
+    /// [`new`](Self::new) and [`from_bytes`](Self::from_bytes) delegate here.
+    /// Each call opens the workbook again, parsing its shared strings and
+    /// sheet list, whether it is read from disk or from bytes already in
+    /// memory; opened workbooks are not cached between readers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the workbook cannot be opened or is not a valid
+    /// xlsx, xlsb or ods file.
+    pub fn from_source(source: WorkbookSource, sheet_name: String) -> Result<Self> {
+        let reader: Box<dyn WorkbookRead> = match &source {
+            WorkbookSource::Path(path) => Box::new(BufReader::new(
+                File::open(path).with_context(|| source.to_string())?,
+            )),
+            WorkbookSource::Bytes(bytes) => Box::new(Cursor::new(Arc::clone(bytes))),
+        };
+        // Callers add the "Failed to open Excel file" context. The spreadsheet
+        // library's errors repeat their source's message, so only their own
+        // message is kept.
+        let workbook = match source.format() {
+            WorkbookFormat::Ods => Ods::new(reader).map(Sheets::Ods).map_err(|e| e.to_string()),
+            WorkbookFormat::Xlsb => Xlsb::new(reader).map(Sheets::Xlsb).map_err(|e| e.to_string()),
+            WorkbookFormat::Xlsx => Xlsx::new(reader).map(Sheets::Xlsx).map_err(|e| e.to_string()),
+        };
+        let workbook = match workbook {
+            Ok(workbook) => workbook,
+            // Explain files that are not zip packages instead of the zip error
+            Err(e) => match source.unsupported_container() {
+                Some(problem) => anyhow::bail!("{}: {}", source, problem),
+                None => anyhow::bail!("{}: {}", source, e),
+            },
+        };
+        
+        info!("Successfully opened Excel file: {}", source);
         
-        info!("Successfully opened Excel file: {:?}", path.as_ref());
+        // Read the sheet the name refers to despite differences in case or spacing
+        let sheet_name = match resolve_sheet_name(&sheet_name, &workbook.sheet_names()) {
+            Some(canonical) if canonical != sheet_name => {
+                info!("Using sheet '{}' for '{}'", canonical, sheet_name);
+                canonical.to_string()
+            },
+            _ => sheet_name,
+        };
         
         Ok(ExcelReader {
             workbook,
+            source,
             sheet_name,
+            options: ReaderOptions::default(),
         })
     }
 
+    /// Replaces the cell conversion settings used by subsequent reads.
+    pub fn set_options(&mut self, options: ReaderOptions) {
+        self.options = options;
+    }
+
     /// Returns a list of all sheet names in the workbook.
     ///
     /// This method is useful for discovering available sheets in an Excel file,
@@ -142,6 +715,54 @@ impl ExcelReader {
         self.workbook.sheet_names().to_vec()
     }
 
+    /// Returns the name of the sheet the reader reads, as spelled in the
+    /// workbook (see [`resolve_sheet_name`]).
+    pub fn sheet_name(&self) -> &str {
+        &self.sheet_name
+    }
+
+    /// Estimates the size in bytes of the record set built from the sheet,
+    /// without reading its cells.
+    ///
+    /// For xlsx files the row count comes from the worksheet's `<dimension>`
+    /// and the text from the unpacked size of its XML and of the shared
+    /// strings; for other formats the text is the size of the whole workbook.
+    /// Markup and strings of other sheets count as text, so the estimate errs
+    /// on the high side. See [`memory::estimate_sheet_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sheet is missing or its xlsx part cannot be
+    /// found.
+    pub fn estimate_record_bytes(&self) -> Result<usize> {
+        self.check_sheet_exists()?;
+        let (rows, text_bytes) = match (&self.workbook, &self.source) {
+            (Sheets::Xlsx(_), WorkbookSource::Path(path)) => {
+                let file = File::open(path).with_context(|| path.display().to_string())?;
+                xlsx_sheet_size(BufReader::new(file), &self.sheet_name)?
+            },
+            (Sheets::Xlsx(_), WorkbookSource::Bytes(bytes)) => {
+                xlsx_sheet_size(Cursor::new(Arc::clone(bytes)), &self.sheet_name)?
+            },
+            (_, WorkbookSource::Path(path)) => {
+                let size = std::fs::metadata(path).with_context(|| path.display().to_string())?.len();
+                (0, size as usize)
+            },
+            (_, WorkbookSource::Bytes(bytes)) => (0, bytes.len()),
+        };
+        Ok(memory::estimate_sheet_bytes(rows, text_bytes))
+    }
+
+    /// Returns [`ReaderError::SheetNotFound`] if the target sheet is not in the workbook.
+    fn check_sheet_exists(&self) -> Result<()> {
+        let available = self.get_sheet_names();
+        if !available.contains(&self.sheet_name) {
+            let suggestion = closest_sheet_name(&self.sheet_name, &available).map(str::to_string);
+            return Err(ReaderError::SheetNotFound { requested: self.sheet_name.clone(), available, suggestion }.into());
+        }
+        Ok(())
+    }
+
     /// Reads the specified sheet and returns processed rows with resolved VLOOKUP values.
     ///
     /// This method processes Excel data with special handling for VLOOKUP formulas.
@@ -176,18 +797,10 @@ impl ExcelReader {
     ///
     /// # Errors
     ///
-    /// Returns an error if the specified sheet is not found in the workbook
-    #[allow(dead_code)]
+    /// Returns [`ReaderError::SheetNotFound`] if the specified sheet is not
+    /// in the workbook
     pub fn read_cascade_fields(&mut self) -> Result<Vec<Vec<Option<String>>>> {
-        // Check if the sheet exists
-        let sheet_names = self.get_sheet_names();
-        if !sheet_names.contains(&self.sheet_name) {
-            anyhow::bail!(
-                "Sheet '{}' not found. Available sheets: {:?}",
-                self.sheet_name,
-                sheet_names
-            );
-        }
+        self.check_sheet_exists()?;
 
         info!("Reading sheet: {}", self.sheet_name);
 
@@ -218,7 +831,7 @@ impl ExcelReader {
                         // Check if this looks like a VLOOKUP formula result
                         if s.starts_with("=VLOOKUP") || s.starts_with("=vlookup") {
                             // Try to resolve the VLOOKUP
-                            match self.resolve_vlookup(s.as_str(), &lookup_tables) {
+                            match resolve_vlookup(s.as_str(), &self.sheet_name, &range, &lookup_tables) {
                                 Some(resolved) => Some(resolved),
                                 None => {
                                     warn!("Failed to resolve VLOOKUP at row {}, col {}: {}", 
@@ -233,8 +846,8 @@ impl ExcelReader {
                     Data::Float(f) => Some(format!("{}", f)),
                     Data::Int(i) => Some(format!("{}", i)),
                     Data::Bool(b) => Some(format!("{}", b)),
-                    Data::DateTime(dt) => Some(format!("{}", dt)),
-                    Data::DateTimeIso(dt) => Some(dt.clone()),
+                    Data::DateTime(dt) => Some(format_excel_datetime(dt, self.options.date_format.as_deref())),
+                    Data::DateTimeIso(dt) => Some(reformat_iso_datetime(dt, self.options.date_format.as_deref())),
                     Data::DurationIso(d) => Some(d.clone()),
                     Data::Error(e) => {
                         warn!("Error cell at row {}, col {}: {:?}", row_idx + 1, col_idx + 1, e);
@@ -246,8 +859,8 @@ impl ExcelReader {
                 processed_row.push(value);
             }
             
-            // Only add non-empty rows
-            if processed_row.iter().any(|v| v.is_some()) {
+            // Only add non-empty rows, unless blanks are kept
+            if self.options.keep_empty_rows || processed_row.iter().any(|v| v.is_some()) {
                 processed_rows.push(processed_row);
             }
         }
@@ -257,6 +870,44 @@ impl ExcelReader {
         Ok(processed_rows)
     }
 
+    /// Fills every cell of the sheet's merged regions with the region's
+    /// top-left value.
+    ///
+    /// Only cells inside a merge rectangle are written; regions whose top-left
+    /// cell is empty are left alone. Merged regions are only read from xlsx
+    /// files; for other formats a warning is logged and the range is unchanged.
+    fn fill_merged_regions(&mut self, range: &mut calamine::Range<Data>) {
+        let Sheets::Xlsx(workbook) = &mut self.workbook else {
+            warn!("Merged cells are only read from xlsx files; --fill-merged has no effect");
+            return;
+        };
+        if let Err(e) = workbook.load_merged_regions() {
+            warn!("Could not read merged cells, leaving them empty: {}", e);
+            return;
+        }
+
+        let regions: Vec<calamine::Dimensions> = workbook
+            .merged_regions_by_sheet(&self.sheet_name)
+            .into_iter()
+            .map(|(_, _, dimensions)| *dimensions)
+            .collect();
+        debug!("Filling {} merged regions in sheet '{}'", regions.len(), self.sheet_name);
+
+        for region in regions {
+            let anchor = match range.get_value(region.start) {
+                Some(Data::Empty) | None => continue,
+                Some(anchor) => anchor.clone(),
+            };
+            for row in region.start.0..=region.end.0 {
+                for col in region.start.1..=region.end.1 {
+                    if (row, col) != region.start {
+                        range.set_value((row, col), anchor.clone());
+                    }
+                }
+            }
+        }
+    }
+
     /// Builds lookup tables from all sheets for VLOOKUP resolution.
     ///
     /// Creates a nested HashMap structure where:
@@ -265,7 +916,10 @@ impl ExcelReader {
     /// - Value: Vector of all column values in that row
     ///
     /// This structure supports efficient VLOOKUP resolution by providing
-    /// O(1) lookup time for finding values.
+    /// O(1) lookup time for finding values. Row values are indexed by absolute
+    /// column (`A` = 0), so sheets whose data starts right of column A are
+    /// padded with empty strings. When a key appears more than once, the first
+    /// row wins, as in Excel.
     ///
     /// # Returns
     ///
@@ -288,14 +942,14 @@ impl ExcelReader {
     /// # Ok(())
     /// # }
     /// ```
-    #[allow(dead_code)]
-    fn build_lookup_tables(&mut self) -> Result<HashMap<String, HashMap<String, Vec<String>>>> {
+    fn build_lookup_tables(&mut self) -> Result<LookupTables> {
         let mut tables = HashMap::new();
         
         for sheet_name in self.get_sheet_names() {
             debug!("Building lookup table for sheet: {}", sheet_name);
             
             if let Ok(range) = self.workbook.worksheet_range(&sheet_name) {
+                    let (_, start_col) = range.start().unwrap_or((0, 0));
                     let mut sheet_table: HashMap<String, Vec<String>> = HashMap::new();
                     
                     for row in range.rows() {
@@ -305,27 +959,15 @@ impl ExcelReader {
                         
                         // Use first column as key
                         let key = match &row[0] {
-                            Data::String(s) => s.clone(),
-                            Data::Float(f) => format!("{}", f),
-                            Data::Int(i) => format!("{}", i),
+                            Data::String(_) | Data::Float(_) | Data::Int(_) => lookup_text(&row[0]),
                             _ => continue,
                         };
                         
-                        // Store entire row as values
-                        let values: Vec<String> = row.iter().map(|cell| {
-                            match cell {
-                                Data::String(s) => s.clone(),
-                                Data::Float(f) => format!("{}", f),
-                                Data::Int(i) => format!("{}", i),
-                                Data::Bool(b) => format!("{}", b),
-                                Data::DateTime(dt) => format!("{}", dt),
-                                Data::DateTimeIso(dt) => dt.clone(),
-                                Data::DurationIso(d) => d.clone(),
-                                _ => String::new(),
-                            }
-                        }).collect();
+                        // Store entire row as values, indexed by absolute column
+                        let mut values = vec![String::new(); start_col as usize];
+                        values.extend(row.iter().map(lookup_text));
                         
-                        sheet_table.insert(key, values);
+                        sheet_table.entry(key).or_insert(values);
                     }
                     
                     tables.insert(sheet_name.clone(), sheet_table);
@@ -337,58 +979,13 @@ impl ExcelReader {
         Ok(tables)
     }
 
-    /// Attempts to resolve a VLOOKUP formula.
-    ///
-    /// This is a placeholder for VLOOKUP formula resolution. In practice,
-    /// calamine should handle formula evaluation automatically. This method
-    /// is kept as a fallback for cases where formulas aren't evaluated.
-    ///
-    /// # Arguments
-    ///
-    /// * `_formula` - The VLOOKUP formula string to resolve
-    /// * `_lookup_tables` - Pre-built lookup tables from all sheets
-    ///
-    /// # Returns
-    ///
-    /// * `Some(String)` - Resolved value if successful
-    /// * `None` - If the formula cannot be resolved
-    ///
-    /// # Example Formula Format
-    ///
-    /// ```text
-    /// =VLOOKUP(A2,Sheet2!A:C,2,FALSE)
-    /// ```
-    ///
-    /// Where:
-    /// - `A2` is the lookup value
-    /// - `Sheet2!A:C` is the table array
-    /// - `2` is the column index
-    /// - `FALSE` indicates exact match
-    ///
-    /// # Note
-    ///
-    /// This implementation currently returns `None` as calamine
-    /// handles formula evaluation. Future implementations could
-    /// parse and resolve VLOOKUP formulas manually if needed.
-    #[allow(dead_code)]
-    fn resolve_vlookup(&self, _formula: &str, _lookup_tables: &HashMap<String, HashMap<String, Vec<String>>>) -> Option<String> {
-        // This is a simplified VLOOKUP resolver
-        // In practice, calamine should handle formula evaluation automatically
-        // This is a fallback for cases where formulas aren't evaluated
-        
-        // Try to extract the lookup value and return column from the formula
-        // Example: =VLOOKUP(A2,Sheet2!A:C,2,FALSE)
-        
-        // For now, return None to indicate unresolved
-        // In a real implementation, you would parse the formula and look up the value
-        None
-    }
-
     /// Process formulas and return evaluated values when available.
     ///
     /// This is the primary method for reading Excel data. It processes the
     /// specified sheet and returns cell values with formula evaluation.
     /// The method handles various data types and converts them to strings.
+    /// The header row is skipped; use [`read_with_headers`](Self::read_with_headers)
+    /// to keep it.
     ///
     /// # Returns
     ///
@@ -401,9 +998,11 @@ impl ExcelReader {
     /// - **Float**: Formatted as string (integers without decimals)
     /// - **Int**: Converted to string
     /// - **Bool**: Converted to "true" or "false"
-    /// - **DateTime**: Formatted as string
+    /// - **DateTime**: ISO-8601, or [`ReaderOptions::date_format`]; see [`format_excel_datetime`]
     /// - **Error**: Returns None with a warning log
     /// - **Empty**: Returns None
+    /// - **VLOOKUP without a cached result** (empty or error cell): resolved
+    ///   against the workbook's sheets for exact-match lookups, otherwise None
     ///
     /// # Example
     ///
@@ -434,85 +1033,1282 @@ impl ExcelReader {
     /// # Performance Note
     ///
     /// This method loads the entire sheet into memory. For very large files,
-    /// consider implementing streaming or chunked processing.
+    /// use [`rows_iter`](Self::rows_iter) to convert one row at a time.
     pub fn read_with_formulas(&mut self) -> Result<Vec<Vec<Option<String>>>> {
-        // Check if the sheet exists
-        let sheet_names = self.get_sheet_names();
-        if !sheet_names.contains(&self.sheet_name) {
-            anyhow::bail!(
-                "Sheet '{}' not found. Available sheets: {:?}",
-                self.sheet_name,
-                sheet_names
-            );
-        }
+        let (_headers, rows) = self.read_with_headers()?;
+        Ok(rows)
+    }
 
-        info!("Reading sheet with formula evaluation: {}", self.sheet_name);
+    /// Reads the sheet like [`read_with_formulas`](Self::read_with_formulas),
+    /// additionally returning the header row.
+    ///
+    /// Header cells are converted with the same rules as data cells; empty
+    /// header cells become empty strings so the header keeps one entry per column.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((headers, rows))` - Header texts and processed data rows
+    /// * `Err` - If the sheet doesn't exist or cannot be read
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use excel_to_json::excel_reader::ExcelReader;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut reader = ExcelReader::new("data.xlsx", "Cascade Fields".to_string())?;
+    /// let (headers, rows) = reader.read_with_headers()?;
+    ///
+    /// println!("Columns: {}", headers.join(", "));
+    /// println!("{} data rows", rows.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_with_headers(&mut self) -> Result<(Vec<String>, Vec<RawRow>)> {
+        let (headers, rows, _types) = self.read_with_types()?;
+        Ok((headers, rows))
+    }
 
-        // Get both the range and formula evaluations
-        let range = self.workbook
-            .worksheet_range(&self.sheet_name)
-            .map_err(|e| anyhow::anyhow!("Error reading sheet '{}': {}", self.sheet_name, e))?;
+    /// Reads the sheet like [`read_with_headers`](Self::read_with_headers),
+    /// additionally returning the spreadsheet type of every data cell.
+    ///
+    /// The types are aligned with the returned rows: `types[r][c]` describes
+    /// `rows[r][c]`. Numeric cells are [`CellType::Number`] (even when rendered
+    /// with a display format), boolean cells [`CellType::Bool`], and everything
+    /// else, including dates, [`CellType::Text`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use excel_to_json::excel_reader::ExcelReader;
+    /// use excel_to_json::models::CellType;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut reader = ExcelReader::new("data.xlsx", "Prices".to_string())?;
+    /// let (_headers, rows, types) = reader.read_with_types()?;
+    ///
+    /// if let (Some(Some(price)), Some(CellType::Number)) = (rows[0].get(1), types[0].get(1)) {
+    ///     println!("Numeric price: {}", price);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_with_types(&mut self) -> Result<(Vec<String>, Vec<RawRow>, Vec<RowTypes>)> {
+        let sheet = self.read_sheet()?;
+        Ok((sheet.headers, sheet.rows, sheet.types))
+    }
 
-        // Try to get formula evaluations
-        let formulas = self.workbook.worksheet_formula(&self.sheet_name);
+    /// Reads the sheet, returning headers, rows, cell types and row numbers.
+    ///
+    /// This is the most complete form of [`read_with_headers`](Self::read_with_headers).
+    /// With [`ReaderOptions::header_row`] set, the rows above the header row are
+    /// skipped, as are those above the row found with
+    /// [`ReaderOptions::expect_headers`]. The row numbers are the true
+    /// spreadsheet rows (1-based), which stay correct when leading or empty
+    /// rows are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sheet doesn't exist or cannot be read, or if the
+    /// configured header row lies beyond the last row of the sheet.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use excel_to_json::excel_reader::{ExcelReader, ReaderOptions};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut reader = ExcelReader::new("template.xlsx", "Data".to_string())?;
+    /// // A title banner fills rows 1-2; the headers are in row 3
+    /// reader.set_options(ReaderOptions { header_row: Some(3), ..ReaderOptions::default() });
+    ///
+    /// let sheet = reader.read_sheet()?;
+    /// println!("First data row is spreadsheet row {}", sheet.row_numbers[0]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_sheet(&mut self) -> Result<SheetRows> {
+        let sheet = SheetRows::try_from(self.rows_iter()?)?;
 
-        let mut processed_rows = Vec::new();
-        let mut is_header = true;
+        info!("Processed {} data rows from sheet '{}'", sheet.rows.len(), self.sheet_name);
         
-        for (row_idx, row) in range.rows().enumerate() {
-            // Skip header row
-            if is_header {
-                is_header = false;
-                debug!("Skipping header row");
-                continue;
-            }
+        Ok(sheet)
+    }
 
-            let mut processed_row = Vec::new();
-            
-            for (col_idx, cell) in row.iter().enumerate() {
-                let value = match cell {
-                    Data::String(s) => Some(s.clone()),
-                    Data::Float(f) => {
-                        // Check if this is an integer that should be displayed without decimals
-                        if f.fract() == 0.0 {
-                            Some(format!("{:.0}", f))
-                        } else {
-                            Some(format!("{}", f))
-                        }
-                    },
-                    Data::Int(i) => Some(format!("{}", i)),
-                    Data::Bool(b) => Some(format!("{}", b)),
-                    Data::DateTime(dt) => Some(format!("{}", dt)),
-                    Data::DateTimeIso(dt) => Some(dt.clone()),
-                    Data::DurationIso(d) => Some(d.clone()),
-                    Data::Error(_) => {
-                        // Check if there's a formula for this cell
-                        match &formulas {
-                            Ok(formula_range) => {
-                                // Try to get the formula result
-                                if let Some(formula_cell) = formula_range.get((row_idx, col_idx)) {
-                                    Some(formula_cell.clone())
-                                } else {
-                                    None
-                                }
-                            },
-                            _ => None,
-                        }
-                    },
-                    Data::Empty => None,
-                };
-                
-                processed_row.push(value);
+    /// Returns an iterator over the sheet's non-empty data rows.
+    ///
+    /// The header row is read up front and available from
+    /// [`SheetRowIter::headers`]; data rows are converted one at a time as the
+    /// iterator advances, with the same rules as [`read_sheet`](Self::read_sheet).
+    ///
+    /// # Memory
+    ///
+    /// calamine still loads the sheet's cells into memory, but the converted
+    /// rows are never collected, so a caller that writes each row out before
+    /// taking the next one avoids holding a second, larger copy of the sheet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sheet doesn't exist or cannot be read, or if the
+    /// configured header row lies beyond the last row of the sheet.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use excel_to_json::excel_reader::ExcelReader;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut reader = ExcelReader::new("large.xlsx", "Data".to_string())?;
+    /// let rows = reader.rows_iter()?;
+    /// println!("Columns: {}", rows.headers().join(", "));
+    ///
+    /// for row in rows {
+    ///     println!("Row {}: {:?}", row.row_number, row.cells);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rows_iter(&mut self) -> Result<SheetRowIter> {
+        self.check_sheet_exists()?;
+
+        info!("Reading sheet with formula evaluation: {}", self.sheet_name);
+
+        // Get both the range and formula evaluations
+        let mut range = self.workbook
+            .worksheet_range(&self.sheet_name)
+            .map_err(|e| anyhow::anyhow!("Error reading sheet '{}': {}", self.sheet_name, e))?;
+
+        if self.options.fill_merged {
+            self.fill_merged_regions(&mut range);
+        }
+
+        if let Some(cells) = &self.options.range {
+            debug!("Restricting sheet '{}' to range {}:{}", self.sheet_name, cells.start, cells.end);
+            range = sub_range(&range, cells);
+        }
+
+        // Try to get formula evaluations; without them (e.g. formula records
+        // calamine cannot parse in an xlsb sheet) cells keep their cached values
+        let formulas = match self.workbook.worksheet_formula(&self.sheet_name) {
+            Ok(formulas) => Some(formulas),
+            Err(e) => {
+                debug!("Could not read formulas of sheet '{}', using cached values: {}", self.sheet_name, e);
+                None
+            },
+        };
+
+        // Number formats, when values should match what Excel displays
+        let needs_formats = self.options.display_format || !self.options.text_columns.is_empty();
+        let cell_formats = if needs_formats && !matches!(self.workbook, Sheets::Xlsx(_)) {
+            warn!("Number formats are only read from xlsx files; using raw values");
+            CellFormats::default()
+        } else if needs_formats {
+            let formats = match &self.source {
+                WorkbookSource::Path(path) => CellFormats::load(path, &self.sheet_name),
+                WorkbookSource::Bytes(bytes) => CellFormats::from_reader(Cursor::new(Arc::clone(bytes)), &self.sheet_name),
+            };
+            formats.unwrap_or_else(|e| {
+                warn!("Could not read number formats, using raw values: {:#}", e);
+                CellFormats::default()
+            })
+        } else {
+            CellFormats::default()
+        };
+        // Hyperlink targets, when linked cells should carry them
+        let hyperlinks = if self.options.with_hyperlinks && !matches!(self.workbook, Sheets::Xlsx(_)) {
+            warn!("Hyperlinks are only read from xlsx files; using cell text");
+            CellHyperlinks::default()
+        } else if self.options.with_hyperlinks {
+            let links = match &self.source {
+                WorkbookSource::Path(path) => CellHyperlinks::load(path, &self.sheet_name),
+                WorkbookSource::Bytes(bytes) => CellHyperlinks::from_reader(Cursor::new(Arc::clone(bytes)), &self.sheet_name),
+            };
+            links.unwrap_or_else(|e| {
+                warn!("Could not read hyperlinks, using cell text: {:#}", e);
+                CellHyperlinks::default()
+            })
+        } else {
+            CellHyperlinks::default()
+        };
+        // Only uncached VLOOKUP cells need the lookup tables
+        let has_uncached_vlookup = formulas.as_ref().is_some_and(|formulas| {
+            let (formula_row, formula_col) = formulas.start().unwrap_or((0, 0));
+            formulas.used_cells().any(|(row, col, formula)| {
+                let position = (formula_row + row as u32, formula_col + col as u32);
+                matches!(range.get_value(position), None | Some(Data::Error(_) | Data::Empty))
+                    && Vlookup::parse(formula).is_some()
+            })
+        });
+        let lookup_tables = if has_uncached_vlookup {
+            self.build_lookup_tables()?
+        } else {
+            LookupTables::new()
+        };
+
+        SheetRowIter::build(range, formulas, cell_formats, hyperlinks, lookup_tables, &self.sheet_name, &self.options)
+    }
+}
+
+/// A data row yielded by [`SheetRowIter`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetRow {
+    /// Converted cell values, one per column
+    pub cells: RawRow,
+    /// Spreadsheet type of each cell
+    pub types: RowTypes,
+    /// 1-based spreadsheet row number
+    pub row_number: usize,
+    /// Cells of the row holding an Excel error value that no VLOOKUP could
+    /// replace
+    pub cell_errors: Vec<CellError>,
+}
+
+impl SheetRow {
+    /// Returns a warning diagnostic for each of the row's error cells.
+    pub fn cell_diagnostics(&self) -> impl Iterator<Item = RowDiagnostic> + '_ {
+        self.cell_errors.iter()
+            .map(|error| RowDiagnostic::warning(self.row_number, None, error.to_string()))
+    }
+}
+
+/// Iterator over a sheet's non-empty data rows, created by
+/// [`ExcelReader::rows_iter`].
+///
+/// Cells are converted to text (applying number formats and hyperlinks and
+/// resolving uncached VLOOKUPs) only when their row is reached.
+pub struct SheetRowIter {
+    range: calamine::Range<Data>,
+    formulas: Option<calamine::Range<String>>,
+    cell_formats: CellFormats,
+    hyperlinks: CellHyperlinks,
+    lookup_tables: LookupTables,
+    date_format: Option<String>,
+    float_precision: Option<usize>,
+    decimal_separator: Option<char>,
+    keep_empty_rows: bool,
+    stop_at_blank: bool,
+    fail_on_cell_error: bool,
+    sheet_name: String,
+    headers: Vec<String>,
+    diagnostics: Vec<RowDiagnostic>,
+    next_idx: usize,
+    /// Index of the first row not read, above the footer rows
+    end_idx: usize,
+    /// Data rows still to skip for [`ReaderOptions::offset`]
+    skip: usize,
+    /// Data rows still to yield for [`ReaderOptions::limit`]
+    remaining: Option<usize>,
+    /// The sheet has no cells at all, not even a header
+    empty: bool,
+    /// Number of columns converted per row, from the first column of `range`
+    width: usize,
+    /// Apply `cell_formats` to every numeric and text cell, not only in text
+    /// columns
+    display_format: bool,
+    /// Columns of [`ReaderOptions::text_columns`], relative to `range`
+    text_columns: Vec<usize>,
+}
+
+/// Returns the index within `range` of the first of its leading
+/// [`HEADER_SCAN_ROWS`] rows whose cells include every `expected` header,
+/// compared ignoring case and surrounding spaces.
+fn find_header_row(range: &calamine::Range<Data>, expected: &[String]) -> Option<usize> {
+    let normalize = |text: &str| text.trim().to_lowercase();
+    let expected: Vec<String> = expected.iter().map(|header| normalize(header)).collect();
+    range.rows()
+        .take(HEADER_SCAN_ROWS)
+        .position(|row| {
+            let cells: Vec<String> = row.iter().map(|cell| normalize(&cell.to_string())).collect();
+            expected.iter().all(|header| cells.contains(header))
+        })
+}
+
+/// Returns the row count from a worksheet's `<dimension>`, or 0 without
+/// one, and the unpacked size of its XML and of the shared strings.
+///
+/// Only the start of the worksheet XML, where the dimension is, is read.
+fn xlsx_sheet_size<R: Read + Seek>(reader: R, sheet_name: &str) -> Result<(usize, usize)> {
+    let mut archive = zip::ZipArchive::new(reader).context("Failed to read xlsx package")?;
+    let sheet_path = number_format::sheet_path(&mut archive, sheet_name)?;
+    let strings = archive.by_name("xl/sharedStrings.xml").map_or(0, |part| part.size() as usize);
+    let mut part = archive.by_name(&sheet_path)
+        .with_context(|| format!("Missing xlsx part: {}", sheet_path))?;
+    let xml_bytes = part.size() as usize;
+    let mut head = Vec::new();
+    (&mut part).take(4096).read_to_end(&mut head)?;
+    let head = String::from_utf8_lossy(&head);
+    let rows = head.split_once("<dimension ref=\"")
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .and_then(|(dimension, _)| {
+            let (start, end) = dimension.split_once(':').unwrap_or((dimension, dimension));
+            Some(CellRef::parse(end).ok()?.row + 1 - CellRef::parse(start).ok()?.row)
+        })
+        .unwrap_or(0);
+    Ok((rows, xml_bytes + strings))
+}
+
+/// Converts a calamine `(row, column)` position to a [`CellRef`].
+fn cell_position((row, col): (u32, u32)) -> CellRef {
+    CellRef { row: row as usize, col: col as usize }
+}
+
+/// Cuts `range` down to the cells of `cells`.
+///
+/// The end is clamped to the sheet's last used cell, so a generous range such
+/// as `A1:Z1048576` does not allocate blank rows. A block lying entirely
+/// outside the used area yields an empty range.
+fn sub_range(range: &calamine::Range<Data>, cells: &cell_ref::CellRange) -> calamine::Range<Data> {
+    let position = |cell: &CellRef| {
+        (u32::try_from(cell.row).unwrap_or(u32::MAX), u32::try_from(cell.col).unwrap_or(u32::MAX))
+    };
+    let start = position(&cells.start);
+    let Some((last_row, last_col)) = range.end() else {
+        return calamine::Range::empty();
+    };
+    let (end_row, end_col) = position(&cells.end);
+    let end = (end_row.min(last_row), end_col.min(last_col));
+    if end.0 < start.0 || end.1 < start.1 {
+        return calamine::Range::empty();
+    }
+    range.range(start, end)
+}
+
+impl SheetRowIter {
+    /// Returns an iterator over the data rows of a sheet that is already in
+    /// memory, so rows can be processed without opening a workbook with
+    /// [`ExcelReader`].
+    ///
+    /// The header row, offset, limit, range and date settings of `options`
+    /// apply as with [`ExcelReader::rows_iter`]. The settings that need the
+    /// workbook file do not: number formats (`display_format`), hyperlinks
+    /// (`with_hyperlinks`) and merged regions (`fill_merged`) are ignored, and formula cells keep the value
+    /// cached in the range, so uncached VLOOKUPs stay empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet_name` - Name used for the sheet in messages
+    /// * `range` - The sheet's cells, e.g. from `calamine::Reader::worksheet_range`
+    /// * `options` - Cell conversion settings
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured header row lies beyond the last row
+    /// of the range.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use calamine::{Data, Range};
+    /// use excel_to_json::excel_reader::{ReaderOptions, SheetRowIter};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut range = Range::new((0, 0), (1, 1));
+    /// range.set_value((0, 0), Data::String("Code".to_string()));
+    /// range.set_value((1, 0), Data::String("CAT001".to_string()));
+    /// range.set_value((1, 1), Data::Float(2.5));
+    ///
+    /// let rows = SheetRowIter::from_range("Data", range, &ReaderOptions::default())?;
+    /// assert_eq!(rows.headers(), ["Code", ""]);
+    /// let rows: Vec<_> = rows.collect();
+    /// assert_eq!(rows[0].cells, vec![Some("CAT001".to_string()), Some("2.5".to_string())]);
+    /// assert_eq!(rows[0].row_number, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_range(sheet_name: &str, mut range: calamine::Range<Data>, options: &ReaderOptions) -> Result<Self> {
+        if let Some(cells) = &options.range {
+            range = sub_range(&range, cells);
+        }
+        Self::build(range, None, CellFormats::default(), CellHyperlinks::default(), LookupTables::new(), sheet_name, options)
+    }
+
+    /// Locates the header row of `range` and returns an iterator positioned
+    /// on the first data row below it.
+    fn build(
+        range: calamine::Range<Data>,
+        formulas: Option<calamine::Range<String>>,
+        cell_formats: CellFormats,
+        hyperlinks: CellHyperlinks,
+        lookup_tables: LookupTables,
+        sheet_name: &str,
+        options: &ReaderOptions,
+    ) -> Result<Self> {
+        let start_row = range.start().map_or(0, |(row, _)| row as usize);
+        let last_row = start_row + range.height();
+        let empty = range.used_cells().next().is_none();
+        let width = if options.keep_blank_columns {
+            range.width()
+        } else {
+            blank_trimmed_width(&range, formulas.as_ref())
+        };
+        if width < range.width() {
+            debug!("Trimming {} blank columns from the right of sheet '{}'", range.width() - width, sheet_name);
+        }
+
+        // Index of the header row within the range; `None` when the header row
+        // lies above the first non-empty row, so every row is data
+        let mut diagnostics = Vec::new();
+        let header_idx = match options.header_row {
+            _ if options.no_header => None,
+            None if !options.expect_headers.is_empty() => {
+                let expected = &options.expect_headers;
+                let found = find_header_row(&range, expected);
+                let header_row = start_row + found.unwrap_or(0) + 1;
+                if found.is_some() {
+                    debug!("Found the expected headers in row {} of sheet '{}'", header_row, sheet_name);
+                    diagnostics.push(RowDiagnostic::info(header_row, None, format!(
+                        "Detected header row {} from the expected headers",
+                        header_row
+                    )));
+                } else {
+                    let message = format!(
+                        "None of the first {} rows has the expected headers ({}); using row {} as the header row",
+                        HEADER_SCAN_ROWS,
+                        expected.join(", "),
+                        header_row
+                    );
+                    warn!("Sheet '{}': {}", sheet_name, message);
+                    diagnostics.push(RowDiagnostic::warning(header_row, None, message));
+                }
+                Some(found.unwrap_or(0))
+            },
+            None => Some(0),
+            Some(row) if row > last_row => anyhow::bail!(
+                "Header row {} is beyond the last row of sheet '{}' ({} rows)",
+                row,
+                sheet_name,
+                last_row
+            ),
+            Some(row) => row.saturating_sub(1).checked_sub(start_row),
+        };
+        if let Some(skipped @ 1..) = header_idx {
+            debug!("Skipping {} rows above the header row", skipped);
+        }
+        let end_idx = range.height().saturating_sub(options.footer_rows);
+        if end_idx < range.height() {
+            let first_footer_row = start_row + end_idx + 1;
+            let message = if first_footer_row == last_row {
+                format!("Ignored row {} as a footer row", last_row)
+            } else {
+                format!("Ignored rows {}-{} as footer rows", first_footer_row, last_row)
+            };
+            diagnostics.push(RowDiagnostic::info(first_footer_row, None, message));
+        }
+
+        let mut rows = SheetRowIter {
+            range,
+            formulas,
+            cell_formats,
+            hyperlinks,
+            lookup_tables,
+            date_format: options.date_format.clone(),
+            float_precision: options.float_precision,
+            decimal_separator: options.decimal_separator,
+            keep_empty_rows: options.keep_empty_rows,
+            stop_at_blank: options.stop_at_blank,
+            fail_on_cell_error: options.fail_on_cell_error,
+            sheet_name: sheet_name.to_string(),
+            headers: Vec::new(),
+            diagnostics,
+            next_idx: header_idx.map_or(0, |header_idx| header_idx + 1),
+            end_idx,
+            skip: options.offset,
+            remaining: options.limit,
+            empty,
+            width,
+            display_format: options.display_format,
+            text_columns: Vec::new(),
+        };
+        if let Some(header_idx) = header_idx {
+            debug!("Capturing header row");
+            let (cells, _types, _errors) = rows.convert_row(header_idx);
+            rows.headers = cells.into_iter().map(|v| v.unwrap_or_default()).collect();
+        } else if options.no_header {
+            // Blank headers, so every column is keyed as column_N
+            rows.headers = vec![String::new(); rows.width];
+        }
+        let header_row = start_row + header_idx.unwrap_or(0) + 1;
+        for name in &options.text_columns {
+            match text_column(name, &rows.headers) {
+                Some(col) => rows.text_columns.push(col),
+                None => rows.diagnostics.push(RowDiagnostic::warning(header_row, None, format!(
+                    "Text column '{}' matches no header or field; its numbers are not kept as text",
+                    name
+                ))),
             }
+        }
+
+        Ok(rows)
+    }
+
+    /// Returns the header row; empty when the header row lies above the data,
+    /// and blank for every column with [`ReaderOptions::no_header`].
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Returns notes about the sheet itself, such as the header row found
+    /// with [`ReaderOptions::expect_headers`].
+    pub fn diagnostics(&self) -> &[RowDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the sheet's width before the columns blank in every row were
+    /// trimmed from its right edge, or `None` if no column was trimmed (see
+    /// [`ReaderOptions::keep_blank_columns`]).
+    pub fn untrimmed_width(&self) -> Option<usize> {
+        (self.width < self.range.width()).then(|| self.range.width())
+    }
+
+    /// Returns whether the sheet has no cells at all, not even a header row,
+    /// as opposed to a sheet whose header has no data below it.
+    pub fn is_sheet_empty(&self) -> bool {
+        self.empty
+    }
+
+    /// Converts the row at `row_idx` (relative to the range) to text values
+    /// and cell types, and collects its error cells.
+    fn convert_row(&self, row_idx: usize) -> (RawRow, RowTypes, Vec<CellError>) {
+        let (start_row, start_col) = self.range.start().unwrap_or((0, 0));
+        let mut cells = Vec::with_capacity(self.width);
+        let mut types = Vec::with_capacity(self.width);
+        let mut errors = Vec::new();
+        
+        for col_idx in 0..self.width {
+            let cell = self.range.get((row_idx, col_idx)).unwrap_or(&Data::Empty);
+            let position = (start_row + row_idx as u32, start_col + col_idx as u32);
+            let text = self.text_columns.contains(&col_idx);
+            let display = |value: f64| {
+                self.cell_formats
+                    .get(position.0 as usize, position.1 as usize)
+                    .filter(|_| self.display_format || text)
+                    .and_then(|code| number_format::format_number(value, code))
+            };
             
-            // Only add non-empty rows
-            if processed_row.iter().any(|v| v.is_some()) {
-                processed_rows.push(processed_row);
-            }
+            types.push(match cell {
+                Data::Float(_) | Data::Int(_) if !text => CellType::Number,
+                Data::Bool(_) => CellType::Bool,
+                _ => CellType::Text,
+            });
+            
+            let value = match cell {
+                Data::String(s) => self.cell_formats
+                    .get(position.0 as usize, position.1 as usize)
+                    .filter(|_| self.display_format)
+                    .and_then(|code| number_format::format_text(s, code))
+                    .or_else(|| Some(s.clone())),
+                Data::Float(f) if text => display(*f).or_else(|| Some(format_float(*f, None, None))),
+                Data::Float(f) => display(*f)
+                    .or_else(|| Some(format_float(*f, self.float_precision, self.decimal_separator))),
+                Data::Int(i) => display(*i as f64).or_else(|| Some(format!("{}", i))),
+                Data::Bool(b) => Some(format!("{}", b)),
+                Data::DateTime(dt) => Some(format_excel_datetime(dt, self.date_format.as_deref())),
+                Data::DateTimeIso(dt) => Some(reformat_iso_datetime(dt, self.date_format.as_deref())),
+                Data::DurationIso(d) => Some(d.clone()),
+                Data::Error(_) | Data::Empty => {
+                    // Check if there's a formula for this cell
+                    let formula = self.formulas.as_ref().and_then(|formulas| formulas.get_value(position));
+                    match formula {
+                        Some(formula) if Vlookup::parse(formula).is_some() => {
+                            let resolved = resolve_vlookup(formula, &self.sheet_name, &self.range, &self.lookup_tables);
+                            if resolved.is_none() {
+                                warn!("Could not resolve VLOOKUP at row {}, col {}: {}",
+                                      position.0 + 1, position.1 + 1, formula);
+                            }
+                            if let (None, Data::Error(error)) = (&resolved, cell) {
+                                errors.push(CellError { cell: cell_position(position), error: error.clone() });
+                            }
+                            resolved
+                        },
+                        _ => {
+                            if let Data::Error(error) = cell {
+                                errors.push(CellError { cell: cell_position(position), error: error.clone() });
+                            }
+                            // Keep the formula text of other error cells
+                            formula.filter(|_| matches!(cell, Data::Error(_))).cloned()
+                        },
+                    }
+                },
+            };
+            let value = match self.hyperlinks.get(position.0 as usize, position.1 as usize) {
+                Some(url) => Some(hyperlink::with_url(value.as_deref(), url)),
+                None => value,
+            };
+            
+            cells.push(value);
         }
+        
+        (cells, types, errors)
+    }
+}
 
-        info!("Processed {} data rows from sheet '{}'", processed_rows.len(), self.sheet_name);
+/// Returns the column of a [`ReaderOptions::text_columns`] entry: the first
+/// header matching it, ignoring case and surrounding spaces, or else the
+/// default column of the cascade field it names.
+fn text_column(name: &str, headers: &[String]) -> Option<usize> {
+    let name = name.trim();
+    headers.iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(name))
+        .or_else(|| CascadeField::FIELD_NAMES.iter().position(|field| *field == name))
+}
+
+/// Returns the number of columns of `range` up to the last one holding a
+/// value or a formula in any row.
+fn blank_trimmed_width(range: &calamine::Range<Data>, formulas: Option<&calamine::Range<String>>) -> usize {
+    let start_col = range.start().map_or(0, |(_, col)| col as usize);
+    let last_value = range.used_cells().map(|(_, col, _)| start_col + col + 1).max();
+    let last_formula = formulas.and_then(|formulas| {
+        let formula_start = formulas.start().map_or(0, |(_, col)| col as usize);
+        formulas.used_cells()
+            .filter(|(_, _, formula)| !formula.is_empty())
+            .map(|(_, col, _)| formula_start + col + 1)
+            .max()
+    });
+    last_value.max(last_formula).map_or(0, |end| end.saturating_sub(start_col).min(range.width()))
+}
+
+impl Iterator for SheetRowIter {
+    type Item = SheetRow;
+
+    fn next(&mut self) -> Option<SheetRow> {
+        let start_row = self.range.start().map_or(0, |(row, _)| row as usize);
+        if self.remaining == Some(0) {
+            return None;
+        }
         
-        Ok(processed_rows)
+        while self.next_idx < self.end_idx {
+            let row_idx = self.next_idx;
+            self.next_idx += 1;
+            
+            // Only yield non-empty rows, unless blanks are kept
+            let (cells, types, cell_errors) = self.convert_row(row_idx);
+            let blank = cells.iter().all(|v| v.is_none());
+            if blank && self.stop_at_blank {
+                debug!("Stopping at blank row {} of sheet '{}'", start_row + row_idx + 1, self.sheet_name);
+                self.next_idx = self.end_idx;
+                return None;
+            }
+            if blank && !self.keep_empty_rows {
+                continue;
+            }
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+            if let Some(remaining) = self.remaining.as_mut() {
+                *remaining -= 1;
+            }
+            return Some(SheetRow { cells, types, row_number: start_row + row_idx + 1, cell_errors });
+        }
+        
+        None
+    }
+
+    /// The upper bound is the number of rows left above the footer, less the
+    /// rows still to skip, capped by the limit.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.end_idx.saturating_sub(self.next_idx).saturating_sub(self.skip);
+        (0, Some(self.remaining.map_or(left, |remaining| left.min(remaining))))
+    }
+}
+
+/// Attempts to resolve a VLOOKUP formula.
+///
+/// Used for formula cells saved without a cached result (or with an error
+/// result), which calamine cannot evaluate itself.
+///
+/// # Arguments
+///
+/// * `formula` - The VLOOKUP formula, with or without the leading `=`
+/// * `sheet_name` - Name of the sheet containing the formula
+/// * `sheet` - The sheet containing the formula, used to read the lookup key
+/// * `lookup_tables` - Pre-built lookup tables from all sheets
+///
+/// # Returns
+///
+/// * `Some(String)` - Resolved value if successful
+/// * `None` - If the formula cannot be resolved
+///
+/// # Example Formula Format
+///
+/// ```text
+/// =VLOOKUP(A2,Sheet2!A:C,2,FALSE)
+/// ```
+///
+/// Where:
+/// - `A2` is the lookup value (a cell on the same sheet or a literal)
+/// - `Sheet2!A:C` is the table array (the sheet defaults to the current one)
+/// - `2` is the column index
+/// - `FALSE` indicates exact match
+///
+/// # Note
+///
+/// Only exact matches (`FALSE` or `0`) are resolved; approximate matches
+/// (`TRUE`, `1` or no fourth argument) return `None`. The table array must
+/// start in the first used column of its sheet, which is what the lookup
+/// tables are keyed by.
+fn resolve_vlookup(
+    formula: &str,
+    sheet_name: &str,
+    sheet: &calamine::Range<Data>,
+    lookup_tables: &LookupTables,
+) -> Option<String> {
+    let call = Vlookup::parse(formula)?;
+    if !call.exact_match {
+        debug!("Approximate-match VLOOKUP is not supported: {}", formula);
+        return None;
+    }
+
+    let key = match &call.key {
+        VlookupKey::Cell(cell) => lookup_text(sheet.get_value((cell.row as u32, cell.col as u32))?),
+        VlookupKey::Literal(text) => text.clone(),
+    };
+    let table_sheet = call.sheet.as_deref().unwrap_or(sheet_name);
+    let row = lookup_tables.get(table_sheet)?.get(&key)?;
+
+    // The table is keyed by its sheet's first column; make sure that is
+    // also the first column of the formula's table array
+    if row.get(call.first_col) != Some(&key) {
+        return None;
+    }
+
+    row.get(call.first_col + call.column - 1)
+        .filter(|value| !value.is_empty())
+        .cloned()
+}
+
+/// Renders a number cell as text.
+///
+/// Integer values are written without decimals. Other values are rounded to
+/// `precision` decimals when given, or else written as the shortest text that
+/// reads back as the same number; `decimal_separator` replaces the `.`.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::excel_reader::format_float;
+///
+/// assert_eq!(format_float(0.1 + 0.2, None, None), "0.30000000000000004");
+/// assert_eq!(format_float(0.1 + 0.2, Some(2), None), "0.30");
+/// assert_eq!(format_float(1234.5, Some(2), Some(',')), "1234,50");
+/// assert_eq!(format_float(42.0, Some(2), Some(',')), "42");
+/// ```
+pub fn format_float(value: f64, precision: Option<usize>, decimal_separator: Option<char>) -> String {
+    // Integers are displayed without decimals
+    if value.fract() == 0.0 {
+        return format!("{:.0}", value);
+    }
+    let text = match precision {
+        Some(precision) => format!("{:.*}", precision, value),
+        None => format!("{}", value),
+    };
+    match decimal_separator {
+        Some(separator) => text.replace('.', &separator.to_string()),
+        None => text,
+    }
+}
+
+/// Renders an Excel date cell as text.
+///
+/// Without a `format`, dates are written as ISO-8601: `2023-01-01` for a
+/// whole day, `2023-01-01T08:30:00` for a date with a time and `08:30:00` for
+/// a time of day. A chrono strftime `format` is used for all three instead.
+/// Durations (cells formatted like `[h]:mm:ss`) are not points in time and
+/// are always written as elapsed `hours:mm:ss`.
+///
+/// # Arguments
+///
+/// * `datetime` - The date cell's value
+/// * `format` - A strftime pattern checked with [`check_date_format`], or `None`
+///
+/// # Example
+///
+/// ```rust
+/// use calamine::{ExcelDateTime, ExcelDateTimeType};
+/// use excel_to_json::excel_reader::format_excel_datetime;
+///
+/// let date = ExcelDateTime::new(44927.0, ExcelDateTimeType::DateTime, false);
+/// assert_eq!(format_excel_datetime(&date, None), "2023-01-01");
+/// assert_eq!(format_excel_datetime(&date, Some("%d/%m/%Y")), "01/01/2023");
+///
+/// let morning = ExcelDateTime::new(44927.25, ExcelDateTimeType::DateTime, false);
+/// assert_eq!(format_excel_datetime(&morning, None), "2023-01-01T06:00:00");
+///
+/// let elapsed = ExcelDateTime::new(1.5, ExcelDateTimeType::TimeDelta, false);
+/// assert_eq!(format_excel_datetime(&elapsed, None), "36:00:00");
+/// ```
+pub fn format_excel_datetime(datetime: &calamine::ExcelDateTime, format: Option<&str>) -> String {
+    if datetime.is_duration() {
+        let seconds = (datetime.as_f64() * 86_400.0).round() as i64;
+        let sign = if seconds < 0 { "-" } else { "" };
+        let seconds = seconds.abs();
+        return format!("{}{}:{:02}:{:02}", sign, seconds / 3600, seconds / 60 % 60, seconds % 60);
+    }
+    let Some(value) = datetime.as_datetime() else {
+        return datetime.as_f64().to_string();
+    };
+    let pattern = match format {
+        Some(format) => format,
+        // Serials below 1 carry no date, only a time of day
+        None if (0.0..1.0).contains(&datetime.as_f64()) => "%H:%M:%S",
+        None if value.time() == chrono::NaiveTime::MIN => "%Y-%m-%d",
+        None => "%Y-%m-%dT%H:%M:%S",
+    };
+    value.format(pattern).to_string()
+}
+
+/// Applies a `--date-format` pattern to an ISO-8601 date read from an ods
+/// file, which calamine returns as text. Values that are not ISO dates or
+/// date-times are returned unchanged.
+fn reformat_iso_datetime(text: &str, format: Option<&str>) -> String {
+    let Some(format) = format else {
+        return text.to_string();
+    };
+    if let Ok(datetime) = chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f") {
+        return datetime.format(format).to_string();
+    }
+    match chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        Ok(date) => date.format(format).to_string(),
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Checks that a strftime pattern for [`ReaderOptions::date_format`] is valid.
+///
+/// # Errors
+///
+/// Returns an error naming the pattern if chrono cannot parse it, such as
+/// an unknown `%` specifier.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::excel_reader::check_date_format;
+///
+/// assert!(check_date_format("%d.%m.%Y %H:%M").is_ok());
+/// assert!(check_date_format("%Q").is_err());
+/// ```
+pub fn check_date_format(format: &str) -> Result<()> {
+    use chrono::format::{Item, StrftimeItems};
+
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        anyhow::bail!("Invalid date format '{}': see chrono's strftime specifiers", format);
+    }
+    Ok(())
+}
+
+/// Converts a cell to the text used for VLOOKUP keys and results.
+fn lookup_text(cell: &Data) -> String {
+    match cell {
+        Data::String(s) => s.clone(),
+        Data::Float(f) => format!("{}", f),
+        Data::Int(i) => format!("{}", i),
+        Data::Bool(b) => format!("{}", b),
+        Data::DateTime(dt) => format_excel_datetime(dt, None),
+        Data::DateTimeIso(dt) => dt.clone(),
+        Data::DurationIso(d) => d.clone(),
+        _ => String::new(),
+    }
+}
+
+/// The lookup value of a VLOOKUP call.
+#[derive(Debug, Clone, PartialEq)]
+enum VlookupKey {
+    /// A cell on the formula's own sheet
+    Cell(CellRef),
+    /// A quoted string or number
+    Literal(String),
+}
+
+/// The parts of a `VLOOKUP(key, table, column, match)` formula.
+#[derive(Debug, Clone, PartialEq)]
+struct Vlookup {
+    key: VlookupKey,
+    /// Sheet of the table array; `None` for the formula's own sheet
+    sheet: Option<String>,
+    /// Zero-based first column of the table array
+    first_col: usize,
+    /// One-based column index within the table array
+    column: usize,
+    exact_match: bool,
+}
+
+impl Vlookup {
+    /// Parses a formula such as `=VLOOKUP(A2,'Line Type'!$A:$C,2,FALSE)`.
+    ///
+    /// Returns `None` for anything else, including VLOOKUPs nested inside
+    /// other functions.
+    fn parse(formula: &str) -> Option<Self> {
+        let formula = formula.trim();
+        let formula = formula.strip_prefix('=').unwrap_or(formula);
+        if formula.len() < 8 || !formula[..8].eq_ignore_ascii_case("VLOOKUP(") {
+            return None;
+        }
+        let args = split_arguments(formula[8..].strip_suffix(')')?)?;
+        if !(3..=4).contains(&args.len()) {
+            return None;
+        }
+
+        let key = match args[0] {
+            quoted if quoted.starts_with('"') => VlookupKey::Literal(
+                quoted.strip_prefix('"')?.strip_suffix('"')?.replace("\"\"", "\""),
+            ),
+            number if number.parse::<f64>().is_ok() => VlookupKey::Literal(number.to_string()),
+            reference => VlookupKey::Cell(CellRef::parse(reference).ok()?),
+        };
+
+        let (sheet, table) = match args[1].rsplit_once('!') {
+            Some((sheet, table)) => {
+                let sheet = match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+                    Some(quoted) => quoted.replace("''", "'"),
+                    None => sheet.to_string(),
+                };
+                (Some(sheet), table)
+            },
+            None => (None, args[1]),
+        };
+        let first = table.split(':').next()?.trim_start_matches('$');
+        let letters: String = first.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+        let first_col = cell_ref::column_to_index(&letters)?;
+
+        let column = args[2].parse::<usize>().ok().filter(|&column| column >= 1)?;
+        let exact_match = match args.get(3).map(|mode| mode.to_ascii_uppercase()) {
+            Some(mode) if mode == "FALSE" || mode == "0" => true,
+            Some(mode) if mode == "TRUE" || mode == "1" => false,
+            Some(_) => return None,
+            None => false,
+        };
+
+        Some(Vlookup { key, sheet, first_col, column, exact_match })
+    }
+}
+
+/// Splits formula arguments at top-level commas, respecting quotes and
+/// parentheses. Returns `None` if quotes or parentheses are unbalanced.
+fn split_arguments(args: &str) -> Option<Vec<&str>> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = 0;
+
+    for (idx, c) in args.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {},
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.checked_sub(1)?,
+            (None, ',') if depth == 0 => {
+                parts.push(args[start..idx].trim());
+                start = idx + 1;
+            },
+            _ => {},
+        }
+    }
+
+    if quote.is_some() || depth != 0 {
+        return None;
+    }
+    parts.push(args[start..].trim());
+    Some(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_bytes_matches_path_for_xlsx_xlsb_and_ods() {
+        for path in ["resources/Cascade Sample.xlsx", "resources/Cascade Sample.xlsb", "resources/Cascade Sample.ods"] {
+            let mut from_path = ExcelReader::new(path, "Cascade Fields".to_string()).unwrap();
+            let bytes = std::fs::read(path).unwrap();
+            let mut from_bytes = ExcelReader::from_bytes(bytes, "Cascade Fields".to_string()).unwrap();
+
+            assert_eq!(from_bytes.get_sheet_names(), from_path.get_sheet_names(), "{}", path);
+            let (expected, actual) = (from_path.read_sheet().unwrap(), from_bytes.read_sheet().unwrap());
+            assert_eq!((actual.headers, actual.rows), (expected.headers, expected.rows), "{}", path);
+        }
+
+        assert!(ExcelReader::from_bytes(b"not a workbook".to_vec(), String::new()).is_err());
+    }
+
+    #[test]
+    fn test_xlsb_reads_like_xlsx() {
+        let read = |path: &str| {
+            let mut reader = ExcelReader::new(path, "Cascade Fields".to_string()).unwrap();
+            (reader.get_sheet_names(), reader.read_with_formulas().unwrap())
+        };
+
+        let (sheets, rows) = read("resources/Cascade Sample.xlsb");
+        assert_eq!(sheets, vec!["Cascade Fields", "Line Type"]);
+        assert_eq!((sheets, rows), read("resources/Cascade Sample.xlsx"));
+    }
+
+    #[test]
+    fn test_password_protected_workbook_is_explained() {
+        let compound_file = |stream: &str| {
+            let mut bytes = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+            bytes.resize(512, 0);
+            bytes.extend(stream.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes.resize(1024, 0);
+            bytes
+        };
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("locked.xlsx");
+        std::fs::write(&path, compound_file("EncryptedPackage")).unwrap();
+        let error = ExcelReader::new(&path, String::new()).err().expect("Encrypted workbooks cannot be opened");
+        assert_eq!(format!("{:#}", error), format!(
+            "{}: workbook is password-protected; decryption is not supported, remove the password in Excel and try again",
+            path.display()
+        ));
+
+        // Other files that are not zip packages keep the zip error, once
+        let path = temp_dir.path().join("notes.xlsx");
+        std::fs::write(&path, "not a workbook").unwrap();
+        let error = ExcelReader::new(&path, String::new()).err().unwrap();
+        assert_eq!(format!("{:#}", error), format!("{}: Zip error: invalid Zip archive: Invalid zip header", path.display()));
+
+        let error = ExcelReader::from_bytes(compound_file("Workbook"), String::new()).err().unwrap();
+        assert!(error.to_string().contains("legacy .xls workbooks are not supported"), "got: {}", error);
+    }
+
+    #[test]
+    fn test_parse_vlookup_variants() {
+        let call = Vlookup::parse("=VLOOKUP(A2, Sheet2!A:C, 2, FALSE)").unwrap();
+        assert_eq!(call.key, VlookupKey::Cell(CellRef { row: 1, col: 0 }));
+        assert_eq!(call.sheet.as_deref(), Some("Sheet2"));
+        assert_eq!((call.first_col, call.column, call.exact_match), (0, 2, true));
+
+        let call = Vlookup::parse("vlookup($B$7,'Line Type'!$B$1:$D$10,3,0)").unwrap();
+        assert_eq!(call.key, VlookupKey::Cell(CellRef { row: 6, col: 1 }));
+        assert_eq!(call.sheet.as_deref(), Some("Line Type"));
+        assert_eq!((call.first_col, call.column, call.exact_match), (1, 3, true));
+
+        let call = Vlookup::parse("VLOOKUP(\"a,b\",A:B,2)").unwrap();
+        assert_eq!(call.key, VlookupKey::Literal("a,b".to_string()));
+        assert_eq!(call.sheet, None);
+        assert!(!call.exact_match);
+
+        assert!(Vlookup::parse("SUM(A1:A3)").is_none());
+        assert!(Vlookup::parse("VLOOKUP(A2,B:C)").is_none());
+        assert!(Vlookup::parse("VLOOKUP(A2,B:C,0,FALSE)").is_none());
+        assert!(Vlookup::parse("IFERROR(VLOOKUP(A2,B:C,2,FALSE),\"\")").is_none());
+    }
+
+    #[test]
+    fn test_fill_merged_stays_inside_region() {
+        use rust_xlsxwriter::{Format, Workbook};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("merged.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Data").unwrap();
+        for (col, header) in ["Category", "Code", "Notes"].iter().enumerate() {
+            sheet.write_string(0, col as u16, *header).unwrap();
+        }
+        sheet.merge_range(1, 0, 3, 0, "Hardware", &Format::new()).unwrap();
+        for row in 1..=4 {
+            sheet.write_string(row, 1, format!("C{}", row)).unwrap();
+        }
+        sheet.write_string(1, 2, "first").unwrap();
+        workbook.save(&path).unwrap();
+
+        let read = |fill_merged: bool| {
+            let mut reader = ExcelReader::new(&path, "Data".to_string()).unwrap();
+            reader.set_options(ReaderOptions { fill_merged, ..ReaderOptions::default() });
+            reader.read_sheet().unwrap().rows
+        };
+
+        let plain = read(false);
+        assert_eq!(plain[1][0], None);
+
+        let filled = read(true);
+        let categories: Vec<Option<&str>> = filled.iter().map(|row| row[0].as_deref()).collect();
+        assert_eq!(categories, vec![Some("Hardware"), Some("Hardware"), Some("Hardware"), None]);
+        // The neighbouring unmerged column is untouched
+        assert_eq!(filled[1][2], None);
+    }
+
+    #[test]
+    fn test_expect_headers_finds_header_below_preamble() {
+        use crate::models::Severity;
+        use rust_xlsxwriter::Workbook;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("preamble.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Data").unwrap();
+        sheet.write_string(0, 0, "Quarterly export").unwrap();
+        sheet.write_string(1, 0, "Main Label").unwrap();
+        for (col, header) in ["Main Label ", "main value", "Notes"].iter().enumerate() {
+            sheet.write_string(3, col as u16, *header).unwrap();
+        }
+        sheet.write_string(4, 1, "CAT001").unwrap();
+        workbook.save(&path).unwrap();
+
+        let read = |expect_headers: &[&str]| {
+            let mut reader = ExcelReader::new(&path, "Data".to_string()).unwrap();
+            let expect_headers = expect_headers.iter().map(|header| header.to_string()).collect();
+            reader.set_options(ReaderOptions { expect_headers, ..ReaderOptions::default() });
+            reader.read_sheet().unwrap()
+        };
+
+        let sheet = read(&["Main Label", "Main Value"]);
+        assert_eq!(sheet.headers, vec!["Main Label ", "main value", "Notes"]);
+        assert_eq!(sheet.row_numbers, vec![5]);
+        assert_eq!(sheet.diagnostics.len(), 1);
+        assert_eq!((sheet.diagnostics[0].row_number, sheet.diagnostics[0].severity), (4, Severity::Info));
+
+        // Without a match the first row is the header row
+        let sheet = read(&["Main Label", "Minor Value"]);
+        assert_eq!(sheet.headers[0], "Quarterly export");
+        assert_eq!(sheet.row_numbers, vec![2, 4, 5]);
+        assert_eq!((sheet.diagnostics[0].row_number, sheet.diagnostics[0].severity), (1, Severity::Warning));
+        assert!(sheet.diagnostics[0].message.starts_with("None of the first 10 rows has the expected headers (Main Label, Minor Value)"));
+    }
+
+    #[test]
+    fn test_record_bytes_are_estimated_from_the_worksheet_size() {
+        use rust_xlsxwriter::Workbook;
+
+        let mut workbook = Workbook::new();
+        let small = workbook.add_worksheet().set_name("Small").unwrap();
+        small.write_string(0, 1, "main_value").unwrap();
+        small.write_string(1, 1, "CAT001").unwrap();
+        let large = workbook.add_worksheet().set_name("Large").unwrap();
+        for row in 2..2002u32 {
+            large.write_string(row, 1, format!("CAT{:04}", row)).unwrap();
+        }
+        let bytes: Arc<[u8]> = workbook.save_to_buffer().unwrap().into();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("sizes.xlsx");
+        std::fs::write(&path, &bytes).unwrap();
+
+        let estimate = |source: WorkbookSource, sheet: &str| {
+            ExcelReader::from_source(source, sheet.to_string()).unwrap().estimate_record_bytes().unwrap()
+        };
+        let large = estimate(WorkbookSource::Path(path.clone()), "Large");
+        // The dimension B3:B2002 gives the row count, the XML the text
+        assert!(large > memory::estimate_sheet_bytes(2000, 2000 * 7), "{}", large);
+        assert_eq!(estimate(WorkbookSource::Bytes(bytes.clone()), "large"), large);
+        // The shared strings of both sheets count toward each
+        assert!(estimate(WorkbookSource::Path(path.clone()), "Small") < large / 4);
+
+        let missing = ExcelReader::from_source(WorkbookSource::Path(path), "Nope".to_string()).unwrap();
+        assert!(missing.estimate_record_bytes().is_err());
+    }
+
+    #[test]
+    fn test_blank_trailing_columns_are_trimmed() {
+        use crate::models::CascadeField;
+        use crate::processor::ProcessorOptions;
+        use calamine::Range;
+
+        // Rows padded with blank cells out to column AZ
+        let mut range = Range::new((0, 0), (2, 51));
+        for (col, name) in CascadeField::FIELD_NAMES.iter().enumerate() {
+            range.set_value((0, col as u32), Data::String(name.to_string()));
+        }
+        range.set_value((1, 1), Data::String("CAT001".to_string()));
+        range.set_value((2, 1), Data::String("CAT002".to_string()));
+
+        let rows = SheetRowIter::from_range("Padded", range.clone(), &ReaderOptions::default()).unwrap();
+        assert_eq!(rows.headers().len(), 12);
+        assert_eq!(rows.untrimmed_width(), Some(52));
+        assert!(rows.map(|row| row.cells.len()).all(|width| width == 12));
+
+        let keep = ReaderOptions { keep_blank_columns: true, ..ReaderOptions::default() };
+        let rows = SheetRowIter::from_range("Padded", range.clone(), &keep).unwrap();
+        assert_eq!(rows.untrimmed_width(), None);
+        assert!(rows.map(|row| row.cells.len()).all(|width| width == 52));
+
+        // The padding no longer reads as a column count mismatch
+        let sheet = crate::pipeline::process_range("Padded", range, &ReaderOptions::default(), &ProcessorOptions::default()).unwrap();
+        let metadata = sheet.metadata.unwrap();
+        assert_eq!(metadata.valid_records, 2);
+        assert_eq!((metadata.detected_columns, metadata.warnings), (None, None));
+
+        // Columns blank in every row, even the header, are restored for the cascade layout
+        let mut narrow = Range::new((0, 0), (1, 11));
+        narrow.set_value((0, 1), Data::String("main_value".to_string()));
+        narrow.set_value((1, 1), Data::String("CAT001".to_string()));
+        let sheet = crate::pipeline::process_range("Narrow", narrow, &ReaderOptions::default(), &ProcessorOptions::default()).unwrap();
+        assert_eq!(sheet.rows.len(), 1);
+        assert_eq!(sheet.metadata.unwrap().malformed_rows, 0);
+    }
+
+    #[test]
+    fn test_float_precision_and_decimal_separator() {
+        let mut range = calamine::Range::new((0, 0), (1, 2));
+        range.set_value((0, 0), Data::String("Price".to_string()));
+        range.set_value((1, 0), Data::Float(0.1 + 0.2));
+        range.set_value((1, 1), Data::Float(2.0));
+        range.set_value((1, 2), Data::String("1.5".to_string()));
+        let read = |options: ReaderOptions| {
+            let rows: Vec<SheetRow> = SheetRowIter::from_range("Prices", range.clone(), &options).unwrap().collect();
+            rows[0].cells.clone()
+        };
+
+        let raw = read(ReaderOptions::default());
+        assert_eq!(raw[0].as_deref(), Some("0.30000000000000004"));
+
+        let rounded = read(ReaderOptions { float_precision: Some(2), decimal_separator: Some(','), ..ReaderOptions::default() });
+        assert_eq!(rounded[0].as_deref(), Some("0,30"));
+        // Integers keep no decimals, and text cells are left alone
+        assert_eq!(rounded[1].as_deref(), Some("2"));
+        assert_eq!(rounded[2].as_deref(), Some("1.5"));
+    }
+
+    #[test]
+    fn test_date_cells_are_written_as_iso_8601() {
+        use rust_xlsxwriter::{ExcelDateTime, Format, Workbook};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("dates.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Data").unwrap();
+        for (col, header) in ["Date", "Timestamp", "Time", "Elapsed"].iter().enumerate() {
+            sheet.write_string(0, col as u16, *header).unwrap();
+        }
+        let date = ExcelDateTime::from_ymd(2023, 1, 1).unwrap();
+        let timestamp = ExcelDateTime::from_ymd(2024, 2, 29).unwrap().and_hms(13, 45, 30).unwrap();
+        let time = ExcelDateTime::from_hms(8, 30, 0).unwrap();
+        sheet.write_datetime_with_format(1, 0, &date, &Format::new().set_num_format("dd/mm/yyyy")).unwrap();
+        sheet.write_datetime_with_format(1, 1, &timestamp, &Format::new().set_num_format("yyyy-mm-dd hh:mm")).unwrap();
+        sheet.write_datetime_with_format(1, 2, &time, &Format::new().set_num_format("hh:mm")).unwrap();
+        sheet.write_number_with_format(1, 3, 1.5, &Format::new().set_num_format("[h]:mm:ss")).unwrap();
+        workbook.save(&path).unwrap();
+
+        let read = |date_format: Option<&str>| {
+            let mut reader = ExcelReader::new(&path, "Data".to_string()).unwrap();
+            reader.set_options(ReaderOptions { date_format: date_format.map(str::to_string), ..ReaderOptions::default() });
+            reader.read_sheet().unwrap().rows.remove(0)
+        };
+
+        let iso = read(None);
+        let values: Vec<Option<&str>> = iso.iter().map(|cell| cell.as_deref()).collect();
+        assert_eq!(values, vec![Some("2023-01-01"), Some("2024-02-29T13:45:30"), Some("08:30:00"), Some("36:00:00")]);
+
+        let custom = read(Some("%d.%m.%Y %H:%M"));
+        assert_eq!(custom[0].as_deref(), Some("01.01.2023 00:00"));
+        assert_eq!(custom[1].as_deref(), Some("29.02.2024 13:45"));
+        // Durations are not points in time
+        assert_eq!(custom[3].as_deref(), Some("36:00:00"));
+    }
+
+    #[test]
+    fn test_uncached_vlookups_are_resolved() {
+        let mut reader = ExcelReader::new("resources/Vlookup Sample.xlsx", "Cascade Fields".to_string()).unwrap();
+        let sheet = reader.read_sheet().unwrap();
+
+        // Exact matches against a whole-column and an anchored table array
+        assert_eq!(sheet.rows[0][0].as_deref(), Some("Labour"));
+        assert_eq!(sheet.rows[0][2].as_deref(), Some("Labour line"));
+        // Approximate match is not supported; a cached error is resolved
+        assert_eq!(sheet.rows[1][0], None);
+        assert_eq!(sheet.rows[1][2].as_deref(), Some("Material line"));
+        // Key not present in the table
+        assert_eq!(sheet.rows[2][0], None);
     }
 }
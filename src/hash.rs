@@ -0,0 +1,105 @@
+//! Output hashes for `--hash`.
+//!
+//! Build systems skip downstream steps when a step's output is unchanged, so
+//! the tool can report a SHA-256 hash of what it produced. The hash covers
+//! exactly the bytes written to the output file or stdout. For it to depend
+//! only on the workbook and the options, the processing times in the output
+//! are written as zero first (see [`zero_processing_times`]).
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::hash::sha256_hex;
+//!
+//! assert_eq!(sha256_hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+//! ```
+
+use crate::models::ProcessingResult;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+
+/// Returns the SHA-256 digest of `bytes` as 64 lowercase hex digits.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Sets the processing time of the run and of each sheet to zero, so that
+/// formatting the result gives the same bytes on every run.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::hash::zero_processing_times;
+/// use excel_to_json::models::{ProcessingMetadata, ProcessingResult};
+///
+/// let mut result = ProcessingResult::success(vec![], ProcessingMetadata { processing_time_ms: 90, ..ProcessingMetadata::default() });
+/// zero_processing_times(&mut result);
+/// assert_eq!(result.metadata.processing_time_ms, 0);
+/// ```
+pub fn zero_processing_times(result: &mut ProcessingResult) {
+    result.metadata.processing_time_ms = 0;
+    for sheet in result.sheet_data.iter_mut().flatten() {
+        if let Some(metadata) = &mut sheet.metadata {
+            metadata.processing_time_ms = 0;
+        }
+    }
+}
+
+/// A writer that hashes the bytes it passes on, for output written in
+/// pieces rather than from one buffer.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::hash::{sha256_hex, HashingWriter};
+/// use std::io::Write;
+///
+/// let mut out = HashingWriter::new(Vec::new());
+/// out.write_all(b"a,b\n1,2\n").unwrap();
+/// assert_eq!(out.hex_digest(), sha256_hex(b"a,b\n1,2\n"));
+/// ```
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    /// Wraps `inner`, with nothing hashed yet.
+    pub fn new(inner: W) -> Self {
+        HashingWriter { inner, hasher: Sha256::new() }
+    }
+
+    /// Returns the SHA-256 digest of the bytes written so far as 64
+    /// lowercase hex digits.
+    pub fn hex_digest(&self) -> String {
+        format!("{:x}", self.hasher.clone().finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashing_writer_hashes_what_it_writes() {
+        let mut out = HashingWriter::new(Vec::new());
+        assert_eq!(out.hex_digest(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        for chunk in [&b"abcdbcdecdefdefgefghfghighijklm"[..], b"", b"klmnlmnomnopnopq"] {
+            out.write_all(chunk).unwrap();
+        }
+        assert_eq!(out.inner, b"abcdbcdecdefdefgefghfghighijklmklmnlmnomnopnopq");
+        assert_eq!(out.hex_digest(), sha256_hex(b"abcdbcdecdefdefgefghfghighijklmklmnlmnomnopnopq"));
+    }
+}
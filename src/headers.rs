@@ -0,0 +1,326 @@
+//! Header row handling for header-keyed output.
+//!
+//! When spreadsheet columns are emitted under their header text, the header
+//! row has to be turned into a set of unique JSON keys. This module resolves
+//! blank and duplicate headers according to a [`DuplicateHeaderPolicy`] and
+//! extracts the keyed values from each data row.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::headers::{DuplicateHeaderPolicy, HeaderLayout};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let headers = vec!["Name".to_string(), "Value".to_string(), "Value".to_string()];
+//! let layout = HeaderLayout::build(&headers, 0, DuplicateHeaderPolicy::Suffix)?;
+//! assert_eq!(layout.keys(), vec!["Name", "Value", "Value_2"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde_json::Value;
+
+/// UTF-8 byte order mark, often left on the first header of CSV-origin sheets.
+const BOM: char = '\u{FEFF}';
+
+/// How to handle two columns sharing the same header text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateHeaderPolicy {
+    /// Collect the values of all same-named columns into an array under one key
+    Merge,
+    /// Refuse to process the sheet, naming the duplicated headers
+    Error,
+    /// Keep every column, renaming repeats to `Name_2`, `Name_3`, ...
+    #[default]
+    Suffix,
+}
+
+impl std::str::FromStr for DuplicateHeaderPolicy {
+    type Err = String;
+
+    /// Parses a policy from "merge", "error" or "suffix" (case-insensitive).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::headers::DuplicateHeaderPolicy;
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(DuplicateHeaderPolicy::from_str("merge"), Ok(DuplicateHeaderPolicy::Merge));
+    /// assert!(DuplicateHeaderPolicy::from_str("drop").is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "merge" => Ok(DuplicateHeaderPolicy::Merge),
+            "error" => Ok(DuplicateHeaderPolicy::Error),
+            "suffix" => Ok(DuplicateHeaderPolicy::Suffix),
+            _ => Err(format!("Unknown duplicate header policy: {}. Use 'merge', 'error' or 'suffix'.", s)),
+        }
+    }
+}
+
+/// The resolved mapping from output keys to spreadsheet columns.
+///
+/// Each key maps to one column, or to several when duplicates are merged.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderLayout {
+    columns: Vec<(String, Vec<usize>)>,
+    /// Repeated headers renamed by [`DuplicateHeaderPolicy::Suffix`], as
+    /// (column, header, key)
+    renamed: Vec<(usize, String, String)>,
+    merged: bool,
+    width: usize,
+}
+
+impl HeaderLayout {
+    /// Builds a layout for the header cells from `start_col` onwards.
+    ///
+    /// Header text is trimmed and a leading byte order mark is removed. Blank
+    /// headers are named `column_N` (1-based spreadsheet column).
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - The full header row
+    /// * `start_col` - First column to include (earlier columns are mapped elsewhere)
+    /// * `policy` - How to resolve duplicate header names
+    ///
+    /// # Errors
+    ///
+    /// With [`DuplicateHeaderPolicy::Error`], returns an error listing every
+    /// header that appears more than once.
+    pub fn build(headers: &[String], start_col: usize, policy: DuplicateHeaderPolicy) -> Result<Self> {
+        let mut columns: IndexMap<String, Vec<usize>> = IndexMap::new();
+        let mut duplicates = Vec::new();
+        let mut renamed = Vec::new();
+
+        for (col_idx, header) in headers.iter().enumerate().skip(start_col) {
+            let name = match header.trim_start_matches(BOM).trim() {
+                "" => format!("column_{}", col_idx + 1),
+                trimmed => trimmed.to_string(),
+            };
+
+            if !columns.contains_key(&name) {
+                columns.insert(name, vec![col_idx]);
+                continue;
+            }
+
+            match policy {
+                DuplicateHeaderPolicy::Merge => columns[&name].push(col_idx),
+                DuplicateHeaderPolicy::Error => {
+                    if !duplicates.contains(&name) {
+                        duplicates.push(name);
+                    }
+                },
+                DuplicateHeaderPolicy::Suffix => {
+                    let mut suffix = 2;
+                    let mut candidate = format!("{}_{}", name, suffix);
+                    while columns.contains_key(&candidate) {
+                        suffix += 1;
+                        candidate = format!("{}_{}", name, suffix);
+                    }
+                    columns.insert(candidate.clone(), vec![col_idx]);
+                    renamed.push((col_idx, name, candidate));
+                },
+            }
+        }
+
+        if !duplicates.is_empty() {
+            anyhow::bail!("Duplicate header(s) found: {}", duplicates.join(", "));
+        }
+
+        Ok(HeaderLayout {
+            columns: columns.into_iter().collect(),
+            renamed,
+            merged: policy == DuplicateHeaderPolicy::Merge,
+            width: headers.len().max(start_col),
+        })
+    }
+
+    /// Returns the output keys in column order.
+    pub fn keys(&self) -> Vec<&str> {
+        self.columns.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    /// Returns a warning listing the repeated headers that were renamed with
+    /// a suffix, or `None` if no header was renamed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::headers::{DuplicateHeaderPolicy, HeaderLayout};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let headers = vec!["Notes".to_string(), "".to_string(), "Notes".to_string()];
+    /// let layout = HeaderLayout::build(&headers, 0, DuplicateHeaderPolicy::Suffix)?;
+    ///
+    /// assert_eq!(layout.keys(), vec!["Notes", "column_2", "Notes_2"]);
+    /// assert_eq!(layout.renamed_warning().as_deref(), Some("Renamed duplicate headers: Notes in column 3 to Notes_2"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn renamed_warning(&self) -> Option<String> {
+        if self.renamed.is_empty() {
+            return None;
+        }
+        let renamed: Vec<String> = self.renamed.iter()
+            .map(|(col, header, key)| format!("{} in column {} to {}", header, col + 1, key))
+            .collect();
+        Some(format!("Renamed duplicate headers: {}", renamed.join(", ")))
+    }
+
+    /// Returns the key of a column, or `None` if the column is not mapped by
+    /// this layout or is merged with other columns under one key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::headers::{DuplicateHeaderPolicy, HeaderLayout};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let headers = vec!["Label".to_string(), "Value".to_string(), "Value".to_string()];
+    /// let layout = HeaderLayout::build(&headers, 0, DuplicateHeaderPolicy::Merge)?;
+    ///
+    /// assert_eq!(layout.key_for_column(0).as_deref(), Some("Label"));
+    /// assert_eq!(layout.key_for_column(1), None);
+    /// assert_eq!(layout.key_for_column(5).as_deref(), Some("column_6"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn key_for_column(&self, col: usize) -> Option<String> {
+        if col >= self.width {
+            return Some(format!("column_{}", col + 1));
+        }
+        self.columns
+            .iter()
+            .find(|(_, cols)| cols.as_slice() == [col])
+            .map(|(key, _)| key.clone())
+    }
+
+    /// Extracts the keyed values for one data row.
+    ///
+    /// Empty cells become `null`, and keys whose columns lie entirely past the
+    /// end of a short row are omitted. Keys backed by several merged columns
+    /// produce an array with one entry per column. Cells beyond the header row
+    /// are named `column_N`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::headers::{DuplicateHeaderPolicy, HeaderLayout};
+    /// use serde_json::json;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let headers = vec!["Value".to_string(), "Value".to_string()];
+    /// let layout = HeaderLayout::build(&headers, 0, DuplicateHeaderPolicy::Merge)?;
+    ///
+    /// let values = layout.extract(&[Some("a".to_string()), None]);
+    /// assert_eq!(values["Value"], json!(["a", null]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract(&self, row: &[Option<String>]) -> IndexMap<String, Value> {
+        let cell = |col: usize| -> Value {
+            row.get(col)
+                .cloned()
+                .flatten()
+                .map(Value::String)
+                .unwrap_or(Value::Null)
+        };
+
+        let mut values: IndexMap<String, Value> = self.columns
+            .iter()
+            .filter(|(_, cols)| cols.iter().any(|&col| col < row.len()))
+            .map(|(key, cols)| {
+                let value = if self.merged && cols.len() > 1 {
+                    Value::Array(cols.iter().map(|&col| cell(col)).collect())
+                } else {
+                    cell(cols[0])
+                };
+                (key.clone(), value)
+            })
+            .collect();
+
+        for col in self.width..row.len() {
+            values.insert(format!("column_{}", col + 1), cell(col));
+        }
+
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn value_headers() -> Vec<String> {
+        ["Label", "Value", "Value"].iter().map(|h| h.to_string()).collect()
+    }
+
+    fn row() -> Vec<Option<String>> {
+        vec![Some("A".to_string()), Some("1".to_string()), Some("2".to_string())]
+    }
+
+    #[test]
+    fn test_suffix_policy() {
+        let layout = HeaderLayout::build(&value_headers(), 0, DuplicateHeaderPolicy::Suffix).unwrap();
+        let values = layout.extract(&row());
+
+        assert_eq!(layout.keys(), vec!["Label", "Value", "Value_2"]);
+        assert_eq!(values["Value"], json!("1"));
+        assert_eq!(values["Value_2"], json!("2"));
+        assert_eq!(layout.renamed_warning().as_deref(), Some("Renamed duplicate headers: Value in column 3 to Value_2"));
+
+        let merged = HeaderLayout::build(&value_headers(), 0, DuplicateHeaderPolicy::Merge).unwrap();
+        assert_eq!(merged.renamed_warning(), None);
+    }
+
+    #[test]
+    fn test_merge_policy() {
+        let layout = HeaderLayout::build(&value_headers(), 0, DuplicateHeaderPolicy::Merge).unwrap();
+        let values = layout.extract(&row());
+
+        assert_eq!(layout.keys(), vec!["Label", "Value"]);
+        assert_eq!(values["Label"], json!("A"));
+        assert_eq!(values["Value"], json!(["1", "2"]));
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let err = HeaderLayout::build(&value_headers(), 0, DuplicateHeaderPolicy::Error).unwrap_err();
+        assert!(err.to_string().contains("Value"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_blank_headers_and_start_column() {
+        let headers: Vec<String> = ["Skip", "", "Notes"].iter().map(|h| h.to_string()).collect();
+        let layout = HeaderLayout::build(&headers, 1, DuplicateHeaderPolicy::Suffix).unwrap();
+
+        assert_eq!(layout.keys(), vec!["column_2", "Notes"]);
+    }
+
+    #[test]
+    fn test_short_and_long_rows() {
+        let headers: Vec<String> = ["A", "B"].iter().map(|h| h.to_string()).collect();
+        let layout = HeaderLayout::build(&headers, 0, DuplicateHeaderPolicy::Suffix).unwrap();
+
+        let short = layout.extract(&[Some("a".to_string())]);
+        assert!(!short.contains_key("B"));
+
+        let long = layout.extract(&[None, None, Some("c".to_string())]);
+        assert_eq!(long["A"], Value::Null);
+        assert_eq!(long["column_3"], json!("c"));
+    }
+
+    #[test]
+    fn test_leading_bom_is_stripped() {
+        let headers: Vec<String> = ["\u{FEFF}main_label", "main_value"].iter().map(|h| h.to_string()).collect();
+        let layout = HeaderLayout::build(&headers, 0, DuplicateHeaderPolicy::Suffix).unwrap();
+
+        assert_eq!(layout.keys(), vec!["main_label", "main_value"]);
+        let values = layout.extract(&[Some("Category".to_string()), None]);
+        assert_eq!(values["main_label"], json!("Category"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Cell hyperlinks for `--with-hyperlinks`.
+//!
+//! calamine returns a hyperlinked cell's display text but not the link
+//! itself, so a catalog cell showing `Widget` loses its canonical URL. This
+//! module reads the links straight from the xlsx package: the worksheet's
+//! `<hyperlinks>` element names the cells, and the worksheet relationships
+//! hold the targets of external links.
+//!
+//! A linked cell is written as its text followed by the URL in angle
+//! brackets, see [`with_url`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::hyperlink::with_url;
+//!
+//! assert_eq!(with_url(Some("Widget"), "https://example.com/widget"), "Widget <https://example.com/widget>");
+//! assert_eq!(with_url(None, "https://example.com/widget"), "https://example.com/widget");
+//! ```
+
+use crate::cell_ref::{CellRange, CellRef};
+use crate::number_format::{attr, read_part, sheet_path};
+use anyhow::{Context, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader as XmlReader;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+/// Hyperlink targets for the cells of one worksheet.
+#[derive(Debug, Clone, Default)]
+pub struct CellHyperlinks {
+    urls: HashMap<(usize, usize), String>,
+}
+
+impl CellHyperlinks {
+    /// Loads the hyperlink of every linked cell in a sheet.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the `.xlsx` file
+    /// * `sheet_name` - Name of the worksheet
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the package cannot be opened, the sheet is not
+    /// listed in the workbook, or a part cannot be parsed.
+    pub fn load<P: AsRef<Path>>(path: P, sheet_name: &str) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Failed to open Excel file: {:?}", path.as_ref()))?;
+        Self::from_reader(BufReader::new(file), sheet_name)
+    }
+
+    /// Loads the hyperlinks of a sheet from an xlsx package held in any
+    /// seekable reader, such as a workbook read into memory.
+    ///
+    /// External links resolve to their relationship target; links to a place
+    /// in the workbook become `#Sheet!A1`. A link on a range of cells applies
+    /// to each of them.
+    ///
+    /// # Errors
+    ///
+    /// As for [`load`](Self::load).
+    pub fn from_reader<R: Read + Seek>(reader: R, sheet_name: &str) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(reader)
+            .context("Failed to read xlsx package")?;
+
+        let sheet_path = sheet_path(&mut archive, sheet_name)?;
+        let sheet_xml = read_part(&mut archive, &sheet_path)?;
+
+        // (cell reference, relationship id, location) of each link
+        let mut links = Vec::new();
+        let mut reader = XmlReader::from_str(&sheet_xml);
+        loop {
+            match reader.read_event().context("Failed to parse worksheet XML")? {
+                Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"hyperlink" => {
+                    if let Some(reference) = attr(&e, b"ref") {
+                        links.push((reference, attr(&e, b"id"), attr(&e, b"location")));
+                    }
+                },
+                Event::Eof => break,
+                _ => {},
+            }
+        }
+        if links.is_empty() {
+            return Ok(CellHyperlinks::default());
+        }
+
+        let targets = match read_part(&mut archive, &relationships_path(&sheet_path)) {
+            Ok(xml) => Self::relationship_targets(&xml)?,
+            Err(_) => HashMap::new(),
+        };
+
+        let mut urls = HashMap::new();
+        for (reference, rel_id, location) in links {
+            let target = rel_id.and_then(|rel_id| targets.get(&rel_id).cloned());
+            let url = match (target, location) {
+                (Some(target), Some(location)) => format!("{}#{}", target, location),
+                (Some(target), None) => target,
+                (None, Some(location)) => format!("#{}", location),
+                (None, None) => continue,
+            };
+            let Some(cells) = parse_cells(&reference) else {
+                continue;
+            };
+            for row in cells.start.row..=cells.end.row {
+                for col in cells.start.col..=cells.end.col {
+                    urls.insert((row, col), url.clone());
+                }
+            }
+        }
+
+        Ok(CellHyperlinks { urls })
+    }
+
+    /// Returns the URL a cell links to (zero-based, absolute position).
+    pub fn get(&self, row: usize, col: usize) -> Option<&str> {
+        self.urls.get(&(row, col)).map(String::as_str)
+    }
+
+    /// Returns the target of each relationship in a `.rels` part, by id.
+    fn relationship_targets(rels: &str) -> Result<HashMap<String, String>> {
+        let mut targets = HashMap::new();
+        let mut reader = XmlReader::from_str(rels);
+        loop {
+            match reader.read_event().context("Failed to parse worksheet relationships")? {
+                Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"Relationship" => {
+                    if let (Some(id), Some(target)) = (attr(&e, b"Id"), attr(&e, b"Target")) {
+                        targets.insert(id, target);
+                    }
+                },
+                Event::Eof => break,
+                _ => {},
+            }
+        }
+        Ok(targets)
+    }
+}
+
+/// Returns the relationships part of a package part:
+/// `xl/worksheets/sheet1.xml` has `xl/worksheets/_rels/sheet1.xml.rels`.
+fn relationships_path(part: &str) -> String {
+    match part.rsplit_once('/') {
+        Some((dir, name)) => format!("{}/_rels/{}.rels", dir, name),
+        None => format!("_rels/{}.rels", part),
+    }
+}
+
+/// Parses a hyperlink's `ref`, a single cell (`B2`) or a range (`B2:C4`).
+fn parse_cells(reference: &str) -> Option<CellRange> {
+    if reference.contains(':') {
+        CellRange::parse(reference).ok()
+    } else {
+        CellRef::parse(reference).ok().map(|cell| CellRange { start: cell, end: cell })
+    }
+}
+
+/// Combines a linked cell's text with its URL as `text <url>`.
+///
+/// A cell without text, or whose text is the URL itself, is written as the
+/// bare URL.
+pub fn with_url(text: Option<&str>, url: &str) -> String {
+    match text {
+        Some(text) if !text.is_empty() && text != url => format!("{} <{}>", text, url),
+        _ => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hyperlinks_resolve_external_and_internal_targets() {
+        use rust_xlsxwriter::{Url, Workbook};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("links.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Catalog").unwrap();
+        sheet.write_string(0, 0, "Product").unwrap();
+        sheet.write_url(1, 0, Url::new("https://example.com/widget").set_text("Widget")).unwrap();
+        sheet.write_url(2, 0, Url::new("internal:Catalog!A1").set_text("Top")).unwrap();
+        sheet.write_string(3, 0, "Plain").unwrap();
+        workbook.save(&path).unwrap();
+
+        let links = CellHyperlinks::load(&path, "Catalog").unwrap();
+        assert_eq!(links.get(1, 0), Some("https://example.com/widget"));
+        assert_eq!(links.get(2, 0), Some("#Catalog!A1"));
+        assert_eq!(links.get(3, 0), None);
+        assert_eq!(relationships_path("xl/worksheets/sheet1.xml"), "xl/worksheets/_rels/sheet1.xml.rels");
+        assert_eq!(with_url(Some("https://a.example"), "https://a.example"), "https://a.example");
+    }
+}
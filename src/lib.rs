@@ -1,4 +1,44 @@
+//! Excel to JSON export library.
+//!
+//! The library behind the `excel-to-json` command-line tool. It reads Excel
+//! and OpenDocument workbooks (evaluating uncached VLOOKUP formulas), cleans
+//! and validates the rows, and renders the records as JSON, CSV, TSV, PHP, SQL,
+//! XML, YAML, Markdown or Parquet.
+//!
+//! [`process_file`] runs the same pipeline as the tool for one workbook; the
+//! modules expose each step for finer control.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::output::{OutputFormat, OutputFormatter};
+//! use excel_to_json::{process_file, ProcessOptions};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let result = process_file("resources/Cascade Sample.xlsx", &ProcessOptions::default())?;
+//! let json = OutputFormatter::format_output(&result, OutputFormat::Json)?;
+//! assert!(json.contains("\"success\": true"));
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod cell_ref;
+pub mod config;
+pub mod hash;
+pub mod headers;
+pub mod hyperlink;
+pub mod memory;
 pub mod models;
+pub mod number_format;
 pub mod processor;
 pub mod excel_reader;
 pub mod output;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod pipeline;
+pub mod profile;
+pub mod progress;
+pub mod schema;
+pub mod tree;
+
+pub use pipeline::{process_file, ProcessOptions};
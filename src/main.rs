@@ -30,18 +30,24 @@
 //! excel-to-json data.xlsx --summary
 //! ```
 
-mod excel_reader;
-mod models;
-mod output;
-mod processor;
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::builder::PossibleValuesParser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use excel_to_json::pipeline::{
+    add_sheet_metadata, compile_sheet_glob, estimate_input_bytes, get_available_sheets,
+    process_excel_file_multiple_sheets, select_sheets, stream_excel_file_multiple_sheets, StreamOptions,
+};
+use excel_to_json::{cell_ref, config, excel_reader, hash, headers, memory, models, output, processor, profile, progress, schema};
+#[cfg(feature = "parquet")]
+use excel_to_json::parquet;
 use models::{ErrorDetails, ProcessingMetadata, ProcessingResult};
-use output::{OutputFormat, OutputFormatter};
-use std::path::Path;
-use tracing::{error, info};
-use tracing_subscriber;
+use output::{OutputFormat, OutputFormatter, OutputOptions};
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
 
 /// Command-line arguments for the excel-to-json tool.
 ///
@@ -62,22 +68,53 @@ use tracing_subscriber;
 #[command(name = "excel-to-json")]
 #[command(about = "Export Excel spreadsheet data to JSON format", long_about = None)]
 struct Args {
-    /// Path to the Excel file to import
-    input_file: String,
+    /// Paths to the Excel files to import, or - to read one from stdin
+    ///
+    /// Input from stdin is buffered in memory before it is read, since xlsx
+    /// and ods files are zip archives that cannot be read as a stream. With
+    /// several files, each sheet is tagged with the file it came from and a
+    /// file that cannot be processed is reported without stopping the others.
+    #[arg(required_unless_present = "emit_schema")]
+    input_file: Vec<String>,
 
     /// Sheet name to process (defaults to first sheet if not specified)
-    /// Can be specified multiple times for multiple sheets
+    /// Can be specified multiple times for multiple sheets. Without an exact
+    /// match, case and surrounding spaces are ignored
     #[arg(short = 's', long)]
     sheet: Vec<String>,
 
     /// Process all sheets in the workbook
-    #[arg(short = 'a', long, conflicts_with = "sheet")]
+    #[arg(short = 'a', long, conflicts_with_all = ["sheet", "sheet_glob"])]
     all_sheets: bool,
 
+    /// Process the sheet at this 0-based position in the workbook, e.g. 1 for
+    /// the second sheet, whatever its name
+    #[arg(long, value_name = "N", conflicts_with_all = ["sheet", "all_sheets", "sheet_glob"])]
+    sheet_index: Option<usize>,
+
+    /// Also process every sheet whose name matches this glob pattern, e.g.
+    /// 'Data_*' (* any text, ? one character, [...] one of a set); repeatable
+    #[arg(long, value_name = "PATTERN", value_parser = parse_sheet_glob)]
+    sheet_glob: Vec<String>,
+
+    /// When a --sheet does not exist, process the sheet whose name is closest
+    /// to it instead (a few typos or a different case) rather than failing
+    #[arg(long, requires = "sheet")]
+    fuzzy_sheet: bool,
+
     /// Enable verbose logging
     #[arg(short = 'v', long)]
     verbose: bool,
 
+    /// Only log errors to stderr; takes precedence over --verbose
+    #[arg(short = 'q', long)]
+    quiet: bool,
+
+    /// Read default options from this TOML file instead of
+    /// ./excel-to-json.toml; options on the command line take precedence
+    #[arg(long, value_name = "PATH")]
+    config: Option<String>,
+
     /// Output file path (if not specified, outputs to stdout)
     #[arg(short = 'f', long)]
     file: Option<String>,
@@ -85,6 +122,538 @@ struct Args {
     /// Show summary instead of full output
     #[arg(long)]
     summary: bool,
+
+    /// Also write the human-readable summary to this file, or to stderr with
+    /// -, next to the data output
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["summary", "validate", "emit_schema"])]
+    summary_file: Option<String>,
+
+    /// Print a profile of each sheet's fields (non-null and distinct counts,
+    /// shortest and longest value, samples) instead of the records, as a
+    /// table (the default) or json
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "table", conflicts_with_all = ["generic", "stream", "zip", "split_output", "summary", "validate"])]
+    profile: Option<profile::ProfileFormat>,
+
+    /// Check the workbook without writing any data: print the summary and exit
+    /// with code 4 if any record is invalid
+    #[arg(long, conflicts_with_all = ["file", "zip", "split_output", "stream", "emit_schema"])]
+    validate: bool,
+
+    /// With --validate, also fail (exit code 4) when processing produced warnings
+    #[arg(long, requires = "validate")]
+    strict: bool,
+
+    /// Output format (json, csv, tsv, php, nested, sql, xml, yaml, markdown or parquet);
+    /// parquet is binary, needs --file and a build with the parquet feature
+    #[arg(short = 'F', long, default_value = "json")]
+    format: OutputFormat,
+
+    /// Field delimiter for CSV output: a single character (e.g. ';' or '|')
+    /// or 'tab'
+    #[arg(long, value_name = "CHAR", default_value_t = ',', value_parser = parse_delimiter)]
+    delimiter: char,
+
+    /// Start CSV output with a UTF-8 byte order mark, so Excel on Windows
+    /// opens it as UTF-8
+    #[arg(long)]
+    csv_bom: bool,
+
+    /// End CSV lines with CRLF (\r\n) instead of LF
+    #[arg(long)]
+    crlf: bool,
+
+    /// Fail TSV output on a value holding a tab or line break instead of
+    /// replacing those with spaces
+    #[arg(long)]
+    tsv_strict: bool,
+
+    /// Table name for SQL output (may be schema-qualified, e.g. app.fields)
+    #[arg(long, value_name = "NAME", default_value = "cascade_fields")]
+    table: String,
+
+    /// Rows per INSERT statement in SQL output
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    batch_size: u32,
+
+    /// Leave empty fields out of XML records (by default they are written as
+    /// empty elements)
+    #[arg(long)]
+    xml_omit_null: bool,
+
+    /// Emit missing fields of PHP array output as null instead of empty
+    /// strings
+    #[arg(long)]
+    php_keep_null: bool,
+
+    /// Also list row diagnostics as "Row N: message" strings in the JSON
+    /// metadata warnings, as earlier versions did
+    #[arg(long)]
+    legacy_warnings: bool,
+
+    /// Add the spreadsheet row each record was read from as `source_row`
+    /// (JSON, YAML and CSV output)
+    #[arg(long, conflicts_with = "generic")]
+    with_row_numbers: bool,
+
+    /// Report each sheet's header row as read in its metadata's
+    /// source_headers, to check the columns line up with the fields
+    #[arg(long)]
+    echo_headers: bool,
+
+    /// Print the JSON Schema (json-schema) or TypeScript declaration
+    /// (typescript) of the output for the chosen --format and options,
+    /// instead of processing any file
+    #[arg(long, value_name = "LANG", num_args = 0..=1, default_missing_value = "json-schema", conflicts_with = "stream")]
+    emit_schema: Option<schema::SchemaFormat>,
+
+    /// Sheets that count toward the aggregate metadata (comma-separated),
+    /// matched like --sheet; each must be a selected sheet. All selected
+    /// sheets are still output; defaults to every selected sheet
+    #[arg(long, value_delimiter = ',')]
+    metadata_sheets: Vec<String>,
+
+    /// Default value for a field left empty, as FIELD=VALUE (repeatable).
+    /// Applied before validation, so a default main_value makes rows valid;
+    /// with --generic, FIELD is a header and defaults for other names are
+    /// reported, not added
+    #[arg(long = "default", value_name = "FIELD=VALUE", value_parser = parse_default)]
+    defaults: Vec<(String, String)>,
+
+    /// Hybrid schema: map the first N columns positionally onto the cascade
+    /// fields and output the remaining columns keyed by their header text
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=12))]
+    positional_columns: Option<u8>,
+
+    /// Number of label/value/description column triples per row (default 4,
+    /// i.e. main/sub/major/minor). Levels past 4 are output as levelN_* keys
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..), conflicts_with = "positional_columns")]
+    levels: Option<u8>,
+
+    /// Generic mode: output every column keyed by its header text instead of
+    /// the fixed cascade field schema
+    #[arg(long, conflicts_with_all = ["positional_columns", "levels"])]
+    generic: bool,
+
+    /// Read cascade fields from these columns instead of the canonical order,
+    /// e.g. main_value=B,sub_value=D,main_label=A; unmapped fields are empty
+    #[arg(long = "map", value_name = "FIELD=COLUMN,...", value_parser = parse_column_map, conflicts_with_all = ["positional_columns", "levels", "generic"])]
+    column_map: Option<models::ColumnMap>,
+
+    /// Keep numeric and boolean cells as JSON numbers and booleans instead of
+    /// strings (JSON output only; CSV stays text)
+    #[arg(long)]
+    preserve_types: bool,
+
+    /// Guarantee ASCII-only output: 'transliterate' (default) replaces
+    /// non-ASCII characters, 'fail' reports the offending rows and fields
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "transliterate")]
+    ascii_only: Option<processor::AsciiMode>,
+
+    /// Periodically write {rows_done, total, sheet} JSON to this file so
+    /// another process can monitor progress
+    #[arg(long, value_name = "PATH")]
+    progress_to: Option<String>,
+
+    /// Print row counts every 10,000 rows and an N/M sheets indicator to
+    /// stderr: 'auto' (default) only when stderr is a terminal, 'always'
+    /// even when it is redirected
+    #[arg(long, value_name = "WHEN", num_args = 0..=1, default_missing_value = "auto")]
+    progress: Option<progress::ProgressMode>,
+
+    /// Emit multi-sheet data as an object keyed by sheet name instead of an array
+    #[arg(long)]
+    sheets_as_object: bool,
+
+    /// Emit the records of all sheets as one array, each with a "sheet" field,
+    /// instead of a {sheet, rows} object per sheet (JSON and YAML only)
+    #[arg(long, conflicts_with_all = ["sheets_as_object", "group_by", "stream"])]
+    flatten: bool,
+
+    /// Key holding the sheets in JSON, nested and YAML output and in PHP
+    /// array errors
+    #[arg(long, value_name = "KEY", default_value = output::DEFAULT_ROOT_KEY, value_parser = output::parse_root_key)]
+    root_key: String,
+
+    /// Emit each sheet's records as an object keyed by this field's values,
+    /// e.g. {"CAT001": [...], "CAT002": [...]} (JSON and YAML only)
+    #[arg(long, value_name = "FIELD", value_parser = PossibleValuesParser::new(models::CascadeField::FIELD_NAMES), conflicts_with_all = ["generic", "stream"])]
+    group_by: Option<String>,
+
+    /// Output only these fields, in this order (comma-separated or repeated),
+    /// e.g. main_value,minor_value (JSON, YAML, CSV and TSV only)
+    #[arg(long, value_name = "FIELD", value_delimiter = ',', value_parser = PossibleValuesParser::new(models::CascadeField::FIELD_NAMES), conflicts_with_all = ["generic", "stream"])]
+    columns: Option<Vec<String>>,
+
+    /// Key grouping the records without a value for the --group-by field
+    #[arg(long, value_name = "KEY", default_value = "", requires = "group_by")]
+    group_null_key: String,
+
+    /// How header-keyed columns handle repeated headers: 'suffix' renames
+    /// repeats to Name_2, Name_3; 'merge' collects values into an array;
+    /// 'error' rejects the sheet
+    #[arg(long, value_name = "POLICY", default_value = "suffix")]
+    duplicate_headers: headers::DuplicateHeaderPolicy,
+
+    /// Also write the processing metadata in Prometheus text format to this
+    /// file, for node_exporter's textfile collector
+    #[arg(long, value_name = "PATH")]
+    metrics_file: Option<String>,
+
+    /// Print a SHA-256 hash of the bytes written to --file or stdout to
+    /// stderr (and add it to the --manifest); processing times are written
+    /// as 0 so that unchanged input gives the same hash
+    #[arg(long, conflicts_with_all = ["stream", "summary", "validate", "profile", "emit_schema", "zip", "split_output", "append"])]
+    hash: bool,
+
+    /// Write only what this query expression selects from the JSON output,
+    /// e.g. 'data[].rows[].main_value'; JSON and nested formats only
+    ///
+    /// The expression is JMESPath (https://jmespath.org/specification.html),
+    /// with all of its operators and built-in functions.
+    #[arg(long, value_name = "EXPRESSION", value_parser = parse_query,
+          conflicts_with_all = ["stream", "summary", "validate", "profile", "zip", "split_output", "append", "hash", "emit_schema"])]
+    query: Option<jmespath::Expression<'static>>,
+
+    /// After the run, write a JSON manifest to this file: the inputs, each
+    /// sheet's record counts, the output files written and the timing
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["stream", "emit_schema"])]
+    manifest: Option<String>,
+
+    /// JSON file of per-field value normalization tables, e.g.
+    /// {"main_label": {"USA": "United States"}}
+    #[arg(long, value_name = "FILE")]
+    value_map: Option<String>,
+
+    /// Drop rows whose field matches a regex, as FIELD=REGEX (repeatable),
+    /// e.g. main_label=^(Sub)?Total; counted as skipped, not invalid
+    #[arg(long, value_name = "FIELD=REGEX", value_parser = parse_skip_rule)]
+    skip_rows_matching: Vec<(String, regex::Regex)>,
+
+    /// Keep only rows matching FIELD=VALUE or FIELD!=VALUE predicates
+    /// (comma-separated or repeated, all must match); counted as filtered
+    #[arg(long = "filter", value_name = "PREDICATE", value_delimiter = ',', value_parser = parse_row_filter, conflicts_with = "generic")]
+    filters: Vec<processor::RowFilter>,
+
+    /// Change the case of a field's values: FIELD=upper, lower, title or trim
+    /// (comma-separated or repeated), e.g. main_value=upper,sub_value=upper
+    #[arg(long = "transform", value_name = "FIELD=TRANSFORM", value_delimiter = ',', value_parser = parse_field_transform, conflicts_with = "generic")]
+    transforms: Vec<(String, processor::Transform)>,
+
+    /// Read numbers as displayed in Excel by applying each cell's number
+    /// format (e.g. 0.5 formatted as a percentage becomes "50%")
+    #[arg(long)]
+    use_display_format: bool,
+
+    /// Keep the numbers of these columns (header texts or cascade field
+    /// names) as text, as their number format displays them, so a part number
+    /// 123 formatted 00000 stays "00123"; comma-separated or repeated
+    #[arg(long, value_name = "COLUMN,...", value_delimiter = ',')]
+    text_columns: Vec<String>,
+
+    /// Write hyperlinked cells as their text followed by the link target,
+    /// e.g. "Widget <https://example.com/widget>" (xlsx only)
+    #[arg(long)]
+    with_hyperlinks: bool,
+
+    /// JSON layout with one compact record per line inside a pretty-printed
+    /// envelope; easier to diff than pretty output, easier to read than compact
+    #[arg(long)]
+    line_per_record: bool,
+
+    /// Emit minified JSON (no indentation or newlines) for piping into other tools
+    #[arg(long, conflicts_with = "line_per_record")]
+    compact: bool,
+
+    /// Spreadsheet row (1-based) holding the headers; rows above it are ignored
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    header_row: Option<u32>,
+
+    /// Ignore this many leading rows; the next row holds the headers
+    #[arg(long, value_name = "N", conflicts_with = "header_row")]
+    skip_rows: Option<u32>,
+
+    /// Header texts (comma-separated) that identify the header row among the
+    /// first 10 rows, for sheets with a variable preamble; the first row is
+    /// used, with a warning, when no row has them all
+    #[arg(long, value_name = "NAMES", value_delimiter = ',', conflicts_with_all = ["header_row", "skip_rows", "no_header", "range"])]
+    expect_headers: Vec<String>,
+
+    /// The sheet has no header row: the first row is data too. Takes
+    /// precedence over --header-row and --skip-rows
+    #[arg(long)]
+    no_header: bool,
+
+    /// Only read this block of cells (e.g. A2:F100); its first row holds the
+    /// headers unless --no-header is set
+    #[arg(long, value_name = "RANGE", value_parser = parse_range, conflicts_with_all = ["header_row", "skip_rows"])]
+    range: Option<cell_ref::CellRange>,
+
+    /// Fill merged cells with the value of the region's top-left cell (xlsx only)
+    #[arg(long)]
+    fill_merged: bool,
+
+    /// Write one file per sheet (in --format) into this zip archive instead
+    /// of a single output
+    #[arg(long, value_name = "PATH", conflicts_with = "file")]
+    zip: Option<String>,
+
+    /// Write one file per sheet (in --format) into this directory, plus the
+    /// run's metadata as _summary.json
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["file", "zip"])]
+    split_output: Option<String>,
+
+    /// Create the --split-output directory if it does not exist
+    #[arg(long, requires = "split_output")]
+    mkdir: bool,
+
+    /// Fields that hold dates (comma-separated), for --parse-text-dates
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',')]
+    date_columns: Vec<String>,
+
+    /// Parse text in --date-columns with this chrono format (e.g. %m/%d/%Y)
+    /// and emit ISO-8601; unparseable values are kept and reported
+    #[arg(long, value_name = "FORMAT", requires = "date_columns")]
+    parse_text_dates: Option<String>,
+
+    /// Write date cells with this strftime pattern (e.g. %d/%m/%Y) instead of
+    /// ISO-8601; durations keep their hours:mm:ss form
+    #[arg(long, value_name = "FORMAT", value_parser = parse_date_format)]
+    date_format: Option<String>,
+
+    /// Round non-integer numbers to N decimals (e.g. 0.30000000000000004 to
+    /// 0.30 with 2); not applied to numbers kept by --preserve-types
+    #[arg(long, value_name = "N")]
+    float_precision: Option<usize>,
+
+    /// Decimal separator of non-integer numbers, e.g. ',' for German locales;
+    /// not applied to numbers kept by --preserve-types
+    #[arg(long, value_name = "CHAR", value_parser = parse_decimal_separator)]
+    decimal_separator: Option<char>,
+
+    /// Soft memory budget in MB for the in-memory record set; JSON output
+    /// that could be written with --stream fails when the estimated size
+    /// exceeds it, other outputs report a warning
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_memory_mb: Option<u64>,
+
+    /// Write records as newline-delimited JSON while the sheets are read,
+    /// instead of buffering the whole result (JSON only)
+    #[arg(long, conflicts_with_all = ["summary", "zip", "split_output", "line_per_record", "sheets_as_object"])]
+    stream: bool,
+
+    /// Add records to the --file instead of overwriting it: lines are
+    /// appended with --stream, and a JSON document is read back and its
+    /// records array extended (JSON only)
+    #[arg(long, requires = "file", conflicts_with_all = ["summary", "validate", "profile", "zip", "split_output", "sheets_as_object"])]
+    append: bool,
+
+    /// Drop records whose main/sub/major/minor values repeat an earlier
+    /// record, keeping the first occurrence
+    #[arg(long, conflicts_with_all = ["generic", "stream"])]
+    dedupe: bool,
+
+    /// Keep only records with all of main/sub/major/minor values; the others
+    /// are counted as filtered, not invalid
+    #[arg(long, conflicts_with_all = ["generic", "stream"])]
+    complete_only: bool,
+
+    /// Pad rows missing trailing columns (such as a blank minor_description
+    /// Excel left out) with empty cells instead of rejecting them as
+    /// malformed; rows ending before a required field are still rejected
+    #[arg(long, conflicts_with = "generic")]
+    pad_rows: bool,
+
+    /// Skip data rows identical to the header row, such as the header
+    /// repeated partway down a sheet of concatenated exports; counted as
+    /// skipped and listed in a warning
+    #[arg(long)]
+    dedupe_headers: bool,
+
+    /// Keep at most N row warnings per sheet; the rest are only counted and
+    /// reported as "... and K more warnings suppressed"
+    #[arg(long, value_name = "N", default_value_t = 5000)]
+    max_warnings: usize,
+
+    /// Fail the run (exit code 1) when a record repeats the main/sub/major/minor
+    /// values of an earlier record, reporting both rows
+    #[arg(long, conflicts_with_all = ["generic", "dedupe"])]
+    strict_unique: bool,
+
+    /// Stop at the first invalid or malformed row and fail the run (exit
+    /// code 1), reporting its row number, instead of counting invalid rows
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Sort each sheet's records by this field; records without a value come
+    /// last and ties keep the spreadsheet order. Needs the records in memory,
+    /// so it cannot be combined with --stream
+    #[arg(long, value_name = "FIELD", value_parser = PossibleValuesParser::new(models::CascadeField::FIELD_NAMES), conflicts_with_all = ["generic", "stream"])]
+    sort_by: Option<String>,
+
+    /// Sort in descending order with --sort-by
+    #[arg(long, requires = "sort_by")]
+    sort_desc: bool,
+
+    /// Keep fully empty rows below the header, counted as invalid, so records
+    /// line up with the sheet's rows; --offset and --limit then count them too
+    #[arg(long)]
+    keep_empty_rows: bool,
+
+    /// Keep columns that are empty in every row at the right edge of each
+    /// sheet instead of ending rows at the last column holding a value
+    #[arg(long)]
+    no_trim_columns: bool,
+
+    /// Stop reading each sheet at the first fully empty row below the header,
+    /// ignoring everything further down
+    #[arg(long, conflicts_with = "keep_empty_rows")]
+    stop_at_blank: bool,
+
+    /// Ignore the last N rows of each sheet, such as a totals row
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    footer_rows: usize,
+
+    /// Fail on the first data cell holding an Excel error such as #REF! or
+    /// #DIV/0! instead of reporting it as a warning
+    #[arg(long)]
+    fail_on_cell_error: bool,
+
+    /// Fields a record needs a value in to be valid (comma-separated);
+    /// defaults to main_value. Each missing field is reported per row
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',', value_parser = PossibleValuesParser::new(models::CascadeField::FIELD_NAMES), conflicts_with = "generic")]
+    require: Vec<String>,
+
+    /// Read at most N data rows from each sheet (after --offset)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    limit: Option<u64>,
+
+    /// Skip the first N data rows (below the header) of each sheet
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    offset: usize,
+}
+
+/// Clap value parser for `--skip-rows-matching FIELD=REGEX` arguments.
+fn parse_skip_rule(spec: &str) -> Result<(String, regex::Regex), String> {
+    processor::parse_skip_rule(spec).map_err(|e| format!("{:#}", e))
+}
+
+/// Clap value parser for `--filter FIELD=VALUE` and `FIELD!=VALUE` predicates.
+fn parse_row_filter(spec: &str) -> Result<processor::RowFilter, String> {
+    processor::parse_row_filter(spec).map_err(|e| e.to_string())
+}
+
+/// Clap value parser for `--transform FIELD=TRANSFORM` pairs.
+fn parse_field_transform(spec: &str) -> Result<(String, processor::Transform), String> {
+    processor::parse_field_transform(spec).map_err(|e| e.to_string())
+}
+
+/// Clap value parser for `--sheet-glob` patterns.
+fn parse_sheet_glob(spec: &str) -> Result<String, String> {
+    compile_sheet_glob(spec).map(|_| spec.to_string()).map_err(|e| format!("{:#}", e))
+}
+
+/// Clap value parser for `--query` expressions.
+fn parse_query(expression: &str) -> Result<jmespath::Expression<'static>, String> {
+    jmespath::compile(expression)
+        .with_context(|| format!("Invalid query '{}'", expression))
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// Clap value parser for `--decimal-separator` characters.
+fn parse_decimal_separator(spec: &str) -> Result<char, String> {
+    let mut chars = spec.chars();
+    match (chars.next(), chars.next()) {
+        (Some(separator), None) if !separator.is_ascii_digit() && separator != '-' => Ok(separator),
+        _ => Err(format!("Invalid decimal separator '{}': expected a single character other than a digit or '-'", spec)),
+    }
+}
+
+/// Clap value parser for `--date-format` patterns.
+fn parse_date_format(spec: &str) -> Result<String, String> {
+    excel_reader::check_date_format(spec).map(|()| spec.to_string()).map_err(|e| e.to_string())
+}
+
+/// Clap value parser for `--delimiter` arguments.
+fn parse_delimiter(spec: &str) -> Result<char, String> {
+    output::parse_delimiter(spec).map_err(|e| e.to_string())
+}
+
+/// Clap value parser for `--range` arguments.
+fn parse_range(spec: &str) -> Result<cell_ref::CellRange, String> {
+    cell_ref::CellRange::parse(spec).map_err(|e| format!("{:#}", e))
+}
+
+/// Clap value parser for `--map FIELD=COLUMN,...` arguments.
+fn parse_column_map(spec: &str) -> Result<models::ColumnMap, String> {
+    models::ColumnMap::parse(spec).map_err(|e| e.to_string())
+}
+
+/// Clap value parser for `--default FIELD=VALUE` arguments.
+fn parse_default(spec: &str) -> Result<(String, String), String> {
+    processor::parse_field_assignment(spec).map_err(|e| e.to_string())
+}
+
+/// Parses the command line, filling in options from the config file.
+///
+/// The config file is the one given with `--config`, or `excel-to-json.toml`
+/// in the working directory if it exists (see [`config`]). Its options are
+/// added to the command line unless the option, or one it conflicts with, is
+/// already there, so the precedence is: command line, then config file, then
+/// the built-in defaults.
+///
+/// # Errors
+///
+/// Returns a clap error for invalid arguments, an unreadable or invalid
+/// config file, and config keys that are not options.
+fn parse_args<I, T>(argv: I) -> Result<Args, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+    let mut command = Args::command();
+    let matches = command.try_get_matches_from_mut(&argv)?;
+    let config_path = match matches.get_one::<String>("config") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => config::ConfigFile::find(Path::new(".")),
+    };
+    let Some(config_path) = config_path else {
+        return Args::from_arg_matches(&matches);
+    };
+    let config_file = config::ConfigFile::load(&config_path)
+        .map_err(|e| command.error(clap::error::ErrorKind::Io, format!("{:#}", e)))?;
+
+    let given: Vec<&clap::Arg> = command.get_arguments()
+        .filter(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .collect();
+    let mut merged = argv.clone();
+    for (key, value) in &config_file.options {
+        let arg = command.get_arguments()
+            .find(|arg| arg.get_long() == Some(key.as_str()) && !matches!(key.as_str(), "config" | "help"))
+            .ok_or_else(|| Args::command().error(
+                clap::error::ErrorKind::UnknownArgument,
+                format!("Unknown option '{}' in config file {}", key, config_path.display()),
+            ))?;
+        let overridden = given.iter()
+            .any(|given| {
+                given.get_id() == arg.get_id()
+                    || command.get_arg_conflicts_with(given).contains(&arg)
+                    || command.get_arg_conflicts_with(arg).contains(given)
+            });
+        if overridden {
+            continue;
+        }
+        match value {
+            config::ConfigValue::Flag(true) => merged.push(format!("--{}", key).into()),
+            config::ConfigValue::Flag(false) => {},
+            config::ConfigValue::Values(values) => {
+                merged.extend(values.iter().map(|value| format!("--{}={}", key, value).into()));
+            },
+        }
+    }
+
+    let matches = command.try_get_matches_from_mut(merged)?;
+    Args::from_arg_matches(&matches)
 }
 
 /// Main entry point for the excel-to-json tool.
@@ -94,7 +663,7 @@ struct Args {
 ///
 /// # Process Flow
 ///
-/// 1. Parse command-line arguments
+/// 1. Parse command-line arguments, with defaults from the config file
 /// 2. Initialize logging (to stderr)
 /// 3. Execute main processing logic
 /// 4. Handle and report any errors
@@ -102,28 +671,114 @@ struct Args {
 /// # Exit Codes
 ///
 /// - `0` - Success
-/// - `1` - Error occurred during processing
+/// - `1` - Invalid arguments, or any other error during processing
+/// - `2` - An input file does not exist
+/// - `3` - A requested sheet is not in the workbook
+/// - `4` - `--validate` found invalid records (or warnings, with `--strict`)
 fn main() {
-    // Parse command-line arguments
-    let args = Args::parse();
+    // Parse command-line arguments; usage errors exit with 1, as code 2 (clap's
+    // default) means a missing file
+    let args = parse_args(std::env::args_os()).unwrap_or_else(|e| {
+        if !e.use_stderr() {
+            e.exit();
+        }
+        let _ = e.print();
+        std::process::exit(ExitStatus::Internal.code());
+    });
 
     // Initialize logging
-    let log_level = if args.verbose {
-        tracing::Level::DEBUG
-    } else {
-        tracing::Level::INFO
-    };
-
     tracing_subscriber::fmt()
-        .with_max_level(log_level)
+        .with_max_level(log_level(&args))
         .with_target(false)
         .with_writer(std::io::stderr) // Log to stderr so stdout is clean for output
         .init();
 
-    // Run the main processing and handle any errors
-    if let Err(e) = run(args) {
-        error!("Fatal error: {:#}", e);
-        std::process::exit(1);
+    // Run the main processing and map its outcome to the exit code
+    let status = match run(args) {
+        Ok(status) => status,
+        Err(e) => {
+            match e.downcast_ref::<ValidationFailed>() {
+                Some(failure) => error!("{}", failure),
+                None => error!("Fatal error: {:#}", e),
+            }
+            ExitStatus::of_error(&e)
+        },
+    };
+    std::process::exit(status.code());
+}
+
+/// How a run ended; `main` exits with its [`code`](ExitStatus::code).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitStatus {
+    /// 0: every input was processed
+    Success,
+    /// 1: an unexpected error, such as an unreadable workbook or an output
+    /// that could not be written
+    Internal,
+    /// 2: an input file does not exist
+    FileNotFound,
+    /// 3: a requested sheet is not in its workbook
+    SheetNotFound,
+    /// 4: `--validate` found invalid records (or warnings, with `--strict`)
+    ValidationFailed,
+}
+
+impl ExitStatus {
+    /// The process exit code.
+    fn code(self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::Internal => 1,
+            ExitStatus::FileNotFound => 2,
+            ExitStatus::SheetNotFound => 3,
+            ExitStatus::ValidationFailed => 4,
+        }
+    }
+
+    /// The status of a run whose output reports `file_error`.
+    fn of_failure(file_error: &models::FileError) -> Self {
+        match file_error.kind {
+            models::FailureKind::FileNotFound => ExitStatus::FileNotFound,
+            models::FailureKind::SheetNotFound => ExitStatus::SheetNotFound,
+            models::FailureKind::Other => ExitStatus::Internal,
+        }
+    }
+
+    /// The status of a run that failed with `error` instead of writing output.
+    fn of_error(error: &anyhow::Error) -> Self {
+        if error.downcast_ref::<ValidationFailed>().is_some() {
+            return ExitStatus::ValidationFailed;
+        }
+        match failure_kind(error) {
+            models::FailureKind::FileNotFound => ExitStatus::FileNotFound,
+            models::FailureKind::SheetNotFound => ExitStatus::SheetNotFound,
+            models::FailureKind::Other => ExitStatus::Internal,
+        }
+    }
+}
+
+/// Categorizes an input failure by the errors in `error`'s chain.
+fn failure_kind(error: &anyhow::Error) -> models::FailureKind {
+    for cause in error.chain() {
+        if cause.downcast_ref::<excel_reader::ReaderError>().is_some() {
+            return models::FailureKind::SheetNotFound;
+        }
+        if cause.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) {
+            return models::FailureKind::FileNotFound;
+        }
+    }
+    models::FailureKind::Other
+}
+
+/// Returns the most detailed level logged: ERROR with `--quiet`, DEBUG with
+/// `--verbose`, INFO otherwise.
+fn log_level(args: &Args) -> tracing::Level {
+    if args.quiet {
+        tracing::Level::ERROR
+    } else if args.verbose {
+        tracing::Level::DEBUG
+    } else {
+        tracing::Level::INFO
     }
 }
 
@@ -138,8 +793,11 @@ fn main() {
 ///
 /// # Returns
 ///
-/// * `Ok(())` - Processing completed successfully
-/// * `Err` - If any step in the process fails
+/// * `Ok(status)` - The output was written; `status` is
+///   [`ExitStatus::Success`], or the failure of the first input file that
+///   could not be processed (whose error the output reports)
+/// * `Err` - If any step in the process fails, or with [`ValidationFailed`]
+///   when `--validate` finds problems
 ///
 /// # Process Steps
 ///
@@ -154,336 +812,642 @@ fn main() {
 /// ```json
 /// {
 ///   "success": true,
-///   "records": [
+///   "data": [
 ///     {
-///       "main_label": "Category",
-///       "main_value": "CAT001",
-///       "main_description": "Main category",
-///       // ... other fields
+///       "sheet": "Cascade Fields",
+///       "rows": [
+///         {
+///           "main_label": "Category",
+///           "main_value": "CAT001",
+///           "main_description": "Main category",
+///           // ... other fields
+///         }
+///       ]
 ///     }
 ///   ],
 ///   "metadata": {
 ///     "total_rows_processed": 100,
 ///     "valid_records": 95,
 ///     "invalid_records": 5,
+///     "malformed_rows": 0,
+///     "skipped_records": 0,
+///     "duplicate_records": 0,
+///     "filtered_records": 0,
 ///     "processing_time_ms": 150
 ///   }
 /// }
 /// ```
-fn run(args: Args) -> Result<()> {
+fn run(args: Args) -> Result<ExitStatus> {
     let start_time = std::time::Instant::now();
     
     info!("Starting excel-to-json");
-    info!("Input file: {}", args.input_file);
+    info!("Input files: {}", args.input_file.join(", "));
     
-    // Determine which sheets to process
-    let sheets_to_process = if args.all_sheets {
-        info!("Processing all sheets");
-        // Get all sheet names from the file
-        let reader = excel_reader::ExcelReader::new(&args.input_file, String::new())
-            .context("Failed to open Excel file")?;
-        reader.get_sheet_names()
-    } else if !args.sheet.is_empty() {
-        info!("Processing sheets: {:?}", args.sheet);
-        args.sheet
-    } else {
-        // Default to first sheet
-        let reader = excel_reader::ExcelReader::new(&args.input_file, String::new())
-            .context("Failed to open Excel file")?;
-        let sheets = reader.get_sheet_names();
-        let first_sheet = sheets.first()
-            .ok_or_else(|| anyhow::anyhow!("No sheets found in Excel file"))?
-            .clone();
-        info!("Processing default sheet: {}", first_sheet);
-        vec![first_sheet]
+    let output_format = args.format;
+    let output_options = OutputOptions {
+        delimiter: args.delimiter,
+        csv_bom: args.csv_bom,
+        crlf: args.crlf,
+        tsv_strict: args.tsv_strict,
+        sheets_as_object: args.sheets_as_object,
+        flatten: args.flatten,
+        line_per_record: args.line_per_record,
+        compact: args.compact,
+        table: args.table.clone(),
+        batch_size: args.batch_size as usize,
+        xml_omit_null: args.xml_omit_null,
+        php_keep_null: args.php_keep_null,
+        legacy_warnings: args.legacy_warnings,
+        with_row_numbers: args.with_row_numbers,
+        group_by: args.group_by.clone(),
+        group_null_key: args.group_null_key.clone(),
+        columns: args.columns.clone(),
+        root_key: args.root_key.clone(),
     };
     
-    // Fixed output format as JSON
-    let output_format = OutputFormat::Json;
-    
-    // Check if input file exists
-    let input_path = Path::new(&args.input_file);
-    if !input_path.exists() {
-        let result = ProcessingResult::error(
-            format!("File not found: {}", args.input_file),
-            Some(ErrorDetails {
-                file: args.input_file.clone(),
-                available_sheets: None,
-                row_number: None,
-                column: None,
-            }),
-            ProcessingMetadata {
-                total_rows_processed: 0,
-                valid_records: 0,
-                invalid_records: 0,
-                processing_time_ms: start_time.elapsed().as_millis(),
-                warnings: None,
+    if let Some(schema_format) = args.emit_schema {
+        let records = schema::RecordShape {
+            generic: args.generic,
+            preserve_types: args.preserve_types,
+        };
+        let shape = schema::output_schema(output_format, &output_options, records)?;
+        let output = match schema_format {
+            schema::SchemaFormat::JsonSchema => {
+                let title = format!("excel-to-json output (--format {})", output_format.name());
+                format!("{}\n", serde_json::to_string_pretty(&shape.to_json_schema(&title))?)
             },
-        );
-        
-        let output = OutputFormatter::format_output(&result, output_format)?;
-        OutputFormatter::write_to_stdout(&output)?;
-        return Ok(());
+            schema::SchemaFormat::TypeScript => shape.to_typescript("ExcelToJsonOutput"),
+        };
+        match &args.file {
+            Some(file_path) => OutputFormatter::write_to_file(&output, file_path)?,
+            None => print!("{}", output),
+        }
+        return Ok(ExitStatus::Success);
     }
     
-    // Process the Excel file with multiple sheets
-    let result = match process_excel_file_multiple_sheets(&args.input_file, sheets_to_process) {
-        Ok((sheet_data, metadata)) => {
-            ProcessingResult::success_multi_sheet(sheet_data, metadata)
-        },
-        Err(e) => {
-            // Try to provide helpful error details
-            let error_msg = format!("{:#}", e);
-            
-            // Check if this is a sheet not found error
-            let details = if error_msg.contains("Sheet") && error_msg.contains("not found") {
-                // Try to get available sheets
-                let sheets = get_available_sheets(&args.input_file).ok();
-                Some(ErrorDetails {
-                    file: args.input_file.clone(),
-                    available_sheets: sheets,
-                    row_number: None,
-                    column: None,
-                })
-            } else {
-                Some(ErrorDetails {
-                    file: args.input_file.clone(),
-                    available_sheets: None,
-                    row_number: None,
-                    column: None,
-                })
-            };
-            
-            ProcessingResult::error(
-                error_msg,
-                details,
-                ProcessingMetadata {
-                    total_rows_processed: 0,
-                    valid_records: 0,
-                    invalid_records: 0,
-                    processing_time_ms: start_time.elapsed().as_millis(),
-                    warnings: None,
-                },
-            )
+    if output_format.is_binary() {
+        if !cfg!(feature = "parquet") {
+            anyhow::bail!("This build has no {} output; rebuild with the 'parquet' feature", output_format.name());
         }
-    };
-    
-    // Format and output the result
-    if args.summary {
-        let summary = OutputFormatter::create_summary(&result);
-        println!("{}", summary);
-    } else {
-        let output = OutputFormatter::format_output(&result, output_format)?;
-        
-        if let Some(file_path) = args.file {
-            OutputFormatter::write_to_file(&output, &file_path)?;
-            info!("Output written to {}", file_path);
-        } else {
-            OutputFormatter::write_to_stdout(&output)?;
+        if args.zip.is_some() {
+            anyhow::bail!("--zip cannot hold {} output; use --file", output_format.name());
+        }
+        if args.split_output.is_some() {
+            anyhow::bail!("--split-output cannot hold {} output; use --file", output_format.name());
+        }
+        if args.file.is_none() && !args.summary && !args.validate && args.profile.is_none() {
+            anyhow::bail!("{} output is binary and cannot be written to stdout; use --file", output_format.name());
         }
     }
     
-    let total_time = start_time.elapsed();
-    info!("Total execution time: {:?}", total_time);
-    
-    Ok(())
-}
-
-/// Processes an Excel file and extracts records from multiple sheets.
-///
-/// This function handles the core Excel processing workflow for multiple sheets:
-/// reading the file, extracting data with formula evaluation,
-/// and transforming rows into structured records.
-///
-/// # Arguments
-///
-/// * `file_path` - Path to the Excel file to process
-/// * `sheet_names` - List of worksheet names to process
-///
-/// # Returns
-///
-/// * `Ok((sheet_data, metadata))` - Successfully processed sheet data and statistics
-/// * `Err` - If file reading or processing fails
-fn process_excel_file_multiple_sheets(
-    file_path: &str,
-    sheet_names: Vec<String>,
-) -> Result<(Vec<models::SheetData>, ProcessingMetadata)> {
-    let mut all_sheet_data = Vec::new();
-    let mut total_metadata = ProcessingMetadata {
-        total_rows_processed: 0,
-        valid_records: 0,
-        invalid_records: 0,
-        processing_time_ms: 0,
-        warnings: None,
-    };
-    let mut all_warnings = Vec::new();
+    // Open each input file and determine which sheets to process
+    let inputs: Vec<Result<InputFile, models::FileError>> = args.input_file.iter()
+        .map(|input| open_input_file(input, &args))
+        .collect();
     
-    for sheet_name in sheet_names {
-        // Create Excel reader for this sheet
-        let mut reader = excel_reader::ExcelReader::new(file_path, sheet_name.clone())
-            .context("Failed to create Excel reader")?;
-        
-        info!("Processing sheet: {}", sheet_name);
-        
-        // Read and process the Excel data
-        let raw_rows = reader.read_with_formulas()
-            .context(format!("Failed to read Excel data from sheet '{}'", sheet_name))?;
-        
-        // Process the rows into records
-        let mut processor = processor::DataProcessor::new();
-        let (records, metadata) = processor.process_rows(raw_rows)
-            .context(format!("Failed to process rows from sheet '{}'", sheet_name))?;
-        
-        // Add sheet data
-        all_sheet_data.push(models::SheetData {
-            sheet: sheet_name,
-            rows: records,
-        });
-        
-        // Aggregate metadata
-        total_metadata.total_rows_processed += metadata.total_rows_processed;
-        total_metadata.valid_records += metadata.valid_records;
-        total_metadata.invalid_records += metadata.invalid_records;
-        total_metadata.processing_time_ms += metadata.processing_time_ms;
-        
-        if let Some(warnings) = metadata.warnings {
-            all_warnings.extend(warnings);
+    // When no file can be opened there is nothing to process or stream
+    if inputs.iter().all(Result::is_err) {
+        let file_errors: Vec<models::FileError> = inputs.into_iter().filter_map(Result::err).collect();
+        let status = file_errors.first().map_or(ExitStatus::Internal, ExitStatus::of_failure);
+        let result = failed_inputs_result(file_errors, start_time.elapsed().as_millis());
+        if output_format.is_binary() {
+            anyhow::bail!(result.error.unwrap_or_default());
         }
+        let output = OutputFormatter::format_output_with_options(&result, output_format, &output_options)?;
+        OutputFormatter::write_to_stdout(&output)?;
+        if let Some(summary_path) = &args.summary_file {
+            write_summary(&result, summary_path)?;
+        }
+        if let Some(manifest_path) = &args.manifest {
+            write_manifest(&result, &args.input_file, &[], None, start_time.elapsed().as_millis(), manifest_path)?;
+        }
+        return Ok(status);
     }
     
-    if !all_warnings.is_empty() {
-        total_metadata.warnings = Some(all_warnings);
-    }
-    
-    Ok((all_sheet_data, total_metadata))
-}
-
-/// Processes an Excel file and extracts records.
-///
-/// This function handles the core Excel processing workflow:
-/// reading the file, extracting data with formula evaluation,
-/// and transforming rows into structured records.
-///
-/// # Arguments
-///
-/// * `file_path` - Path to the Excel file to process
-/// * `sheet_name` - Optional name of the worksheet to read (uses first sheet if None)
-///
-/// # Returns
-///
-/// * `Ok((records, metadata))` - Successfully processed records and statistics
-/// * `Err` - If file reading or processing fails
-///
-/// # Example
-///
-/// ```rust,no_run
-/// # use excel_to_json::models::{CascadeField, ProcessingMetadata};
-/// # fn process_excel_file(
-/// #     file_path: &str,
-/// #     sheet_name: &str,
-/// # ) -> anyhow::Result<(Vec<CascadeField>, ProcessingMetadata)> {
-/// #     Ok((vec![], ProcessingMetadata {
-/// #         total_rows_processed: 0,
-/// #         valid_records: 0,
-/// #         invalid_records: 0,
-/// #         processing_time_ms: 0,
-/// #         warnings: None,
-/// #     }))
-/// # }
-/// # fn main() -> anyhow::Result<()> {
-/// let (records, metadata) = process_excel_file(
-///     "data.xlsx",
-///     "Cascade Fields"
-/// )?;
-///
-/// println!("Processed {} records", records.len());
-/// println!("Processing time: {}ms", metadata.processing_time_ms);
-///
-/// if let Some(warnings) = &metadata.warnings {
-///     for warning in warnings {
-///         println!("Warning: {}", warning);
-///     }
-/// }
-/// # Ok(())
-/// # }
-/// ```
-fn process_excel_file(
-    file_path: &str,
-    sheet_name: Option<&str>,
-) -> Result<(Vec<models::CascadeField>, ProcessingMetadata)> {
-    // Get sheet name - use provided name or first sheet
-    let sheet = if let Some(name) = sheet_name {
-        name.to_string()
-    } else {
-        // Get the first sheet name
-        let reader = excel_reader::ExcelReader::new(file_path, String::new())
-            .context("Failed to open Excel file")?;
-        let sheets = reader.get_sheet_names();
-        sheets.first()
-            .ok_or_else(|| anyhow::anyhow!("No sheets found in Excel file"))?
-            .clone()
+    let processor_options = processor::ProcessorOptions {
+        defaults: args.defaults.clone(),
+        positional_columns: args.positional_columns.map(usize::from),
+        levels: args.levels.map(usize::from),
+        column_map: args.column_map.clone(),
+        ascii_only: args.ascii_only,
+        duplicate_headers: args.duplicate_headers,
+        value_map: match &args.value_map {
+            Some(path) => processor::load_value_map(path)?,
+            None => processor::ValueMap::new(),
+        },
+        skip_rows_matching: args.skip_rows_matching.clone(),
+        filters: args.filters.clone(),
+        transforms: args.transforms.clone(),
+        date_columns: args.date_columns.clone(),
+        text_date_format: args.parse_text_dates.clone(),
+        generic: args.generic,
+        preserve_types: args.preserve_types,
+        dedupe: args.dedupe,
+        complete_only: args.complete_only,
+        pad_rows: args.pad_rows,
+        strict_unique: args.strict_unique,
+        fail_fast: args.fail_fast,
+        echo_headers: args.echo_headers,
+        dedupe_headers: args.dedupe_headers,
+        max_warnings: Some(args.max_warnings),
+        sort_by: args.sort_by.clone(),
+        sort_desc: args.sort_desc,
+        required_fields: args.require.clone(),
     };
     
-    // Create Excel reader with the determined sheet
-    let mut reader = excel_reader::ExcelReader::new(file_path, sheet.clone())
-        .context("Failed to create Excel reader")?;
+    // Process the Excel file with multiple sheets
+    let mut progress = progress::Progress {
+        file: args.progress_to.as_ref().map(progress::ProgressFile::new),
+        stderr: false,
+        observer: None,
+    };
+    if let Some(mode) = args.progress {
+        progress = progress.with_stderr(mode);
+    }
     
-    info!("Processing sheet: {}", sheet);
+    let reader_options = excel_reader::ReaderOptions {
+        display_format: args.use_display_format,
+        text_columns: args.text_columns.clone(),
+        with_hyperlinks: args.with_hyperlinks,
+        header_row: args.header_row.or(args.skip_rows.map(|rows| rows.saturating_add(1))).map(|row| row as usize),
+        expect_headers: args.expect_headers.clone(),
+        no_header: args.no_header,
+        fill_merged: args.fill_merged,
+        keep_empty_rows: args.keep_empty_rows,
+        keep_blank_columns: args.no_trim_columns,
+        stop_at_blank: args.stop_at_blank,
+        footer_rows: args.footer_rows,
+        offset: args.offset,
+        limit: args.limit.map(|limit| limit as usize),
+        range: args.range,
+        date_format: args.date_format.clone(),
+        // Typed numbers are stored as they are; rounding is for their text
+        float_precision: args.float_precision.filter(|_| !args.preserve_types),
+        decimal_separator: args.decimal_separator.filter(|_| !args.preserve_types),
+        fail_on_cell_error: args.fail_on_cell_error,
+    };
+    if args.no_header && reader_options.header_row.is_some() {
+        warn!("--no-header takes precedence; ignoring --header-row/--skip-rows");
+    }
+    if args.preserve_types && (args.float_precision.is_some() || args.decimal_separator.is_some()) {
+        warn!("--preserve-types keeps numbers unrounded; ignoring --float-precision/--decimal-separator");
+    }
+    let row_window = reader_options.row_window();
     
-    // Read and process the Excel data
-    let raw_rows = reader.read_with_formulas()
-        .context("Failed to read Excel data")?;
+    let stream = args.stream;
+    if stream && !matches!(output_format, OutputFormat::Json) {
+        anyhow::bail!("--stream writes newline-delimited JSON; it can only be used with --format json");
+    }
+    if args.append && !matches!(output_format, OutputFormat::Json) {
+        anyhow::bail!("--append can only be used with --format json");
+    }
+    if args.query.is_some() && !matches!(output_format, OutputFormat::Json | OutputFormat::NestedJson) {
+        anyhow::bail!("--query applies to JSON output; it can only be used with --format json or nested");
+    }
+    
+    // Refuse to buffer a record set over the budget that could be streamed
+    let streamable = matches!(output_format, OutputFormat::Json)
+        && !args.summary && args.zip.is_none() && args.split_output.is_none() && !args.line_per_record && !args.sheets_as_object && !args.flatten && args.group_by.is_none() && args.columns.is_none()
+        && !args.dedupe && !args.complete_only && !args.validate && args.profile.is_none() && args.manifest.is_none() && !args.hash && !args.append && args.query.is_none();
+    if let (Some(megabytes), false, true) = (args.max_memory_mb, stream, streamable || args.sort_by.is_some()) {
+        let budget = memory::MemoryBudget::from_megabytes(megabytes);
+        let estimated: Result<usize> = inputs.iter().flatten()
+            .map(|input| estimate_input_bytes(&input.source, &input.sheets))
+            .sum();
+        if let Ok(estimated) = estimated {
+            if budget.is_exceeded_by(estimated) && args.sort_by.is_some() {
+                // Sorting needs every record at once, so it cannot fall back to streaming
+                anyhow::bail!(
+                    "Estimated record set of {:.1} MB exceeds --max-memory-mb {}; --sort-by needs every record in memory",
+                    estimated as f64 / (1024.0 * 1024.0),
+                    megabytes
+                );
+            }
+            if budget.is_exceeded_by(estimated) {
+                // Streaming writes another shape than the JSON document asked for
+                anyhow::bail!(
+                    "Estimated record set of {:.1} MB exceeds --max-memory-mb {}; pass --stream to write records as newline-delimited JSON without buffering them",
+                    estimated as f64 / (1024.0 * 1024.0),
+                    megabytes
+                );
+            }
+        }
+    }
+    
+    let metrics_label = args.input_file.join(",");
+    let multiple_inputs = inputs.len() > 1;
+    
+    if stream {
+        let mut out: Box<dyn Write> = match &args.file {
+            Some(file_path) => {
+                let file = std::fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .append(args.append)
+                    .truncate(!args.append)
+                    .open(file_path)
+                    .with_context(|| format!("Failed to create output file '{}'", file_path))?;
+                Box::new(std::io::BufWriter::new(file))
+            },
+            None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+        };
+        
+        let mut metadata = ProcessingMetadata::default();
+        let mut warnings = Vec::new();
+        let mut status = ExitStatus::Success;
+        for input in &inputs {
+            let streamed = match input {
+                Ok(input) => {
+                    // Lines of several sheets or files say where they come from
+                    let stream_options = StreamOptions {
+                        with_row_numbers: args.with_row_numbers,
+                        tag_sheets: multiple_inputs || input.sheets.len() > 1,
+                        file: multiple_inputs.then(|| input.name.clone()),
+                        progress: Some(&progress),
+                    };
+                    stream_excel_file_multiple_sheets(&input.source, input.sheets.clone(), &args.metadata_sheets, &reader_options, &processor_options, &stream_options, &mut out)
+                        .map_err(|e| file_error(&input.name, Some(&input.source), e))
+                },
+                Err(file_error) => Err(file_error.clone()),
+            };
+            match streamed {
+                Ok(file_metadata) => add_sheet_metadata(&mut metadata, &mut warnings, &file_metadata),
+                // A repeated key under --strict-unique or an invalid row under
+                // --fail-fast fails the whole run
+                Err(file_error) if multiple_inputs && file_error.row_number.is_none() => {
+                    // Lines already written for the failed file stay in the output
+                    warn!("Skipping input file {}: {}", file_error.file, file_error.error);
+                    warnings.push(format!("{}: {}", file_error.file, file_error.error));
+                    if status == ExitStatus::Success {
+                        status = ExitStatus::of_failure(&file_error);
+                    }
+                },
+                Err(file_error) => anyhow::bail!(file_error.error),
+            }
+        }
+        if !warnings.is_empty() {
+            metadata.warnings = Some(warnings);
+        }
+        out.flush()?;
+        if let Some(file_path) = &args.file {
+            info!("Output written to {}", file_path);
+        }
+        
+        let result = ProcessingResult::success_multi_sheet(Vec::new(), metadata);
+        if let Some(metrics_path) = &args.metrics_file {
+            let metrics = OutputFormatter::format_metrics(&result, &metrics_label);
+            OutputFormatter::write_to_file(&metrics, metrics_path)
+                .context("Failed to write metrics file")?;
+        }
+        if let Some(summary_path) = &args.summary_file {
+            write_summary(&result, summary_path)?;
+        }
+        
+        info!("Total execution time: {:?}", start_time.elapsed());
+        return Ok(status);
+    }
+    
+    // Process every file, collecting the failures instead of stopping at them
+    let mut sheet_data = Vec::new();
+    let mut metadata = ProcessingMetadata::default();
+    let mut warnings = Vec::new();
+    let mut file_errors = Vec::new();
+    for input in inputs {
+        let processed = input.and_then(|input| {
+            match process_excel_file_multiple_sheets(&input.source, input.sheets, &args.metadata_sheets, &reader_options, &processor_options, Some(&progress)) {
+                Ok(processed) => Ok((input.name, processed)),
+                Err(e) => Err(file_error(&input.name, Some(&input.source), e)),
+            }
+        });
+        match processed {
+            Ok((name, (mut sheets, file_metadata))) => {
+                if multiple_inputs {
+                    for sheet in &mut sheets {
+                        sheet.file = Some(name.clone());
+                    }
+                }
+                add_sheet_metadata(&mut metadata, &mut warnings, &file_metadata);
+                sheet_data.extend(sheets);
+            },
+            Err(file_error) => {
+                warn!("Could not process input file {}: {}", file_error.file, file_error.error);
+                file_errors.push(file_error);
+            },
+        }
+    }
+    
+    let status = file_errors.first().map_or(ExitStatus::Success, ExitStatus::of_failure);
+    // A repeated key under --strict-unique fails the whole run
+    let duplicate_key = file_errors.iter().position(|file_error| file_error.row_number.is_some());
+    let mut result = if let Some(idx) = duplicate_key {
+        failed_inputs_result(vec![file_errors.swap_remove(idx)], start_time.elapsed().as_millis())
+    } else if file_errors.len() == args.input_file.len() {
+        failed_inputs_result(file_errors, start_time.elapsed().as_millis())
+    } else {
+        if !warnings.is_empty() {
+            metadata.warnings = Some(warnings);
+        }
+        metadata.row_window = row_window;
+        let mut result = ProcessingResult::success_multi_sheet(sheet_data, metadata);
+        if !file_errors.is_empty() {
+            result.file_errors = Some(file_errors);
+        }
+        result
+    };
+    
+    if let Some(megabytes) = args.max_memory_mb {
+        let budget = memory::MemoryBudget::from_megabytes(megabytes);
+        let estimated = memory::estimate_result_bytes(&result);
+        if budget.is_exceeded_by(estimated) {
+            let message = format!(
+                "Estimated record set of {:.1} MB exceeds --max-memory-mb {}; the result is still buffered in memory",
+                estimated as f64 / (1024.0 * 1024.0),
+                megabytes
+            );
+            warn!("{}", message);
+            result.metadata.warnings.get_or_insert_with(Vec::new).push(message);
+        }
+    }
+    
+    if let Some(metrics_path) = &args.metrics_file {
+        let metrics = OutputFormatter::format_metrics(&result, &metrics_label);
+        OutputFormatter::write_to_file(&metrics, metrics_path)
+            .context("Failed to write metrics file")?;
+    }
+    
+    // Timings differ between runs, so they are left out of hashed output
+    if args.hash {
+        hash::zero_processing_times(&mut result);
+    }
+    
+    // Format and output the result, keeping the paths of the files written
+    // and the hash of what was written for --hash
+    let mut outputs: Vec<String> = Vec::new();
+    let mut written_hash = None;
+    if args.summary || args.validate {
+        let summary = OutputFormatter::create_summary(&result);
+        println!("{}", summary);
+    } else if let (Some(zip_path), true) = (&args.zip, result.success) {
+        OutputFormatter::write_zip(&result, output_format, &output_options, zip_path)?;
+        info!("Output written to {}", zip_path);
+        outputs.push(zip_path.clone());
+    } else if let (Some(dir), true) = (&args.split_output, result.success) {
+        let dir = std::path::Path::new(dir);
+        if args.mkdir {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+        } else if !dir.is_dir() {
+            anyhow::bail!("Output directory {} does not exist; create it or pass --mkdir", dir.display());
+        }
+        let written = OutputFormatter::write_split(&result, output_format, &output_options, dir)?;
+        outputs.extend(written.iter().map(|path| path.display().to_string()));
+    } else if let (Some(file_path), true, None) = (&args.file, output_format.is_binary(), args.profile) {
+        #[cfg(feature = "parquet")]
+        {
+            let bytes = parquet::to_parquet(&result)?;
+            std::fs::write(file_path, &bytes).with_context(|| format!("Failed to write Parquet file {}", file_path))?;
+            written_hash = args.hash.then(|| hash::sha256_hex(&bytes));
+        }
+        info!("Output written to {}", file_path);
+        outputs.push(file_path.clone());
+    } else if let (OutputFormat::Csv, None) = (output_format, args.profile) {
+        // CSV rows are written as they are formatted, without building the whole text first
+        match &args.file {
+            Some(file_path) => {
+                let file = std::fs::File::create(file_path)
+                    .with_context(|| format!("Failed to create output file {}", file_path))?;
+                let mut out = hash::HashingWriter::new(std::io::BufWriter::new(file));
+                OutputFormatter::write_csv(&result, &output_options, &mut out)?;
+                out.flush()?;
+                info!("Output written to {}", file_path);
+                outputs.push(file_path.clone());
+                written_hash = args.hash.then(|| out.hex_digest());
+            },
+            None => {
+                let mut out = hash::HashingWriter::new(std::io::BufWriter::new(std::io::stdout().lock()));
+                OutputFormatter::write_csv(&result, &output_options, &mut out)?;
+                out.flush()?;
+                written_hash = args.hash.then(|| out.hex_digest());
+            },
+        }
+    } else if let (Some(file_path), true) = (&args.file, args.append) {
+        OutputFormatter::append_json(&result, &output_options, std::path::Path::new(file_path))?;
+        outputs.push(file_path.clone());
+    } else {
+        let output = match (args.profile, &args.query, result.success) {
+            (Some(profile_format), _, true) => {
+                let profiles = profile::profile_sheets(result.sheet_data.as_deref().unwrap_or_default());
+                match profile_format {
+                    profile::ProfileFormat::Table => profile::format_table(&profiles),
+                    profile::ProfileFormat::Json => serde_json::to_string_pretty(&profiles)?,
+                }
+            },
+            // Failures are written whole, so the error is not queried away
+            (None, Some(query), true) => OutputFormatter::format_query(&result, output_format, &output_options, query)?,
+            _ => OutputFormatter::format_output_with_options(&result, output_format, &output_options)?,
+        };
+        
+        if let Some(file_path) = &args.file {
+            OutputFormatter::write_to_file(&output, file_path)?;
+            info!("Output written to {}", file_path);
+            outputs.push(file_path.clone());
+        } else {
+            OutputFormatter::write_to_stdout(&output)?;
+        }
+        written_hash = args.hash.then(|| hash::sha256_hex(output.as_bytes()));
+    }
+    
+    let total_time = start_time.elapsed();
+    info!("Total execution time: {:?}", total_time);
+    
+    if let Some(summary_path) = &args.summary_file {
+        write_summary(&result, summary_path)?;
+    }
     
-    // Process the rows into records
-    let mut processor = processor::DataProcessor::new();
-    let (records, metadata) = processor.process_rows(raw_rows)
-        .context("Failed to process rows")?;
+    // Reported on stderr, so it never mixes with data written to stdout
+    if let Some(hash) = &written_hash {
+        eprintln!("sha256:{}", hash);
+    }
+    
+    if let Some(manifest_path) = &args.manifest {
+        write_manifest(&result, &args.input_file, &outputs, written_hash.as_deref(), total_time.as_millis(), manifest_path)?;
+    }
     
-    Ok((records, metadata))
+    if duplicate_key.is_some() {
+        anyhow::bail!(result.error.unwrap_or_default());
+    }
+
+    if args.validate {
+        if !result.success {
+            error!("Fatal error: {}", result.error.as_deref().unwrap_or_default());
+            return Ok(if status == ExitStatus::Success { ExitStatus::Internal } else { status });
+        }
+        let warnings = result.metadata.warnings.as_ref().map_or(0, Vec::len)
+            + result.metadata.diagnostics.iter().filter(|d| d.severity == models::Severity::Warning).count();
+        if result.metadata.invalid_records > 0 || result.metadata.malformed_rows > 0 || (args.strict && warnings > 0) {
+            return Err(ValidationFailed {
+                invalid_records: result.metadata.invalid_records,
+                malformed_rows: result.metadata.malformed_rows,
+                warnings,
+            }.into());
+        }
+    }
+
+    Ok(status)
 }
 
-/// Retrieves the list of available sheet names from an Excel file.
-///
-/// This helper function is used primarily for error reporting when
-/// a requested sheet is not found, providing users with the list of
-/// available sheets they can choose from.
-///
-/// # Arguments
-///
-/// * `file_path` - Path to the Excel file
+/// Writes the `--summary-file` summary of a run to `path`, or to stderr
+/// when `path` is `-`.
+fn write_summary(result: &ProcessingResult, path: &str) -> Result<()> {
+    let summary = OutputFormatter::create_summary(result);
+    if path == "-" {
+        eprint!("{}", summary);
+        return Ok(());
+    }
+    OutputFormatter::write_to_file(&summary, path)
+        .context("Failed to write summary file")?;
+    info!("Summary written to {}", path);
+    Ok(())
+}
+
+/// Writes the `--manifest` summary of a run to `path`, with the `--hash` of
+/// the output as `sha256` when one was taken.
+fn write_manifest(result: &ProcessingResult, inputs: &[String], outputs: &[String], hash: Option<&str>, elapsed_ms: u128, path: &str) -> Result<()> {
+    let mut manifest = OutputFormatter::manifest_json(result, inputs, outputs, elapsed_ms);
+    if let (Some(hash), serde_json::Value::Object(map)) = (hash, &mut manifest) {
+        map.insert("sha256".to_string(), serde_json::json!(hash));
+    }
+    OutputFormatter::write_to_file(&format!("{}\n", serde_json::to_string_pretty(&manifest)?), path)
+        .context("Failed to write manifest file")?;
+    info!("Manifest written to {}", path);
+    Ok(())
+}
+
+/// Returned by [`run`] when `--validate` finds problems, so that `main`
+/// exits with code 4 instead of 1.
+#[derive(Debug)]
+struct ValidationFailed {
+    invalid_records: usize,
+    malformed_rows: usize,
+    warnings: usize,
+}
+
+impl std::fmt::Display for ValidationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Validation failed: {} invalid record(s), ", self.invalid_records)?;
+        if self.malformed_rows > 0 {
+            write!(f, "{} malformed row(s), ", self.malformed_rows)?;
+        }
+        write!(f, "{} warning(s)", self.warnings)
+    }
+}
+
+impl std::error::Error for ValidationFailed {}
+
+/// An input file ready to process: its workbook and the sheets selected from it.
+struct InputFile {
+    /// The file as given on the command line
+    name: String,
+    source: excel_reader::WorkbookSource,
+    sheets: Vec<String>,
+}
+
+/// Opens an input file and selects its sheets from `--sheet`/`--all-sheets`.
 ///
-/// # Returns
+/// Without either option the first sheet of the workbook is selected. `-`
+/// reads the workbook from stdin.
 ///
-/// * `Ok(Vec<String>)` - List of sheet names in the workbook
-/// * `Err` - If the file cannot be opened or read
+/// # Errors
 ///
-/// # Example
+/// Returns a [`FileError`](models::FileError) if the file does not exist or
+/// cannot be opened, or if it has no sheets.
+fn open_input_file(input: &str, args: &Args) -> std::result::Result<InputFile, models::FileError> {
+    // `-` reads the whole workbook from stdin
+    let source = if input == "-" {
+        excel_reader::WorkbookSource::stdin().map_err(|e| file_error(input, None, e))?
+    } else {
+        excel_reader::WorkbookSource::from(input)
+    };
+    
+    // Check if input file exists
+    if matches!(source, excel_reader::WorkbookSource::Path(_)) && !Path::new(input).exists() {
+        return Err(file_error(input, None, std::io::Error::new(std::io::ErrorKind::NotFound, format!("File not found: {}", input)).into()));
+    }
+    
+    // Determine which sheets to process
+    let sheets = select_sheets(&source, &args.sheet, &args.sheet_glob, args.all_sheets, args.sheet_index, args.fuzzy_sheet)
+        .map_err(|e| file_error(input, None, e))?;
+    
+    Ok(InputFile { name: input.to_string(), source, sheets })
+}
+
+/// Describes why an input file could not be processed.
 ///
-/// ```rust,no_run
-/// # fn get_available_sheets(file_path: &str) -> anyhow::Result<Vec<String>> {
-/// #     Ok(vec!["Sheet1".to_string()])
-/// # }
-/// # fn main() -> anyhow::Result<()> {
-/// let sheets = get_available_sheets("data.xlsx")?;
+/// The file's sheets are listed to help the user pick the right one: those of
+/// a [`ReaderError`](excel_reader::ReaderError), or otherwise the workbook's
+/// own when it can still be opened. A repeated
+/// composite key or a row failing `--fail-fast` keeps the row it was found at.
+fn file_error(file: &str, source: Option<&excel_reader::WorkbookSource>, error: anyhow::Error) -> models::FileError {
+    let row_number = error.downcast_ref::<processor::DuplicateKeyError>().map(|duplicate| duplicate.row_number)
+        .or_else(|| error.downcast_ref::<processor::InvalidRowError>().map(|invalid| invalid.row_number));
+    let (available_sheets, suggested_sheet) = match error.chain().find_map(|cause| cause.downcast_ref::<excel_reader::ReaderError>()) {
+        Some(excel_reader::ReaderError::SheetNotFound { available, suggestion, .. }) => (Some(available.clone()), suggestion.clone()),
+        Some(excel_reader::ReaderError::NoSheetMatches { available, .. } | excel_reader::ReaderError::SheetIndexOutOfRange { available, .. }) => (Some(available.clone()), None),
+        None => (source.and_then(|source| get_available_sheets(source.clone()).ok()), None),
+    };
+    let kind = failure_kind(&error);
+    let error = format!("{:#}", error);
+    
+    models::FileError {
+        file: file.to_string(),
+        error,
+        available_sheets,
+        suggested_sheet,
+        row_number,
+        kind,
+    }
+}
+
+/// Builds the error result of a run in which no input file could be processed.
 ///
-/// // Check if desired sheet exists
-/// if !sheets.contains(&"Cascade Fields".to_string()) {
-///     eprintln!("Sheet 'Cascade Fields' not found.");
-///     eprintln!("Available sheets: {:?}", sheets);
-/// }
-/// # Ok(())
-/// # }
-/// ```
-fn get_available_sheets(file_path: &str) -> Result<Vec<String>> {
-    let reader = excel_reader::ExcelReader::new(file_path, String::new())?;
-    Ok(reader.get_sheet_names())
+/// A single input keeps the familiar error with [`ErrorDetails`]; several
+/// inputs list every failure in `file_errors`.
+fn failed_inputs_result(mut file_errors: Vec<models::FileError>, processing_time_ms: u128) -> ProcessingResult {
+    let metadata = ProcessingMetadata {
+        processing_time_ms,
+        ..ProcessingMetadata::default()
+    };
+    
+    if file_errors.len() == 1 {
+        let file_error = file_errors.remove(0);
+        return ProcessingResult::error(
+            file_error.error,
+            Some(ErrorDetails {
+                file: file_error.file,
+                available_sheets: file_error.available_sheets,
+                suggested_sheet: file_error.suggested_sheet,
+                row_number: file_error.row_number,
+                column: None,
+            }),
+            metadata,
+        );
+    }
+    
+    let mut result = ProcessingResult::error(
+        format!("None of the {} input files could be processed", file_errors.len()),
+        None,
+        metadata,
+    );
+    result.file_errors = Some(file_errors);
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use excel_to_json::pipeline::process_excel_file;
+    use rust_xlsxwriter::{Workbook, Worksheet};
     use std::fs;
-    use std::path::PathBuf;
     use tempfile::TempDir;
 
     // Helper function to get the test Excel file path
@@ -496,6 +1460,67 @@ mod tests {
         Args::parse_from(args)
     }
 
+    // Helper function to run the tool on `input` with `extra` arguments and
+    // return what it wrote to `output`
+    fn export(input: &Path, output: &Path, extra: &[&str]) -> String {
+        let mut args = vec!["excel-to-json", input.to_str().unwrap(), "-f", output.to_str().unwrap()];
+        args.extend_from_slice(extra);
+        run(parse_test_args(args)).expect("Should process workbook");
+        fs::read_to_string(output).unwrap()
+    }
+
+    // Helper function to run the tool like `export` and parse the JSON it wrote
+    fn export_json(input: &Path, output: &Path, extra: &[&str]) -> serde_json::Value {
+        serde_json::from_str(&export(input, output, extra)).unwrap()
+    }
+
+    // Helper function to write the cascade field names into `row` of a sheet
+    fn write_headers(sheet: &mut Worksheet, row: u32) {
+        for (col, name) in models::CascadeField::FIELD_NAMES.iter().enumerate() {
+            sheet.write_string(row, col as u16, *name).unwrap();
+        }
+    }
+
+    // Helper function to save a workbook at `path` with one sheet, `Data`,
+    // whose headers are in `header_row`; `fill` writes the other cells
+    fn write_fixture(path: &Path, header_row: u32, fill: impl FnOnce(&mut Worksheet)) -> PathBuf {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Data").unwrap();
+        write_headers(sheet, header_row);
+        fill(sheet);
+        workbook.save(path).unwrap();
+        path.to_path_buf()
+    }
+
+    #[test]
+    fn test_cli_config_file_fills_in_absent_options() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("excel-to-json.toml");
+        fs::write(&config_path, "format = \"csv\"\nall_sheets = true\nheader-row = 2\nrequire = [\"main_value\", \"minor_value\"]\n").unwrap();
+        let parse = |extra: &[&str]| {
+            let mut args = vec!["excel-to-json", "input.xlsx", "--config", config_path.to_str().unwrap()];
+            args.extend_from_slice(extra);
+            parse_args(args)
+        };
+
+        let args = parse(&[]).unwrap();
+        assert!(matches!(args.format, OutputFormat::Csv));
+        assert!(args.all_sheets);
+        assert_eq!(args.header_row, Some(2));
+        assert_eq!(args.require, vec!["main_value", "minor_value"]);
+
+        // Flags on the command line win, including over conflicting config options
+        let args = parse(&["-F", "json", "-s", "Line Type", "--skip-rows", "3", "--require", "sub_value"]).unwrap();
+        assert!(matches!(args.format, OutputFormat::Json));
+        assert!(!args.all_sheets);
+        assert_eq!((args.header_row, args.skip_rows), (None, Some(3)));
+        assert_eq!(args.require, vec!["sub_value"]);
+
+        fs::write(&config_path, "formatt = \"csv\"\n").unwrap();
+        let error = parse(&[]).unwrap_err().to_string();
+        assert!(error.contains("Unknown option 'formatt' in config file"), "{}", error);
+    }
+
     #[test]
     fn test_basic_excel_processing() {
         let test_file = get_test_excel_path();
@@ -512,7 +1537,7 @@ mod tests {
         
         // Basic validation that we got some records
         assert!(metadata.total_rows_processed > 0);
-        assert!(records.len() > 0 || metadata.invalid_records > 0);
+        assert!(!records.is_empty() || metadata.invalid_records > 0);
     }
 
     #[test]
@@ -618,6 +1643,40 @@ mod tests {
         
         // Should complete without panicking (error is in the output)
         assert!(result.is_ok());
+        
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("error.json");
+        
+        let missing = export_json(&test_file, &output, &["-s", "NonexistentSheet"]);
+        assert_eq!(missing["success"], false);
+        let sheets = get_available_sheets(test_file.to_str().unwrap()).unwrap();
+        assert_eq!(missing["details"]["available_sheets"], serde_json::json!(sheets));
+        
+        // Other failures while reading a sheet list the sheets too
+        let unmapped = export_json(&test_file, &output, &["--map", "main_value=Z"]);
+        assert!(unmapped["error"].as_str().unwrap().contains("Mapped column(s) not found"));
+        assert_eq!(unmapped["details"]["available_sheets"], serde_json::json!(sheets));
+    }
+
+    #[test]
+    fn test_cli_unreadable_workbook_error_names_the_file_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("error.json");
+        let locked = temp_dir.path().join("locked.xlsx");
+        let mut compound_file = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+        compound_file.extend("EncryptedPackage".encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(&locked, compound_file).unwrap();
+        let notes = temp_dir.path().join("notes.xlsx");
+        fs::write(&notes, "not a workbook").unwrap();
+
+        let source = excel_reader::WorkbookSource::from(locked.to_str().unwrap());
+        let error = select_sheets(&source, &[], &[], true, None, false).unwrap_err();
+        assert_eq!(format!("{:#}", error), format!(
+            "Failed to open Excel file: {}: workbook is password-protected; decryption is not supported, remove the password in Excel and try again",
+            locked.display()
+        ));
+        let json = export_json(&notes, &output, &["-s", "Data"]);
+        assert_eq!(json["error"], format!("Failed to create Excel reader: {}: Zip error: invalid Zip archive: Invalid zip header", notes.display()));
     }
 
     #[test]
@@ -661,8 +1720,12 @@ mod tests {
         
         if sheets_to_process.len() >= 2 {
             let result = process_excel_file_multiple_sheets(
-                test_file.to_str().unwrap(),
-                sheets_to_process.clone()
+                &excel_reader::WorkbookSource::Path(test_file.clone()),
+                sheets_to_process.clone(),
+                &[],
+                &excel_reader::ReaderOptions::default(),
+                &processor::ProcessorOptions::default(),
+                None
             );
 
             assert!(result.is_ok(), "Should process multiple sheets successfully");
@@ -861,8 +1924,12 @@ mod tests {
             // Process all available sheets one by one to test individual processing
             for sheet_name in &sheets {
                 let result = process_excel_file_multiple_sheets(
-                    test_file.to_str().unwrap(),
-                    vec![sheet_name.clone()]
+                    &excel_reader::WorkbookSource::Path(test_file.clone()),
+                    vec![sheet_name.clone()],
+                    &[],
+                    &excel_reader::ReaderOptions::default(),
+                    &processor::ProcessorOptions::default(),
+                    None
                 );
                 
                 // Each sheet should process successfully (even if it has no valid data)
@@ -892,13 +1959,15 @@ mod tests {
             );
             
             let multi_result = process_excel_file_multiple_sheets(
-                test_file.to_str().unwrap(),
-                vec![first_sheet.clone()]
+                &excel_reader::WorkbookSource::Path(test_file.clone()),
+                vec![first_sheet.clone()],
+                &[],
+                &excel_reader::ReaderOptions::default(),
+                &processor::ProcessorOptions::default(),
+                None
             );
             
-            if single_result.is_ok() && multi_result.is_ok() {
-                let (single_records, single_meta) = single_result.unwrap();
-                let (multi_sheets, multi_meta) = multi_result.unwrap();
+            if let (Ok((single_records, single_meta)), Ok((multi_sheets, multi_meta))) = (single_result, multi_result) {
                 
                 // Should have same number of total rows processed
                 assert_eq!(single_meta.total_rows_processed, multi_meta.total_rows_processed,
@@ -923,8 +1992,12 @@ mod tests {
         // Process each sheet individually to see how empty sheets are handled
         for sheet_name in sheets {
             let result = process_excel_file_multiple_sheets(
-                test_file.to_str().unwrap(),
-                vec![sheet_name.clone()]
+                &excel_reader::WorkbookSource::Path(test_file.clone()),
+                vec![sheet_name.clone()],
+                &[],
+                &excel_reader::ReaderOptions::default(),
+                &processor::ProcessorOptions::default(),
+                None
             );
             
             assert!(result.is_ok(), "Empty/small sheet '{}' should be handled gracefully", sheet_name);
@@ -938,11 +2011,1374 @@ mod tests {
                 assert_eq!(metadata.valid_records, sheet_data[0].rows.len(),
                     "Valid records should equal returned rows for sheet '{}'", sheet_name);
                 
-                // Total rows processed should be sum of valid and invalid
-                assert_eq!(metadata.total_rows_processed, metadata.valid_records + metadata.invalid_records,
-                    "Total rows processed should equal valid + invalid records for sheet '{}'", sheet_name);
+                // Total rows processed should be sum of valid, invalid and malformed
+                assert_eq!(metadata.total_rows_processed, metadata.valid_records + metadata.invalid_records + metadata.malformed_rows,
+                    "Total rows processed should equal valid + invalid records + malformed rows for sheet '{}'", sheet_name);
             }
         }
     }
 
+    #[test]
+    fn test_metadata_sheets_exclude_reference_sheets() {
+        let test_file = get_test_excel_path();
+        let sheets = get_available_sheets(test_file.to_str().unwrap())
+            .expect("Should get sheet names");
+        let business_sheet = "Cascade Fields".to_string();
+        assert!(sheets.contains(&business_sheet), "Fixture should contain the business sheet");
+
+        let (sheet_data, metadata) = process_excel_file_multiple_sheets(
+            &excel_reader::WorkbookSource::Path(test_file.clone()),
+            sheets.clone(),
+            std::slice::from_ref(&business_sheet),
+            &excel_reader::ReaderOptions::default(),
+            &processor::ProcessorOptions::default(),
+            None
+        ).expect("Should process all sheets");
+
+        // Every sheet is still output with its own metadata
+        assert_eq!(sheet_data.len(), sheets.len());
+        assert!(sheet_data.iter().all(|sheet| sheet.metadata.is_some()));
+
+        // Only the business sheet contributes to the aggregate
+        let business = sheet_data.iter()
+            .find(|sheet| sheet.sheet == business_sheet)
+            .and_then(|sheet| sheet.metadata.as_ref())
+            .expect("Business sheet should have metadata");
+        assert_eq!(metadata.total_rows_processed, business.total_rows_processed);
+        assert_eq!(metadata.valid_records, business.valid_records);
+        assert_eq!(metadata.invalid_records, business.invalid_records);
+
+        let reference_rows: usize = sheet_data.iter()
+            .filter(|sheet| sheet.sheet != business_sheet)
+            .filter_map(|sheet| sheet.metadata.as_ref())
+            .map(|m| m.total_rows_processed)
+            .sum();
+        assert!(reference_rows > 0, "Reference sheets should have rows that were excluded");
+
+        // Names match like --sheet, and must name a selected sheet
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+        let matched = export_json(&test_file, &output, &["-a", "--metadata-sheets", " cascade FIELDS"]);
+        assert_eq!(matched["metadata"]["total_rows_processed"], business.total_rows_processed);
+
+        let args = vec!["excel-to-json", test_file.to_str().unwrap(), "-s", "Line Type", "--metadata-sheets", "Cascade Fields", "-f", output.to_str().unwrap()];
+        assert_eq!(run(parse_test_args(args)).unwrap(), ExitStatus::SheetNotFound);
+        let failed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert!(failed["error"].as_str().unwrap().starts_with("Metadata sheet 'Cascade Fields' is not one of the processed sheets"), "{}", failed);
+    }
+
+    #[test]
+    fn test_cli_with_progress_file() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let progress_path = temp_dir.path().join("progress.json");
+
+        let args = vec![
+            "excel-to-json",
+            test_file.to_str().unwrap(),
+            "-s", "Cascade Fields",
+            "--summary",
+            "--progress-to", progress_path.to_str().unwrap()
+        ];
+        let result = run(parse_test_args(args));
+        assert!(result.is_ok());
+
+        let contents = fs::read_to_string(&progress_path).expect("Progress file should exist");
+        let update: progress::ProgressUpdate = serde_json::from_str(&contents)
+            .expect("Progress file should be valid JSON");
+        assert_eq!(update.sheet, "Cascade Fields");
+        assert!(update.total > 0);
+        assert_eq!(update.rows_done, update.total, "Final update should report completion");
+    }
+
+    #[test]
+    fn test_progress_file_counts_rows_across_updates() {
+        use std::sync::{Arc, Mutex};
+
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("large.xlsx"), 0, |sheet| {
+            for row in 1..=2500u32 {
+                sheet.write_string(row, 1, format!("M{}", row)).unwrap();
+            }
+            sheet.write_string(1, 11, "").unwrap();
+        });
+
+        // Each snapshot is read back from the file right after it is written
+        let progress_path = temp_dir.path().join("progress.json");
+        let snapshots = Arc::new(Mutex::new(Vec::new()));
+        let observer: progress::ProgressObserver = {
+            let (snapshots, path) = (Arc::clone(&snapshots), progress_path.clone());
+            Arc::new(move |_| {
+                let update: progress::ProgressUpdate = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+                snapshots.lock().unwrap().push((update.rows_done, update.total));
+            })
+        };
+        let progress = progress::Progress {
+            file: Some(progress::ProgressFile::new(&progress_path)),
+            stderr: false,
+            observer: Some(observer),
+        };
+
+        let source = excel_reader::WorkbookSource::from(input.to_str().unwrap());
+        let options = processor::ProcessorOptions::default();
+        process_excel_file_multiple_sheets(&source, vec!["Data".to_string()], &[], &excel_reader::ReaderOptions::default(), &options, Some(&progress))
+            .expect("Should process workbook");
+
+        assert_eq!(*snapshots.lock().unwrap(), vec![(1000, 2500), (2000, 2500), (2500, 2500)]);
+
+        snapshots.lock().unwrap().clear();
+        let stream_options = StreamOptions { progress: Some(&progress), ..StreamOptions::default() };
+        stream_excel_file_multiple_sheets(&source, vec!["Data".to_string()], &[], &excel_reader::ReaderOptions::default(), &options, &stream_options, &mut std::io::sink())
+            .expect("Should stream workbook");
+        assert_eq!(*snapshots.lock().unwrap(), vec![(1000, 2500), (2000, 2500), (2500, 2500)]);
+    }
+
+    #[test]
+    fn test_cli_stream_writes_progress_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("large.xlsx"), 0, |sheet| {
+            for row in 1..=1500u32 {
+                sheet.write_string(row, 1, format!("M{}", row)).unwrap();
+            }
+        });
+        let progress_path = temp_dir.path().join("progress.json");
+
+        let lines = export(&input, &temp_dir.path().join("out.ndjson"), &["--stream", "--progress-to", progress_path.to_str().unwrap()]);
+        assert_eq!(lines.lines().count(), 1500);
+
+        let update: progress::ProgressUpdate = serde_json::from_str(&fs::read_to_string(&progress_path).unwrap()).unwrap();
+        assert_eq!(update, progress::ProgressUpdate { rows_done: 1500, total: 1500, sheet: "Data".to_string() });
+    }
+
+    #[test]
+    fn test_cli_with_metrics_file() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let metrics_path = temp_dir.path().join("excel_to_json.prom");
+
+        let args = vec![
+            "excel-to-json",
+            test_file.to_str().unwrap(),
+            "-s", "Cascade Fields",
+            "--summary",
+            "--metrics-file", metrics_path.to_str().unwrap()
+        ];
+        let result = run(parse_test_args(args));
+        assert!(result.is_ok());
+
+        let metrics = fs::read_to_string(&metrics_path).expect("Metrics file should exist");
+        let label = format!("{{file=\"{}\"}}", test_file.to_str().unwrap());
+        assert!(metrics.contains(&format!("excel_to_json_success{} 1\n", label)));
+        assert!(metrics.contains(&format!("excel_to_json_valid_records{} 9735\n", label)));
+        assert!(metrics.contains("# TYPE excel_to_json_invalid_records gauge"));
+        assert!(metrics.contains("excel_to_json_total_rows_processed{"));
+        assert!(metrics.contains("excel_to_json_processing_time_ms{"));
+    }
+
+    #[test]
+    fn test_cli_use_display_format_renders_percentage() {
+        use rust_xlsxwriter::Format;
+
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("rates.xlsx"), 0, |sheet| {
+            sheet.write_string(1, 1, "M1").unwrap();
+            sheet.write_number_with_format(1, 2, 0.5, &Format::new().set_num_format("0%")).unwrap();
+            sheet.write_string_with_format(1, 3, "n/a", &Format::new().set_num_format("0;-0;0;\"Note: \"@")).unwrap();
+            sheet.write_string(1, 11, "").unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+        let row = |extra: &[&str]| export_json(&input, &output, extra)["data"][0]["rows"][0].clone();
+
+        let displayed = row(&["--use-display-format"]);
+        assert_eq!(displayed["main_description"], "50%");
+        assert_eq!(displayed["sub_label"], "Note: n/a");
+        let raw = row(&[]);
+        assert_eq!(raw["main_description"], "0.5");
+        assert_eq!(raw["sub_label"], "n/a");
+    }
+
+    #[test]
+    fn test_cli_text_columns_keep_leading_zeros() {
+        use rust_xlsxwriter::Format;
+
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("parts.xlsx"), 0, |sheet| {
+            sheet.write_number_with_format(1, 1, 123.0, &Format::new().set_num_format("00000")).unwrap();
+            sheet.write_number(1, 4, 7.25).unwrap();
+            sheet.write_string(1, 11, "").unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+        let record = |extra: &[&str]| {
+            let mut args = vec!["--preserve-types"];
+            args.extend_from_slice(extra);
+            export_json(&input, &output, &args)["data"][0]["rows"][0].clone()
+        };
+
+        let collapsed = record(&[]);
+        assert_eq!(collapsed["main_value"], 123);
+
+        let kept = record(&["--text-columns", "main_value,sub_value"]);
+        assert_eq!(kept["main_value"], "00123");
+        assert_eq!(kept["sub_value"], "7.25");
+        // Header texts name columns too
+        assert_eq!(record(&["--text-columns", "MAIN_VALUE"])["main_value"], "00123");
+    }
+
+    #[test]
+    fn test_cli_with_hyperlinks_keeps_link_targets() {
+        use rust_xlsxwriter::Url;
+
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("catalog.xlsx"), 0, |sheet| {
+            sheet.write_string(1, 1, "M1").unwrap();
+            sheet.write_url(1, 2, Url::new("https://example.com/widget").set_text("Widget")).unwrap();
+            sheet.write_string(1, 4, "S1").unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+        let row = |extra: &[&str]| export_json(&input, &output, extra)["data"][0]["rows"][0].clone();
+
+        let linked = row(&["--with-hyperlinks"]);
+        assert_eq!(linked["main_description"], "Widget <https://example.com/widget>");
+        // Cells without a link are unchanged
+        assert_eq!(linked["sub_value"], "S1");
+        assert_eq!(row(&[])["main_description"], "Widget");
+    }
+
+    #[test]
+    fn test_cli_with_zip_output() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let zip_path = temp_dir.path().join("export.zip");
+
+        let args = vec![
+            "excel-to-json",
+            test_file.to_str().unwrap(),
+            "-s", "Cascade Fields",
+            "-s", "Line Type",
+            "--format", "csv",
+            "--zip", zip_path.to_str().unwrap()
+        ];
+        let result = run(parse_test_args(args));
+        assert!(result.is_ok());
+
+        let archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).expect("Should be a zip file");
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, vec!["Cascade Fields.csv", "Line Type.csv"]);
+    }
+
+    #[test]
+    fn test_cli_split_output_writes_a_file_per_sheet() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("sheets");
+        let split = |extra: &[&str]| {
+            let mut args = vec![
+                "excel-to-json",
+                test_file.to_str().unwrap(),
+                "-s", "Cascade Fields",
+                "-s", "Line Type",
+                "--format", "csv",
+                "--split-output", dir.to_str().unwrap(),
+            ];
+            args.extend_from_slice(extra);
+            run(parse_test_args(args))
+        };
+
+        let missing = split(&[]).expect_err("Should reject a missing directory");
+        assert!(missing.to_string().contains("does not exist; create it or pass --mkdir"));
+
+        split(&["--mkdir"]).expect("Should write the sheets");
+        let mut names: Vec<String> = fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["Cascade Fields.csv", "Line Type.csv", "_summary.json"]);
+        assert!(fs::read_to_string(dir.join("Cascade Fields.csv")).unwrap().starts_with("main_label,"));
+
+        let summary: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("_summary.json")).unwrap()).unwrap();
+        assert_eq!(summary["files"], serde_json::json!(["Cascade Fields.csv", "Line Type.csv"]));
+        assert!(summary["metadata"]["valid_records"].as_u64().unwrap() > 0);
+
+        let with_file = vec!["excel-to-json", "in.xlsx", "--split-output", "out", "-f", "out.json"];
+        assert!(Args::try_parse_from(with_file).is_err());
+    }
+
+    #[test]
+    fn test_cli_manifest_lists_sheets_and_outputs() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("regions.xlsx");
+        let mut workbook = Workbook::new();
+        for (name, values) in [("North", vec!["N1", "N2"]), ("South", vec!["S1"])] {
+            let sheet = workbook.add_worksheet().set_name(name).unwrap();
+            write_headers(sheet, 0);
+            for (row, value) in values.iter().enumerate() {
+                sheet.write_string(row as u32 + 1, 1, *value).unwrap();
+                sheet.write_string(row as u32 + 1, 11, "").unwrap();
+            }
+        }
+        workbook.save(&input).unwrap();
+
+        let dir = temp_dir.path().join("sheets");
+        let manifest_path = temp_dir.path().join("manifest.json");
+        let args = vec![
+            "excel-to-json",
+            input.to_str().unwrap(),
+            "-a",
+            "--split-output", dir.to_str().unwrap(),
+            "--mkdir",
+            "--manifest", manifest_path.to_str().unwrap(),
+        ];
+        run(parse_test_args(args)).expect("Should write the sheets and the manifest");
+
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        assert_eq!(manifest["success"], true);
+        assert_eq!(manifest["inputs"], serde_json::json!([input.to_str().unwrap()]));
+        let sheets: Vec<(&str, u64)> = manifest["sheets"].as_array().unwrap().iter()
+            .map(|sheet| (sheet["sheet"].as_str().unwrap(), sheet["records"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(sheets, vec![("North", 2), ("South", 1)]);
+        let outputs: Vec<String> = ["North.json", "South.json", "_summary.json"].iter()
+            .map(|name| dir.join(name).display().to_string())
+            .collect();
+        assert_eq!(manifest["outputs"], serde_json::json!(outputs));
+        assert!(manifest["elapsed_ms"].is_u64());
+
+        let streamed = vec!["excel-to-json", "in.xlsx", "--stream", "--manifest", "m.json"];
+        assert!(Args::try_parse_from(streamed).is_err());
+    }
+
+    #[test]
+    fn test_cli_hash_covers_the_bytes_written() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let manifest = temp_dir.path().join("manifest.json");
+        let hash = |sheet: &str, output: &Path, extra: &[&str]| {
+            let mut args = vec!["-s", sheet, "--hash", "--manifest", manifest.to_str().unwrap()];
+            args.extend_from_slice(extra);
+            let written = export(&test_file, output, &args);
+            let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+            let hash = manifest["sha256"].as_str().unwrap().to_string();
+            assert_eq!(hash, hash::sha256_hex(written.as_bytes()));
+            hash
+        };
+
+        let json = temp_dir.path().join("out.json");
+        let first = hash("Line Type", &json, &[]);
+        assert_eq!(hash("Line Type", &json, &[]), first);
+        assert_ne!(hash("Cascade Fields", &json, &[]), first);
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+        assert_eq!(written["metadata"]["processing_time_ms"], 0);
+
+        // CSV is hashed as it is written out
+        let csv = temp_dir.path().join("out.csv");
+        assert_ne!(hash("Line Type", &csv, &["-F", "csv"]), first);
+
+        // Archives and split files are not one stream of bytes to hash
+        let zipped = Args::try_parse_from(["excel-to-json", "in.xlsx", "--hash", "--zip", "out.zip"]).unwrap_err();
+        assert_eq!(zipped.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_cli_query_writes_selected_values() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+
+        let full = export_json(&test_file, &output, &["-s", "Cascade Fields", "--limit", "3"]);
+        let values = export_json(&test_file, &output, &["-s", "Cascade Fields", "--limit", "3", "--query", "data[0].rows[].main_value"]);
+        let expected: Vec<serde_json::Value> = full["data"][0]["rows"].as_array().unwrap().iter()
+            .map(|row| row["main_value"].clone())
+            .collect();
+        assert_eq!(values, serde_json::Value::Array(expected));
+        assert_eq!(values.as_array().unwrap().len(), 3);
+
+        let sorted = export_json(&test_file, &output, &["-s", "Cascade Fields", "--limit", "3", "--query", "sort_by(data[0].rows, &main_value)[].main_value"]);
+        let mut expected = values.as_array().unwrap().clone();
+        expected.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+        assert_eq!(sorted, serde_json::Value::Array(expected));
+
+        let args = vec!["excel-to-json", test_file.to_str().unwrap(), "-f", output.to_str().unwrap(), "--query", "data[0].rows[]", "-F", "csv"];
+        let error = run(parse_test_args(args)).unwrap_err();
+        assert_eq!(error.to_string(), "--query applies to JSON output; it can only be used with --format json or nested");
+
+        let invalid = Args::try_parse_from(["excel-to-json", "in.xlsx", "--query", "data[0"]).unwrap_err();
+        assert!(invalid.to_string().contains("Invalid query 'data[0': Parse error: Expected ':', or ']' -- found Eof (line 0, column 6)"), "{}", invalid);
+    }
+
+    #[test]
+    fn test_cli_append_grows_ndjson_and_json_output() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let append = |output: &Path, extra: &[&str]| {
+            let mut args = vec!["-s", "Cascade Fields", "--limit", "3", "--append"];
+            args.extend_from_slice(extra);
+            export(&test_file, output, &args)
+        };
+
+        let ndjson = temp_dir.path().join("records.ndjson");
+        let lines = append(&ndjson, &["--stream"]).lines().count();
+        assert!(lines > 0);
+        assert_eq!(append(&ndjson, &["--stream"]).lines().count(), lines * 2);
+
+        let json = temp_dir.path().join("records.json");
+        append(&json, &[]);
+        let document: serde_json::Value = serde_json::from_str(&append(&json, &[])).unwrap();
+        let sheets = document["data"].as_array().unwrap();
+        assert_eq!(sheets.len(), 2);
+        assert_eq!(sheets[0]["rows"], sheets[1]["rows"]);
+
+        let csv = vec!["excel-to-json", test_file.to_str().unwrap(), "-f", "out.csv", "--format", "csv", "--append"];
+        let error = run(parse_test_args(csv)).unwrap_err();
+        assert_eq!(error.to_string(), "--append can only be used with --format json");
+    }
+
+    #[test]
+    fn test_cli_dedupe_headers_skips_repeated_header_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("concatenated.xlsx"), 0, |sheet| {
+            sheet.write_string(1, 1, "CAT001").unwrap();
+            write_headers(sheet, 2);
+            sheet.write_string(3, 1, "CAT002").unwrap();
+            // Differs from the header in one cell, so it is data
+            write_headers(sheet, 4);
+            sheet.write_string(4, 0, "Main").unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+
+        let plain = export_json(&input, &output, &[]);
+        assert_eq!(plain["data"][0]["rows"].as_array().unwrap().len(), 4);
+
+        let deduped = export_json(&input, &output, &["--dedupe-headers"]);
+        let values: Vec<&serde_json::Value> = deduped["data"][0]["rows"].as_array().unwrap().iter()
+            .map(|record| &record["main_value"])
+            .collect();
+        assert_eq!(values, vec!["CAT001", "CAT002", "main_value"]);
+        assert_eq!(deduped["metadata"]["skipped_records"], 1);
+        assert_eq!(deduped["metadata"]["warnings"], serde_json::json!(["Sheet 'Data': Skipped 1 repeated header row(s): 3"]));
+    }
+
+    #[test]
+    fn test_cli_summary_file_accompanies_json_output() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+        let summary = temp_dir.path().join("summary.txt");
+
+        let json = export_json(&test_file, &output, &["-s", "Cascade Fields", "--summary-file", summary.to_str().unwrap()]);
+        assert_eq!(json["success"], true);
+        assert_eq!(json["metadata"]["valid_records"], 9735);
+        let summary = fs::read_to_string(&summary).unwrap();
+        assert!(summary.contains("✓ Successfully processed"));
+        assert!(summary.contains("9735 records"));
+
+        let with_summary = vec!["excel-to-json", "in.xlsx", "--summary", "--summary-file", "-"];
+        assert!(Args::try_parse_from(with_summary).is_err());
+    }
+
+    #[test]
+    fn test_cli_columns_rejects_unknown_fields() {
+        let args = Args::try_parse_from(["excel-to-json", "in.xlsx", "--columns", "main_value,minor_value"]).unwrap();
+        assert_eq!(args.columns, Some(vec!["main_value".to_string(), "minor_value".to_string()]));
+
+        let unknown = Args::try_parse_from(["excel-to-json", "in.xlsx", "--columns", "main_value,price"]).unwrap_err();
+        assert!(unknown.to_string().contains("invalid value 'price' for '--columns <FIELD>'"));
+    }
+
+    #[test]
+    fn test_cli_suggests_closest_sheet_name() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+        let near_miss = export_json(&test_file, &output, &["-s", "Cascaed Fields"]);
+        assert_eq!(near_miss["success"], false);
+        assert!(near_miss["error"].as_str().unwrap().contains("Sheet 'Cascaed Fields' not found; did you mean 'Cascade Fields'?"));
+        assert_eq!(near_miss["details"]["suggested_sheet"], "Cascade Fields");
+
+        let unrelated = export_json(&test_file, &output, &["-s", "Quarterly Revenue"]);
+        assert!(!unrelated["error"].as_str().unwrap().contains("did you mean"));
+        assert!(unrelated["details"].get("suggested_sheet").is_none());
+        assert!(unrelated["details"]["available_sheets"].is_array());
+
+        let fuzzy = export_json(&test_file, &output, &["-s", "cascaed fields", "--fuzzy-sheet"]);
+        assert_eq!(fuzzy["success"], true);
+        assert_eq!(fuzzy["data"][0]["sheet"], "Cascade Fields");
+    }
+
+    #[test]
+    fn test_cli_sheet_index_selects_sheet_by_position() {
+        let test_file = get_test_excel_path();
+        let sheets = get_available_sheets(test_file.to_str().unwrap()).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+        let args = vec!["excel-to-json", test_file.to_str().unwrap(), "--sheet-index", "1", "-f", output.to_str().unwrap()];
+        assert_eq!(run(parse_test_args(args)).unwrap(), ExitStatus::Success);
+        let second: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(second["success"], true);
+        assert_eq!(second["data"][0]["sheet"], sheets[1].as_str());
+
+        // A failure to select sheets is reported on stdout, as for --sheet-glob
+        fs::remove_file(&output).unwrap();
+        let past_last = sheets.len().to_string();
+        let args = vec!["excel-to-json", test_file.to_str().unwrap(), "--sheet-index", &past_last, "-f", output.to_str().unwrap()];
+        assert_eq!(run(parse_test_args(args)).unwrap(), ExitStatus::SheetNotFound);
+        assert!(!output.exists());
+
+        let source = excel_reader::WorkbookSource::from(test_file.to_str().unwrap());
+        let error = select_sheets(&source, &[], &[], false, Some(sheets.len()), false).unwrap_err();
+        assert_eq!(error.to_string(), format!(
+            "Sheet index {} is out of range; the workbook has {} sheet(s): {:?}",
+            sheets.len(), sheets.len(), sheets
+        ));
+        assert_eq!(file_error("in.xlsx", None, error).available_sheets, Some(sheets));
+
+        assert!(Args::try_parse_from(["excel-to-json", "in.xlsx", "--sheet-index", "0", "-s", "Data"]).is_err());
+        assert!(Args::try_parse_from(["excel-to-json", "in.xlsx", "--sheet-index", "0", "--all-sheets"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_quiet_logs_only_errors() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("stderr.log");
+        let output = temp_dir.path().join("out.json");
+
+        let logged = |extra: &[&str]| {
+            let mut args = vec!["excel-to-json", test_file.to_str().unwrap(), "-f", output.to_str().unwrap()];
+            args.extend_from_slice(extra);
+            let args = parse_test_args(args);
+            let log_file = fs::File::create(&log_path).unwrap();
+            let subscriber = tracing_subscriber::fmt()
+                .with_max_level(log_level(&args))
+                .with_ansi(false)
+                .with_writer(std::sync::Mutex::new(log_file))
+                .finish();
+            tracing::subscriber::with_default(subscriber, || run(args)).unwrap();
+            fs::read_to_string(&log_path).unwrap()
+        };
+
+        assert!(logged(&[]).contains(" INFO "));
+        assert_eq!(logged(&["--quiet", "--verbose"]), "");
+        // Fatal errors are logged at ERROR, which --quiet still lets through
+        assert_eq!(log_level(&parse_test_args(vec!["excel-to-json", "in.xlsx", "-q"])), tracing::Level::ERROR);
+    }
+
+    #[test]
+    #[cfg(feature = "parquet")]
+    fn test_cli_parquet_output_needs_a_file() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("export.parquet");
+
+        let to_stdout = run(parse_test_args(vec!["excel-to-json", test_file.to_str().unwrap(), "-F", "parquet"]));
+        assert!(to_stdout.unwrap_err().to_string().contains("cannot be written to stdout"));
+
+        let args = vec!["excel-to-json", test_file.to_str().unwrap(), "-F", "parquet", "-f", output.to_str().unwrap()];
+        run(parse_test_args(args)).expect("Should write Parquet file");
+        let bytes = fs::read(&output).unwrap();
+        assert!(bytes.starts_with(b"PAR1") && bytes.ends_with(b"PAR1"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "parquet"))]
+    fn test_cli_parquet_output_needs_the_feature() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("export.parquet");
+
+        let args = vec!["excel-to-json", test_file.to_str().unwrap(), "-F", "parquet", "-f", output.to_str().unwrap()];
+        let error = run(parse_test_args(args)).unwrap_err();
+        assert_eq!(error.to_string(), "This build has no parquet output; rebuild with the 'parquet' feature");
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn test_cli_max_memory_budget_refuses_to_buffer_json() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+        let args = |budget: &'static str, extra: &[&'static str]| {
+            let mut args = vec!["excel-to-json", test_file.to_str().unwrap(), "-s", "Cascade Fields", "-f", output.to_str().unwrap(), "--max-memory-mb", budget];
+            args.extend_from_slice(extra);
+            parse_test_args(args)
+        };
+
+        // Over budget: no output, and a pointer to --stream
+        let error = run(args("1", &[])).unwrap_err();
+        assert!(error.to_string().ends_with("exceeds --max-memory-mb 1; pass --stream to write records as newline-delimited JSON without buffering them"), "{}", error);
+        assert!(!output.exists());
+
+        run(args("1", &["--stream"])).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap().lines().count(), 9735);
+
+        run(args("4096", &[])).unwrap();
+        let buffered: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert!(buffered["success"].as_bool().unwrap());
+        assert!(buffered["metadata"]["warnings"].is_null());
+    }
+
+    #[test]
+    fn test_cli_stream_matches_buffered_output_for_large_sheet() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("large.xlsx"), 0, |sheet| {
+            for row in 1..=20_000u32 {
+                sheet.write_string(row, 0, "Category").unwrap();
+                sheet.write_string(row, 1, format!("CAT{:05}", row % 500)).unwrap();
+                sheet.write_string(row, 3, "Item").unwrap();
+                sheet.write_number(row, 4, row).unwrap();
+            }
+            // A row without a main value is dropped in both modes
+            sheet.write_string(20_001, 0, "Orphan label").unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+
+        let streamed: Vec<serde_json::Value> = export(&input, &output, &["--stream"]).lines()
+            .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+            .collect();
+        let buffered = export_json(&input, &output, &[]);
+
+        assert_eq!(streamed.len(), 20_000);
+        assert_eq!(streamed[0]["sub_value"], "1");
+        assert_eq!(&streamed, buffered["data"][0]["rows"].as_array().unwrap());
+    }
+
+    #[test]
+    fn test_cli_stream_tags_records_of_several_sheets() {
+        let temp_dir = TempDir::new().unwrap();
+        let write_input = |name: &str| {
+            let input = temp_dir.path().join(name);
+            let mut workbook = Workbook::new();
+            for sheet_name in ["Jan", "Feb"] {
+                let sheet = workbook.add_worksheet().set_name(sheet_name).unwrap();
+                write_headers(sheet, 0);
+                sheet.write_string(1, 1, format!("{}-1", sheet_name)).unwrap();
+                sheet.write_string(1, 11, "").unwrap();
+            }
+            workbook.save(&input).unwrap();
+            input
+        };
+        let first = write_input("first.xlsx");
+        let second = write_input("second.xlsx");
+        let output = temp_dir.path().join("out.ndjson");
+        let stream = |inputs: &[&Path], extra: &[&str]| {
+            let mut args = vec!["excel-to-json"];
+            args.extend(inputs.iter().map(|input| input.to_str().unwrap()));
+            args.extend_from_slice(&["--stream", "-f", output.to_str().unwrap()]);
+            args.extend_from_slice(extra);
+            run(parse_test_args(args)).expect("Should stream workbooks");
+            fs::read_to_string(&output).unwrap().lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // One sheet: lines keep the buffered record shape
+        let lines = stream(&[&first], &["-s", "Feb"]);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["main_value"], "Feb-1");
+        assert!(lines[0].get("sheet").is_none());
+
+        let lines = stream(&[&first], &["--all-sheets"]);
+        let tags: Vec<_> = lines.iter().map(|line| (line["main_value"].as_str().unwrap(), line["sheet"].as_str().unwrap())).collect();
+        assert_eq!(tags, vec![("Jan-1", "Jan"), ("Feb-1", "Feb")]);
+        assert!(lines[0].get("file").is_none());
+
+        // Several files also name the file, even with a single sheet each
+        let lines = stream(&[&first, &second], &["-s", "Jan"]);
+        let tags: Vec<_> = lines.iter().map(|line| (line["sheet"].as_str().unwrap(), line["file"].as_str().unwrap())).collect();
+        assert_eq!(tags, vec![("Jan", first.to_str().unwrap()), ("Jan", second.to_str().unwrap())]);
+    }
+
+    #[test]
+    fn test_stream_requires_json_format() {
+        let test_file = get_test_excel_path();
+        let args = vec!["excel-to-json", test_file.to_str().unwrap(), "--stream", "--format", "csv"];
+        let error = run(parse_test_args(args)).unwrap_err();
+        assert!(error.to_string().contains("--format json"), "got: {}", error);
+    }
+
+    #[test]
+    fn test_ods_matches_equivalent_xlsx() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let output = temp_dir.path().join("out.json");
+        let converted = |input: &str| {
+            let mut json = export_json(Path::new(input), &output, &["--all-sheets"]);
+            json["metadata"]["processing_time_ms"] = serde_json::Value::Null;
+            for sheet in json["data"].as_array_mut().unwrap() {
+                sheet["metadata"]["processing_time_ms"] = serde_json::Value::Null;
+            }
+            json
+        };
+
+        let xlsx = "resources/Cascade Sample.xlsx";
+        let ods = "resources/Cascade Sample.ods";
+
+        assert_eq!(get_available_sheets(ods).unwrap(), get_available_sheets(xlsx).unwrap());
+        assert_eq!(get_available_sheets(ods).unwrap(), vec!["Cascade Fields", "Line Type"]);
+
+        let from_xlsx = converted(xlsx);
+        assert_eq!(from_xlsx["data"][0]["rows"].as_array().unwrap().len(), 2);
+        assert_eq!(from_xlsx["data"][0]["rows"][1]["major_value"], "200");
+        assert_eq!(converted(ods), from_xlsx);
+    }
+
+    #[test]
+    fn test_cli_generic_mode_keys_rows_by_header() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("generic.json");
+        let json = export_json(Path::new("resources/Cascade Sample.xlsx"), &output, &["--sheet", "Cascade Fields", "--generic"]);
+        let rows = json["data"][0]["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), 3, "Rows without a main value are kept in generic mode");
+        assert_eq!(rows[0]["Main Value"], "CAT001");
+        assert_eq!(rows[0]["Sub Description"], serde_json::Value::Null);
+        assert!(rows[0].get("main_value").is_none());
+
+        // Keys keep the order of the sheet's header row
+        let keys: Vec<&str> = rows[0].as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(keys[..4], ["Main Label", "Main Value", "Main Description", "Sub Label"]);
+        assert_eq!(keys.last(), Some(&"Minor Description"));
+    }
+
+    #[test]
+    fn test_cli_preserve_types_keeps_numbers() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = Path::new("resources/Cascade Sample.xlsx");
+        let output = temp_dir.path().join("typed.json");
+
+        let typed = export_json(input, &output, &["--sheet", "Cascade Fields", "--preserve-types"]);
+        let row = &typed["data"][0]["rows"][1];
+        assert_eq!(row["major_value"], serde_json::json!(200));
+        assert_eq!(row["main_value"], "CAT002");
+        assert_eq!(row["main_description"], "");
+
+        let untyped = export_json(input, &output, &["--sheet", "Cascade Fields"]);
+        assert_eq!(untyped["data"][0]["rows"][1]["major_value"], "200");
+    }
+
+    #[test]
+    fn test_cli_float_precision_rounds_text_not_typed_numbers() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("prices.xlsx"), 0, |sheet| {
+            sheet.write_string(1, 1, "M1").unwrap();
+            sheet.write_number(1, 2, 0.1 + 0.2).unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+        let description = |extra: &[&str]| export_json(&input, &output, extra)["data"][0]["rows"][0]["main_description"].clone();
+
+        assert_eq!(description(&[]), "0.30000000000000004");
+        assert_eq!(description(&["--float-precision", "2"]), "0.30");
+        assert_eq!(description(&["--float-precision", "2", "--decimal-separator", ","]), "0,30");
+        assert_eq!(description(&["--float-precision", "2", "--preserve-types"]), serde_json::json!(0.1 + 0.2));
+
+        assert!(Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--decimal-separator", "7"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_format_csv_and_php_outputs() {
+        let temp_dir = TempDir::new().unwrap();
+        let formatted = |format: &str| {
+            let output = temp_dir.path().join(format!("out.{}", format));
+            export(Path::new("resources/Cascade Sample.xlsx"), &output, &["--sheet", "Cascade Fields", "-F", format])
+        };
+
+        let csv = formatted("csv");
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "sheet,main_label,main_value,main_description,sub_label,sub_value,sub_description,major_label,major_value,major_description,minor_label,minor_value,minor_description");
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("Cascade Fields,Category,CAT001,"));
+
+        let php: serde_json::Value = serde_json::from_str(&formatted("php")).unwrap();
+        let rows = php["Cascade Fields"].as_array().expect("PHP output is keyed by sheet");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["major_value"], "200");
+        assert_eq!(rows[1]["main_description"], "");
+    }
+
+    #[test]
+    fn test_cli_nested_format_groups_by_level() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("tree.json");
+        let json = export_json(Path::new("resources/Cascade Sample.xlsx"), &output, &["--sheet", "Cascade Fields", "-F", "nested"]);
+        let mains = json["data"][0]["rows"].as_array().unwrap();
+        assert_eq!(mains.len(), 2);
+        assert_eq!(mains[0]["value"], "CAT001");
+        assert_eq!(mains[0]["label"], "Category");
+        let minor = &mains[1]["children"][0]["children"][0]["children"][0];
+        assert_eq!((minor["level"].as_str(), minor["value"].as_str()), (Some("minor"), Some("1.5")));
+        assert!(minor.get("children").is_none());
+        assert_eq!(json["metadata"]["valid_records"], 2);
+    }
+
+    #[test]
+    fn test_invalid_format_is_rejected() {
+        let result = Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--format", "yaml-ish"]);
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("Unknown output format: yaml-ish"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_delimiter_accepts_tab_and_rejects_several_characters() {
+        let args = parse_test_args(vec!["excel-to-json", "input.xlsx", "-F", "csv", "--delimiter", "tab"]);
+        assert_eq!(args.delimiter, '\t');
+
+        let err = Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--delimiter", ";;"]).unwrap_err().to_string();
+        assert!(err.contains("Delimiter must be a single character or 'tab', got ';;'"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_cli_strict_unique_fails_on_repeated_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("keys.xlsx"), 0, |sheet| {
+            for (row, (main, sub)) in [("M1", "S1"), ("M1", "S2"), ("M1", "S1")].iter().enumerate() {
+                sheet.write_string(row as u32 + 1, 1, *main).unwrap();
+                sheet.write_string(row as u32 + 1, 4, *sub).unwrap();
+            }
+        });
+        let output = temp_dir.path().join("out.json");
+
+        // Duplicates are allowed by default
+        let json = export_json(&input, &output, &[]);
+        assert_eq!(json["data"][0]["rows"].as_array().unwrap().len(), 3);
+
+        let args = vec!["excel-to-json", input.to_str().unwrap(), "-f", output.to_str().unwrap(), "--strict-unique"];
+        let error = run(parse_test_args(args)).expect_err("A repeated key should fail the run");
+        assert!(error.to_string().contains("Row 4: duplicate composite key (main_value=M1, sub_value=S1), first seen at row 2"), "got: {}", error);
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(json["success"], false);
+    }
+
+    #[test]
+    fn test_cli_fail_fast_reports_first_invalid_row() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("template.xlsx"), 0, |sheet| {
+            // Rows 4 and 6 have a label but no main_value
+            for (row, main) in ["M1", "M2", "", "M4", ""].iter().enumerate() {
+                sheet.write_string(row as u32 + 1, 0, "Label").unwrap();
+                sheet.write_string(row as u32 + 1, 1, *main).unwrap();
+            }
+            sheet.write_string(1, 11, "").unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+
+        // Invalid rows are counted by default
+        let json = export_json(&input, &output, &[]);
+        assert_eq!(json["metadata"]["invalid_records"], 2);
+
+        let args = vec!["excel-to-json", input.to_str().unwrap(), "-f", output.to_str().unwrap(), "--fail-fast"];
+        let error = run(parse_test_args(args)).expect_err("An invalid row should fail the run");
+        assert!(error.to_string().contains("Row 4: Incomplete composite keys"), "got: {}", error);
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["details"]["row_number"], 4);
+    }
+
+    #[test]
+    fn test_cli_validate_fails_on_invalid_records_and_strict_warnings() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("validate.xlsx");
+        let mut workbook = Workbook::new();
+        for (name, mains) in [("Clean", ["M1", "M1"]), ("Broken", ["M1", ""])] {
+            let sheet = workbook.add_worksheet().set_name(name).unwrap();
+            write_headers(sheet, 0);
+            for (row, main) in mains.iter().enumerate() {
+                sheet.write_string(row as u32 + 1, 1, *main).unwrap();
+                sheet.write_string(row as u32 + 1, 4, "S1").unwrap();
+            }
+        }
+        // One column short of a cascade record, as if read with an offset
+        let narrow = workbook.add_worksheet().set_name("Narrow").unwrap();
+        for (col, field) in models::CascadeField::FIELD_NAMES[1..].iter().enumerate() {
+            narrow.write_string(0, col as u16, *field).unwrap();
+            narrow.write_string(1, col as u16, "x").unwrap();
+        }
+        workbook.save(&input).unwrap();
+
+        let validate = |extra: &[&str]| {
+            let mut args = vec!["excel-to-json", input.to_str().unwrap(), "--validate"];
+            args.extend_from_slice(extra);
+            run(parse_test_args(args))
+        };
+
+        validate(&["--sheet", "Clean"]).expect("A clean sheet should pass");
+        validate(&["--sheet", "Clean", "--dedupe"]).expect("Warnings only fail with --strict");
+
+        let error = validate(&["--sheet", "Clean", "--dedupe", "--strict"]).expect_err("Warnings should fail with --strict");
+        assert!(error.downcast_ref::<ValidationFailed>().is_some(), "got: {}", error);
+        assert_eq!(error.to_string(), "Validation failed: 0 invalid record(s), 1 warning(s)");
+        assert_eq!(ExitStatus::of_error(&error).code(), 4);
+
+        let error = validate(&["--sheet", "Broken"]).expect_err("An invalid record should fail");
+        assert!(error.downcast_ref::<ValidationFailed>().is_some(), "got: {}", error);
+
+        let error = validate(&["--sheet", "Narrow"]).expect_err("A malformed row should fail");
+        // The sheet's column count is reported too
+        assert_eq!(error.to_string(), "Validation failed: 0 invalid record(s), 1 malformed row(s), 1 warning(s)");
+
+        // Validation never writes data, so it cannot be combined with an output file
+        assert!(Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--validate", "-f", "out.json"]).is_err());
+        assert!(Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--strict"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_map_reads_fields_from_reordered_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("reordered.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Data").unwrap();
+        for (col, header) in ["Name", "Code", "Notes", "Sub Code"].iter().enumerate() {
+            sheet.write_string(0, col as u16, *header).unwrap();
+        }
+        for (col, value) in ["Category", "CAT001", "ignored", "SUB001"].iter().enumerate() {
+            sheet.write_string(1, col as u16, *value).unwrap();
+        }
+        workbook.save(&input).unwrap();
+
+        let output = temp_dir.path().join("out.json");
+        let json = export_json(&input, &output, &["--map", "main_value=B,sub_value=D,main_label=A"]);
+        let row = &json["data"][0]["rows"][0];
+        assert_eq!(row["main_label"], "Category");
+        assert_eq!(row["main_value"], "CAT001");
+        assert_eq!(row["sub_value"], "SUB001");
+        assert_eq!(row["main_description"], "");
+
+        // The error is reported in the output
+        let json = export_json(&input, &output, &["--map", "main_value=F"]);
+        assert_eq!(json["success"], false);
+        let error = json["error"].as_str().unwrap();
+        assert!(error.contains("main_value=F; the sheet has 4 columns (A-D)"), "got: {}", error);
+    }
+
+    #[test]
+    fn test_cli_no_header_keeps_the_first_row() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("headerless.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Dump").unwrap();
+        for (row, main) in ["M1", "M2"].iter().enumerate() {
+            sheet.write_string(row as u32, 0, "Main").unwrap();
+            sheet.write_string(row as u32, 1, *main).unwrap();
+            sheet.write_string(row as u32, 11, "Desc").unwrap();
+        }
+        workbook.save(&input).unwrap();
+
+        let output = temp_dir.path().join("out.json");
+        let main_values = |extra: &[&str]| -> Vec<String> {
+            export_json(&input, &output, extra)["data"][0]["rows"].as_array().unwrap().iter()
+                .map(|row| row["main_value"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(main_values(&[]), vec!["M2"]);
+        assert_eq!(main_values(&["--no-header"]), vec!["M1", "M2"]);
+        assert_eq!(main_values(&["--no-header", "--header-row", "2"]), vec!["M1", "M2"]);
+
+        let generic = export_json(&input, &output, &["--no-header", "--generic"]);
+        assert_eq!(generic["data"][0]["rows"][0]["column_2"], "M1");
+    }
+
+    #[test]
+    fn test_cli_range_reads_only_the_selected_block() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("report.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Data").unwrap();
+        sheet.write_string(0, 0, "Summary").unwrap();
+        sheet.write_string(1, 0, "Totals").unwrap();
+        for (col, name) in models::CascadeField::FIELD_NAMES.iter().enumerate() {
+            sheet.write_string(3, col as u16 + 1, *name).unwrap();
+        }
+        sheet.write_string(4, 2, "M1").unwrap();
+        sheet.write_string(4, 13, "Notes outside the range").unwrap();
+        sheet.write_string(5, 2, "M2").unwrap();
+        sheet.write_string(7, 2, "Below the range").unwrap();
+        workbook.save(&input).unwrap();
+
+        let output = temp_dir.path().join("out.json");
+        let json = export_json(&input, &output, &["--range", "B4:M7"]);
+        let rows = json["data"][0]["rows"].as_array().unwrap();
+        assert_eq!(rows.iter().map(|row| row["main_value"].as_str().unwrap()).collect::<Vec<_>>(), vec!["M1", "M2"]);
+        assert!(!fs::read_to_string(&output).unwrap().contains("outside"));
+
+        for bad in ["A2:", "F100:A2"] {
+            let error = Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--range", bad]).unwrap_err();
+            assert!(error.to_string().contains(bad), "got: {}", error);
+        }
+    }
+
+    #[test]
+    fn test_cli_filter_flags_are_anded() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = Path::new("resources/Cascade Sample.xlsx");
+        let output = temp_dir.path().join("out.json");
+
+        let json = export_json(input, &output, &["--filter", "main_value=CAT002"]);
+        assert_eq!(json["data"][0]["rows"].as_array().unwrap().len(), 1);
+        assert_eq!(json["data"][0]["rows"][0]["sub_value"], "SUB002");
+        assert_eq!(json["metadata"]["filtered_records"], 2);
+        assert_eq!(json["metadata"]["invalid_records"], 0);
+
+        let json = export_json(input, &output, &["--filter", "main_label=Category", "--filter", "main_description!=,sub_value!=SUB002"]);
+        assert_eq!(json["data"][0]["rows"].as_array().unwrap().len(), 1);
+        assert_eq!(json["data"][0]["rows"][0]["main_value"], "CAT001");
+
+        assert!(Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--filter", "colour=red"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_profile_replaces_records_with_field_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("profile.out");
+        let profile = |format: &str| export(Path::new("resources/Cascade Sample.xlsx"), &output, &["-a", format]);
+
+        let json: serde_json::Value = serde_json::from_str(&profile("--profile=json")).unwrap();
+        let sheet = &json[0];
+        assert_eq!(sheet["sheet"], "Cascade Fields");
+        assert_eq!(sheet["records"], 2);
+        assert_eq!(sheet["fields"].as_array().unwrap().len(), 12);
+        assert_eq!(sheet["fields"][1]["field"], "main_value");
+        assert_eq!(sheet["fields"][1]["non_null"], 2);
+        assert_eq!(sheet["fields"][1]["samples"], serde_json::json!(["CAT001", "CAT002"]));
+        assert_eq!(json[1]["sheet"], "Line Type");
+
+        let table = profile("--profile");
+        assert!(table.starts_with("Sheet: Cascade Fields (2 records)\n"), "{}", table);
+        assert!(table.contains("\nSheet: Line Type (1 record)\n"), "{}", table);
+
+        assert!(Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--profile", "--generic"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_flatten_concatenates_sheets() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("flat.json");
+        let json = export_json(Path::new("resources/Cascade Sample.xlsx"), &output, &["-a", "--flatten"]);
+        let sheets: Vec<&str> = json["data"].as_array().unwrap().iter()
+            .map(|record| record["sheet"].as_str().unwrap())
+            .collect();
+        assert_eq!(sheets, vec!["Cascade Fields", "Cascade Fields", "Line Type"]);
+        assert_eq!(json["data"][0]["main_value"], "CAT001");
+        assert_eq!(json["metadata"]["valid_records"], 3);
+
+        assert!(Args::try_parse_from(vec!["excel-to-json", "input.xlsx", "--flatten", "--sheets-as-object"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_sort_by_sorts_each_sheet() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+        let main_values = |extra: &[&str]| -> Vec<String> {
+            export_json(Path::new("resources/Cascade Sample.xlsx"), &output, extra)["data"][0]["rows"].as_array().unwrap().iter()
+                .map(|row| row["main_value"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(main_values(&["--sort-by", "main_value"]), vec!["CAT001", "CAT002"]);
+        assert_eq!(main_values(&["--sort-by", "main_value", "--sort-desc"]), vec!["CAT002", "CAT001"]);
+
+        for rejected in [vec!["--sort-by", "colour"], vec!["--sort-by", "main_value", "--stream"], vec!["--sort-desc"]] {
+            let mut args = vec!["excel-to-json", "input.xlsx"];
+            args.extend(rejected);
+            assert!(Args::try_parse_from(args).is_err());
+        }
+    }
+
+    #[test]
+    fn test_cli_header_row_below_title_banner() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("template.xlsx"), 2, |sheet| {
+            sheet.write_string(0, 0, "Price List 2026").unwrap();
+            sheet.write_string(3, 1, "M1").unwrap();
+            sheet.write_string(3, 11, "Desc").unwrap();
+            sheet.write_string(5, 0, "Label only").unwrap();
+            sheet.write_string(5, 11, "Desc").unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+        let document = |extra: &[&str]| export_json(&input, &output, extra);
+
+        let json = document(&["--header-row", "3"]);
+        assert_eq!(json["data"][0]["rows"].as_array().unwrap().len(), 1);
+        assert_eq!(json["data"][0]["rows"][0]["main_value"], "M1");
+        let diagnostic = &json["metadata"]["diagnostics"][0];
+        assert_eq!(diagnostic["row_number"], 6);
+        assert_eq!(diagnostic["severity"], "error");
+        assert_eq!(diagnostic["field"], "main_value");
+        assert_eq!(diagnostic["message"], "Incomplete composite keys");
+        assert_eq!(document(&["--skip-rows", "2"])["data"], json["data"]);
+
+        let beyond = document(&["--header-row", "10"]);
+        assert_eq!(beyond["success"], false);
+        let error = beyond["error"].as_str().unwrap();
+        assert!(error.contains("Header row 10 is beyond the last row of sheet 'Data' (6 rows)"), "got: {}", error);
+    }
+
+    #[test]
+    fn test_cli_dedupe_drops_repeated_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("duplicates.xlsx"), 0, |sheet| {
+            for (row, (value, description)) in [("M1", "Original"), ("M2", "Other"), ("M1", "Reworded")].iter().enumerate() {
+                sheet.write_string(row as u32 + 1, 1, *value).unwrap();
+                sheet.write_string(row as u32 + 1, 2, *description).unwrap();
+            }
+        });
+        let output = temp_dir.path().join("out.json");
+        let document = |extra: &[&str]| export_json(&input, &output, extra);
+
+        let json = document(&["--dedupe"]);
+        let rows = json["data"][0]["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["main_description"], "Original");
+        assert_eq!(json["metadata"]["valid_records"], 2);
+        assert_eq!(json["metadata"]["duplicate_records"], 1);
+        assert_eq!(json["metadata"]["diagnostics"][0]["message"], "duplicate of row 2, skipped");
+        assert_eq!(json["metadata"]["diagnostics"][0]["severity"], "warning");
+        assert!(json["metadata"]["warnings"].is_null());
+
+        let legacy = document(&["--dedupe", "--legacy-warnings"]);
+        assert_eq!(legacy["metadata"]["warnings"][0], "Row 4: duplicate of row 2, skipped");
+        assert_eq!(legacy["metadata"]["diagnostics"], json["metadata"]["diagnostics"]);
+
+        let plain = document(&[]);
+        assert_eq!(plain["data"][0]["rows"].as_array().unwrap().len(), 3);
+        assert_eq!(plain["metadata"]["duplicate_records"], 0);
+    }
+
+    #[test]
+    fn test_cli_complete_only_drops_partial_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("levels.xlsx"), 0, |sheet| {
+            // Row 2 has every key, row 3 stops at major_value, row 4 at main_value
+            for (col, value) in [(1, "M1"), (4, "S1"), (7, "J1"), (10, "N1")] {
+                sheet.write_string(1, col, value).unwrap();
+            }
+            for (col, value) in [(1, "M2"), (4, "S2"), (7, "J2")] {
+                sheet.write_string(2, col, value).unwrap();
+            }
+            sheet.write_string(3, 1, "M3").unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+        let document = |extra: &[&str]| export_json(&input, &output, extra);
+
+        let json = document(&["--complete-only"]);
+        let rows = json["data"][0]["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["minor_value"], "N1");
+        assert_eq!(json["metadata"]["valid_records"], 1);
+        assert_eq!(json["metadata"]["filtered_records"], 2);
+        assert_eq!(json["metadata"]["invalid_records"], 0);
+
+        let plain = document(&[]);
+        assert_eq!(plain["data"][0]["rows"].as_array().unwrap().len(), 3);
+        assert_eq!(plain["metadata"]["filtered_records"], 0);
+    }
+
+    #[test]
+    fn test_cli_keep_empty_rows_counts_blanks_as_invalid() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("gaps.xlsx"), 0, |sheet| {
+            // Rows 3 and 4 are left blank
+            for (row, value) in [(1, "M1"), (4, "M2")] {
+                sheet.write_string(row, 1, value).unwrap();
+                sheet.write_string(row, 11, "Desc").unwrap();
+            }
+        });
+        let output = temp_dir.path().join("out.json");
+        let document = |extra: &[&str]| export_json(&input, &output, extra);
+
+        let plain = document(&[]);
+        assert_eq!(plain["metadata"]["total_rows_processed"], 2);
+        assert_eq!(plain["metadata"]["invalid_records"], 0);
+
+        let kept = document(&["--keep-empty-rows"]);
+        assert_eq!(kept["metadata"]["total_rows_processed"], 4);
+        assert_eq!(kept["metadata"]["valid_records"], 2);
+        assert_eq!(kept["metadata"]["invalid_records"], 2);
+        let diagnostics = kept["metadata"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.iter().map(|d| d["row_number"].as_u64().unwrap()).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(diagnostics[0]["message"], "Empty row");
+
+        // The window counts the blank rows, as the sheet does
+        let window = document(&["--keep-empty-rows", "--offset", "1", "--limit", "2"]);
+        assert_eq!(window["metadata"]["total_rows_processed"], 2);
+        assert_eq!(window["metadata"]["valid_records"], 0);
+        let window = document(&["--keep-empty-rows", "--offset", "3"]);
+        assert_eq!(window["data"][0]["rows"][0]["main_value"], "M2");
+    }
+
+    #[test]
+    fn test_cli_footer_rows_and_stop_at_blank_drop_totals() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("totals.xlsx"), 0, |sheet| {
+            for (row, value) in [(1, "M1"), (2, "M2")] {
+                sheet.write_string(row, 1, value).unwrap();
+            }
+            // A blank row, then a totals row
+            sheet.write_string(4, 1, "Total").unwrap();
+            sheet.write_number(4, 8, 2.0).unwrap();
+        });
+        let output = temp_dir.path().join("out.json");
+        let records = |extra: &[&str]| {
+            let json = export_json(&input, &output, extra);
+            let values: Vec<String> = json["data"][0]["rows"].as_array().unwrap().iter()
+                .map(|row| row["main_value"].as_str().unwrap().to_string())
+                .collect();
+            (values, json["metadata"].clone())
+        };
+
+        assert_eq!(records(&[]).0, vec!["M1", "M2", "Total"]);
+        assert_eq!(records(&["--stop-at-blank"]).0, vec!["M1", "M2"]);
+
+        let (values, metadata) = records(&["--footer-rows", "1"]);
+        assert_eq!(values, vec!["M1", "M2"]);
+        let diagnostics = metadata["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics[0]["severity"], "info");
+        assert_eq!(diagnostics[0]["message"], "Ignored row 5 as a footer row");
+
+        // With --keep-empty-rows the blank row above the footer is kept
+        let (values, metadata) = records(&["--footer-rows", "1", "--keep-empty-rows"]);
+        assert_eq!(values.len(), 2);
+        assert_eq!(metadata["total_rows_processed"], 3);
+
+        assert!(Args::try_parse_from(["excel-to-json", "in.xlsx", "--stop-at-blank", "--keep-empty-rows"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_with_row_numbers_reports_spreadsheet_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = write_fixture(&temp_dir.path().join("titled.xlsx"), 2, |sheet| {
+            sheet.write_string(0, 0, "Export title").unwrap();
+            // Row 5 is blank
+            for (row, value) in [(3, "M1"), (5, "M2")] {
+                sheet.write_string(row, 1, value).unwrap();
+            }
+        });
+        let output = temp_dir.path().join("out");
+        let numbered = |extra: &[&str]| {
+            let mut args = vec!["--header-row", "3", "--with-row-numbers"];
+            args.extend_from_slice(extra);
+            export(&input, &output, &args)
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&numbered(&[])).unwrap();
+        let rows = json["data"][0]["rows"].as_array().unwrap();
+        assert_eq!(rows.iter().map(|row| row["source_row"].as_u64().unwrap()).collect::<Vec<_>>(), vec![4, 6]);
+
+        let streamed = numbered(&["--stream"]);
+        let first: serde_json::Value = serde_json::from_str(streamed.lines().next().unwrap()).unwrap();
+        assert_eq!(first["source_row"], 4);
+
+        let csv = numbered(&["-F", "csv"]);
+        assert!(csv.starts_with("sheet,source_row,main_label,"));
+        assert!(csv.contains("\nData,6,,M2,"));
+    }
+
+    #[test]
+    fn test_cli_echo_headers_in_sheet_metadata() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+
+        let first_sheet = excel_reader::ExcelReader::new(&test_file, String::new()).unwrap().get_sheet_names()[0].clone();
+        let (headers, _rows) = excel_reader::ExcelReader::new(&test_file, first_sheet).unwrap().read_with_headers().unwrap();
+
+        let echoed = export_json(&test_file, &output, &["--echo-headers"]);
+        assert_eq!(echoed["data"][0]["metadata"]["source_headers"], serde_json::json!(headers));
+        // Headers differ between sheets, so the totals leave them out
+        assert!(echoed["metadata"].get("source_headers").is_none());
+        assert!(export_json(&test_file, &output, &[])["data"][0]["metadata"].get("source_headers").is_none());
+    }
+
+    #[test]
+    fn test_cli_offset_and_limit_window_each_sheet() {
+        let test_file = get_test_excel_path();
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+
+        let full = export_json(&test_file, &output, &["-s", "Cascade Fields"]);
+        let window = export_json(&test_file, &output, &["-s", "Cascade Fields", "--offset", "5", "--limit", "3"]);
+        assert_eq!(window["data"][0]["rows"].as_array().unwrap()[..], full["data"][0]["rows"].as_array().unwrap()[5..8]);
+        assert_eq!(window["metadata"]["total_rows_processed"], 3);
+        assert_eq!(window["metadata"]["row_window"], serde_json::json!({"offset": 5, "limit": 3}));
+        assert!(full["metadata"].get("row_window").is_none());
+
+        // The limit applies to every sheet, not to the run as a whole
+        let all = export_json(&test_file, &output, &["--all-sheets", "--limit", "2"]);
+        let sheets = all["data"].as_array().unwrap();
+        assert!(sheets.len() > 1);
+        for sheet in sheets {
+            assert_eq!(sheet["metadata"]["total_rows_processed"], 2, "sheet {}", sheet["sheet"]);
+        }
+    }
+
+    #[test]
+    fn test_cli_emit_schema_needs_no_input_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("schema.json");
+
+        run(parse_test_args(vec!["excel-to-json", "--emit-schema", "-f", output.to_str().unwrap()])).unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(schema["title"], "excel-to-json output (--format json)");
+        assert!(schema["properties"]["data"]["items"]["properties"]["rows"]["items"]["properties"]["major_value"].is_object());
+
+        let output = temp_dir.path().join("output.ts");
+        run(parse_test_args(vec!["excel-to-json", "--emit-schema", "typescript", "--generic", "-f", output.to_str().unwrap()])).unwrap();
+        let typescript = fs::read_to_string(&output).unwrap();
+        assert!(typescript.contains("rows: Record<string, string | null>[];"), "{}", typescript);
+
+        assert!(run(parse_test_args(vec!["excel-to-json", "--emit-schema", "--format", "csv"])).is_err());
+        assert!(Args::try_parse_from(vec!["excel-to-json"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_multiple_input_files_are_tagged_and_failures_recorded() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+        let missing = temp_dir.path().join("missing.xlsx");
+        let xlsx = "resources/Cascade Sample.xlsx";
+        let ods = "resources/Cascade Sample.ods";
+
+        run(parse_test_args(vec![
+            "excel-to-json", xlsx, missing.to_str().unwrap(), ods,
+            "-s", "Cascade Fields",
+            "-f", output.to_str().unwrap(),
+        ])).expect("A missing file should not abort the run");
+
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(json["success"], true);
+        let sheets = json["data"].as_array().unwrap();
+        assert_eq!(sheets.iter().map(|sheet| sheet["file"].as_str().unwrap()).collect::<Vec<_>>(), vec![xlsx, ods]);
+        assert_eq!(sheets[0]["rows"], sheets[1]["rows"]);
+
+        // Metadata adds up across files as it does across sheets
+        assert_eq!(json["metadata"]["valid_records"], 4);
+        let file_errors = json["file_errors"].as_array().unwrap();
+        assert_eq!(file_errors.len(), 1);
+        assert_eq!(file_errors[0]["file"], missing.to_str().unwrap());
+        assert!(file_errors[0]["error"].as_str().unwrap().contains("File not found"));
+
+        // A single file keeps the untagged output
+        let json = export_json(Path::new(xlsx), &output, &[]);
+        assert!(json["data"][0].get("file").is_none());
+        assert!(json.get("file_errors").is_none());
+    }
+
+    #[test]
+    fn test_exit_status_tells_missing_files_from_missing_sheets() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.json");
+        let missing = temp_dir.path().join("missing.xlsx");
+        let xlsx = "resources/Cascade Sample.xlsx";
+
+        let status = run(parse_test_args(vec![
+            "excel-to-json", missing.to_str().unwrap(), "-f", output.to_str().unwrap(),
+        ])).unwrap();
+        assert_eq!((status, status.code()), (ExitStatus::FileNotFound, 2));
+
+        let status = run(parse_test_args(vec![
+            "excel-to-json", xlsx, "-s", "No Such Sheet", "-f", output.to_str().unwrap(),
+        ])).unwrap();
+        assert_eq!((status, status.code()), (ExitStatus::SheetNotFound, 3));
+
+        let status = run(parse_test_args(vec!["excel-to-json", xlsx, "-f", output.to_str().unwrap()])).unwrap();
+        assert_eq!((status, status.code()), (ExitStatus::Success, 0));
+    }
 }
@@ -0,0 +1,135 @@
+//! Memory budget estimation for constrained environments.
+//!
+//! Unless output is streamed, the whole record set is held in memory before
+//! it is written out. This module estimates how large that record set is so
+//! the tool can tell when a `--max-memory-mb` budget is exceeded, either from
+//! a sheet's size before it is read or from a finished result.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::memory::MemoryBudget;
+//!
+//! let budget = MemoryBudget::from_megabytes(64);
+//! assert!(!budget.is_exceeded_by(10 * 1024 * 1024));
+//! assert!(budget.is_exceeded_by(100 * 1024 * 1024));
+//! ```
+
+use crate::models::{CascadeField, GenericRecord, ProcessingResult};
+use serde_json::Value;
+
+/// A soft limit on the estimated size of the in-memory record set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    limit_bytes: usize,
+}
+
+impl MemoryBudget {
+    /// Creates a budget of `megabytes` MiB.
+    pub fn from_megabytes(megabytes: u64) -> Self {
+        MemoryBudget {
+            limit_bytes: (megabytes as usize).saturating_mul(1024 * 1024),
+        }
+    }
+
+    /// Returns the budget in bytes.
+    pub fn limit_bytes(&self) -> usize {
+        self.limit_bytes
+    }
+
+    /// Returns true if an estimate of `bytes` does not fit in the budget.
+    pub fn is_exceeded_by(&self, bytes: usize) -> bool {
+        bytes > self.limit_bytes
+    }
+}
+
+/// Estimates the heap and inline size of a single record in bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::memory::estimate_record_bytes;
+/// use excel_to_json::models::CascadeField;
+///
+/// let empty = CascadeField::from_row(vec![None; 12]).unwrap();
+/// let mut row = vec![None; 12];
+/// row[1] = Some("CAT001".to_string());
+/// let filled = CascadeField::from_row(row).unwrap();
+///
+/// assert!(estimate_record_bytes(&filled) > estimate_record_bytes(&empty));
+/// ```
+pub fn estimate_record_bytes(record: &CascadeField) -> usize {
+    let fields: usize = record.field_values().iter().flatten().map(|value| value.len()).sum();
+    let extra: usize = record.extra.iter().map(|(key, value)| key.len() + value_bytes(value)).sum();
+    std::mem::size_of::<CascadeField>() + fields + extra
+}
+
+/// Estimates the size of the record set built from a sheet before it is read.
+///
+/// # Arguments
+///
+/// * `rows` - Number of rows in the sheet
+/// * `text_bytes` - Total length of the sheet's cell values as text
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::memory::{estimate_sheet_bytes, MemoryBudget};
+///
+/// let estimate = estimate_sheet_bytes(100_000, 50 * 1024 * 1024);
+/// assert!(MemoryBudget::from_megabytes(32).is_exceeded_by(estimate));
+/// ```
+pub fn estimate_sheet_bytes(rows: usize, text_bytes: usize) -> usize {
+    rows.saturating_mul(std::mem::size_of::<CascadeField>()).saturating_add(text_bytes)
+}
+
+/// Estimates the size of every record held by a processing result.
+pub fn estimate_result_bytes(result: &ProcessingResult) -> usize {
+    let records = result.records.iter().flatten();
+    let sheet_rows = result.sheet_data.iter().flatten().flat_map(|sheet| sheet.rows.iter());
+    let generic = result.generic_records.iter().flatten()
+        .chain(result.sheet_data.iter().flatten().flat_map(|sheet| sheet.generic_rows.iter().flatten()));
+    records.chain(sheet_rows).map(estimate_record_bytes).sum::<usize>()
+        + generic.map(estimate_generic_bytes).sum::<usize>()
+}
+
+/// Estimates the size of a single header-keyed record in bytes.
+pub fn estimate_generic_bytes(record: &GenericRecord) -> usize {
+    let values: usize = record.0.iter().map(|(key, value)| key.len() + value_bytes(value)).sum();
+    std::mem::size_of::<GenericRecord>() + values
+}
+
+/// Estimates the size of a header-keyed value.
+fn value_bytes(value: &Value) -> usize {
+    let inline = std::mem::size_of::<Value>();
+    match value {
+        Value::String(s) => inline + s.len(),
+        Value::Array(items) => inline + items.iter().map(value_bytes).sum::<usize>(),
+        _ => inline,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProcessingMetadata, SheetData};
+
+    #[test]
+    fn test_estimate_counts_every_sheet() {
+        let record = || {
+            let mut row = vec![None; 12];
+            row[1] = Some("X".repeat(1000));
+            CascadeField::from_row(row).unwrap()
+        };
+        let sheet = |rows| SheetData { sheet: "Sheet".to_string(), rows, generic_rows: None, metadata: None, file: None };
+        let result = ProcessingResult::success_multi_sheet(
+            vec![sheet(vec![record(), record()]), sheet(vec![record()])],
+            ProcessingMetadata::default(),
+        );
+
+        let estimate = estimate_result_bytes(&result);
+
+        assert!(estimate >= 3000, "got {}", estimate);
+        assert!(MemoryBudget::from_megabytes(1).limit_bytes() > estimate);
+    }
+}
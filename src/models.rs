@@ -4,8 +4,11 @@
 //! including the main `CascadeField` struct that represents database records
 //! and supporting types for processing results and error handling.
 
+use crate::headers::HeaderLayout;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 
 /// Represents a single Cascade Field record matching the database schema.
 ///
@@ -52,9 +55,74 @@ pub struct CascadeField {
     pub minor_label: Option<String>,
     pub minor_value: Option<String>,
     pub minor_description: Option<String>,
+    /// Additional columns keyed by header text, populated in hybrid schema mode.
+    /// Values are strings or `null`, or arrays when duplicate headers are merged.
+    #[serde(default, flatten, skip_serializing_if = "IndexMap::is_empty")]
+    pub extra: IndexMap<String, Value>,
+    /// Cascade fields whose source cell was numeric or boolean, populated
+    /// when types are preserved. Used by [`to_typed_array`](Self::to_typed_array).
+    #[serde(skip)]
+    pub cell_types: HashMap<String, CellType>,
+    /// Spreadsheet row the record was read from, set by the processor.
+    #[serde(skip)]
+    pub row_number: Option<usize>,
+}
+
+/// The spreadsheet type of a cell, kept so JSON output can use native types.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::models::CellType;
+/// use serde_json::json;
+///
+/// assert_eq!(CellType::Number.to_value("12.5"), json!(12.5));
+/// assert_eq!(CellType::Number.to_value("12"), json!(12));
+/// assert_eq!(CellType::Bool.to_value("true"), json!(true));
+/// assert_eq!(CellType::Text.to_value("12"), json!("12"));
+/// // Numbers rendered with a display format stay text
+/// assert_eq!(CellType::Number.to_value("50%"), json!("50%"));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CellType {
+    #[default]
+    Text,
+    Number,
+    Bool,
+}
+
+impl CellType {
+    /// Converts a cell's text to a JSON value of this type.
+    ///
+    /// Text that does not parse as the type is returned as a JSON string.
+    pub fn to_value(self, text: &str) -> Value {
+        let typed = match self {
+            CellType::Text => None,
+            CellType::Number => text.parse::<i64>().ok().map(Value::from)
+                .or_else(|| text.parse::<f64>().ok().and_then(|f| serde_json::Number::from_f64(f).map(Value::Number))),
+            CellType::Bool => text.parse::<bool>().ok().map(Value::Bool),
+        };
+        typed.unwrap_or_else(|| Value::String(text.to_string()))
+    }
 }
 
 impl CascadeField {
+    /// Field names in column order, matching the `cascade_fields` table columns.
+    pub const FIELD_NAMES: [&'static str; 12] = [
+        "main_label",
+        "main_value",
+        "main_description",
+        "sub_label",
+        "sub_value",
+        "sub_description",
+        "major_label",
+        "major_value",
+        "major_description",
+        "minor_label",
+        "minor_value",
+        "minor_description",
+    ];
+
     /// Creates a new CascadeField from raw row data.
     ///
     /// This function takes a vector of optional strings representing a row from
@@ -124,7 +192,7 @@ impl CascadeField {
         }
 
         Some(CascadeField {
-            main_label: row.get(0).cloned().flatten(),
+            main_label: row.first().cloned().flatten(),
             main_value: row.get(1).cloned().flatten(),
             main_description: row.get(2).cloned().flatten(),
             sub_label: row.get(3).cloned().flatten(),
@@ -136,9 +204,172 @@ impl CascadeField {
             minor_label: row.get(9).cloned().flatten(),
             minor_value: row.get(10).cloned().flatten(),
             minor_description: row.get(11).cloned().flatten(),
+            extra: IndexMap::new(),
+            cell_types: HashMap::new(),
+            row_number: None,
         })
     }
 
+    /// Creates a CascadeField from a row holding `levels` hierarchy levels.
+    ///
+    /// Each level occupies three columns (label, value, description). The first
+    /// four levels map onto main, sub, major and minor; with fewer levels the
+    /// remaining fields are `None`. Levels beyond the fourth are captured in
+    /// `extra` as `level5_label`, `level5_value`, `level5_description`, and so on.
+    /// Columns past `levels * 3` are ignored, as with [`from_row`](Self::from_row).
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The Excel row data
+    /// * `levels` - Number of label/value/description triples to expect
+    ///
+    /// # Returns
+    ///
+    /// * `Some(CascadeField)` if the row has at least `levels * 3` columns
+    /// * `None` if the row is shorter, or `levels` is zero
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::CascadeField;
+    ///
+    /// // Two levels: main and sub only
+    /// let row: Vec<Option<String>> = ["Cat", "C1", "Category", "Sub", "S1", "Subcategory"]
+    ///     .iter().map(|v| Some(v.to_string())).collect();
+    /// let field = CascadeField::from_row_with_arity(row.clone(), 2).unwrap();
+    /// assert_eq!(field.sub_value, Some("S1".to_string()));
+    /// assert_eq!(field.major_value, None);
+    ///
+    /// // Too short for three levels
+    /// assert!(CascadeField::from_row_with_arity(row, 3).is_none());
+    /// ```
+    pub fn from_row_with_arity(row: Vec<Option<String>>, levels: usize) -> Option<Self> {
+        let width = levels * 3;
+        if levels == 0 || row.len() < width {
+            return None;
+        }
+
+        let mut prefix = row;
+        prefix.truncate(width);
+        let tail = if width > Self::FIELD_NAMES.len() {
+            prefix.split_off(Self::FIELD_NAMES.len())
+        } else {
+            Vec::new()
+        };
+        prefix.resize(Self::FIELD_NAMES.len(), None);
+
+        let mut field = Self::from_row(prefix)?;
+        for (idx, value) in tail.into_iter().enumerate() {
+            let level = 5 + idx / 3;
+            let part = ["label", "value", "description"][idx % 3];
+            field.extra.insert(
+                format!("level{}_{}", level, part),
+                value.map(Value::String).unwrap_or(Value::Null),
+            );
+        }
+
+        Some(field)
+    }
+
+    /// Creates a CascadeField from a row with a fixed prefix and a variable tail.
+    ///
+    /// The first `positional` columns map onto the cascade fields in order (like
+    /// [`from_row`](Self::from_row)); cascade fields beyond that prefix are `None`.
+    /// Every remaining column is captured in `extra` under the key assigned by
+    /// the header layout, which decides how blank and duplicate headers are named.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The Excel row data
+    /// * `positional` - Number of leading columns mapped positionally (at most 12)
+    /// * `layout` - Header layout for the tail columns, starting at `positional`
+    ///
+    /// # Returns
+    ///
+    /// * `Some(CascadeField)` if the row has at least `positional` columns
+    /// * `None` if the row is shorter than the positional prefix
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::headers::{DuplicateHeaderPolicy, HeaderLayout};
+    /// use excel_to_json::models::CascadeField;
+    /// use serde_json::{json, Value};
+    ///
+    /// let headers: Vec<String> = ["Label", "Value", "Color", "Size"]
+    ///     .iter().map(|h| h.to_string()).collect();
+    /// let layout = HeaderLayout::build(&headers, 2, DuplicateHeaderPolicy::Suffix).unwrap();
+    /// let row = vec![
+    ///     Some("Category".to_string()),
+    ///     Some("CAT001".to_string()),
+    ///     Some("Red".to_string()),
+    ///     None,
+    /// ];
+    ///
+    /// let field = CascadeField::from_row_hybrid(row, 2, &layout).unwrap();
+    /// assert_eq!(field.main_value, Some("CAT001".to_string()));
+    /// assert_eq!(field.sub_label, None);
+    /// assert_eq!(field.extra.get("Color"), Some(&json!("Red")));
+    /// assert_eq!(field.extra.get("Size"), Some(&Value::Null));
+    /// ```
+    pub fn from_row_hybrid(row: Vec<Option<String>>, positional: usize, layout: &HeaderLayout) -> Option<Self> {
+        let positional = positional.min(Self::FIELD_NAMES.len());
+        if row.len() < positional {
+            return None;
+        }
+
+        let extra = layout.extract(&row);
+        let mut prefix = row;
+        prefix.truncate(positional);
+        prefix.resize(Self::FIELD_NAMES.len(), None);
+
+        let mut field = Self::from_row(prefix)?;
+        field.extra = extra;
+        Some(field)
+    }
+
+    /// Creates a CascadeField from a row whose columns are not in the
+    /// canonical order.
+    ///
+    /// Each field is read from the column assigned to it by `map`; unmapped
+    /// fields are `None`. Columns not named in the map are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The Excel row data
+    /// * `map` - Column of each mapped field
+    ///
+    /// # Returns
+    ///
+    /// * `Some(CascadeField)` if the row reaches every mapped column
+    /// * `None` if the row is shorter than [`ColumnMap::width`]
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, ColumnMap};
+    ///
+    /// let map = ColumnMap::parse("main_value=B,sub_value=D,main_label=A").unwrap();
+    /// let row: Vec<Option<String>> = ["Category", "CAT001", "ignored", "SUB001"]
+    ///     .iter().map(|v| Some(v.to_string())).collect();
+    ///
+    /// let field = CascadeField::from_row_mapped(row, &map).unwrap();
+    /// assert_eq!(field.main_label, Some("Category".to_string()));
+    /// assert_eq!(field.sub_value, Some("SUB001".to_string()));
+    /// assert_eq!(field.main_description, None);
+    /// ```
+    pub fn from_row_mapped(row: Vec<Option<String>>, map: &ColumnMap) -> Option<Self> {
+        if row.len() < map.width() {
+            return None;
+        }
+
+        let fields = map.columns
+            .iter()
+            .map(|column| column.and_then(|col| row[col].clone()))
+            .collect();
+        Self::from_row(fields)
+    }
+
 
     /// Validates that the record has the required composite keys.
     ///
@@ -240,6 +471,83 @@ impl CascadeField {
     }
 
 
+    /// Returns the field values in column order.
+    ///
+    /// The order matches [`CascadeField::FIELD_NAMES`], which makes it easy to
+    /// emit a record as a row for tabular formats such as CSV.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::CascadeField;
+    ///
+    /// let row = vec![
+    ///     Some("Category".to_string()),
+    ///     Some("CAT001".to_string()),
+    ///     None, None, None, None, None, None, None, None, None, None,
+    /// ];
+    ///
+    /// let field = CascadeField::from_row(row).unwrap();
+    /// let values = field.field_values();
+    /// assert_eq!(values[1], Some("CAT001"));
+    /// assert_eq!(values[2], None);
+    /// ```
+    pub fn field_values(&self) -> [Option<&str>; 12] {
+        [
+            self.main_label.as_deref(),
+            self.main_value.as_deref(),
+            self.main_description.as_deref(),
+            self.sub_label.as_deref(),
+            self.sub_value.as_deref(),
+            self.sub_description.as_deref(),
+            self.major_label.as_deref(),
+            self.major_value.as_deref(),
+            self.major_description.as_deref(),
+            self.minor_label.as_deref(),
+            self.minor_value.as_deref(),
+            self.minor_description.as_deref(),
+        ]
+    }
+
+    /// Returns a mutable reference to a field by its column name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - One of [`CascadeField::FIELD_NAMES`]
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&mut Option<String>)` - The named field
+    /// * `None` - If the name is not a known field
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::CascadeField;
+    ///
+    /// let mut field = CascadeField::from_row(vec![None; 12]).unwrap();
+    /// *field.field_mut("main_value").unwrap() = Some("CAT001".to_string());
+    /// assert!(field.is_valid());
+    /// assert!(field.field_mut("unknown").is_none());
+    /// ```
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Option<String>> {
+        match name {
+            "main_label" => Some(&mut self.main_label),
+            "main_value" => Some(&mut self.main_value),
+            "main_description" => Some(&mut self.main_description),
+            "sub_label" => Some(&mut self.sub_label),
+            "sub_value" => Some(&mut self.sub_value),
+            "sub_description" => Some(&mut self.sub_description),
+            "major_label" => Some(&mut self.major_label),
+            "major_value" => Some(&mut self.major_value),
+            "major_description" => Some(&mut self.major_description),
+            "minor_label" => Some(&mut self.minor_label),
+            "minor_value" => Some(&mut self.minor_value),
+            "minor_description" => Some(&mut self.minor_description),
+            _ => None,
+        }
+    }
+
     /// Converts the CascadeField to a PHP-compatible associative array representation.
     ///
     /// This method creates a JSON object that can be easily consumed by PHP applications.
@@ -273,26 +581,317 @@ impl CascadeField {
     /// assert!(json_str.contains("\"main_description\":\"\""));  // Empty string for None
     /// ```
     pub fn to_php_array(&self) -> Value {
-        json!({
-            "main_label": self.main_label.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "main_value": self.main_value.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "main_description": self.main_description.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "sub_label": self.sub_label.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "sub_value": self.sub_value.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "sub_description": self.sub_description.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "major_label": self.major_label.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "major_value": self.major_value.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "major_description": self.major_description.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "minor_label": self.minor_label.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "minor_value": self.minor_value.as_ref().map(|s| s.as_str()).unwrap_or(""),
-            "minor_description": self.minor_description.as_ref().map(|s| s.as_str()).unwrap_or(""),
+        self.to_php_array_with(false)
+    }
+
+    /// Converts the CascadeField to a PHP-compatible associative array,
+    /// optionally keeping missing fields as `null`.
+    ///
+    /// With `keep_null` set, fields without a value are emitted as JSON `null`
+    /// rather than `""`, so a blank cell can be told apart from an empty
+    /// string; otherwise this is [`to_php_array`](Self::to_php_array).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::CascadeField;
+    /// use serde_json::json;
+    ///
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("CAT001".to_string());
+    /// let field = CascadeField::from_row(row).unwrap();
+    ///
+    /// assert_eq!(field.to_php_array_with(true)["main_label"], json!(null));
+    /// assert_eq!(field.to_php_array_with(false)["main_label"], json!(""));
+    /// assert_eq!(field.to_php_array_with(true)["main_value"], json!("CAT001"));
+    /// ```
+    pub fn to_php_array_with(&self, keep_null: bool) -> Value {
+        let mut map = serde_json::Map::new();
+        for (name, value) in Self::FIELD_NAMES.iter().zip(self.field_values()) {
+            let value = match value {
+                Some(text) => json!(text),
+                None if keep_null => Value::Null,
+                None => json!(""),
+            };
+            map.insert(name.to_string(), value);
+        }
+        for (name, value) in &self.extra {
+            map.insert(name.clone(), php_value(value, keep_null));
+        }
+
+        Value::Object(map)
+    }
+
+    /// Converts the CascadeField to a JSON object that keeps native types.
+    ///
+    /// Like [`to_php_array`](Self::to_php_array), missing fields become empty
+    /// strings, but fields listed in `cell_types` and numeric or boolean
+    /// header-keyed values are emitted as JSON numbers and booleans.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, CellType};
+    /// use serde_json::json;
+    ///
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("12".to_string());
+    /// let mut field = CascadeField::from_row(row).unwrap();
+    /// field.cell_types.insert("main_value".to_string(), CellType::Number);
+    ///
+    /// assert_eq!(field.to_typed_array()["main_value"], json!(12));
+    /// assert_eq!(field.to_php_array()["main_value"], json!("12"));
+    /// ```
+    pub fn to_typed_array(&self) -> Value {
+        let mut array = self.to_php_array();
+
+        if let Value::Object(map) = &mut array {
+            for (name, value) in &self.extra {
+                map.insert(name.clone(), typed_value(value));
+            }
+            for (name, cell_type) in &self.cell_types {
+                if let Some(Value::String(text)) = map.get(name) {
+                    let typed = cell_type.to_value(text);
+                    map.insert(name.clone(), typed);
+                }
+            }
+        }
+
+        array
+    }
+
+    /// Converts the record like [`to_typed_array`](Self::to_typed_array),
+    /// adding the spreadsheet row it was read from as `source_row`.
+    ///
+    /// Records that did not come through the processor have no row number and
+    /// are converted without `source_row`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::CascadeField;
+    /// use serde_json::json;
+    ///
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("CAT001".to_string());
+    /// let mut field = CascadeField::from_row(row).unwrap();
+    /// assert_eq!(field.to_numbered_array().get("source_row"), None);
+    ///
+    /// field.row_number = Some(7);
+    /// assert_eq!(field.to_numbered_array()["source_row"], json!(7));
+    /// ```
+    pub fn to_numbered_array(&self) -> Value {
+        let mut array = self.to_typed_array();
+        if let (Value::Object(map), Some(row_number)) = (&mut array, self.row_number) {
+            map.insert("source_row".to_string(), json!(row_number));
+        }
+        array
+    }
+}
+
+/// Replaces `null` with an empty string, keeping numbers and booleans.
+fn typed_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!(""),
+        Value::Array(items) => Value::Array(items.iter().map(typed_value).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Stringifies numbers and booleans, including inside merged arrays, and
+/// replaces `null` with an empty string unless `keep_null` is set.
+fn php_value(value: &Value, keep_null: bool) -> Value {
+    match value {
+        Value::Null if keep_null => Value::Null,
+        Value::Null => json!(""),
+        Value::Number(n) => Value::String(n.to_string()),
+        Value::Bool(b) => Value::String(b.to_string()),
+        Value::Array(items) => Value::Array(items.iter().map(|item| php_value(item, keep_null)).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Spreadsheet column of each cascade field, for sheets whose columns are not
+/// in the canonical main/sub/major/minor order.
+///
+/// Parsed from a spec such as `main_value=B,sub_value=D,main_label=A` and
+/// used by [`CascadeField::from_row_mapped`]. Fields left out of the spec are
+/// unmapped.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::models::ColumnMap;
+///
+/// let map = ColumnMap::parse("main_value=B,minor_value=AA").unwrap();
+/// assert_eq!(map.column("main_value"), Some(1));
+/// assert_eq!(map.column("minor_value"), Some(26));
+/// assert_eq!(map.column("sub_value"), None);
+/// assert_eq!(map.width(), 27);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnMap {
+    /// 0-based column index of each field, in [`CascadeField::FIELD_NAMES`] order
+    columns: [Option<usize>; 12],
+}
+
+impl ColumnMap {
+    /// Parses a comma-separated list of `field=COLUMN` assignments.
+    ///
+    /// Columns are spreadsheet letters (`A`, `B`, ..., `AA`). Several fields
+    /// may read the same column.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spec is empty, an entry is not `field=COLUMN`,
+    /// a field is unknown or mapped twice, or a column is not valid letters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::ColumnMap;
+    ///
+    /// assert!(ColumnMap::parse("main_value=B").is_ok());
+    /// assert!(ColumnMap::parse("main_value=2").is_err());
+    /// assert!(ColumnMap::parse("bogus=B").is_err());
+    /// assert!(ColumnMap::parse("main_value=B,main_value=C").is_err());
+    /// ```
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        let mut map = ColumnMap::default();
+        if spec.trim().is_empty() {
+            anyhow::bail!("Empty column map; expected FIELD=COLUMN pairs such as main_value=B");
+        }
+
+        for entry in spec.split(',') {
+            let (field, column) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected FIELD=COLUMN, got '{}'", entry.trim()))?;
+            let (field, column) = (field.trim(), column.trim());
+
+            let field_idx = CascadeField::FIELD_NAMES
+                .iter()
+                .position(|name| *name == field)
+                .ok_or_else(|| anyhow::anyhow!(
+                    "Unknown field '{}'. Valid fields: {}",
+                    field,
+                    CascadeField::FIELD_NAMES.join(", ")
+                ))?;
+            let col = crate::cell_ref::column_to_index(column)
+                .ok_or_else(|| anyhow::anyhow!("Invalid column '{}' for field '{}': expected letters such as B or AA", column, field))?;
+
+            if map.columns[field_idx].replace(col).is_some() {
+                anyhow::bail!("Field '{}' is mapped more than once", field);
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Returns the 0-based column index mapped to `field`, if any.
+    pub fn column(&self, field: &str) -> Option<usize> {
+        let field_idx = CascadeField::FIELD_NAMES.iter().position(|name| *name == field)?;
+        self.columns[field_idx]
+    }
+
+    /// Iterates over the mapped fields and their 0-based columns, in
+    /// [`CascadeField::FIELD_NAMES`] order.
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        CascadeField::FIELD_NAMES
+            .iter()
+            .zip(&self.columns)
+            .filter_map(|(field, column)| column.map(|col| (*field, col)))
+    }
+
+    /// Number of columns a row needs to reach every mapped column.
+    pub fn width(&self) -> usize {
+        self.columns.iter().flatten().max().map_or(0, |col| col + 1)
+    }
+
+    /// Checks that every mapped column exists in a sheet `column_count`
+    /// columns wide.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming each field mapped past the last column, with
+    /// the sheet's column count and range.
+    pub fn check_columns(&self, column_count: usize) -> anyhow::Result<()> {
+        let missing: Vec<String> = self.fields()
+            .filter(|(_, col)| *col >= column_count)
+            .map(|(field, col)| format!("{}={}", field, crate::cell_ref::index_to_column(col)))
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let available = match column_count {
+            0 => "the sheet has no columns".to_string(),
+            1 => "the sheet has 1 column (A)".to_string(),
+            n => format!("the sheet has {} columns (A-{})", n, crate::cell_ref::index_to_column(n - 1)),
+        };
+        anyhow::bail!("Mapped column(s) not found: {}; {}", missing.join(", "), available)
+    }
+}
+
+/// A record keyed by the sheet's own header text, used in generic mode.
+///
+/// Unlike [`CascadeField`], a generic record has no fixed schema: each key is
+/// a (deduplicated) header from the sheet and each value is the cell text,
+/// `null` for empty cells, or an array when duplicate headers are merged.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::models::GenericRecord;
+/// use indexmap::IndexMap;
+/// use serde_json::json;
+///
+/// let mut values = IndexMap::new();
+/// values.insert("Header A".to_string(), json!("a"));
+/// values.insert("Header B".to_string(), json!(null));
+/// let record = GenericRecord(values);
+///
+/// assert_eq!(serde_json::to_value(&record).unwrap(), json!({"Header A": "a", "Header B": null}));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GenericRecord(pub IndexMap<String, Value>);
+
+impl GenericRecord {
+    /// Returns true if at least one value is set.
+    pub fn has_values(&self) -> bool {
+        self.0.values().any(|value| match value {
+            Value::Null => false,
+            Value::Array(items) => items.iter().any(|item| !item.is_null()),
+            _ => true,
         })
     }
+
+    /// Converts the record to a JSON object with one entry per header, in
+    /// header order.
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.0.iter().map(|(key, value)| (key.clone(), value.clone())).collect())
+    }
+
+    /// Converts the record to a PHP-compatible object: `null` becomes an empty
+    /// string and numbers and booleans are stringified.
+    pub fn to_php_array(&self) -> Value {
+        self.to_php_array_with(false)
+    }
+
+    /// Like [`to_php_array`](Self::to_php_array), but keeps `null` values
+    /// when `keep_null` is set.
+    pub fn to_php_array_with(&self, keep_null: bool) -> Value {
+        Value::Object(self.0.iter().map(|(key, value)| (key.clone(), php_value(value, keep_null))).collect())
+    }
 }
 
 /// Represents data from a single Excel sheet.
 ///
-/// This struct contains the sheet name and all processed rows from that sheet.
+/// This struct contains the sheet name, all processed rows from that sheet
+/// and, when available, the processing statistics for that sheet alone.
+/// In generic mode the rows are held in `generic_rows` and `rows` is empty.
+/// When several input files are processed together, `file` names the file
+/// the sheet came from.
 ///
 /// # Example
 ///
@@ -304,12 +903,59 @@ impl CascadeField {
 ///     rows: vec![
 ///         // ... CascadeField instances
 ///     ],
+///     generic_rows: None,
+///     metadata: None,
+///     file: None,
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SheetData {
     pub sheet: String,
     pub rows: Vec<CascadeField>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generic_rows: Option<Vec<GenericRecord>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ProcessingMetadata>,
+    /// Input file the sheet was read from; set only for multi-file runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl SheetData {
+    /// Returns the name identifying this sheet in output that keys or labels
+    /// rows by sheet.
+    ///
+    /// This is the sheet name, prefixed with the input file (`jan.xlsx/Data`)
+    /// when the sheet is tagged with one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::SheetData;
+    ///
+    /// let mut sheet = SheetData { sheet: "Data".to_string(), rows: vec![], generic_rows: None, metadata: None, file: None };
+    /// assert_eq!(sheet.key(), "Data");
+    ///
+    /// sheet.file = Some("jan.xlsx".to_string());
+    /// assert_eq!(sheet.key(), "jan.xlsx/Data");
+    /// ```
+    pub fn key(&self) -> String {
+        match &self.file {
+            Some(file) => format!("{}/{}", file, self.sheet),
+            None => self.sheet.clone(),
+        }
+    }
+
+    /// Returns the sheet's rows as JSON objects, whichever mode produced them.
+    ///
+    /// Cascade rows use [`CascadeField::to_typed_array`]; generic rows are
+    /// returned keyed by header.
+    pub fn row_values(&self) -> Vec<Value> {
+        match &self.generic_rows {
+            Some(rows) => rows.iter().map(GenericRecord::to_value).collect(),
+            None => self.rows.iter().map(CascadeField::to_typed_array).collect(),
+        }
+    }
 }
 
 /// Represents the output structure for PHP integration.
@@ -331,8 +977,16 @@ pub struct SheetData {
 ///     total_rows_processed: 100,
 ///     valid_records: 95,
 ///     invalid_records: 5,
+///     malformed_rows: 0,
+///     skipped_records: 0,
+///     duplicate_records: 0,
+///     filtered_records: 0,
+///     row_window: None,
+///     source_headers: None,
+///     detected_columns: None,
 ///     processing_time_ms: 250,
-///     warnings: Some(vec!["Row 10: Missing minor_value".to_string()]),
+///     diagnostics: Vec::new(),
+///     warnings: Some(vec!["Estimated record set exceeds --max-memory-mb".to_string()]),
 /// };
 ///
 /// let success_result = ProcessingResult::success(records, metadata);
@@ -346,23 +1000,36 @@ pub struct SheetData {
 ///         total_rows_processed: 0,
 ///         valid_records: 0,
 ///         invalid_records: 0,
+///         malformed_rows: 0,
+///         skipped_records: 0,
+///         duplicate_records: 0,
+///         filtered_records: 0,
+///         row_window: None,
+///         source_headers: None,
+///         detected_columns: None,
 ///         processing_time_ms: 10,
+///         diagnostics: Vec::new(),
 ///         warnings: None,
 ///     },
 /// );
 /// assert!(!error_result.success);
 /// ```
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ProcessingResult {
     pub success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub records: Option<Vec<CascadeField>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub generic_records: Option<Vec<GenericRecord>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sheet_data: Option<Vec<SheetData>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<ErrorDetails>,
+    /// Input files that could not be processed in a multi-file run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_errors: Option<Vec<FileError>>,
     pub metadata: ProcessingMetadata,
 }
 
@@ -383,21 +1050,74 @@ pub struct ProcessingResult {
 ///         "Data".to_string(),
 ///         "Summary".to_string(),
 ///     ]),
+///     suggested_sheet: None,
 ///     row_number: Some(42),
 ///     column: Some("minor_value".to_string()),
 /// };
 /// ```
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ErrorDetails {
     pub file: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub available_sheets: Option<Vec<String>>,
+    /// The sheet a missing sheet's name is a near miss of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_sheet: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub row_number: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub column: Option<String>,
 }
 
+/// An input file that failed in a multi-file run.
+///
+/// The other files are still processed; their records are output as usual
+/// and each failure is reported once.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::models::{FailureKind, FileError};
+///
+/// let error = FileError {
+///     file: "feb.xlsx".to_string(),
+///     error: "File not found: feb.xlsx".to_string(),
+///     available_sheets: None,
+///     suggested_sheet: None,
+///     row_number: None,
+///     kind: FailureKind::FileNotFound,
+/// };
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct FileError {
+    pub file: String,
+    pub error: String,
+    /// Sheets of the file, when the workbook could be opened
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_sheets: Option<Vec<String>>,
+    /// The sheet a missing sheet's name is a near miss of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_sheet: Option<String>,
+    /// Spreadsheet row the error points at, such as a repeated composite key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row_number: Option<usize>,
+    /// What went wrong, for the command-line tool's exit code; not output
+    #[serde(skip)]
+    pub kind: FailureKind,
+}
+
+/// The category of a [`FileError`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The input file does not exist
+    FileNotFound,
+    /// A requested sheet is not in the workbook, or no sheet matches a pattern
+    SheetNotFound,
+    /// Any other failure, such as an unreadable workbook
+    #[default]
+    Other,
+}
+
 /// Metadata about the processing operation.
 ///
 /// Contains statistics and performance metrics about the import process,
@@ -406,33 +1126,165 @@ pub struct ErrorDetails {
 /// # Example
 ///
 /// ```rust
-/// use excel_to_json::models::ProcessingMetadata;
+/// use excel_to_json::models::{ProcessingMetadata, RowDiagnostic};
 ///
 /// let metadata = ProcessingMetadata {
 ///     total_rows_processed: 1000,
 ///     valid_records: 950,
 ///     invalid_records: 50,
+///     malformed_rows: 0,
+///     skipped_records: 0,
+///     duplicate_records: 0,
+///     filtered_records: 0,
+///     row_window: None,
+///     source_headers: None,
+///     detected_columns: None,
 ///     processing_time_ms: 1500,
-///     warnings: Some(vec![
-///         "Row 100: Duplicate key detected".to_string(),
-///         "Row 250: Missing description fields".to_string(),
-///     ]),
+///     diagnostics: vec![
+///         RowDiagnostic::warning(100, None, "duplicate of row 12, skipped"),
+///         RowDiagnostic::error(250, Some("main_value"), "Incomplete composite keys"),
+///     ],
+///     warnings: None,
 /// };
 ///
 /// // Calculate success rate
 /// let success_rate = (metadata.valid_records as f64 / metadata.total_rows_processed as f64) * 100.0;
 /// println!("Success rate: {:.2}%", success_rate);
 /// ```
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProcessingMetadata {
     pub total_rows_processed: usize,
     pub valid_records: usize,
     pub invalid_records: usize,
+    /// Rows with too few columns to form a record; not counted as invalid
+    #[serde(default)]
+    pub malformed_rows: usize,
+    /// Rows deliberately dropped by a skip rule; not counted as invalid
+    #[serde(default)]
+    pub skipped_records: usize,
+    /// Valid records dropped by `--dedupe` as repeats of an earlier record
+    #[serde(default)]
+    pub duplicate_records: usize,
+    /// Rows excluded by `--filter` or `--complete-only`; not counted as invalid
+    #[serde(default)]
+    pub filtered_records: usize,
+    /// The `--offset`/`--limit` window applied to each sheet, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_window: Option<RowWindow>,
+    /// The sheet's header row as read, one entry per column, when requested
+    /// with `--echo-headers`; set in per-sheet metadata only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_headers: Option<Vec<String>>,
+    /// Number of columns most of the sheet's rows have, when it differs from
+    /// the number the cascade layout expects (see
+    /// [`DataProcessor::process_row`](crate::processor::DataProcessor::process_row));
+    /// set in per-sheet metadata only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_columns: Option<usize>,
     pub processing_time_ms: u128,
+    /// Problems found in individual rows, in row order per sheet
+    #[serde(default)]
+    pub diagnostics: Vec<RowDiagnostic>,
+    /// Messages about the run as a whole, such as a skipped input file
     #[serde(skip_serializing_if = "Option::is_none")]
     pub warnings: Option<Vec<String>>,
 }
 
+/// How serious a [`RowDiagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The row was rejected and produced no record
+    Error,
+    /// The row was kept, or dropped on purpose, but needs attention
+    Warning,
+    /// For information only, such as the detected header row
+    Info,
+}
+
+/// A problem found while processing one spreadsheet row.
+///
+/// Displayed as `Row N: message`, the form row problems took in
+/// [`ProcessingMetadata::warnings`] before diagnostics were structured.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::models::{RowDiagnostic, Severity};
+///
+/// let diagnostic = RowDiagnostic::error(6, Some("main_value"), "Incomplete composite keys");
+/// assert_eq!(diagnostic.severity, Severity::Error);
+/// assert_eq!(diagnostic.to_string(), "Row 6: Incomplete composite keys");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowDiagnostic {
+    /// Spreadsheet row number (1-based, counting the header row)
+    pub row_number: usize,
+    pub severity: Severity,
+    /// The field the problem concerns, if it concerns a single field
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl RowDiagnostic {
+    /// Creates an [`Error`](Severity::Error) diagnostic for a rejected row.
+    pub fn error(row_number: usize, field: Option<&str>, message: impl Into<String>) -> Self {
+        Self::new(row_number, Severity::Error, field, message)
+    }
+
+    /// Creates a [`Warning`](Severity::Warning) diagnostic.
+    pub fn warning(row_number: usize, field: Option<&str>, message: impl Into<String>) -> Self {
+        Self::new(row_number, Severity::Warning, field, message)
+    }
+
+    /// Creates an [`Info`](Severity::Info) diagnostic.
+    pub fn info(row_number: usize, field: Option<&str>, message: impl Into<String>) -> Self {
+        Self::new(row_number, Severity::Info, field, message)
+    }
+
+    fn new(row_number: usize, severity: Severity, field: Option<&str>, message: impl Into<String>) -> Self {
+        RowDiagnostic {
+            row_number,
+            severity,
+            field: field.map(str::to_string),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for RowDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}: {}", self.row_number, self.message)
+    }
+}
+
+/// The window of data rows read from each sheet with `--offset` and `--limit`.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::models::RowWindow;
+///
+/// let window = RowWindow { offset: 100, limit: Some(10) };
+/// assert_eq!(window.to_string(), "rows 101-110 of each sheet");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowWindow {
+    /// Data rows skipped before the window
+    pub offset: usize,
+    /// Maximum data rows read; `None` reads to the end of the sheet
+    pub limit: Option<usize>,
+}
+
+impl std::fmt::Display for RowWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            Some(limit) => write!(f, "rows {}-{} of each sheet", self.offset + 1, self.offset + limit),
+            None => write!(f, "rows {} onwards of each sheet", self.offset + 1),
+        }
+    }
+}
+
 impl ProcessingResult {
     /// Creates a successful processing result.
     ///
@@ -457,7 +1309,15 @@ impl ProcessingResult {
     ///     total_rows_processed: 100,
     ///     valid_records: 100,
     ///     invalid_records: 0,
+    ///     malformed_rows: 0,
+    ///     skipped_records: 0,
+    ///     duplicate_records: 0,
+    ///     filtered_records: 0,
+    ///     row_window: None,
+    ///     source_headers: None,
+    ///     detected_columns: None,
     ///     processing_time_ms: 150,
+    ///     diagnostics: Vec::new(),
     ///     warnings: None,
     /// };
     ///
@@ -469,9 +1329,30 @@ impl ProcessingResult {
         ProcessingResult {
             success: true,
             records: Some(records),
+            generic_records: None,
             sheet_data: None,
             error: None,
             details: None,
+            file_errors: None,
+            metadata,
+        }
+    }
+
+    /// Creates a successful result holding header-keyed generic records.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - Records keyed by the sheet's header text
+    /// * `metadata` - Processing statistics and metrics
+    pub fn success_generic(records: Vec<GenericRecord>, metadata: ProcessingMetadata) -> Self {
+        ProcessingResult {
+            success: true,
+            records: None,
+            generic_records: Some(records),
+            sheet_data: None,
+            error: None,
+            details: None,
+            file_errors: None,
             metadata,
         }
     }
@@ -488,9 +1369,11 @@ impl ProcessingResult {
         ProcessingResult {
             success: true,
             records: None,
+            generic_records: None,
             sheet_data: Some(sheet_data),
             error: None,
             details: None,
+            file_errors: None,
             metadata,
         }
     }
@@ -513,6 +1396,7 @@ impl ProcessingResult {
     /// let details = ErrorDetails {
     ///     file: "data.xlsx".to_string(),
     ///     available_sheets: Some(vec!["Sheet1".to_string()]),
+    ///     suggested_sheet: None,
     ///     row_number: None,
     ///     column: None,
     /// };
@@ -521,7 +1405,15 @@ impl ProcessingResult {
     ///     total_rows_processed: 0,
     ///     valid_records: 0,
     ///     invalid_records: 0,
+    ///     malformed_rows: 0,
+    ///     skipped_records: 0,
+    ///     duplicate_records: 0,
+    ///     filtered_records: 0,
+    ///     row_window: None,
+    ///     source_headers: None,
+    ///     detected_columns: None,
     ///     processing_time_ms: 5,
+    ///     diagnostics: Vec::new(),
     ///     warnings: None,
     /// };
     ///
@@ -539,9 +1431,11 @@ impl ProcessingResult {
         ProcessingResult {
             success: false,
             records: None,
+            generic_records: None,
             sheet_data: None,
             error: Some(error),
             details,
+            file_errors: None,
             metadata,
         }
     }
@@ -550,6 +1444,7 @@ impl ProcessingResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::headers::DuplicateHeaderPolicy;
 
     #[test]
     fn test_cascade_field_creation() {
@@ -624,7 +1519,15 @@ mod tests {
             total_rows_processed: 1,
             valid_records: 1,
             invalid_records: 0,
+            malformed_rows: 0,
+            skipped_records: 0,
+            duplicate_records: 0,
+            filtered_records: 0,
+            row_window: None,
+            source_headers: None,
+            detected_columns: None,
             processing_time_ms: 100,
+            diagnostics: Vec::new(),
             warnings: None,
         };
         
@@ -642,7 +1545,15 @@ mod tests {
             total_rows_processed: 0,
             valid_records: 0,
             invalid_records: 0,
+            malformed_rows: 0,
+            skipped_records: 0,
+            duplicate_records: 0,
+            filtered_records: 0,
+            row_window: None,
+            source_headers: None,
+            detected_columns: None,
             processing_time_ms: 10,
+            diagnostics: Vec::new(),
             warnings: None,
         };
         
@@ -693,6 +1604,9 @@ mod tests {
         let sheet_data = SheetData {
             sheet: "TestSheet".to_string(),
             rows: records.clone(),
+            generic_rows: None,
+            metadata: None,
+            file: None,
         };
         
         assert_eq!(sheet_data.sheet, "TestSheet");
@@ -725,10 +1639,16 @@ mod tests {
             SheetData {
                 sheet: "Sheet1".to_string(),
                 rows: sheet1_records,
+                generic_rows: None,
+                metadata: None,
+                file: None,
             },
             SheetData {
                 sheet: "Sheet2".to_string(),
                 rows: sheet2_records,
+                generic_rows: None,
+                metadata: None,
+                file: None,
             },
         ];
         
@@ -736,7 +1656,15 @@ mod tests {
             total_rows_processed: 2,
             valid_records: 2,
             invalid_records: 0,
+            malformed_rows: 0,
+            skipped_records: 0,
+            duplicate_records: 0,
+            filtered_records: 0,
+            row_window: None,
+            source_headers: None,
+            detected_columns: None,
             processing_time_ms: 100,
+            diagnostics: Vec::new(),
             warnings: None,
         };
         
@@ -752,4 +1680,103 @@ mod tests {
         assert_eq!(result_sheet_data[0].sheet, "Sheet1");
         assert_eq!(result_sheet_data[1].sheet, "Sheet2");
     }
+
+    #[test]
+    fn test_hybrid_row_captures_named_tail() {
+        let headers: Vec<String> = [
+            "Main Label", "Main Value", "Main Description",
+            "Sub Label", "Sub Value", "Sub Description",
+            "Major Label", "Major Value", "Major Description",
+            "Minor Label", "Minor Value", "Minor Description",
+            "Color", "", "Weight",
+        ].iter().map(|h| h.to_string()).collect();
+
+        let mut row: Vec<Option<String>> = (0..12).map(|i| Some(format!("v{}", i))).collect();
+        row.push(Some("Red".to_string()));
+        row.push(Some("unnamed".to_string()));
+        row.push(None);
+
+        let layout = HeaderLayout::build(&headers, 12, DuplicateHeaderPolicy::Suffix).unwrap();
+        let field = CascadeField::from_row_hybrid(row, 12, &layout).expect("Should create field");
+
+        assert_eq!(field.main_value, Some("v1".to_string()));
+        assert_eq!(field.minor_description, Some("v11".to_string()));
+        let keys: Vec<&str> = field.extra.keys().map(|k| k.as_str()).collect();
+        assert_eq!(keys, vec!["Color", "column_14", "Weight"]);
+
+        let php = field.to_php_array();
+        assert_eq!(php["main_value"], "v1");
+        assert_eq!(php["Color"], "Red");
+        assert_eq!(php["column_14"], "unnamed");
+        assert_eq!(php["Weight"], "");
+    }
+
+    #[test]
+    fn test_hybrid_row_shorter_than_prefix() {
+        let headers = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let row = vec![Some("a".to_string()), Some("b".to_string())];
+
+        let layout = HeaderLayout::build(&headers, 3, DuplicateHeaderPolicy::Suffix).unwrap();
+
+        assert!(CascadeField::from_row_hybrid(row, 3, &layout).is_none());
+    }
+
+    #[test]
+    fn test_from_row_with_arity_beyond_four_levels() {
+        let row: Vec<Option<String>> = (0..18).map(|i| Some(format!("v{}", i))).collect();
+
+        let field = CascadeField::from_row_with_arity(row, 6).expect("Should create field");
+
+        assert_eq!(field.minor_description, Some("v11".to_string()));
+        let keys: Vec<&str> = field.extra.keys().map(|k| k.as_str()).collect();
+        assert_eq!(keys, vec![
+            "level5_label", "level5_value", "level5_description",
+            "level6_label", "level6_value", "level6_description",
+        ]);
+        assert_eq!(field.extra["level6_description"], "v17");
+    }
+
+    #[test]
+    fn test_from_row_with_arity_four_levels_matches_from_row() {
+        let row: Vec<Option<String>> = (0..14).map(|i| Some(format!("v{}", i))).collect();
+
+        let arity = CascadeField::from_row_with_arity(row.clone(), 4).unwrap();
+        let fixed = CascadeField::from_row(row).unwrap();
+
+        assert_eq!(arity.field_values(), fixed.field_values());
+        assert!(arity.extra.is_empty());
+    }
+
+    #[test]
+    fn test_column_map_reads_mapped_columns_and_reports_missing_ones() {
+        let map = ColumnMap::parse(" main_value = c, sub_value=A,main_label=c ").unwrap();
+        let row: Vec<Option<String>> = vec![Some("S1".to_string()), None, Some("M1".to_string())];
+
+        let field = CascadeField::from_row_mapped(row.clone(), &map).unwrap();
+        assert_eq!(field.main_value.as_deref(), Some("M1"));
+        assert_eq!(field.main_label.as_deref(), Some("M1"));
+        assert_eq!(field.sub_value.as_deref(), Some("S1"));
+        assert_eq!(field.minor_value, None);
+        assert!(CascadeField::from_row_mapped(row[..2].to_vec(), &map).is_none());
+
+        assert!(map.check_columns(3).is_ok());
+        let error = map.check_columns(2).unwrap_err().to_string();
+        assert_eq!(error, "Mapped column(s) not found: main_label=C, main_value=C; the sheet has 2 columns (A-B)");
+
+        let error = ColumnMap::parse("main_value=B,").unwrap_err().to_string();
+        assert!(error.contains("Expected FIELD=COLUMN"), "got: {}", error);
+    }
+
+    #[test]
+    fn test_generic_record_keeps_header_order() {
+        let record = GenericRecord(["Zone", "Amount", "Code"].iter()
+            .map(|header| (header.to_string(), Value::Null))
+            .collect());
+
+        for value in [record.to_value(), record.to_php_array(), serde_json::to_value(&record).unwrap()] {
+            let keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+            assert_eq!(keys, vec!["Zone", "Amount", "Code"]);
+        }
+        assert_eq!(serde_json::to_string(&record.to_value()).unwrap(), r#"{"Zone":null,"Amount":null,"Code":null}"#);
+    }
 }
@@ -0,0 +1,537 @@
+//! Excel number format support for display-formatted output.
+//!
+//! calamine returns raw cell values and does not expose the number format
+//! attached to each cell, so `50%` arrives as `0.5` and `$1,000` as `1000`.
+//! This module reads the format codes straight from the xlsx package
+//! (`xl/styles.xml` plus the worksheet XML) and renders numbers the way
+//! Excel displays them.
+//!
+//! Only the common numeric formats are rendered: fixed decimals, thousands
+//! separators and scaling, percentages, literal prefixes/suffixes such as
+//! currency symbols, and separate negative, zero and text sections. Date,
+//! time, scientific, fraction and conditional formats return `None` so
+//! callers fall back to the raw value.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::number_format::format_number;
+//!
+//! assert_eq!(format_number(0.5, "0%"), Some("50%".to_string()));
+//! assert_eq!(format_number(1000.0, "\"$\"#,##0.00"), Some("$1,000.00".to_string()));
+//! assert_eq!(format_number(45000.0, "yyyy-mm-dd"), None);
+//! ```
+
+use crate::cell_ref::CellRef;
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader as XmlReader;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+/// Number format codes for the cells of one worksheet.
+///
+/// Cells using the `General` format are not recorded.
+#[derive(Debug, Clone, Default)]
+pub struct CellFormats {
+    codes: HashMap<(usize, usize), String>,
+}
+
+impl CellFormats {
+    /// Loads the number format codes for every styled cell in a sheet.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the `.xlsx` file
+    /// * `sheet_name` - Name of the worksheet
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the package cannot be opened, the sheet is not
+    /// listed in the workbook, or a part cannot be parsed.
+    pub fn load<P: AsRef<Path>>(path: P, sheet_name: &str) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Failed to open Excel file: {:?}", path.as_ref()))?;
+        Self::from_reader(BufReader::new(file), sheet_name)
+    }
+
+    /// Loads the number format codes of a sheet from an xlsx package held in
+    /// any seekable reader, such as a workbook read into memory.
+    ///
+    /// # Errors
+    ///
+    /// As for [`load`](Self::load).
+    pub fn from_reader<R: Read + Seek>(reader: R, sheet_name: &str) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(reader)
+            .context("Failed to read xlsx package")?;
+
+        let sheet_path = sheet_path(&mut archive, sheet_name)?;
+        let styles = match read_part(&mut archive, "xl/styles.xml") {
+            Ok(xml) => Self::style_codes(&xml)?,
+            Err(_) => Vec::new(),
+        };
+
+        let mut codes = HashMap::new();
+        let sheet_xml = read_part(&mut archive, &sheet_path)?;
+        let mut reader = XmlReader::from_str(&sheet_xml);
+        loop {
+            match reader.read_event().context("Failed to parse worksheet XML")? {
+                Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"c" => {
+                    let (Some(reference), Some(style)) = (attr(&e, b"r"), attr(&e, b"s")) else {
+                        continue;
+                    };
+                    let code = style.parse::<usize>().ok().and_then(|idx| styles.get(idx).cloned().flatten());
+                    if let (Some(code), Ok(cell)) = (code, CellRef::parse(&reference)) {
+                        codes.insert((cell.row, cell.col), code);
+                    }
+                },
+                Event::Eof => break,
+                _ => {},
+            }
+        }
+
+        Ok(CellFormats { codes })
+    }
+
+    /// Returns the format code for a cell (zero-based, absolute position).
+    pub fn get(&self, row: usize, col: usize) -> Option<&str> {
+        self.codes.get(&(row, col)).map(String::as_str)
+    }
+
+    /// Returns the format code of each cell style (`cellXfs` entry), by index.
+    fn style_codes(styles: &str) -> Result<Vec<Option<String>>> {
+        let mut custom: HashMap<u32, String> = HashMap::new();
+        let mut xf_ids = Vec::new();
+        let mut in_cell_xfs = false;
+
+        let mut reader = XmlReader::from_str(styles);
+        loop {
+            match reader.read_event().context("Failed to parse styles XML")? {
+                Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"numFmt" => {
+                    if let (Some(id), Some(code)) = (attr(&e, b"numFmtId"), attr(&e, b"formatCode")) {
+                        if let Ok(id) = id.parse() {
+                            custom.insert(id, code);
+                        }
+                    }
+                },
+                Event::Start(e) if e.local_name().as_ref() == b"cellXfs" => in_cell_xfs = true,
+                Event::End(e) if e.local_name().as_ref() == b"cellXfs" => in_cell_xfs = false,
+                Event::Start(e) | Event::Empty(e) if in_cell_xfs && e.local_name().as_ref() == b"xf" => {
+                    xf_ids.push(attr(&e, b"numFmtId").and_then(|id| id.parse::<u32>().ok()).unwrap_or(0));
+                },
+                Event::Eof => break,
+                _ => {},
+            }
+        }
+
+        Ok(xf_ids
+            .into_iter()
+            .map(|id| custom.get(&id).cloned().or_else(|| builtin_format(id).map(str::to_string)))
+            .collect())
+    }
+}
+
+/// Returns the code of a built-in number format, for the numeric formats
+/// this module can render.
+fn builtin_format(id: u32) -> Option<&'static str> {
+    match id {
+        1 => Some("0"),
+        2 => Some("0.00"),
+        3 => Some("#,##0"),
+        4 => Some("#,##0.00"),
+        9 => Some("0%"),
+        10 => Some("0.00%"),
+        37 => Some("#,##0 ;(#,##0)"),
+        38 => Some("#,##0 ;[Red](#,##0)"),
+        39 => Some("#,##0.00;(#,##0.00)"),
+        40 => Some("#,##0.00;[Red](#,##0.00)"),
+        _ => None,
+    }
+}
+
+/// Resolves a sheet name to its worksheet part via the workbook relationships.
+pub(crate) fn sheet_path<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, sheet_name: &str) -> Result<String> {
+    let workbook = read_part(archive, "xl/workbook.xml")?;
+    let mut rel_id = None;
+    let mut reader = XmlReader::from_str(&workbook);
+    loop {
+        match reader.read_event().context("Failed to parse workbook XML")? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"sheet" && attr(&e, b"name").as_deref() == Some(sheet_name) =>
+            {
+                rel_id = attr(&e, b"id");
+                break;
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+    let rel_id = rel_id.ok_or_else(|| anyhow::anyhow!("Sheet '{}' not found in workbook", sheet_name))?;
+
+    let rels = read_part(archive, "xl/_rels/workbook.xml.rels")?;
+    let mut reader = XmlReader::from_str(&rels);
+    loop {
+        match reader.read_event().context("Failed to parse workbook relationships")? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"Relationship" && attr(&e, b"Id").as_deref() == Some(rel_id.as_str()) =>
+            {
+                let target = attr(&e, b"Target").unwrap_or_default();
+                return Ok(match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                });
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    anyhow::bail!("No worksheet part found for sheet '{}'", sheet_name)
+}
+
+/// Reads a part of the xlsx package as text.
+pub(crate) fn read_part<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Result<String> {
+    let mut part = archive
+        .by_name(name)
+        .with_context(|| format!("Missing xlsx part: {}", name))?;
+    let mut contents = String::new();
+    part.read_to_string(&mut contents)
+        .with_context(|| format!("Failed to read xlsx part: {}", name))?;
+    Ok(contents)
+}
+
+/// Returns the unescaped value of an attribute, matched by local name.
+pub(crate) fn attr(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| {
+            let raw = std::str::from_utf8(&a.value).ok()?;
+            quick_xml::escape::unescape(raw).ok().map(|v| v.into_owned())
+        })
+}
+
+/// Renders a number with an Excel number format code.
+///
+/// A code has up to four `;`-separated sections: positive numbers, negative
+/// numbers, zero and text. Numbers use the zero section when there are
+/// three or more sections and the negative one when there are two or more.
+/// Commas right after the digit placeholders divide by 1,000 each, so
+/// `#,##0,"K"` renders 12345 as `12K`.
+///
+/// Returns `None` for `General` and for formats this module does not render
+/// (dates, times, scientific, fractions, conditions such as `[>=100]`, and
+/// literals between digit placeholders), so the caller can fall back to the
+/// raw value.
+///
+/// # Arguments
+///
+/// * `value` - The raw cell value
+/// * `code` - The cell's number format code, e.g. `0.00%` or `#,##0`
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::number_format::format_number;
+///
+/// assert_eq!(format_number(0.125, "0.0%"), Some("12.5%".to_string()));
+/// assert_eq!(format_number(-1234.5, "#,##0.00;(#,##0.00)"), Some("(1,234.50)".to_string()));
+/// assert_eq!(format_number(0.0, "0.00;-0.00;\"zero\""), Some("zero".to_string()));
+/// assert_eq!(format_number(2_500_000.0, "0.0,,\"M\""), Some("2.5M".to_string()));
+/// assert_eq!(format_number(3.0, "General"), None);
+/// ```
+pub fn format_number(value: f64, code: &str) -> Option<String> {
+    if code.eq_ignore_ascii_case("general") || !value.is_finite() {
+        return None;
+    }
+
+    let sections = split_sections(code);
+    let (section, value) = match (sections.get(1), sections.get(2)) {
+        (_, Some(zero)) if value == 0.0 => (*zero, 0.0),
+        (Some(negative), _) if value < 0.0 => (*negative, value.abs()),
+        _ => (sections[0], value),
+    };
+
+    let mut prefix = String::new();
+    let mut suffix = String::new();
+    let mut int_pattern = String::new();
+    let mut frac_pattern = String::new();
+    let mut in_fraction = false;
+    let mut seen_digits = false;
+    let mut digits_ended = false;
+    let mut grouping = false;
+    // Commas after a digit placeholder group thousands when more digits
+    // follow and scale by 1,000 each when they end the number
+    let mut commas = 0;
+    let mut percent = 0;
+
+    let mut chars = section.chars().peekable();
+    while let Some(c) = chars.next() {
+        let literal = match c {
+            // Digits split by literals, e.g. `000-0000`, are not rendered
+            '0' | '#' | '?' if digits_ended => return None,
+            '0' | '#' | '?' => {
+                if in_fraction {
+                    frac_pattern.push(c);
+                } else {
+                    grouping |= commas > 0;
+                    commas = 0;
+                    int_pattern.push(c);
+                }
+                seen_digits = true;
+                continue;
+            },
+            ',' if seen_digits && !digits_ended => {
+                commas += 1;
+                continue;
+            },
+            '.' if !in_fraction && !digits_ended => {
+                in_fraction = true;
+                seen_digits = true;
+                continue;
+            },
+            '%' => {
+                percent += 1;
+                "%".to_string()
+            },
+            '"' => {
+                let mut text = String::new();
+                for q in chars.by_ref() {
+                    if q == '"' {
+                        break;
+                    }
+                    text.push(q);
+                }
+                text
+            },
+            // `*x` fills the column with `x`; one is written
+            '\\' | '*' => chars.next().map(String::from).unwrap_or_default(),
+            // `_x` pads by the width of `x`
+            '_' => {
+                chars.next();
+                String::new()
+            },
+            '[' => {
+                let mut token = String::new();
+                for b in chars.by_ref() {
+                    if b == ']' {
+                        break;
+                    }
+                    token.push(b);
+                }
+                // `[$€-407]` carries a currency symbol and colours are dropped;
+                // conditions and elapsed times are not rendered
+                match token.strip_prefix('$') {
+                    Some(currency) => currency.split('-').next().unwrap_or_default().to_string(),
+                    None if is_colour(&token) => continue,
+                    None => return None,
+                }
+            },
+            'E' | 'e' | '/' | '@' => return None,
+            c if c.is_ascii_alphabetic() => return None,
+            c => c.to_string(),
+        };
+
+        if seen_digits {
+            digits_ended = true;
+            suffix.push_str(&literal);
+        } else {
+            prefix.push_str(&literal);
+        }
+    }
+
+    // A section of literals only, such as `"zero"`, replaces the number
+    if !seen_digits {
+        return Some(prefix);
+    }
+
+    let scaled = value * 100f64.powi(percent) / 1000f64.powi(commas);
+    let decimals = frac_pattern.len();
+    // Excel rounds halves away from zero, where formatting rounds them to even
+    let factor = 10f64.powi(decimals as i32);
+    let rendered = format!("{:.*}", decimals, (scaled.abs() * factor).round() / factor);
+    let (int_digits, frac_digits) = rendered.split_once('.').unwrap_or((&rendered, ""));
+
+    let min_int = int_pattern.chars().filter(|&c| c == '0').count();
+    let mut int_part = int_digits.trim_start_matches('0').to_string();
+    if int_part.len() < min_int {
+        int_part = format!("{:0>width$}", int_part, width = min_int);
+    }
+    if grouping {
+        int_part = group_thousands(&int_part);
+    }
+
+    // Decimals for `#` and `?` are shown only when they are not trailing zeros
+    let min_frac = frac_pattern.chars().take_while(|&c| c == '0').count();
+    let mut frac_part = frac_digits.to_string();
+    while frac_part.len() > min_frac && frac_part.ends_with('0') {
+        frac_part.pop();
+    }
+
+    let sign = if scaled < 0.0 && rendered.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+    let fraction = if decimals > 0 { format!(".{}", frac_part) } else { String::new() };
+
+    Some(format!("{}{}{}{}{}", sign, prefix, int_part, fraction, suffix))
+}
+
+/// Renders text with the text section of an Excel number format code.
+///
+/// The text section is the fourth section of the code, or its only section
+/// when that contains `@`, and `@` stands for the cell's text. Returns `None`
+/// when the code has no text section, as Excel then shows the text as is.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::number_format::format_text;
+///
+/// assert_eq!(format_text("A12", "0;-0;0;\"Code \"@"), Some("Code A12".to_string()));
+/// assert_eq!(format_text("A12", "0.00"), None);
+/// ```
+pub fn format_text(text: &str, code: &str) -> Option<String> {
+    let section = match split_sections(code).as_slice() {
+        [_, _, _, section, ..] => *section,
+        [section] if section.contains('@') => *section,
+        _ => return None,
+    };
+
+    let mut rendered = String::new();
+    let mut chars = section.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '@' => rendered.push_str(text),
+            '"' => rendered.extend(chars.by_ref().take_while(|&q| q != '"')),
+            '\\' | '*' => rendered.extend(chars.next()),
+            '_' => {
+                chars.next();
+            },
+            '[' => {
+                chars.by_ref().find(|&b| b == ']');
+            },
+            c => rendered.push(c),
+        }
+    }
+    Some(rendered)
+}
+
+/// Returns true for a colour token of a format code, such as `Red` or
+/// `Color12`.
+fn is_colour(token: &str) -> bool {
+    const COLOURS: [&str; 8] = ["Black", "Blue", "Cyan", "Green", "Magenta", "Red", "White", "Yellow"];
+    COLOURS.iter().any(|colour| token.eq_ignore_ascii_case(colour))
+        || token.get(..5).is_some_and(|name| name.eq_ignore_ascii_case("color"))
+            && token.len() > 5
+            && token[5..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// Splits a format code into its `;`-separated sections, ignoring quoted text.
+fn split_sections(code: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (idx, c) in code.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                sections.push(&code[start..idx]);
+                start = idx + 1;
+            },
+            _ => {},
+        }
+    }
+    sections.push(&code[start..]);
+    sections
+}
+
+/// Inserts `,` thousands separators into a string of digits.
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::new();
+    for (idx, c) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_xlsxwriter::{Format, Workbook};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_format_number_codes() {
+        assert_eq!(format_number(0.5, "0%").as_deref(), Some("50%"));
+        assert_eq!(format_number(0.12345, "0.00%").as_deref(), Some("12.35%"));
+        assert_eq!(format_number(1234567.891, "#,##0.00").as_deref(), Some("1,234,567.89"));
+        assert_eq!(format_number(1000.0, "[$€-407] #,##0").as_deref(), Some("€ 1,000"));
+        assert_eq!(format_number(0.5, "#.00").as_deref(), Some(".50"));
+        assert_eq!(format_number(-3.0, "0.0").as_deref(), Some("-3.0"));
+        assert_eq!(format_number(-3.0, "0.0;[Red]\"neg \"0.0").as_deref(), Some("neg 3.0"));
+        assert_eq!(format_number(1.5, "0.00E+00"), None);
+        assert_eq!(format_number(45000.0, "dd/mm/yyyy"), None);
+        assert_eq!(format_number(1.5, "0.0#").as_deref(), Some("1.5"));
+        assert_eq!(format_number(1.25, "0.0#").as_deref(), Some("1.25"));
+    }
+
+    #[test]
+    fn test_format_number_scaling_commas() {
+        assert_eq!(format_number(12345.0, "#,##0,\"K\"").as_deref(), Some("12K"));
+        assert_eq!(format_number(2_500_000.0, "#,##0,,\"M\"").as_deref(), Some("3M"));
+        assert_eq!(format_number(2_500_000.0, "#,##0.0,,\"M\"").as_deref(), Some("2.5M"));
+        assert_eq!(format_number(1_234_567_890.0, "#,##0,").as_deref(), Some("1,234,568"));
+        assert_eq!(format_number(-12345.0, "0,\"K\";(0,\"K\")").as_deref(), Some("(12K)"));
+    }
+
+    #[test]
+    fn test_format_number_zero_and_text_sections() {
+        let accounting = "_(\"$\"* #,##0.00_);_(\"$\"* \\(#,##0.00\\);_(\"$\"* \"-\"??_);_(@_)";
+        assert_eq!(format_number(0.0, accounting).as_deref(), Some("$ -"));
+        assert_eq!(format_number(1234.5, accounting).as_deref(), Some("$ 1,234.50"));
+        assert_eq!(format_number(-1234.5, accounting).as_deref(), Some("$ (1,234.50)"));
+        assert_eq!(format_text("n/a", accounting).as_deref(), Some("n/a"));
+
+        assert_eq!(format_number(0.0, "0.00;-0.00;\"zero\"").as_deref(), Some("zero"));
+        assert_eq!(format_number(-1.5, "0.00;-0.00;\"zero\"").as_deref(), Some("-1.50"));
+        assert_eq!(format_number(0.0, "0;-0;").as_deref(), Some(""));
+        // Two sections put zero with the positive numbers
+        assert_eq!(format_number(0.0, "0.0;(0.0)").as_deref(), Some("0.0"));
+
+        assert_eq!(format_text("abc", "0;-0;0;[Blue]\"Note: \"@").as_deref(), Some("Note: abc"));
+        assert_eq!(format_text("abc", "@").as_deref(), Some("abc"));
+        assert_eq!(format_text("abc", "0.00;-0.00"), None);
+    }
+
+    #[test]
+    fn test_format_number_unsupported_codes() {
+        assert_eq!(format_number(150.0, "[>=100]0;0.0"), None);
+        assert_eq!(format_number(5551234.0, "000-0000"), None);
+        assert_eq!(format_number(1.5, "[h]:mm"), None);
+        assert_eq!(format_number(1.5, "@"), None);
+        assert_eq!(format_number(-2.0, "0;[Color10]0").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_load_percentage_cell_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("formats.xlsx");
+
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Rates").unwrap();
+        sheet.write_string(0, 0, "Rate").unwrap();
+        sheet.write_number_with_format(1, 0, 0.5, &Format::new().set_num_format("0%")).unwrap();
+        sheet.write_number(2, 0, 0.25).unwrap();
+        workbook.save(&path).unwrap();
+
+        let formats = CellFormats::load(&path, "Rates").expect("Should load formats");
+
+        assert_eq!(formats.get(1, 0), Some("0%"));
+        assert_eq!(formats.get(2, 0), None);
+        assert!(CellFormats::load(&path, "Missing").is_err());
+    }
+}
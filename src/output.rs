@@ -3,9 +3,17 @@
 //! This module handles the formatting and output of processed Excel data
 //! as JSON for consumption by various systems.
 //!
-//! # Supported Format
+//! # Supported Formats
 //!
 //! - **JSON** - Standard JSON format for API responses and data interchange
+//! - **CSV** - Delimited text with a header row, for spreadsheets and ETL tools
+//! - **PHP array** - The bare records as string-valued JSON objects, ready for
+//!   `json_decode($output, true)`
+//! - **Nested JSON** - The JSON envelope with each sheet's records grouped into
+//!   a main → sub → major → minor tree (see [`crate::tree`])
+//! - **SQL** - `INSERT` statements for the `cascade_fields` table (or another
+//!   table), one per record or batched
+//! - **YAML** - The JSON document written as block-style YAML
 //!
 //! # Example
 //!
@@ -20,7 +28,15 @@
 //!         total_rows_processed: 100,
 //!         valid_records: 95,
 //!         invalid_records: 5,
+//!         malformed_rows: 0,
+//!         skipped_records: 0,
+//!         duplicate_records: 0,
+//!         filtered_records: 0,
+//!         row_window: None,
+//!         source_headers: None,
+//!         detected_columns: None,
 //!         processing_time_ms: 150,
+//!         diagnostics: Vec::new(),
 //!         warnings: None,
 //!     },
 //! );
@@ -32,16 +48,19 @@
 //! # }
 //! ```
 
-use crate::models::ProcessingResult;
-use anyhow::Result;
+use crate::models::{CascadeField, GenericRecord, ProcessingMetadata, ProcessingResult, Severity, SheetData};
+use crate::processor::DataProcessor;
+use crate::tree;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
 use serde_json::{self, json, Value};
+use std::collections::HashSet;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
 /// Output format options for processed data.
 ///
-/// Currently only supports JSON output format.
-///
 /// # Example
 ///
 /// ```rust
@@ -55,6 +74,17 @@ use tracing::info;
 #[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     Json,
+    Csv,
+    PhpArray,
+    NestedJson,
+    Sql,
+    Xml,
+    Yaml,
+    Markdown,
+    Tsv,
+    /// Binary; written with [`crate::parquet::write_parquet`] rather than
+    /// [`OutputFormatter::format_output`]
+    Parquet,
 }
 
 impl std::str::FromStr for OutputFormat {
@@ -62,7 +92,9 @@ impl std::str::FromStr for OutputFormat {
     
     /// Parses an OutputFormat from a string.
     ///
-    /// Accepts "json" (case-insensitive)
+    /// Accepts "json", "csv", "php" (also "php-array"), "nested" (also
+    /// "nested-json"), "sql", "xml", "yaml" (also "yml"), "markdown" (also "md"),
+    /// "tsv" or "parquet"; case-insensitive
     ///
     /// # Example
     ///
@@ -72,16 +104,247 @@ impl std::str::FromStr for OutputFormat {
     ///
     /// assert!(matches!(OutputFormat::from_str("json"), Ok(OutputFormat::Json)));
     /// assert!(matches!(OutputFormat::from_str("JSON"), Ok(OutputFormat::Json)));
+    /// assert!(matches!(OutputFormat::from_str("csv"), Ok(OutputFormat::Csv)));
+    /// assert!(matches!(OutputFormat::from_str("php"), Ok(OutputFormat::PhpArray)));
+    /// assert!(matches!(OutputFormat::from_str("nested"), Ok(OutputFormat::NestedJson)));
+    /// assert!(matches!(OutputFormat::from_str("sql"), Ok(OutputFormat::Sql)));
+    /// assert!(matches!(OutputFormat::from_str("xml"), Ok(OutputFormat::Xml)));
+    /// assert!(matches!(OutputFormat::from_str("yml"), Ok(OutputFormat::Yaml)));
+    /// assert!(matches!(OutputFormat::from_str("md"), Ok(OutputFormat::Markdown)));
+    /// assert!(matches!(OutputFormat::from_str("tsv"), Ok(OutputFormat::Tsv)));
+    /// assert!(matches!(OutputFormat::from_str("parquet"), Ok(OutputFormat::Parquet)));
     /// assert!(OutputFormat::from_str("invalid").is_err());
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "json" => Ok(OutputFormat::Json),
-            _ => Err(format!("Unknown output format: {}. Only 'json' is supported.", s)),
+            "csv" => Ok(OutputFormat::Csv),
+            "php" | "php-array" => Ok(OutputFormat::PhpArray),
+            "nested" | "nested-json" => Ok(OutputFormat::NestedJson),
+            "sql" => Ok(OutputFormat::Sql),
+            "xml" => Ok(OutputFormat::Xml),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "parquet" => Ok(OutputFormat::Parquet),
+            _ => Err(format!("Unknown output format: {}. Supported formats: json, csv, php, nested, sql, xml, yaml, markdown, tsv, parquet.", s)),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Returns the name the format is selected by with `--format`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::PhpArray => "php",
+            OutputFormat::NestedJson => "nested",
+            OutputFormat::Sql => "sql",
+            OutputFormat::Xml => "xml",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+
+    /// Returns the conventional file extension for the format, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Json | OutputFormat::PhpArray | OutputFormat::NestedJson => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Sql => "sql",
+            OutputFormat::Xml => "xml",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+
+    /// Whether the format is binary and can only be written to a file.
+    pub fn is_binary(&self) -> bool {
+        matches!(self, OutputFormat::Parquet)
+    }
+}
+
+/// Settings that tune how a result is rendered in a given format.
+///
+/// Options that do not apply to the selected format are ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::output::OutputOptions;
+///
+/// // Semicolon-separated CSV, as expected by European Excel locales
+/// let options = OutputOptions {
+///     delimiter: ';',
+///     ..OutputOptions::default()
+/// };
+/// assert_eq!(options.delimiter, ';');
+/// ```
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    /// Field separator used by the CSV formatter
+    pub delimiter: char,
+    /// Start CSV output with a UTF-8 byte order mark, so Excel on Windows
+    /// reads it as UTF-8
+    pub csv_bom: bool,
+    /// End CSV lines with `\r\n` instead of `\n`
+    pub crlf: bool,
+    /// Fail TSV output on a value holding a tab or line break instead of
+    /// replacing those with spaces
+    pub tsv_strict: bool,
+    /// Emit multi-sheet JSON `data` as an object keyed by sheet name
+    /// instead of an array of `{sheet, rows}` objects
+    pub sheets_as_object: bool,
+    /// Emit multi-sheet JSON `data` as one array of every sheet's records,
+    /// each tagged with its `sheet`, instead of `{sheet, rows}` objects
+    pub flatten: bool,
+    /// Pretty-print the JSON envelope but keep each record compact on its own line
+    pub line_per_record: bool,
+    /// Emit minified JSON with no indentation or newlines
+    pub compact: bool,
+    /// Table that SQL output inserts into; may be schema-qualified (`app.fields`)
+    pub table: String,
+    /// Rows per SQL `INSERT` statement
+    pub batch_size: usize,
+    /// Leave empty fields out of XML records instead of writing empty elements
+    pub xml_omit_null: bool,
+    /// Emit missing fields of PHP array records as `null` instead of `""`
+    pub php_keep_null: bool,
+    /// Also list each row diagnostic as a `"Row N: message"` string in the
+    /// JSON `warnings`, as before diagnostics were structured
+    pub legacy_warnings: bool,
+    /// Add each cascade record's spreadsheet row as `source_row` to JSON and
+    /// YAML records and as a CSV column
+    pub with_row_numbers: bool,
+    /// Group each sheet's JSON and YAML records into an object keyed by this
+    /// cascade field's value instead of listing them in an array
+    pub group_by: Option<String>,
+    /// Key of the group holding records without a value for `group_by`
+    pub group_null_key: String,
+    /// Output only these cascade fields, in this order, in JSON, YAML, CSV
+    /// and TSV records; `None` outputs all twelve
+    pub columns: Option<Vec<String>>,
+    /// Key of the records in the JSON, nested JSON and YAML envelopes and in
+    /// PHP array errors; see [`parse_root_key`]
+    pub root_key: String,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            delimiter: ',',
+            csv_bom: false,
+            crlf: false,
+            tsv_strict: false,
+            sheets_as_object: false,
+            flatten: false,
+            line_per_record: false,
+            compact: false,
+            table: "cascade_fields".to_string(),
+            batch_size: 1,
+            xml_omit_null: false,
+            php_keep_null: false,
+            legacy_warnings: false,
+            with_row_numbers: false,
+            group_by: None,
+            group_null_key: String::new(),
+            columns: None,
+            root_key: DEFAULT_ROOT_KEY.to_string(),
         }
     }
 }
 
+/// Key of the records in the JSON envelope unless `--root-key` is given.
+pub const DEFAULT_ROOT_KEY: &str = "data";
+
+/// Keys of the JSON envelope other than the records.
+const ENVELOPE_KEYS: [&str; 5] = ["success", "error", "details", "metadata", "file_errors"];
+
+/// Parses the key that holds the records in the JSON envelope.
+///
+/// # Errors
+///
+/// Returns an error for an empty key and for keys the envelope already uses
+/// (`success`, `error`, `details`, `metadata` and `file_errors`).
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::output::parse_root_key;
+///
+/// assert_eq!(parse_root_key("records").unwrap(), "records");
+/// assert!(parse_root_key("metadata").is_err());
+/// ```
+pub fn parse_root_key(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        Err("The root key cannot be empty".to_string())
+    } else if ENVELOPE_KEYS.contains(&s) {
+        Err(format!("'{}' is already used by the output envelope", s))
+    } else {
+        Ok(s.to_string())
+    }
+}
+
+/// Name of the metadata file written by [`OutputFormatter::write_split`].
+pub const SPLIT_SUMMARY_FILE: &str = "_summary.json";
+
+/// Replaces characters that are not allowed in file names on common file
+/// systems (path separators, `:*?"<>|` and control characters) with `_`.
+///
+/// A name made of dots only, such as `..`, is also replaced, so the result
+/// never refers to a directory.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::output::safe_file_name;
+///
+/// assert_eq!(safe_file_name(" Q1/Q2 <draft> "), "Q1_Q2 _draft_");
+/// assert_eq!(safe_file_name(".."), "__");
+/// ```
+pub fn safe_file_name(name: &str) -> String {
+    let name = name.trim();
+    if !name.is_empty() && name.chars().all(|c| c == '.') {
+        return "_".repeat(name.len());
+    }
+    name.chars()
+        .map(|c| if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect()
+}
+
+/// Parses a CSV field delimiter: a single character, or `tab` (also `\t`).
+///
+/// # Errors
+///
+/// Returns an error for an empty or multi-character delimiter, and for a
+/// quote or line break, which CSV reserves.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::output::parse_delimiter;
+///
+/// assert_eq!(parse_delimiter(";").unwrap(), ';');
+/// assert_eq!(parse_delimiter("tab").unwrap(), '\t');
+/// assert!(parse_delimiter(";;").is_err());
+/// ```
+pub fn parse_delimiter(spec: &str) -> Result<char> {
+    if spec.eq_ignore_ascii_case("tab") || spec == "\\t" {
+        return Ok('\t');
+    }
+    let mut chars = spec.chars();
+    match (chars.next(), chars.next()) {
+        (Some('"' | '\n' | '\r'), None) => anyhow::bail!("A quote or line break cannot be used as the CSV delimiter"),
+        (Some(delimiter), None) => Ok(delimiter),
+        _ => anyhow::bail!("Delimiter must be a single character or 'tab', got '{}'", spec),
+    }
+}
+
 /// Handles output formatting for JSON export.
 ///
 /// The `OutputFormatter` provides static methods to format processing results
@@ -104,7 +367,15 @@ impl std::str::FromStr for OutputFormat {
 ///         total_rows_processed: 10,
 ///         valid_records: 10,
 ///         invalid_records: 0,
+///         malformed_rows: 0,
+///         skipped_records: 0,
+///         duplicate_records: 0,
+///         filtered_records: 0,
+///         row_window: None,
+///         source_headers: None,
+///         detected_columns: None,
 ///         processing_time_ms: 50,
+///         diagnostics: Vec::new(),
 ///         warnings: None,
 ///     },
 /// );
@@ -114,7 +385,8 @@ impl std::str::FromStr for OutputFormat {
 /// OutputFormatter::write_to_stdout(&output)?;
 ///
 /// // Or write to file
-/// OutputFormatter::write_to_file(&output, "output.json")?;
+/// let dir = tempfile::TempDir::new()?;
+/// OutputFormatter::write_to_file(&output, dir.path().join("output.json").to_str().unwrap())?;
 ///
 /// // Create a summary report
 /// let summary = OutputFormatter::create_summary(&result);
@@ -150,7 +422,15 @@ impl OutputFormatter {
     ///         total_rows_processed: 5,
     ///         valid_records: 5,
     ///         invalid_records: 0,
+    ///         malformed_rows: 0,
+    ///         skipped_records: 0,
+    ///         duplicate_records: 0,
+    ///         filtered_records: 0,
+    ///         row_window: None,
+    ///         source_headers: None,
+    ///         detected_columns: None,
     ///         processing_time_ms: 25,
+    ///         diagnostics: Vec::new(),
     ///         warnings: None,
     ///     },
     /// );
@@ -162,19 +442,188 @@ impl OutputFormatter {
     /// # }
     /// ```
     pub fn format_output(result: &ProcessingResult, format: OutputFormat) -> Result<String> {
+        Self::format_output_with_options(result, format, &OutputOptions::default())
+    }
+
+    /// Formats the processing result using explicit output options.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The processing result to format
+    /// * `format` - The desired output format
+    /// * `options` - Format-specific settings such as the CSV delimiter
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for [`OutputFormat::Parquet`], which is binary; see
+    /// [`crate::parquet::write_parquet`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::output::{OutputFormatter, OutputFormat, OutputOptions};
+    /// use excel_to_json::models::{ProcessingResult, ProcessingMetadata};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let result = ProcessingResult::success(
+    ///     vec![],
+    ///     ProcessingMetadata {
+    ///         total_rows_processed: 0,
+    ///         valid_records: 0,
+    ///         invalid_records: 0,
+    ///         malformed_rows: 0,
+    ///         skipped_records: 0,
+    ///         duplicate_records: 0,
+    ///         filtered_records: 0,
+    ///         row_window: None,
+    ///         source_headers: None,
+    ///         detected_columns: None,
+    ///         processing_time_ms: 1,
+    ///         diagnostics: Vec::new(),
+    ///         warnings: None,
+    ///     },
+    /// );
+    ///
+    /// let options = OutputOptions { delimiter: ';', ..OutputOptions::default() };
+    /// let csv = OutputFormatter::format_output_with_options(&result, OutputFormat::Csv, &options)?;
+    /// assert!(csv.starts_with("main_label;main_value;"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn format_output_with_options(
+        result: &ProcessingResult,
+        format: OutputFormat,
+        options: &OutputOptions,
+    ) -> Result<String> {
+        match format {
+            OutputFormat::Json => Self::format_json(result, options),
+            OutputFormat::NestedJson => Self::format_nested_json(result, options),
+            OutputFormat::Csv => Self::format_csv(result, options),
+            OutputFormat::PhpArray => Self::format_php_array(result, options),
+            OutputFormat::Sql => Self::format_sql(result, options),
+            OutputFormat::Xml => Self::format_xml(result, options),
+            OutputFormat::Yaml => Self::format_yaml(result, options),
+            OutputFormat::Markdown => Self::format_markdown(result),
+            OutputFormat::Tsv => Self::format_tsv(result, options),
+            OutputFormat::Parquet => anyhow::bail!("Parquet is a binary format; write it with parquet::write_parquet"),
+        }
+    }
+
+    /// Builds the output of `format` as a JSON value instead of text.
+    ///
+    /// Saves callers that embed the library from parsing the formatted output
+    /// back: JSON, nested JSON and PHP array output return the document that
+    /// [`format_output`](Self::format_output) would serialize, and YAML the
+    /// document it renders (the one JSON writes). The text formats (CSV, TSV,
+    /// SQL, XML, Markdown) return their text as a [`Value::String`].
+    ///
+    /// # Errors
+    ///
+    /// As for [`format_output_with_options`](Self::format_output_with_options).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("CAT001".to_string());
+    /// let result = ProcessingResult::success(
+    ///     vec![CascadeField::from_row(row).unwrap()],
+    ///     ProcessingMetadata::default(),
+    /// );
+    ///
+    /// let mut value = OutputFormatter::to_value(&result, OutputFormat::Json)?;
+    /// assert_eq!(value["data"][0]["main_value"], "CAT001");
+    /// value["source"] = "catalog".into();
+    ///
+    /// let csv = OutputFormatter::to_value(&result, OutputFormat::Csv)?;
+    /// assert!(csv.as_str().is_some_and(|text| text.starts_with("main_label,")));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_value(result: &ProcessingResult, format: OutputFormat) -> Result<Value> {
+        Self::to_value_with_options(result, format, &OutputOptions::default())
+    }
+
+    /// Builds the output of `format` as a JSON value, using explicit output
+    /// options; see [`to_value`](Self::to_value).
+    ///
+    /// Options that only affect how JSON text is laid out (`compact`,
+    /// `line_per_record`) have no effect on the value.
+    ///
+    /// # Errors
+    ///
+    /// As for [`format_output_with_options`](Self::format_output_with_options).
+    pub fn to_value_with_options(
+        result: &ProcessingResult,
+        format: OutputFormat,
+        options: &OutputOptions,
+    ) -> Result<Value> {
         match format {
-            OutputFormat::Json => Self::format_json(result),
+            OutputFormat::Json | OutputFormat::Yaml => Ok(Self::json_document(result, options)),
+            OutputFormat::NestedJson => Self::nested_json_document(result, options),
+            OutputFormat::PhpArray => Ok(Self::php_array_document(result, options)),
+            _ => Self::format_output_with_options(result, format, options).map(Value::String),
         }
     }
     
+    /// Formats the result of a query expression over the output of `format`,
+    /// for `--query`.
+    ///
+    /// The query searches the document [`to_value_with_options`](Self::to_value_with_options)
+    /// builds, and what it selects is written as JSON in the layout the
+    /// options select. Only JSON and nested JSON output can be queried.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for other formats and when the query fails, such as a
+    /// function given an argument of the wrong type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter, OutputOptions};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("CAT001".to_string());
+    /// let result = ProcessingResult::success(vec![CascadeField::from_row(row).unwrap()], ProcessingMetadata::default());
+    ///
+    /// let options = OutputOptions { compact: true, ..OutputOptions::default() };
+    /// let query = jmespath::compile("data[].main_value")?;
+    /// assert_eq!(OutputFormatter::format_query(&result, OutputFormat::Json, &options, &query)?, r#"["CAT001"]"#);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn format_query(
+        result: &ProcessingResult,
+        format: OutputFormat,
+        options: &OutputOptions,
+        query: &jmespath::Expression<'_>,
+    ) -> Result<String> {
+        if !matches!(format, OutputFormat::Json | OutputFormat::NestedJson) {
+            anyhow::bail!("Queries apply to JSON output; {} output cannot be queried", format.name());
+        }
+        let document = Self::to_value_with_options(result, format, options)?;
+        let selected = query.search(document)?;
+        Self::render_json(&serde_json::to_value(&*selected)?, options)
+    }
+
     /// Formats the result as JSON.
     ///
     /// Creates a JSON representation with all records converted to a generic
     /// format where None values become empty strings for compatibility.
+    /// Numeric and boolean cells recorded with `--preserve-types` are written
+    /// as JSON numbers and booleans.
     ///
     /// # Arguments
     ///
     /// * `result` - The processing result to format
+    /// * `options` - Output settings; `sheets_as_object` selects the keyed shape
     ///
     /// # Returns
     ///
@@ -203,153 +652,1588 @@ impl OutputFormatter {
     ///   }
     /// }
     /// ```
-    fn format_json(result: &ProcessingResult) -> Result<String> {
+    ///
+    /// With `sheets_as_object`, `data` is instead `{"Sheet1": [...], "Sheet2": [...]}`.
+    /// Keys are the trimmed sheet names; a name that is already taken gets a
+    /// numeric suffix (`Sheet1_2`). Per-sheet metadata is omitted in this shape.
+    ///
+    /// With `flatten`, `data` is instead a single array of all the sheets'
+    /// records in sheet order, each with a `sheet` key (and `file`, in
+    /// multi-file runs); see [`flat_rows_json`](Self::flat_rows_json).
+    ///
+    /// Whether `data` lists sheets or records depends only on the result:
+    /// results with sheet data (`ProcessingResult::success_multi_sheet`, which
+    /// the command-line tool always builds, even for one sheet) list
+    /// `{sheet, rows}` objects, while `ProcessingResult::success` results list
+    /// the records themselves. `options.root_key` renames the `data` key.
+    fn format_json(result: &ProcessingResult, options: &OutputOptions) -> Result<String> {
+        let json = Self::render_json(&Self::json_document(result, options), options)?;
+        info!("Formatted output as JSON ({} bytes)", json.len());
+        Ok(json)
+    }
+
+    /// Builds the document written by [`OutputFormat::Json`], before rendering.
+    fn json_document(result: &ProcessingResult, options: &OutputOptions) -> Value {
         if !result.success {
             // For errors, return an error structure
-            let error_response = json!({
+            let mut error_response = json!({
                 "success": false,
                 "error": result.error.as_ref().unwrap_or(&"Unknown error".to_string()),
                 "data": []
             });
-            return Ok(serde_json::to_string_pretty(&error_response)?);
+            if let Some(details) = &result.details {
+                error_response["details"] = json!(details);
+            }
+            return Self::with_file_errors(Self::with_root_key(error_response, options), result);
         }
         
         // Check if this is a multi-sheet result
-        let data = if let Some(sheet_data) = &result.sheet_data {
+        let data = if let (Some(sheet_data), true) = (&result.sheet_data, options.sheets_as_object) {
+            let mut sheets = serde_json::Map::new();
+            let mut taken = HashSet::new();
+            for sheet in sheet_data {
+                let key = Self::unique_sheet_key(sheet.key().trim(), &mut taken);
+                sheets.insert(key, Self::sheet_rows_json(sheet, options));
+            }
+            Value::Object(sheets)
+        } else if let (Some(sheet_data), true) = (&result.sheet_data, options.flatten) {
+            Self::flat_rows_json(sheet_data, options)
+        } else if let Some(sheet_data) = &result.sheet_data {
             // Format multi-sheet data
             sheet_data.iter()
                 .map(|sheet| {
-                    json!({
+                    let mut sheet_json = json!({
                         "sheet": sheet.sheet,
-                        "rows": sheet.rows.iter()
-                            .map(|record| record.to_php_array())
-                            .collect::<Vec<Value>>()
-                    })
+                        "rows": Self::sheet_rows_json(sheet, options)
+                    });
+                    if let Some(file) = &sheet.file {
+                        sheet_json["file"] = json!(file);
+                    }
+                    if let Some(metadata) = &sheet.metadata {
+                        sheet_json["metadata"] = Self::metadata_json(metadata, options);
+                    }
+                    sheet_json
                 })
                 .collect::<Vec<Value>>()
+                .into()
+        } else if let Some(records) = &result.generic_records {
+            records.iter()
+                .map(GenericRecord::to_value)
+                .collect::<Vec<Value>>()
+                .into()
         } else if let Some(records) = &result.records {
             // Format single-sheet data (backwards compatibility)
-            records.iter()
-                .map(|record| record.to_php_array())
-                .collect()
+            Self::records_json(records, options)
         } else {
-            Vec::new()
+            Value::Array(Vec::new())
         };
         
         // Create the response structure
         let response = json!({
             "success": true,
             "data": data,
-            "metadata": {
-                "total_rows_processed": result.metadata.total_rows_processed,
-                "valid_records": result.metadata.valid_records,
-                "invalid_records": result.metadata.invalid_records,
-                "processing_time_ms": result.metadata.processing_time_ms,
-                "warnings": result.metadata.warnings
-            }
+            "metadata": Self::metadata_json(&result.metadata, options)
         });
         
-        let json = serde_json::to_string_pretty(&response)?;
-        info!("Formatted output as JSON ({} bytes)", json.len());
-        Ok(json)
+        Self::with_file_errors(Self::with_root_key(response, options), result)
     }
-    
-    /// Writes the output to stdout.
-    ///
-    /// Writes the formatted output directly to standard output and flushes
-    /// the buffer to ensure immediate delivery.
-    ///
-    /// # Arguments
-    ///
-    /// * `output` - The formatted string to write
-    ///
-    /// # Returns
+
+    /// Formats the result as YAML.
     ///
-    /// * `Ok(())` - Successfully written to stdout
-    /// * `Err` - If write or flush fails
+    /// The document is the one [`OutputFormat::Json`] writes, including the
+    /// error shape and the fields left out of it, rendered as block-style
+    /// YAML. Strings are double-quoted whenever YAML could read them as
+    /// something else, such as `true`, `null`, `2024-01-01` or `12`.
     ///
     /// # Example
     ///
-    /// ```rust,no_run
-    /// use excel_to_json::output::OutputFormatter;
+    /// ```rust
+    /// use excel_to_json::models::{ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter};
     ///
     /// # fn main() -> anyhow::Result<()> {
-    /// let output = r#"{
-    ///   "success": true,
-    ///   "records": []
-    /// }"#;
-    ///
-    /// OutputFormatter::write_to_stdout(output)?;
+    /// let error = ProcessingResult::error("File not found".to_string(), None, ProcessingMetadata::default());
+    /// let yaml = OutputFormatter::format_output(&error, OutputFormat::Yaml)?;
+    /// assert_eq!(yaml, "success: false\nerror: File not found\ndata: []\n");
     /// # Ok(())
     /// # }
     /// ```
-    pub fn write_to_stdout(output: &str) -> Result<()> {
-        let mut stdout = std::io::stdout();
-        stdout.write_all(output.as_bytes())?;
-        stdout.flush()?;
-        Ok(())
+    fn format_yaml(result: &ProcessingResult, options: &OutputOptions) -> Result<String> {
+        let mut yaml = String::new();
+        match Self::json_document(result, options) {
+            Value::Object(map) => Self::write_yaml_entries(&mut yaml, &map, 0, false),
+            other => yaml.push_str(&format!("{}\n", Self::yaml_scalar(&other))),
+        }
+        info!("Formatted output as YAML ({} bytes)", yaml.len());
+        Ok(yaml)
     }
-    
-    /// Writes the output to a file.
-    ///
-    /// Creates or overwrites a file with the formatted output.
+
+    /// Appends the entries of a non-empty mapping, one `key: value` per line.
     ///
-    /// # Arguments
+    /// With `inline_first`, the first key continues the current line (after a
+    /// sequence's `- `) instead of starting an indented one.
+    fn write_yaml_entries(out: &mut String, map: &serde_json::Map<String, Value>, indent: usize, inline_first: bool) {
+        for (idx, (key, value)) in map.iter().enumerate() {
+            if idx > 0 || !inline_first {
+                out.push_str(&" ".repeat(indent));
+            }
+            out.push_str(&Self::yaml_string(key));
+            out.push(':');
+            Self::write_yaml_node(out, value, indent + 2);
+        }
+    }
+
+    /// Appends a value following a `key:` or `-` on the current line.
+    fn write_yaml_node(out: &mut String, value: &Value, indent: usize) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                out.push('\n');
+                Self::write_yaml_entries(out, map, indent, false);
+            },
+            Value::Array(items) if !items.is_empty() => {
+                out.push('\n');
+                for item in items {
+                    out.push_str(&" ".repeat(indent));
+                    out.push('-');
+                    match item {
+                        Value::Object(map) if !map.is_empty() => {
+                            out.push(' ');
+                            Self::write_yaml_entries(out, map, indent + 2, true);
+                        },
+                        _ => Self::write_yaml_node(out, item, indent + 2),
+                    }
+                }
+            },
+            scalar => {
+                out.push(' ');
+                out.push_str(&Self::yaml_scalar(scalar));
+                out.push('\n');
+            },
+        }
+    }
+
+    /// Renders a scalar, or an empty mapping or sequence, in flow style.
+    fn yaml_scalar(value: &Value) -> String {
+        match value {
+            Value::String(s) => Self::yaml_string(s),
+            Value::Object(_) => "{}".to_string(),
+            Value::Array(_) => "[]".to_string(),
+            // null, booleans and numbers are written as in JSON
+            other => other.to_string(),
+        }
+    }
+
+    /// Writes a string plain when YAML reads it back as the same string,
+    /// otherwise as a double-quoted (JSON-escaped) string.
+    fn yaml_string(s: &str) -> String {
+        let plain = s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && !s.ends_with(' ')
+            && s.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ' '))
+            && s.parse::<f64>().is_err()
+            && !matches!(
+                s.to_lowercase().as_str(),
+                "true" | "false" | "null" | "yes" | "no" | "on" | "off" | "y" | "n"
+            );
+        if plain {
+            s.to_string()
+        } else {
+            Value::String(s.to_string()).to_string()
+        }
+    }
+
+    /// Formats the result as JSON with each sheet's records nested by level.
     ///
-    /// * `output` - The formatted string to write
-    /// * `path` - Path to the output file
+    /// The envelope matches [`OutputFormat::Json`], but every `rows` array (or
+    /// single-sheet `data` array) holds the top-level `main` nodes built by
+    /// [`build_tree`](crate::tree::build_tree) instead of flat records.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// * `Ok(())` - Successfully written to file
-    /// * `Err` - If file creation or write fails
+    /// Returns an error for header-keyed (`--generic`) results, which have no
+    /// cascade levels to nest.
     ///
     /// # Example
     ///
-    /// ```rust,no_run
-    /// use excel_to_json::output::OutputFormatter;
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter};
     ///
     /// # fn main() -> anyhow::Result<()> {
-    /// let output = "main_label,main_value\nCategory,CAT001";
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("CAT001".to_string());
+    /// row[4] = Some("SUB001".to_string());
+    /// let result = ProcessingResult::success(
+    ///     vec![CascadeField::from_row(row).unwrap()],
+    ///     ProcessingMetadata::default(),
+    /// );
     ///
-    /// OutputFormatter::write_to_file(output, "output.csv")?;
-    /// println!("Output written to output.csv");
+    /// let json: serde_json::Value = serde_json::from_str(
+    ///     &OutputFormatter::format_output(&result, OutputFormat::NestedJson)?,
+    /// )?;
+    /// assert_eq!(json["data"][0]["value"], "CAT001");
+    /// assert_eq!(json["data"][0]["children"][0]["level"], "sub");
     /// # Ok(())
     /// # }
     /// ```
-    pub fn write_to_file(output: &str, path: &str) -> Result<()> {
-        std::fs::write(path, output)?;
-        info!("Output written to file: {}", path);
-        Ok(())
+    fn format_nested_json(result: &ProcessingResult, options: &OutputOptions) -> Result<String> {
+        let json = Self::render_json(&Self::nested_json_document(result, options)?, options)?;
+        info!("Formatted output as nested JSON ({} bytes)", json.len());
+        Ok(json)
     }
-    
-    /// Creates a summary report of the processing.
-    ///
-    /// Generates a human-readable summary of the processing results,
-    /// including success/failure status, record counts, warnings, and timing.
-    ///
-    /// # Arguments
-    ///
-    /// * `result` - The processing result to summarize
-    ///
-    /// # Returns
+
+    /// Builds the document written by [`OutputFormat::NestedJson`], before rendering.
+    fn nested_json_document(result: &ProcessingResult, options: &OutputOptions) -> Result<Value> {
+        if !result.success {
+            return Ok(Self::json_document(result, options));
+        }
+        let generic = result.generic_records.is_some()
+            || result.sheet_data.iter().flatten().any(|sheet| sheet.generic_rows.is_some());
+        if generic {
+            anyhow::bail!("Nested JSON needs cascade records; it cannot be used with --generic");
+        }
+
+        let tree = |records: &[CascadeField]| serde_json::to_value(tree::build_tree(records));
+        let data = if let (Some(sheet_data), true) = (&result.sheet_data, options.sheets_as_object) {
+            let mut sheets = serde_json::Map::new();
+            let mut taken = HashSet::new();
+            for sheet in sheet_data {
+                let key = Self::unique_sheet_key(sheet.key().trim(), &mut taken);
+                sheets.insert(key, tree(&sheet.rows)?);
+            }
+            Value::Object(sheets)
+        } else if let Some(sheet_data) = &result.sheet_data {
+            let mut sheets = Vec::new();
+            for sheet in sheet_data {
+                let mut sheet_json = json!({
+                    "sheet": sheet.sheet,
+                    "rows": tree(&sheet.rows)?
+                });
+                if let Some(file) = &sheet.file {
+                    sheet_json["file"] = json!(file);
+                }
+                if let Some(metadata) = &sheet.metadata {
+                    sheet_json["metadata"] = Self::metadata_json(metadata, options);
+                }
+                sheets.push(sheet_json);
+            }
+            Value::Array(sheets)
+        } else {
+            tree(result.records.as_deref().unwrap_or_default())?
+        };
+
+        let response = json!({
+            "success": true,
+            "data": data,
+            "metadata": Self::metadata_json(&result.metadata, options)
+        });
+
+        Ok(Self::with_file_errors(Self::with_root_key(response, options), result))
+    }
+
+    /// Formats the result as a PHP-ready array.
     ///
-    /// Formatted summary string with emoji indicators
+    /// Emits only the records, as JSON objects whose values are all strings
+    /// (see [`CascadeField::to_php_array`]), so that `json_decode($output, true)`
+    /// yields a plain list of associative arrays. With `options.php_keep_null`
+    /// missing fields are `null` instead of empty strings. Multi-sheet results
+    /// become an object keyed by sheet name. Failures produce the same error
+    /// structure as the JSON format.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use excel_to_json::models::{ProcessingResult, ProcessingMetadata};
-    /// use excel_to_json::output::OutputFormatter;
+    /// use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter};
     ///
-    /// // Success case
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("CAT001".to_string());
     /// let result = ProcessingResult::success(
-    ///     vec![],
-    ///     ProcessingMetadata {
-    ///         total_rows_processed: 100,
-    ///         valid_records: 95,
-    ///         invalid_records: 5,
-    ///         processing_time_ms: 150,
+    ///     vec![CascadeField::from_row(row).unwrap()],
+    ///     ProcessingMetadata::default(),
+    /// );
+    ///
+    /// let php = OutputFormatter::format_output(&result, OutputFormat::PhpArray)?;
+    /// let records: serde_json::Value = serde_json::from_str(&php)?;
+    /// assert_eq!(records[0]["main_value"], "CAT001");
+    /// assert_eq!(records[0]["main_label"], "");
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn format_php_array(result: &ProcessingResult, options: &OutputOptions) -> Result<String> {
+        let php = Self::render_json(&Self::php_array_document(result, options), options)?;
+        info!("Formatted output as PHP array ({} bytes)", php.len());
+        Ok(php)
+    }
+
+    /// Builds the document written by [`OutputFormat::PhpArray`], before rendering.
+    fn php_array_document(result: &ProcessingResult, options: &OutputOptions) -> Value {
+        if !result.success {
+            let error_response = json!({
+                "success": false,
+                "error": result.error.as_deref().unwrap_or("Unknown error"),
+                "data": []
+            });
+            return Self::with_file_errors(Self::with_root_key(error_response, options), result);
+        }
+
+        let php_rows = |records: &[CascadeField], generic: Option<&Vec<GenericRecord>>| -> Value {
+            match generic {
+                Some(rows) => rows.iter().map(|row| row.to_php_array_with(options.php_keep_null)).collect(),
+                None => records.iter().map(|record| record.to_php_array_with(options.php_keep_null)).collect(),
+            }
+        };
+
+        if let Some(sheet_data) = &result.sheet_data {
+            let mut sheets = serde_json::Map::new();
+            let mut taken = HashSet::new();
+            for sheet in sheet_data {
+                let key = Self::unique_sheet_key(sheet.key().trim(), &mut taken);
+                sheets.insert(key, php_rows(&sheet.rows, sheet.generic_rows.as_ref()));
+            }
+            Value::Object(sheets)
+        } else {
+            php_rows(result.records.as_deref().unwrap_or_default(), result.generic_records.as_ref())
+        }
+    }
+
+    /// Formats the result as SQL `INSERT` statements.
+    ///
+    /// Each record becomes a row of `options.table`; `options.batch_size` rows
+    /// share one multi-row `VALUES` statement. Cascade records use the twelve
+    /// cascade field columns (plus header-keyed columns in hybrid mode);
+    /// generic records use the union of their keys. `None` and missing values
+    /// are written as `NULL`, text is single-quoted with embedded quotes
+    /// doubled, and numbers and booleans kept with `--preserve-types` are
+    /// written unquoted. Identifiers that are not plain words are
+    /// double-quoted. Multi-sheet results start each sheet with a
+    /// `-- Sheet: <name>` comment.
+    ///
+    /// Error results, and input files that failed in a multi-file run, are
+    /// written as comments, so the output stays valid SQL.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut row = vec![None; 12];
+    /// row[0] = Some("Children's".to_string());
+    /// row[1] = Some("CAT001".to_string());
+    /// let result = ProcessingResult::success(
+    ///     vec![CascadeField::from_row(row).unwrap()],
+    ///     ProcessingMetadata::default(),
+    /// );
+    ///
+    /// let sql = OutputFormatter::format_output(&result, OutputFormat::Sql)?;
+    /// assert!(sql.starts_with("INSERT INTO cascade_fields (main_label, main_value, main_description,"));
+    /// assert!(sql.contains("VALUES ('Children''s', 'CAT001', NULL,"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn format_sql(result: &ProcessingResult, options: &OutputOptions) -> Result<String> {
+        let mut errors = Vec::new();
+        if !result.success {
+            errors.push(result.error.as_deref().unwrap_or("Unknown error").to_string());
+        }
+        for file_error in result.file_errors.iter().flatten() {
+            errors.push(format!("{}: {}", file_error.file, file_error.error));
+        }
+        let mut sql = String::new();
+        for error in &errors {
+            for line in format!("Error: {}", error).lines() {
+                sql.push_str(&format!("-- {}\n", line));
+            }
+        }
+        if !result.success {
+            return Ok(sql);
+        }
+
+        let rows = |records: &[CascadeField], generic: Option<&Vec<GenericRecord>>| -> Vec<IndexMap<String, Value>> {
+            match generic {
+                Some(rows) => rows.iter().map(|record| record.0.clone()).collect(),
+                None => records.iter().map(Self::typed_row).collect(),
+            }
+        };
+
+        let table = Self::sql_table_name(&options.table);
+        if let Some(sheet_data) = &result.sheet_data {
+            for (idx, sheet) in sheet_data.iter().enumerate() {
+                if idx > 0 || !errors.is_empty() {
+                    sql.push('\n');
+                }
+                sql.push_str(&format!("-- Sheet: {}\n", sheet.key().replace(['\r', '\n'], " ")));
+                let sheet_rows = rows(&sheet.rows, sheet.generic_rows.as_ref());
+                Self::write_sql_inserts(&mut sql, &table, &sheet_rows, options.batch_size);
+            }
+        } else {
+            let records = rows(result.records.as_deref().unwrap_or_default(), result.generic_records.as_ref());
+            Self::write_sql_inserts(&mut sql, &table, &records, options.batch_size);
+        }
+
+        info!("Formatted output as SQL ({} bytes)", sql.len());
+        Ok(sql)
+    }
+
+    /// Formats the result as an XML document.
+    ///
+    /// Records become `<record>` elements under a `<records>` root, with one
+    /// child element per field in field order. Multi-sheet results wrap each
+    /// sheet's records in `<sheet name="...">` (plus a `file` attribute in
+    /// multi-file runs). Text is escaped, so `<`, `>`, `&` and quotes are
+    /// written as entities.
+    ///
+    /// Empty fields are written as empty elements (`<sub_value/>`) by default,
+    /// or left out with `options.xml_omit_null`. Header texts used as field
+    /// names (`--generic` and hybrid mode) have characters that are not valid
+    /// in an element name replaced with `_`. Failures produce an `<error>`
+    /// document, and input files that failed in a multi-file run are listed
+    /// as `<file_error file="...">` elements.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut row = vec![None; 12];
+    /// row[0] = Some("Tops & Tees".to_string());
+    /// row[1] = Some("CAT001".to_string());
+    /// let result = ProcessingResult::success(
+    ///     vec![CascadeField::from_row(row).unwrap()],
+    ///     ProcessingMetadata::default(),
+    /// );
+    ///
+    /// let xml = OutputFormatter::format_output(&result, OutputFormat::Xml)?;
+    /// assert!(xml.contains("<main_label>Tops &amp; Tees</main_label>"));
+    /// assert!(xml.contains("<main_value>CAT001</main_value>"));
+    /// assert!(xml.contains("<main_description/>"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn format_xml(result: &ProcessingResult, options: &OutputOptions) -> Result<String> {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        if !result.success {
+            let error = result.error.as_deref().unwrap_or("Unknown error");
+            xml.push_str(&format!("<error>{}</error>\n", Self::escape_xml(error)));
+            return Ok(xml);
+        }
+
+        let rows = |records: &[CascadeField], generic: Option<&Vec<GenericRecord>>| -> Vec<IndexMap<String, Value>> {
+            match generic {
+                Some(rows) => rows.iter().map(|record| record.0.clone()).collect(),
+                None => records.iter().map(Self::typed_row).collect(),
+            }
+        };
+
+        xml.push_str("<records>\n");
+        for file_error in result.file_errors.iter().flatten() {
+            xml.push_str(&format!(
+                "  <file_error file=\"{}\">{}</file_error>\n",
+                Self::escape_xml(&file_error.file),
+                Self::escape_xml(&file_error.error)
+            ));
+        }
+        if let Some(sheet_data) = &result.sheet_data {
+            for sheet in sheet_data {
+                let file = sheet.file.as_deref()
+                    .map(|file| format!(" file=\"{}\"", Self::escape_xml(file)))
+                    .unwrap_or_default();
+                xml.push_str(&format!("  <sheet name=\"{}\"{}>\n", Self::escape_xml(&sheet.sheet), file));
+                Self::write_xml_records(&mut xml, &rows(&sheet.rows, sheet.generic_rows.as_ref()), "    ", options.xml_omit_null);
+                xml.push_str("  </sheet>\n");
+            }
+        } else {
+            let records = rows(result.records.as_deref().unwrap_or_default(), result.generic_records.as_ref());
+            Self::write_xml_records(&mut xml, &records, "  ", options.xml_omit_null);
+        }
+        xml.push_str("</records>\n");
+
+        info!("Formatted output as XML ({} bytes)", xml.len());
+        Ok(xml)
+    }
+
+    /// Appends a `<record>` element for each row, indented by `indent`.
+    fn write_xml_records(xml: &mut String, rows: &[IndexMap<String, Value>], indent: &str, omit_null: bool) {
+        for row in rows {
+            xml.push_str(&format!("{}<record>\n", indent));
+            for (name, value) in row {
+                let name = Self::xml_name(name);
+                let text = match value {
+                    Value::Null if omit_null => continue,
+                    Value::Null => None,
+                    Value::String(s) => Some(s.clone()),
+                    // Merged duplicate-header columns are written as JSON text
+                    other => Some(other.to_string()),
+                };
+                match text {
+                    Some(text) => xml.push_str(&format!("{}  <{}>{}</{}>\n", indent, name, Self::escape_xml(&text), name)),
+                    None => xml.push_str(&format!("{}  <{}/>\n", indent, name)),
+                }
+            }
+            xml.push_str(&format!("{}</record>\n", indent));
+        }
+    }
+
+    /// Escapes the characters that are not allowed literally in XML text or
+    /// attribute values.
+    fn escape_xml(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// Turns a field name into a valid XML element name.
+    ///
+    /// Characters other than letters, digits, `_`, `-` and `.` become `_`, and
+    /// names that do not start with a letter or `_` are prefixed with `_`.
+    fn xml_name(name: &str) -> String {
+        let mut element: String = name.chars()
+            .map(|c| if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+            .collect();
+        if !element.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            element.insert(0, '_');
+        }
+        element
+    }
+
+    /// Returns a cascade record's fields in order, followed by its header-keyed
+    /// columns, with `None` as `null` and preserved numbers and booleans typed.
+    fn typed_row(record: &CascadeField) -> IndexMap<String, Value> {
+        let mut row: IndexMap<String, Value> = CascadeField::FIELD_NAMES.iter()
+            .zip(record.field_values())
+            .map(|(name, value)| {
+                let value = match (value, record.cell_types.get(*name)) {
+                    (Some(text), Some(cell_type)) => cell_type.to_value(text),
+                    (Some(text), None) => Value::String(text.to_string()),
+                    (None, _) => Value::Null,
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+        row.extend(record.extra.iter().map(|(key, value)| (key.clone(), value.clone())));
+        row
+    }
+
+    /// Appends `INSERT` statements for `rows`, `batch_size` rows per statement.
+    ///
+    /// The column list is the union of the rows' keys in order of first appearance.
+    fn write_sql_inserts(sql: &mut String, table: &str, rows: &[IndexMap<String, Value>], batch_size: usize) {
+        let mut columns: Vec<&str> = Vec::new();
+        for key in rows.iter().flat_map(|row| row.keys()) {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+        let column_list = columns.iter()
+            .map(|column| Self::sql_identifier(column))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        for batch in rows.chunks(batch_size.max(1)) {
+            let tuples: Vec<String> = batch.iter()
+                .map(|row| {
+                    let values: Vec<String> = columns.iter()
+                        .map(|column| Self::sql_literal(row.get(*column).unwrap_or(&Value::Null)))
+                        .collect();
+                    format!("({})", values.join(", "))
+                })
+                .collect();
+            if tuples.len() == 1 {
+                sql.push_str(&format!("INSERT INTO {} ({}) VALUES {};\n", table, column_list, tuples[0]));
+            } else {
+                sql.push_str(&format!("INSERT INTO {} ({}) VALUES\n  {};\n", table, column_list, tuples.join(",\n  ")));
+            }
+        }
+    }
+
+    /// Renders a value as an SQL literal.
+    fn sql_literal(value: &Value) -> String {
+        match value {
+            Value::Null => "NULL".to_string(),
+            Value::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+            // Merged duplicate-header columns are stored as JSON text
+            other => format!("'{}'", other.to_string().replace('\'', "''")),
+        }
+    }
+
+    /// Quotes an identifier unless it is a plain word.
+    fn sql_identifier(name: &str) -> String {
+        let plain = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if plain {
+            name.to_string()
+        } else {
+            format!("\"{}\"", name.replace('"', "\"\""))
+        }
+    }
+
+    /// Quotes each part of a possibly schema-qualified table name.
+    fn sql_table_name(table: &str) -> String {
+        table.split('.').map(Self::sql_identifier).collect::<Vec<_>>().join(".")
+    }
+
+    /// Adds the result's per-file errors, if any, to a JSON response as `file_errors`.
+    fn with_file_errors(mut response: Value, result: &ProcessingResult) -> Value {
+        if let Some(file_errors) = &result.file_errors {
+            response["file_errors"] = json!(file_errors);
+        }
+        response
+    }
+
+    /// Renames the `data` key of a JSON envelope to `options.root_key`.
+    fn with_root_key(mut response: Value, options: &OutputOptions) -> Value {
+        if options.root_key != DEFAULT_ROOT_KEY {
+            if let Some(data) = response.as_object_mut().and_then(|map| map.remove(DEFAULT_ROOT_KEY)) {
+                response[options.root_key.as_str()] = data;
+            }
+        }
+        response
+    }
+
+    /// Serializes a JSON document in the layout selected by the options.
+    fn render_json(value: &Value, options: &OutputOptions) -> Result<String> {
+        if options.compact {
+            Ok(serde_json::to_string(value)?)
+        } else if options.line_per_record {
+            let mut out = String::new();
+            Self::write_line_per_record(&mut out, value, 0)?;
+            Ok(out)
+        } else {
+            Ok(serde_json::to_string_pretty(value)?)
+        }
+    }
+
+    /// Pretty-prints `value` with two-space indentation, except that objects
+    /// inside arrays are written compactly on a single line. Sheet objects
+    /// (those with a `rows` key) are still expanded so their records get one
+    /// line each.
+    fn write_line_per_record(out: &mut String, value: &Value, indent: usize) -> Result<()> {
+        let pad = " ".repeat(indent + 2);
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                out.push_str("{\n");
+                for (idx, (key, item)) in map.iter().enumerate() {
+                    out.push_str(&pad);
+                    out.push_str(&serde_json::to_string(key)?);
+                    out.push_str(": ");
+                    Self::write_line_per_record(out, item, indent + 2)?;
+                    out.push_str(if idx + 1 < map.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&" ".repeat(indent));
+                out.push('}');
+            },
+            Value::Array(items) if !items.is_empty() => {
+                out.push_str("[\n");
+                for (idx, item) in items.iter().enumerate() {
+                    out.push_str(&pad);
+                    match item {
+                        Value::Object(map) if !map.contains_key("rows") => {
+                            out.push_str(&serde_json::to_string(item)?);
+                        },
+                        _ => Self::write_line_per_record(out, item, indent + 2)?,
+                    }
+                    out.push_str(if idx + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&" ".repeat(indent));
+                out.push(']');
+            },
+            _ => out.push_str(&serde_json::to_string(value)?),
+        }
+        Ok(())
+    }
+
+    /// Returns `base`, or `base_2`, `base_3`, ... if already taken, and marks
+    /// the returned key as taken.
+    fn unique_sheet_key(base: &str, taken: &mut HashSet<String>) -> String {
+        let mut key = base.to_string();
+        let mut suffix = 2;
+        while taken.contains(&key) {
+            key = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        taken.insert(key.clone());
+        key
+    }
+
+    /// Builds the JSON `metadata` block shared by the result and each sheet.
+    fn metadata_json(metadata: &ProcessingMetadata, options: &OutputOptions) -> Value {
+        let mut warnings = metadata.warnings.clone();
+        if options.legacy_warnings && !metadata.diagnostics.is_empty() {
+            warnings.get_or_insert_with(Vec::new).extend(metadata.diagnostics.iter()
+                .filter(|diagnostic| diagnostic.severity != Severity::Info)
+                .map(ToString::to_string));
+        }
+        let mut value = json!({
+            "total_rows_processed": metadata.total_rows_processed,
+            "valid_records": metadata.valid_records,
+            "invalid_records": metadata.invalid_records,
+            "malformed_rows": metadata.malformed_rows,
+            "skipped_records": metadata.skipped_records,
+            "duplicate_records": metadata.duplicate_records,
+            "filtered_records": metadata.filtered_records,
+            "processing_time_ms": metadata.processing_time_ms,
+            "diagnostics": metadata.diagnostics,
+            "warnings": warnings
+        });
+        if let Some(window) = &metadata.row_window {
+            value["row_window"] = json!(window);
+        }
+        if let Some(headers) = &metadata.source_headers {
+            value["source_headers"] = json!(headers);
+        }
+        if let Some(columns) = metadata.detected_columns {
+            value["detected_columns"] = json!(columns);
+        }
+        value
+    }
+    
+    /// Returns a sheet's rows as a JSON array of objects, numbering cascade
+    /// records when `options.with_row_numbers` is set.
+    fn sheet_rows_json(sheet: &SheetData, options: &OutputOptions) -> Value {
+        match &sheet.generic_rows {
+            Some(_) => Value::Array(sheet.row_values()),
+            None => Self::records_json(&sheet.rows, options),
+        }
+    }
+
+    /// Returns the rows of every sheet as one JSON array, adding the sheet's
+    /// name to each row as `sheet` and its file, when tagged, as `file`.
+    ///
+    /// The tags replace a generic column of the same name. Per-sheet metadata
+    /// has no place in this shape and is left out.
+    fn flat_rows_json(sheets: &[SheetData], options: &OutputOptions) -> Value {
+        let mut rows = Vec::new();
+        for sheet in sheets {
+            let Value::Array(sheet_rows) = Self::sheet_rows_json(sheet, options) else {
+                continue;
+            };
+            for mut row in sheet_rows {
+                if let Value::Object(map) = &mut row {
+                    map.insert("sheet".to_string(), json!(sheet.sheet));
+                    if let Some(file) = &sheet.file {
+                        map.insert("file".to_string(), json!(file));
+                    }
+                }
+                rows.push(row);
+            }
+        }
+        Value::Array(rows)
+    }
+
+    /// Returns cascade records as a JSON array, or with `options.group_by`
+    /// as an object mapping each value of that field to its records. Records
+    /// without a value are grouped under `options.group_null_key`.
+    fn records_json(records: &[CascadeField], options: &OutputOptions) -> Value {
+        let Some(field) = &options.group_by else {
+            return records.iter().map(|record| Self::record_value(record, options)).collect();
+        };
+        let mut groups = serde_json::Map::new();
+        for (value, group) in DataProcessor::group_by_field(records, field) {
+            let key = value.unwrap_or(&options.group_null_key).to_string();
+            // A value equal to the null key shares its group
+            if let Value::Array(rows) = groups.entry(key).or_insert_with(|| Value::Array(Vec::new())) {
+                rows.extend(group.into_iter().map(|record| Self::record_value(record, options)));
+            }
+        }
+        Value::Object(groups)
+    }
+
+    /// Converts a cascade record to its JSON object, with `source_row` when
+    /// `options.with_row_numbers` is set.
+    /// Cascade records keep only the fields of `options.columns`, when given,
+    /// in that order, and `source_row`.
+    fn record_value(record: &CascadeField, options: &OutputOptions) -> Value {
+        let value = if options.with_row_numbers {
+            record.to_numbered_array()
+        } else {
+            record.to_typed_array()
+        };
+        match (value, &options.columns) {
+            (Value::Object(mut map), Some(columns)) => {
+                let mut projected: serde_json::Map<String, Value> = columns.iter()
+                    .filter_map(|column| map.remove(column).map(|value| (column.clone(), value)))
+                    .collect();
+                if let Some(source_row) = map.remove("source_row") {
+                    projected.insert("source_row".to_string(), source_row);
+                }
+                Value::Object(projected)
+            },
+            (value, _) => value,
+        }
+    }
+
+    /// Formats the result as CSV.
+    ///
+    /// Emits a header row with the field names followed by one line per record.
+    /// Multi-sheet results get a leading `sheet` column so rows from different
+    /// sheets remain distinguishable. None values become empty fields. With
+    /// `options.with_row_numbers`, cascade records get a `source_row` column
+    /// (after `sheet`) holding the spreadsheet row they were read from.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The processing result to format
+    /// * `options` - Output settings; `delimiter` separates fields (e.g. `,`,
+    ///   `;` or `|`), `crlf` selects `\r\n` line endings and `csv_bom`
+    ///   prepends a UTF-8 byte order mark
+    ///
+    /// # Returns
+    ///
+    /// CSV text with `\n` line endings, or `\r\n` with `options.crlf`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::ProcessingResult;
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter, OutputOptions};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let result = ProcessingResult::success(Vec::new(), Default::default());
+    /// let options = OutputOptions { csv_bom: true, crlf: true, ..OutputOptions::default() };
+    ///
+    /// let csv = OutputFormatter::format_output_with_options(&result, OutputFormat::Csv, &options)?;
+    /// assert!(csv.starts_with("\u{FEFF}main_label,"));
+    /// assert!(csv.ends_with(",minor_description\r\n"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn format_csv(result: &ProcessingResult, options: &OutputOptions) -> Result<String> {
+        let mut csv = Vec::new();
+        Self::write_csv(result, options, &mut csv)?;
+        info!("Formatted output as CSV ({} bytes)", csv.len());
+        Ok(String::from_utf8(csv)?)
+    }
+
+    /// Writes the result as CSV to `out`, one row at a time.
+    ///
+    /// The output is the text [`OutputFormat::Csv`] formats, but rows are
+    /// written as they are built instead of being collected into a string
+    /// first, which keeps large exports out of memory. Wrap files and stdout
+    /// in a `BufWriter`, as every row is a separate write.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `out` fails; rows already written stay
+    /// in the output.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::ProcessingResult;
+    /// use excel_to_json::output::{OutputFormatter, OutputOptions};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let result = ProcessingResult::success(Vec::new(), Default::default());
+    /// let mut out = Vec::new();
+    /// OutputFormatter::write_csv(&result, &OutputOptions::default(), &mut out)?;
+    /// assert!(String::from_utf8(out)?.starts_with("main_label,main_value,"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_csv(result: &ProcessingResult, options: &OutputOptions, out: &mut dyn Write) -> Result<()> {
+        if options.csv_bom {
+            out.write_all("\u{FEFF}".as_bytes())?;
+        }
+        if !result.success {
+            let error = result.error.as_deref().unwrap_or("Unknown error");
+            Self::write_csv_row(out, &["error"], options)?;
+            Self::write_csv_row(out, &[error], options)?;
+            return Ok(());
+        }
+
+        Self::for_each_table_row(result, options, |row| Self::write_csv_row(out, row, options))
+    }
+
+    /// Formats the result as tab-separated values.
+    ///
+    /// The columns and header row are those of the CSV output, but values are
+    /// never quoted: fields are joined with tabs and lines end with `\n`. A
+    /// tab or line break inside a value is replaced with a space, or is an
+    /// error with `options.tsv_strict`. A failed result is written as an
+    /// `error` column holding the message.
+    ///
+    /// # Errors
+    ///
+    /// With `options.tsv_strict`, returns an error naming the column of the
+    /// first value holding a tab or line break.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut row = vec![None; 12];
+    /// row[0] = Some("Tops, \"Tees\"\tand more".to_string());
+    /// row[1] = Some("CAT001".to_string());
+    /// let result = ProcessingResult::success(
+    ///     vec![CascadeField::from_row(row).unwrap()],
+    ///     ProcessingMetadata::default(),
+    /// );
+    ///
+    /// let tsv = OutputFormatter::format_output(&result, OutputFormat::Tsv)?;
+    /// let lines: Vec<&str> = tsv.lines().collect();
+    /// assert!(lines[0].starts_with("main_label\tmain_value\tmain_description\t"));
+    /// assert!(lines[1].starts_with("Tops, \"Tees\" and more\tCAT001\t\t"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn format_tsv(result: &ProcessingResult, options: &OutputOptions) -> Result<String> {
+        let mut tsv = String::new();
+        let rows = if result.success {
+            Self::table_rows(result, options)
+        } else {
+            vec![vec!["error".to_string()], vec![result.error.clone().unwrap_or_else(|| "Unknown error".to_string())]]
+        };
+        let header = rows.first().cloned().unwrap_or_default();
+
+        for row in &rows {
+            let mut cells = Vec::with_capacity(row.len());
+            for (column, value) in header.iter().zip(row) {
+                if !value.contains(['\t', '\n', '\r']) {
+                    cells.push(value.clone());
+                } else if options.tsv_strict {
+                    anyhow::bail!("A value in column '{}' contains a tab or line break, which TSV cannot hold", column);
+                } else {
+                    cells.push(value.replace("\r\n", " ").replace(['\t', '\n', '\r'], " "));
+                }
+            }
+            tsv.push_str(&cells.join("\t"));
+            tsv.push('\n');
+        }
+
+        info!("Formatted output as TSV ({} bytes)", tsv.len());
+        Ok(tsv)
+    }
+
+    /// Returns the header row and data rows shared by the CSV and TSV output.
+    ///
+    /// Multi-sheet results get a leading `sheet` column and, with
+    /// `options.with_row_numbers`, cascade records a `source_row` column.
+    /// Cascade records have a column per field of `options.columns`, or all
+    /// twelve fields without it. Header-keyed records use the union of every record's keys, in order of
+    /// first appearance, as columns. Missing and `null` values become empty
+    /// strings; merged arrays are written as JSON.
+    fn table_rows(result: &ProcessingResult, options: &OutputOptions) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let Ok(()) = Self::for_each_table_row(result, options, |row| {
+            rows.push(row.to_vec());
+            Ok::<_, std::convert::Infallible>(())
+        });
+        rows
+    }
+
+    /// Calls `visit` with each row of [`table_rows`](Self::table_rows), header
+    /// first, building one row at a time.
+    ///
+    /// # Errors
+    ///
+    /// Stops at and returns the first error of `visit`.
+    fn for_each_table_row<F, E>(result: &ProcessingResult, options: &OutputOptions, mut visit: F) -> Result<(), E>
+    where
+        F: FnMut(&[String]) -> Result<(), E>,
+    {
+        let sheet_keys: Vec<String> = result.sheet_data.iter().flatten().map(SheetData::key).collect();
+        let with_sheet = result.sheet_data.is_some();
+        let generic_sheets: Vec<(&str, &Vec<GenericRecord>)> = match (&result.sheet_data, &result.generic_records) {
+            (Some(sheet_data), _) => sheet_data.iter()
+                .zip(&sheet_keys)
+                .filter_map(|(sheet, key)| sheet.generic_rows.as_ref().map(|rows| (key.as_str(), rows)))
+                .collect(),
+            (None, Some(records)) => vec![("", records)],
+            (None, None) => Vec::new(),
+        };
+        let mut header: Vec<String> = if with_sheet { vec!["sheet".to_string()] } else { Vec::new() };
+
+        if !generic_sheets.is_empty() {
+            let mut columns: Vec<&str> = Vec::new();
+            for key in generic_sheets.iter().flat_map(|(_, rows)| rows.iter()).flat_map(|record| record.0.keys()) {
+                if !columns.contains(&key.as_str()) {
+                    columns.push(key);
+                }
+            }
+            header.extend(columns.iter().map(|column| column.to_string()));
+            visit(&header)?;
+
+            let mut row = Vec::with_capacity(header.len());
+            for (sheet, records) in &generic_sheets {
+                for record in records.iter() {
+                    row.clear();
+                    if with_sheet {
+                        row.push(sheet.to_string());
+                    }
+                    row.extend(columns.iter().map(|column| match record.0.get(*column) {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(Value::Null) | None => String::new(),
+                        Some(other) => other.to_string(),
+                    }));
+                    visit(&row)?;
+                }
+            }
+            return Ok(());
+        }
+
+        let sheets: Vec<(Option<&str>, &[CascadeField])> = match &result.sheet_data {
+            Some(sheet_data) => sheet_data.iter()
+                .zip(&sheet_keys)
+                .map(|(sheet, key)| (Some(key.as_str()), sheet.rows.as_slice()))
+                .collect(),
+            None => vec![(None, result.records.as_deref().unwrap_or_default())],
+        };
+        if options.with_row_numbers {
+            header.push("source_row".to_string());
+        }
+        // Indices of the fields to write, in output order
+        let fields: Vec<usize> = match &options.columns {
+            Some(columns) => columns.iter()
+                .filter_map(|column| CascadeField::FIELD_NAMES.iter().position(|name| name == column))
+                .collect(),
+            None => (0..CascadeField::FIELD_NAMES.len()).collect(),
+        };
+        header.extend(fields.iter().map(|&idx| CascadeField::FIELD_NAMES[idx].to_string()));
+        visit(&header)?;
+
+        let mut row = Vec::with_capacity(header.len());
+        for (sheet, records) in sheets {
+            for record in records {
+                row.clear();
+                row.extend(sheet.map(str::to_string));
+                if options.with_row_numbers {
+                    row.push(record.row_number.map(|row| row.to_string()).unwrap_or_default());
+                }
+                let values = record.field_values();
+                row.extend(fields.iter().map(|&idx| values[idx].unwrap_or("").to_string()));
+                visit(&row)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a single CSV line, escaping each value and terminating with a
+    /// newline (`\r\n` with `options.crlf`).
+    fn write_csv_row<S: AsRef<str>>(out: &mut dyn Write, values: &[S], options: &OutputOptions) -> Result<()> {
+        let mut delimiter = [0; 4];
+        let delimiter = options.delimiter.encode_utf8(&mut delimiter).as_bytes();
+        for (idx, value) in values.iter().enumerate() {
+            if idx > 0 {
+                out.write_all(delimiter)?;
+            }
+            let value = value.as_ref();
+            if Self::needs_csv_quotes(value, options.delimiter) {
+                write!(out, "\"{}\"", value.replace('"', "\"\""))?;
+            } else {
+                out.write_all(value.as_bytes())?;
+            }
+        }
+        out.write_all(if options.crlf { b"\r\n" } else { b"\n" })?;
+        Ok(())
+    }
+
+    /// Whether a CSV value must be quoted: when it contains the delimiter, a
+    /// quote or a line break. Embedded quotes are then doubled as per RFC 4180.
+    fn needs_csv_quotes(value: &str, delimiter: char) -> bool {
+        value.contains(delimiter) || value.contains('"') || value.contains('\n') || value.contains('\r')
+    }
+
+    /// Formats the processing result as GitHub-flavored Markdown tables.
+    ///
+    /// Each record is a table row with one column per field; empty fields are
+    /// blank cells. Multi-sheet results get a `## sheet` heading before each
+    /// sheet's table, and input files that failed in a multi-file run are
+    /// listed first. Pipes in values are escaped as `\|` and line breaks
+    /// written as `<br>`, so a value never breaks the table. Header-keyed
+    /// (`--generic`) records use the union of their keys as columns. A failed
+    /// result is written as a `**Error:**` line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+    /// use excel_to_json::output::{OutputFormat, OutputFormatter};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut row = vec![None; 12];
+    /// row[0] = Some("Tops | Tees".to_string());
+    /// row[1] = Some("CAT001".to_string());
+    /// let result = ProcessingResult::success(
+    ///     vec![CascadeField::from_row(row).unwrap()],
+    ///     ProcessingMetadata::default(),
+    /// );
+    ///
+    /// let markdown = OutputFormatter::format_output(&result, OutputFormat::Markdown)?;
+    /// let lines: Vec<&str> = markdown.lines().collect();
+    /// assert!(lines[0].starts_with("| main_label | main_value | main_description |"));
+    /// assert!(lines[1].starts_with("| --- | --- | --- |"));
+    /// assert!(lines[2].starts_with("| Tops \\| Tees | CAT001 |  |"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn format_markdown(result: &ProcessingResult) -> Result<String> {
+        if !result.success {
+            let error = result.error.as_deref().unwrap_or("Unknown error");
+            return Ok(format!("**Error:** {}\n", Self::escape_markdown(error)));
+        }
+
+        let rows = |records: &[CascadeField], generic: Option<&Vec<GenericRecord>>| -> Vec<IndexMap<String, Value>> {
+            match generic {
+                Some(rows) => rows.iter().map(|record| record.0.clone()).collect(),
+                None => records.iter().map(Self::typed_row).collect(),
+            }
+        };
+
+        let mut markdown = String::new();
+        let file_errors = result.file_errors.as_deref().unwrap_or_default();
+        for file_error in file_errors {
+            markdown.push_str(&format!(
+                "- **{}**: {}\n",
+                Self::escape_markdown(&file_error.file),
+                Self::escape_markdown(&file_error.error)
+            ));
+        }
+        if let Some(sheet_data) = &result.sheet_data {
+            for (idx, sheet) in sheet_data.iter().enumerate() {
+                if idx > 0 || !file_errors.is_empty() {
+                    markdown.push('\n');
+                }
+                markdown.push_str(&format!("## {}\n\n", Self::escape_markdown(&sheet.key())));
+                Self::write_markdown_table(&mut markdown, &rows(&sheet.rows, sheet.generic_rows.as_ref()));
+            }
+        } else {
+            if !file_errors.is_empty() {
+                markdown.push('\n');
+            }
+            let records = rows(result.records.as_deref().unwrap_or_default(), result.generic_records.as_ref());
+            Self::write_markdown_table(&mut markdown, &records);
+        }
+
+        info!("Formatted output as Markdown ({} bytes)", markdown.len());
+        Ok(markdown)
+    }
+
+    /// Appends a table with a header row, a `---` separator row and one row
+    /// per record. Without records the columns are the cascade fields.
+    fn write_markdown_table(markdown: &mut String, rows: &[IndexMap<String, Value>]) {
+        let mut columns: Vec<&str> = Vec::new();
+        for key in rows.iter().flat_map(|row| row.keys()) {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+        if columns.is_empty() {
+            columns.extend(CascadeField::FIELD_NAMES);
+        }
+
+        let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        markdown.push_str(&line(columns.iter().map(|column| Self::escape_markdown(column)).collect()));
+        markdown.push_str(&line(vec!["---".to_string(); columns.len()]));
+        for row in rows {
+            let cells = columns.iter()
+                .map(|column| match row.get(*column) {
+                    Some(Value::String(s)) => Self::escape_markdown(s),
+                    Some(Value::Null) | None => String::new(),
+                    Some(other) => Self::escape_markdown(&other.to_string()),
+                })
+                .collect();
+            markdown.push_str(&line(cells));
+        }
+    }
+
+    /// Escapes pipes and replaces line breaks with `<br>` so a value stays
+    /// inside its table cell.
+    fn escape_markdown(value: &str) -> String {
+        value.replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace(['\n', '\r'], "<br>")
+    }
+
+    /// Writes the output to stdout.
+    ///
+    /// Writes the formatted output directly to standard output and flushes
+    /// the buffer to ensure immediate delivery.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The formatted string to write
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Successfully written to stdout
+    /// * `Err` - If write or flush fails
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use excel_to_json::output::OutputFormatter;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let output = r#"{
+    ///   "success": true,
+    ///   "data": []
+    /// }"#;
+    ///
+    /// OutputFormatter::write_to_stdout(output)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to_stdout(output: &str) -> Result<()> {
+        let mut stdout = std::io::stdout();
+        stdout.write_all(output.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+    
+    /// Writes one value as a line of newline-delimited JSON.
+    ///
+    /// Used by streaming output, where each record is written as soon as it
+    /// is processed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::output::OutputFormatter;
+    /// use serde_json::json;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut out = Vec::new();
+    /// OutputFormatter::write_ndjson_line(&mut out, &json!({"main_value": "CAT001"}))?;
+    /// assert_eq!(String::from_utf8(out)?, "{\"main_value\":\"CAT001\"}\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_ndjson_line<W: Write>(mut writer: W, value: &Value) -> Result<()> {
+        serde_json::to_writer(&mut writer, value)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+    
+    /// Writes the output to a file.
+    ///
+    /// Creates or overwrites a file with the formatted output.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The formatted string to write
+    /// * `path` - Path to the output file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Successfully written to file
+    /// * `Err` - If file creation or write fails
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use excel_to_json::output::OutputFormatter;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let output = "main_label,main_value\nCategory,CAT001";
+    ///
+    /// OutputFormatter::write_to_file(output, "output.csv")?;
+    /// println!("Output written to output.csv");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to_file(output: &str, path: &str) -> Result<()> {
+        std::fs::write(path, output)?;
+        info!("Output written to file: {}", path);
+        Ok(())
+    }
+    
+    /// Appends the records of `result` to the JSON document in a file, for
+    /// incremental runs that grow one output file.
+    ///
+    /// The existing document is read, the new records are added to the end
+    /// of its records array (`options.root_key`) and the whole document is
+    /// written back, so each append costs a parse and rewrite of the file;
+    /// newline-delimited JSON (`--stream`) can be appended to without that.
+    /// The rest of the document, such as `metadata`, describes the latest run.
+    /// A missing or empty file is written as a new document. The document is
+    /// written to `<path>.tmp` and renamed over `path`, so readers never see
+    /// a partly written file.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - A successful processing result
+    /// * `options` - Formatting options; must not key sheets by name
+    /// * `path` - The JSON file to append to
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving the file untouched, if the result is a
+    /// failure, the file is not a successful JSON output document (such as
+    /// an error document), or its records have another shape than the new
+    /// ones: `{sheet, rows}` objects versus flattened records.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{ProcessingMetadata, ProcessingResult, SheetData};
+    /// use excel_to_json::output::{OutputFormatter, OutputOptions};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let dir = tempfile::TempDir::new()?;
+    /// let path = dir.path().join("daily.json");
+    /// let day = |sheet: &str| ProcessingResult::success_multi_sheet(
+    ///     vec![SheetData { sheet: sheet.to_string(), rows: vec![], generic_rows: None, metadata: None, file: None }],
+    ///     ProcessingMetadata::default(),
+    /// );
+    ///
+    /// OutputFormatter::append_json(&day("Monday"), &OutputOptions::default(), &path)?;
+    /// OutputFormatter::append_json(&day("Tuesday"), &OutputOptions::default(), &path)?;
+    ///
+    /// let document: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    /// assert_eq!(document["data"][1]["sheet"], "Tuesday");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn append_json(result: &ProcessingResult, options: &OutputOptions, path: &Path) -> Result<()> {
+        if !result.success {
+            anyhow::bail!("Cannot append a failed result: {}", result.error.as_deref().unwrap_or("Unknown error"));
+        }
+        let mut document = Self::json_document(result, options);
+        let root_key = options.root_key.as_str();
+
+        let existing = match std::fs::read_to_string(path) {
+            Ok(text) if !text.trim().is_empty() => Some(text),
+            Ok(_) => None,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        if let Some(existing) = existing {
+            let mut existing: Value = serde_json::from_str(&existing)
+                .with_context(|| format!("Cannot append to {}: it is not a JSON document", path.display()))?;
+            if existing.get("success") != Some(&Value::Bool(true)) {
+                anyhow::bail!("Cannot append to {}: it is not the output of a successful run", path.display());
+            }
+            let Some(Value::Array(records)) = existing.get_mut(root_key).map(Value::take) else {
+                anyhow::bail!("Cannot append to {}: it has no '{}' array of records", path.display(), root_key);
+            };
+            let Some(Value::Array(new_records)) = document.get_mut(root_key).map(Value::take) else {
+                anyhow::bail!("Cannot append records keyed by sheet name; drop --sheets-as-object");
+            };
+            // Sheet entries hold their records under `rows`; flattened records do not
+            let is_sheet = |record: &Value| record.get("rows").is_some_and(Value::is_array);
+            if let (Some(old), Some(new)) = (records.first(), new_records.first()) {
+                if is_sheet(old) != is_sheet(new) {
+                    let shape = |sheet: bool| if sheet { "{sheet, rows} objects" } else { "flattened records" };
+                    anyhow::bail!(
+                        "Cannot append to {}: it holds {} but this run produces {}",
+                        path.display(),
+                        shape(is_sheet(old)),
+                        shape(is_sheet(new))
+                    );
+                }
+            }
+            document[root_key] = Value::Array(records.into_iter().chain(new_records).collect());
+        }
+
+        // Written beside the file and renamed over it, so an interrupted
+        // append leaves the previous document whole
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_name);
+        std::fs::write(&tmp_path, Self::render_json(&document, options)?)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        info!("Output appended to {}", path.display());
+        Ok(())
+    }
+
+    /// Writes one output file per sheet into a zip archive.
+    ///
+    /// Each sheet is formatted on its own, as if it were the only sheet
+    /// processed, and stored as `<sheet>.<ext>` (e.g. `Cascade Fields.csv`).
+    /// Characters not allowed in file names, such as path separators, are
+    /// replaced with `_` in sheet names, and repeated names get a numeric
+    /// suffix. Sheets tagged with an input file are stored in a folder named
+    /// after the file (`jan.xlsx/Data.csv`).
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - A successful processing result
+    /// * `format` - Format of each archive entry
+    /// * `options` - Formatting options applied to every entry
+    /// * `path` - Path of the zip file to create
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the result is a failure or the archive cannot be written.
+    pub fn write_zip(result: &ProcessingResult, format: OutputFormat, options: &OutputOptions, path: &str) -> Result<()> {
+        if !result.success {
+            anyhow::bail!("Cannot archive a failed result: {}", result.error.as_deref().unwrap_or("Unknown error"));
+        }
+
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create zip file: {}", path))?;
+        let mut archive = zip::ZipWriter::new(file);
+        let entry_options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        for (entry, content) in Self::split_sheets(result, format, options)? {
+            archive.start_file(entry.as_str(), entry_options)
+                .with_context(|| format!("Failed to add {} to zip file", entry))?;
+            archive.write_all(content.as_bytes())?;
+        }
+
+        archive.finish().context("Failed to finish zip file")?;
+        info!("Output written to zip archive: {}", path);
+        Ok(())
+    }
+
+    /// Writes one output file per sheet into a directory.
+    ///
+    /// Sheets are formatted and named as in [`write_zip`](Self::write_zip),
+    /// with a subdirectory per input file in multi-file runs. The run's
+    /// metadata is written next to them as `_summary.json`, with the list of
+    /// files written:
+    ///
+    /// ```json
+    /// {
+    ///   "success": true,
+    ///   "files": ["Cascade Fields.json", "Line Type.json"],
+    ///   "metadata": { "total_rows_processed": 100, ... }
+    /// }
+    /// ```
+    ///
+    /// Existing files of the same names are overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - A successful processing result
+    /// * `format` - Format of each sheet's file
+    /// * `options` - Formatting options applied to every file
+    /// * `dir` - Existing directory to write into
+    ///
+    /// # Returns
+    ///
+    /// The path of every file written, `_summary.json` last.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the result is a failure, `dir` does not exist or a
+    /// file cannot be written.
+    pub fn write_split(result: &ProcessingResult, format: OutputFormat, options: &OutputOptions, dir: &Path) -> Result<Vec<PathBuf>> {
+        if !result.success {
+            anyhow::bail!("Cannot split a failed result: {}", result.error.as_deref().unwrap_or("Unknown error"));
+        }
+        if !dir.is_dir() {
+            anyhow::bail!("Output directory {} does not exist", dir.display());
+        }
+
+        let mut files = Vec::new();
+        for (name, content) in Self::split_sheets(result, format, options)? {
+            let path = dir.join(&name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+            }
+            std::fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            files.push(name);
+        }
+
+        let summary = json!({
+            "success": true,
+            "files": files,
+            "metadata": Self::metadata_json(&result.metadata, options)
+        });
+        let summary_path = dir.join(SPLIT_SUMMARY_FILE);
+        std::fs::write(&summary_path, Self::render_json(&summary, options)?)
+            .with_context(|| format!("Failed to write {}", summary_path.display()))?;
+        info!("Output written to {} files in {}", files.len(), dir.display());
+        let mut paths: Vec<PathBuf> = files.iter().map(|name| dir.join(name)).collect();
+        paths.push(summary_path);
+        Ok(paths)
+    }
+
+    /// Formats each sheet of `result` on its own, returning the relative
+    /// file name (`Data.csv`, or `jan.xlsx/Data.csv` for a sheet tagged with
+    /// its file) and content of each.
+    fn split_sheets(result: &ProcessingResult, format: OutputFormat, options: &OutputOptions) -> Result<Vec<(String, String)>> {
+        let sheets: Vec<(Option<&str>, &str, ProcessingResult)> = match (&result.sheet_data, &result.generic_records, &result.records) {
+            (Some(sheet_data), _, _) => sheet_data.iter()
+                .map(|sheet| {
+                    let metadata = sheet.metadata.clone().unwrap_or_default();
+                    let sheet_result = match &sheet.generic_rows {
+                        Some(rows) => ProcessingResult::success_generic(rows.clone(), metadata),
+                        None => ProcessingResult::success(sheet.rows.clone(), metadata),
+                    };
+                    (sheet.file.as_deref(), sheet.sheet.as_str(), sheet_result)
+                })
+                .collect(),
+            (None, Some(records), _) => vec![(None, "data", ProcessingResult::success_generic(records.clone(), result.metadata.clone()))],
+            (None, None, Some(records)) => vec![(None, "data", ProcessingResult::success(records.clone(), result.metadata.clone()))],
+            (None, None, None) => Vec::new(),
+        };
+
+        let mut taken = HashSet::new();
+        let mut files = Vec::new();
+        for (file, name, sheet_result) in sheets {
+            let content = Self::format_output_with_options(&sheet_result, format, options)?;
+            let mut base = safe_file_name(name);
+            if let Some(file) = file {
+                base = format!("{}/{}", safe_file_name(file), base);
+            }
+            let name = format!("{}.{}", Self::unique_sheet_key(&base, &mut taken), format.extension());
+            files.push((name, content));
+        }
+        Ok(files)
+    }
+
+    /// Creates a summary report of the processing.
+    ///
+    /// Generates a human-readable summary of the processing results,
+    /// including success/failure status, record counts, warnings, and timing.
+    /// Results of several sheets also get a line per sheet with its own
+    /// counts and timing, followed by the grand total.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The processing result to summarize
+    ///
+    /// # Returns
+    ///
+    /// Formatted summary string with emoji indicators
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{ProcessingResult, ProcessingMetadata};
+    /// use excel_to_json::output::OutputFormatter;
+    ///
+    /// // Success case
+    /// let result = ProcessingResult::success(
+    ///     vec![],
+    ///     ProcessingMetadata {
+    ///         total_rows_processed: 100,
+    ///         valid_records: 95,
+    ///         invalid_records: 5,
+    ///         malformed_rows: 0,
+    ///         skipped_records: 0,
+    ///         duplicate_records: 0,
+    ///         filtered_records: 0,
+    ///         row_window: None,
+    ///         source_headers: None,
+    ///         detected_columns: None,
+    ///         processing_time_ms: 150,
+    ///         diagnostics: Vec::new(),
     ///         warnings: Some(vec![
     ///             "Row 10: Missing description".to_string(),
     ///             "Row 20: Duplicate key".to_string(),
@@ -371,7 +2255,15 @@ impl OutputFormatter {
     ///         total_rows_processed: 0,
     ///         valid_records: 0,
     ///         invalid_records: 0,
+    ///         malformed_rows: 0,
+    ///         skipped_records: 0,
+    ///         duplicate_records: 0,
+    ///         filtered_records: 0,
+    ///         row_window: None,
+    ///         source_headers: None,
+    ///         detected_columns: None,
     ///         processing_time_ms: 5,
+    ///         diagnostics: Vec::new(),
     ///         warnings: None,
     ///     },
     /// );
@@ -396,20 +2288,58 @@ impl OutputFormatter {
                 ));
             }
             
+            if result.metadata.malformed_rows > 0 {
+                summary.push_str(&format!(
+                    "⚠ {} malformed rows had too few columns\n",
+                    result.metadata.malformed_rows
+                ));
+            }
+            
+            if result.metadata.skipped_records > 0 {
+                summary.push_str(&format!(
+                    "⏭ {} rows matched a skip rule\n",
+                    result.metadata.skipped_records
+                ));
+            }
+            
+            if result.metadata.filtered_records > 0 {
+                summary.push_str(&format!(
+                    "⏭ {} rows excluded by filters\n",
+                    result.metadata.filtered_records
+                ));
+            }
+            
+            if let Some(window) = &result.metadata.row_window {
+                summary.push_str(&format!("✂ Limited to {}\n", window));
+            }
+            
+            if result.metadata.duplicate_records > 0 {
+                summary.push_str(&format!(
+                    "⧉ {} duplicate records were dropped\n",
+                    result.metadata.duplicate_records
+                ));
+            }
+            
             summary.push_str(&format!(
                 "⏱ Processing time: {}ms\n",
                 result.metadata.processing_time_ms
             ));
             
-            if let Some(warnings) = &result.metadata.warnings {
-                if !warnings.is_empty() {
-                    summary.push_str("\nWarnings:\n");
-                    for warning in warnings.iter().take(5) {
-                        summary.push_str(&format!("  - {}\n", warning));
-                    }
-                    if warnings.len() > 5 {
-                        summary.push_str(&format!("  ... and {} more warnings\n", warnings.len() - 5));
-                    }
+            Self::summarize_sheets(&mut summary, result);
+            Self::summarize_file_errors(&mut summary, result);
+            
+            let warnings: Vec<String> = result.metadata.warnings.iter().flatten().cloned()
+                .chain(result.metadata.diagnostics.iter()
+                    .filter(|diagnostic| diagnostic.severity != Severity::Info)
+                    .map(ToString::to_string))
+                .collect();
+            if !warnings.is_empty() {
+                summary.push_str("\nWarnings:\n");
+                for warning in warnings.iter().take(5) {
+                    summary.push_str(&format!("  - {}\n", warning));
+                }
+                if warnings.len() > 5 {
+                    summary.push_str(&format!("  ... and {} more warnings\n", warnings.len() - 5));
                 }
             }
         } else {
@@ -421,14 +2351,920 @@ impl OutputFormatter {
             if let Some(details) = &result.details {
                 summary.push_str(&format!("  File: {}\n", details.file));
                 
+                if let Some(row_number) = details.row_number {
+                    summary.push_str(&format!("  Row: {}\n", row_number));
+                }
+                
                 if let Some(sheets) = &details.available_sheets {
                     summary.push_str("  Available sheets: ");
                     summary.push_str(&sheets.join(", "));
                     summary.push('\n');
                 }
+                
+                if let Some(sheet) = &details.suggested_sheet {
+                    summary.push_str(&format!("  Did you mean: {}\n", sheet));
+                }
             }
+            
+            Self::summarize_file_errors(&mut summary, result);
         }
         
         summary
     }
+
+    /// Appends a line per sheet and a grand total to a summary of several
+    /// sheets, with columns aligned on the sheet names.
+    fn summarize_sheets(summary: &mut String, result: &ProcessingResult) {
+        let Some(sheets) = result.sheet_data.as_ref().filter(|sheets| sheets.len() > 1) else {
+            return;
+        };
+        let line = |name: &str, metadata: &ProcessingMetadata, width: usize| {
+            format!(
+                "  {:<width$}  {} valid, {} invalid, {}ms\n",
+                name,
+                metadata.valid_records,
+                metadata.invalid_records,
+                metadata.processing_time_ms,
+                width = width
+            )
+        };
+
+        let names: Vec<String> = sheets.iter().map(SheetData::key).collect();
+        let width = names.iter().map(|name| name.chars().count()).chain([5]).max().unwrap_or(0);
+        summary.push_str("\nSheets:\n");
+        for (name, sheet) in names.iter().zip(sheets) {
+            let metadata = sheet.metadata.clone().unwrap_or_else(|| ProcessingMetadata {
+                valid_records: sheet.generic_rows.as_ref().map_or(sheet.rows.len(), Vec::len),
+                ..ProcessingMetadata::default()
+            });
+            summary.push_str(&line(name, &metadata, width));
+        }
+        summary.push_str(&line("Total", &result.metadata, width));
+    }
+
+    /// Appends the input files that failed in a multi-file run to a summary.
+    fn summarize_file_errors(summary: &mut String, result: &ProcessingResult) {
+        let Some(file_errors) = result.file_errors.as_ref().filter(|errors| !errors.is_empty()) else {
+            return;
+        };
+        summary.push_str(&format!("✗ {} input files could not be processed:\n", file_errors.len()));
+        for file_error in file_errors {
+            summary.push_str(&format!("  - {}: {}\n", file_error.file, file_error.error));
+        }
+    }
+
+    /// Renders the processing metadata in Prometheus text exposition format.
+    ///
+    /// The output is meant for node_exporter's textfile collector. Every
+    /// metric is a gauge labelled with the input file.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The processing result whose metadata is exported
+    /// * `file` - Input file path, used as the `file` label
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{ProcessingResult, ProcessingMetadata};
+    /// use excel_to_json::output::OutputFormatter;
+    ///
+    /// let result = ProcessingResult::success(vec![], ProcessingMetadata {
+    ///     total_rows_processed: 100,
+    ///     valid_records: 95,
+    ///     invalid_records: 5,
+    ///     malformed_rows: 0,
+    ///     skipped_records: 0,
+    ///     duplicate_records: 0,
+    ///     filtered_records: 0,
+    ///     row_window: None,
+    ///     source_headers: None,
+    ///     detected_columns: None,
+    ///     processing_time_ms: 150,
+    ///     diagnostics: Vec::new(),
+    ///     warnings: None,
+    /// });
+    ///
+    /// let metrics = OutputFormatter::format_metrics(&result, "data.xlsx");
+    /// assert!(metrics.contains("excel_to_json_valid_records{file=\"data.xlsx\"} 95\n"));
+    /// ```
+    pub fn format_metrics(result: &ProcessingResult, file: &str) -> String {
+        let metadata = &result.metadata;
+        let warnings = metadata.warnings.as_ref().map_or(0, |w| w.len());
+        let metrics: [(&str, &str, u128); 11] = [
+            ("success", "Whether processing succeeded (1) or failed (0)", u128::from(result.success)),
+            ("total_rows_processed", "Rows read from the selected sheets", metadata.total_rows_processed as u128),
+            ("valid_records", "Rows that produced a valid record", metadata.valid_records as u128),
+            ("invalid_records", "Rows skipped as invalid", metadata.invalid_records as u128),
+            ("malformed_rows", "Rows with too few columns to form a record", metadata.malformed_rows as u128),
+            ("skipped_records", "Rows dropped by skip rules", metadata.skipped_records as u128),
+            ("duplicate_records", "Records dropped as duplicates", metadata.duplicate_records as u128),
+            ("filtered_records", "Rows excluded by filters", metadata.filtered_records as u128),
+            ("processing_time_ms", "Processing time in milliseconds", metadata.processing_time_ms),
+            ("warnings", "Number of processing warnings", warnings as u128),
+            ("diagnostics", "Number of row diagnostics", metadata.diagnostics.len() as u128),
+        ];
+
+        let label = file
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+
+        let mut output = String::new();
+        for (name, help, value) in metrics {
+            output.push_str(&format!("# HELP excel_to_json_{} {}\n", name, help));
+            output.push_str(&format!("# TYPE excel_to_json_{} gauge\n", name));
+            output.push_str(&format!("excel_to_json_{}{{file=\"{}\"}} {}\n", name, label, value));
+        }
+        output
+    }
+
+    /// Builds the run manifest written by `--manifest`: what was read, what
+    /// was written and how long it took.
+    ///
+    /// ```json
+    /// {
+    ///   "success": true,
+    ///   "inputs": ["data.xlsx"],
+    ///   "sheets": [{ "sheet": "Cascade Fields", "records": 2, "total_rows_processed": 2, ... }],
+    ///   "outputs": ["out/Cascade Fields.json", "out/_summary.json"],
+    ///   "processing_time_ms": 150,
+    ///   "elapsed_ms": 180
+    /// }
+    /// ```
+    ///
+    /// Sheets from a multi-file run carry the `file` they came from, and
+    /// inputs that could not be processed are listed under `file_errors`.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The processing result
+    /// * `inputs` - Input file paths as given
+    /// * `outputs` - Paths of the files written; empty for stdout
+    /// * `elapsed_ms` - Wall-clock time of the whole run
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::{ProcessingMetadata, ProcessingResult, SheetData};
+    /// use excel_to_json::output::OutputFormatter;
+    ///
+    /// let sheet = SheetData { sheet: "Data".to_string(), rows: vec![], generic_rows: None, metadata: None, file: None };
+    /// let result = ProcessingResult::success_multi_sheet(vec![sheet], ProcessingMetadata::default());
+    ///
+    /// let manifest = OutputFormatter::manifest_json(&result, &["data.xlsx".to_string()], &["data.json".to_string()], 12);
+    /// assert_eq!(manifest["sheets"][0]["sheet"], "Data");
+    /// assert_eq!(manifest["sheets"][0]["records"], 0);
+    /// assert_eq!(manifest["outputs"][0], "data.json");
+    /// ```
+    pub fn manifest_json(result: &ProcessingResult, inputs: &[String], outputs: &[String], elapsed_ms: u128) -> Value {
+        let sheets: Vec<Value> = result.sheet_data.iter().flatten()
+            .map(|sheet| {
+                let records = sheet.generic_rows.as_ref().map_or(sheet.rows.len(), Vec::len);
+                let metadata = sheet.metadata.clone().unwrap_or_default();
+                let mut entry = serde_json::Map::new();
+                if let Some(file) = &sheet.file {
+                    entry.insert("file".to_string(), json!(file));
+                }
+                entry.insert("sheet".to_string(), json!(sheet.sheet));
+                entry.insert("records".to_string(), json!(records));
+                entry.insert("total_rows_processed".to_string(), json!(metadata.total_rows_processed));
+                entry.insert("valid_records".to_string(), json!(metadata.valid_records));
+                entry.insert("invalid_records".to_string(), json!(metadata.invalid_records));
+                entry.insert("processing_time_ms".to_string(), json!(metadata.processing_time_ms));
+                Value::Object(entry)
+            })
+            .collect();
+
+        let mut manifest = serde_json::Map::new();
+        manifest.insert("success".to_string(), json!(result.success));
+        manifest.insert("inputs".to_string(), json!(inputs));
+        manifest.insert("sheets".to_string(), Value::Array(sheets));
+        manifest.insert("outputs".to_string(), json!(outputs));
+        manifest.insert("processing_time_ms".to_string(), json!(result.metadata.processing_time_ms));
+        manifest.insert("elapsed_ms".to_string(), json!(elapsed_ms));
+        if let Some(error) = &result.error {
+            manifest.insert("error".to_string(), json!(error));
+        }
+        if let Some(file_errors) = result.file_errors.as_ref().filter(|errors| !errors.is_empty()) {
+            manifest.insert("file_errors".to_string(), json!(file_errors));
+        }
+        Value::Object(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProcessingMetadata, RowDiagnostic};
+
+    fn sample_result() -> ProcessingResult {
+        let record = CascadeField::from_row(vec![
+            Some("Main; primary".to_string()),
+            Some("M1".to_string()),
+            Some("Plain".to_string()),
+            None, None, None, None, None, None, None, None, None,
+        ]).unwrap();
+
+        ProcessingResult::success(
+            vec![record],
+            ProcessingMetadata {
+                total_rows_processed: 1,
+                valid_records: 1,
+                invalid_records: 0,
+                malformed_rows: 0,
+                skipped_records: 0,
+                duplicate_records: 0,
+                filtered_records: 0,
+                row_window: None,
+                source_headers: None,
+                detected_columns: None,
+                processing_time_ms: 1,
+                diagnostics: Vec::new(),
+                warnings: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_yaml_mirrors_json_document() {
+        let mut record = sample_result().records.unwrap().remove(0);
+        record.sub_value = Some("2024-01-01".to_string());
+        record.major_value = Some("yes".to_string());
+        record.minor_label = Some("Line \"one\"\nLine two".to_string());
+        let sheet = SheetData { sheet: "Sheet 1".to_string(), rows: vec![record], generic_rows: None, metadata: None, file: None };
+        let mut metadata = ProcessingMetadata::default();
+        metadata.diagnostics.push(RowDiagnostic::error(3, None, "Empty row"));
+        let result = ProcessingResult::success_multi_sheet(vec![sheet], metadata);
+
+        let yaml = OutputFormatter::format_output(&result, OutputFormat::Yaml).unwrap();
+        assert!(yaml.starts_with("success: true\ndata:\n  - sheet: Sheet 1\n    rows:\n      - main_label: \"Main; primary\"\n        main_value: M1\n"), "{}", yaml);
+        assert!(yaml.contains("        minor_label: \"Line \\\"one\\\"\\nLine two\"\n"), "{}", yaml);
+        // Strings YAML would read as a date or a boolean stay strings
+        assert!(yaml.contains("        sub_value: \"2024-01-01\"\n"));
+        assert!(yaml.contains("        major_value: \"yes\"\n"));
+        assert!(yaml.contains("        minor_description: \"\"\nmetadata:\n"), "{}", yaml);
+        assert!(yaml.contains("  diagnostics:\n    - row_number: 3\n      severity: error\n      field: null\n      message: Empty row\n"));
+        assert!(yaml.ends_with("  warnings: null\n"));
+    }
+
+    #[test]
+    fn test_csv_bom_and_crlf_line_endings() {
+        let mut result = sample_result();
+        result.records.as_mut().unwrap()[0].sub_label = Some("two\nlines".to_string());
+        let options = OutputOptions { csv_bom: true, crlf: true, ..OutputOptions::default() };
+
+        let csv = OutputFormatter::format_output_with_options(&result, OutputFormat::Csv, &options).unwrap();
+        assert!(csv.as_bytes().starts_with(&[0xEF, 0xBB, 0xBF, b'm']));
+        assert_eq!(csv.matches("\r\n").count(), 2);
+        // Line breaks inside a quoted value are kept as they are
+        assert!(csv.contains(",\"two\nlines\","));
+
+        let plain = OutputFormatter::format_output(&result, OutputFormat::Csv).unwrap();
+        assert!(plain.starts_with("main_label,"));
+        assert!(!plain.contains('\r'));
+
+        let error = ProcessingResult::error("Sheet missing".to_string(), None, ProcessingMetadata::default());
+        let csv = OutputFormatter::format_output_with_options(&error, OutputFormat::Csv, &options).unwrap();
+        assert_eq!(csv, "\u{FEFF}error\r\nSheet missing\r\n");
+    }
+
+    #[test]
+    fn test_csv_with_semicolon_delimiter() {
+        let options = OutputOptions { delimiter: ';', ..OutputOptions::default() };
+        let csv = OutputFormatter::format_output_with_options(&sample_result(), OutputFormat::Csv, &options)
+            .expect("Should format CSV");
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("main_label;main_value;main_description;"));
+        assert!(lines[1].starts_with("\"Main; primary\";M1;Plain;"));
+        assert_eq!(lines[1].matches(';').count(), 12, "11 separators plus the quoted one");
+    }
+
+    #[test]
+    fn test_csv_with_tab_delimiter() {
+        let mut result = sample_result();
+        result.records.as_mut().unwrap()[0].main_description = Some("a\tb".to_string());
+        let options = OutputOptions { delimiter: parse_delimiter("tab").unwrap(), ..OutputOptions::default() };
+        let csv = OutputFormatter::format_output_with_options(&result, OutputFormat::Csv, &options).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("main_label\tmain_value\tmain_description\t"));
+        // Semicolons and commas need no quoting; the tab does
+        assert!(lines[1].starts_with("Main; primary\tM1\t\"a\tb\"\t"));
+
+        assert_eq!(parse_delimiter("\\t").unwrap(), '\t');
+        assert_eq!(parse_delimiter("|").unwrap(), '|');
+        assert_eq!(parse_delimiter("TAB").unwrap(), '\t');
+        assert_eq!(parse_delimiter("").unwrap_err().to_string(), "Delimiter must be a single character or 'tab', got ''");
+        assert!(parse_delimiter("\"").is_err());
+    }
+
+    #[test]
+    fn test_tsv_replaces_or_rejects_tabs_and_line_breaks() {
+        let mut result = sample_result();
+        result.records.as_mut().unwrap()[0].main_description = Some("a\tb\r\nc".to_string());
+
+        let tsv = OutputFormatter::format_output(&result, OutputFormat::Tsv).unwrap();
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], CascadeField::FIELD_NAMES.join("\t"));
+        assert_eq!(lines[1], format!("Main; primary\tM1\ta b c{}", "\t".repeat(9)));
+
+        let strict = OutputOptions { tsv_strict: true, ..OutputOptions::default() };
+        let error = OutputFormatter::format_output_with_options(&result, OutputFormat::Tsv, &strict).unwrap_err();
+        assert_eq!(error.to_string(), "A value in column 'main_description' contains a tab or line break, which TSV cannot hold");
+    }
+
+    #[test]
+    fn test_streamed_csv_matches_formatted_csv() {
+        let record = |main: &str| {
+            let mut row = vec![None; 12];
+            row[0] = Some("Tops ¦ \"Tees\"".to_string());
+            row[1] = Some(main.to_string());
+            CascadeField::from_row(row).unwrap()
+        };
+        let sheet = |name: &str, rows| SheetData { sheet: name.to_string(), rows, generic_rows: None, metadata: None, file: None };
+        let multi = ProcessingResult::success_multi_sheet(
+            vec![sheet("First", vec![record("A1"), record("A2")]), sheet("Second", vec![record("B1")])],
+            ProcessingMetadata::default(),
+        );
+        let error = ProcessingResult::error("Sheet missing".to_string(), None, ProcessingMetadata::default());
+        let options = [
+            OutputOptions::default(),
+            OutputOptions { delimiter: '¦', csv_bom: true, crlf: true, with_row_numbers: true, ..OutputOptions::default() },
+        ];
+
+        for result in [&sample_result(), &multi, &error] {
+            for options in &options {
+                let mut streamed = Vec::new();
+                OutputFormatter::write_csv(result, options, &mut streamed).unwrap();
+                let formatted = OutputFormatter::format_output_with_options(result, OutputFormat::Csv, options).unwrap();
+                assert_eq!(String::from_utf8(streamed).unwrap(), formatted);
+            }
+        }
+
+        let formatted = OutputFormatter::format_output_with_options(&multi, OutputFormat::Csv, &options[1]).unwrap();
+        assert!(formatted.contains("\r\nFirst¦¦\"Tops ¦ \"\"Tees\"\"\"¦A1¦"), "{}", formatted);
+    }
+
+    #[test]
+    fn test_csv_default_delimiter_does_not_quote_semicolons() {
+        let csv = OutputFormatter::format_output(&sample_result(), OutputFormat::Csv)
+            .expect("Should format CSV");
+
+        assert!(csv.lines().nth(1).unwrap().starts_with("Main; primary,M1,Plain,"));
+    }
+
+    #[test]
+    fn test_root_key_renames_data_in_every_envelope() {
+        let options = OutputOptions { root_key: "records".to_string(), ..OutputOptions::default() };
+        let parse = |result: &ProcessingResult, format| -> Value {
+            serde_json::from_str(&OutputFormatter::format_output_with_options(result, format, &options).unwrap()).unwrap()
+        };
+        let result = sample_result();
+        let error = ProcessingResult::error("File not found".to_string(), None, ProcessingMetadata::default());
+
+        for format in [OutputFormat::Json, OutputFormat::NestedJson] {
+            let json = parse(&result, format);
+            assert!(json.get("data").is_none());
+            assert_eq!(json["records"].as_array().unwrap().len(), 1);
+            assert_eq!(parse(&error, format)["records"], json!([]));
+        }
+        assert_eq!(parse(&error, OutputFormat::PhpArray)["records"], json!([]));
+        let yaml = OutputFormatter::format_output_with_options(&result, OutputFormat::Yaml, &options).unwrap();
+        assert!(yaml.contains("\nrecords:\n"));
+
+        assert!(parse_root_key("success").is_err());
+        assert!(parse_root_key("").is_err());
+    }
+
+    #[test]
+    fn test_sheets_as_object() {
+        use crate::models::SheetData;
+
+        let record = |value: &str| {
+            let mut row = vec![None; 12];
+            row[1] = Some(value.to_string());
+            CascadeField::from_row(row).unwrap()
+        };
+        let sheet = |name: &str, rows| SheetData { sheet: name.to_string(), rows, generic_rows: None, metadata: None, file: None };
+        let result = ProcessingResult::success_multi_sheet(
+            vec![
+                sheet("Sheet1", vec![record("A1"), record("A2")]),
+                sheet("Sheet2", vec![record("B1")]),
+                sheet("Sheet1 ", vec![record("C1")]),
+            ],
+            ProcessingMetadata::default(),
+        );
+
+        let options = OutputOptions { sheets_as_object: true, ..OutputOptions::default() };
+        let json = OutputFormatter::format_output_with_options(&result, OutputFormat::Json, &options)
+            .expect("Should format JSON");
+        let value: Value = serde_json::from_str(&json).unwrap();
+
+        let data = value["data"].as_object().expect("data should be an object");
+        let mut keys: Vec<&str> = data.keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["Sheet1", "Sheet1_2", "Sheet2"]);
+        assert_eq!(data["Sheet1"].as_array().unwrap().len(), 2);
+        assert_eq!(data["Sheet1"][1]["main_value"], "A2");
+        assert_eq!(data["Sheet2"][0]["main_value"], "B1");
+        assert_eq!(data["Sheet1_2"][0]["main_value"], "C1");
+    }
+
+    #[test]
+    fn test_group_by_keys_records_by_field_value() {
+        let record = |main: Option<&str>, description: &str| {
+            let mut row = vec![None; 12];
+            row[1] = main.map(str::to_string);
+            row[2] = Some(description.to_string());
+            CascadeField::from_row(row).unwrap()
+        };
+        let records = vec![
+            record(Some("CAT002"), "first"),
+            record(None, "no category"),
+            record(Some("CAT001"), "second"),
+            record(Some("CAT002"), "third"),
+        ];
+        let sheet = SheetData { sheet: "Data".to_string(), rows: records, generic_rows: None, metadata: None, file: None };
+        let result = ProcessingResult::success_multi_sheet(vec![sheet], ProcessingMetadata::default());
+        let export = |options: &OutputOptions| -> Value {
+            let json = OutputFormatter::format_output_with_options(&result, OutputFormat::Json, options).unwrap();
+            serde_json::from_str(&json).unwrap()
+        };
+
+        let options = OutputOptions { group_by: Some("main_value".to_string()), ..OutputOptions::default() };
+        let rows = &export(&options)["data"][0]["rows"];
+        let mut keys: Vec<&str> = rows.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["", "CAT001", "CAT002"]);
+        assert_eq!(rows["CAT002"][0]["main_description"], "first");
+        assert_eq!(rows["CAT002"][1]["main_description"], "third");
+        assert_eq!(rows[""][0]["main_description"], "no category");
+
+        // A real value equal to the null key shares its group
+        let options = OutputOptions { group_null_key: "CAT001".to_string(), ..options };
+        let rows = &export(&options)["data"][0]["rows"];
+        assert_eq!(rows.as_object().unwrap().len(), 2);
+        assert_eq!(rows["CAT001"][0]["main_description"], "no category");
+        assert_eq!(rows["CAT001"][1]["main_description"], "second");
+
+        let options = OutputOptions { sheets_as_object: true, ..options };
+        assert_eq!(export(&options)["data"]["Data"]["CAT002"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_append_json_extends_records_and_rejects_other_shapes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("daily.json");
+        let day = |value: &str| {
+            let mut row = vec![None; 12];
+            row[1] = Some(value.to_string());
+            let sheet = SheetData {
+                sheet: "Data".to_string(),
+                rows: vec![CascadeField::from_row(row).unwrap()],
+                generic_rows: None,
+                metadata: None,
+                file: None,
+            };
+            ProcessingResult::success_multi_sheet(vec![sheet], ProcessingMetadata::default())
+        };
+        let flat = OutputOptions { flatten: true, ..OutputOptions::default() };
+
+        OutputFormatter::append_json(&day("MON"), &flat, &path).unwrap();
+        OutputFormatter::append_json(&day("TUE"), &flat, &path).unwrap();
+        let document: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let values: Vec<&Value> = document["data"].as_array().unwrap().iter().map(|record| &record["main_value"]).collect();
+        assert_eq!(values, vec!["MON", "TUE"]);
+        // The temporary file is renamed over the document
+        let files: Vec<_> = std::fs::read_dir(temp_dir.path()).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(files, vec!["daily.json"]);
+
+        let mismatch = OutputFormatter::append_json(&day("WED"), &OutputOptions::default(), &path).unwrap_err();
+        assert!(mismatch.to_string().ends_with("it holds flattened records but this run produces {sheet, rows} objects"));
+
+        let error = OutputFormatter::format_output(&ProcessingResult::error("Sheet not found".to_string(), None, ProcessingMetadata::default()), OutputFormat::Json).unwrap();
+        std::fs::write(&path, &error).unwrap();
+        let rejected = OutputFormatter::append_json(&day("WED"), &flat, &path).unwrap_err();
+        assert!(rejected.to_string().ends_with("it is not the output of a successful run"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), error);
+    }
+
+    #[test]
+    fn test_flatten_tags_each_record_with_its_sheet() {
+        let record = |value: &str| {
+            let mut row = vec![None; 12];
+            row[1] = Some(value.to_string());
+            CascadeField::from_row(row).unwrap()
+        };
+        let sheet = |name: &str, rows, file: Option<&str>| SheetData {
+            sheet: name.to_string(),
+            rows,
+            generic_rows: None,
+            metadata: Some(ProcessingMetadata::default()),
+            file: file.map(str::to_string),
+        };
+        let result = ProcessingResult::success_multi_sheet(
+            vec![
+                sheet("Sheet1", vec![record("A1"), record("A2")], None),
+                sheet("Empty", vec![], None),
+                sheet("Sheet2", vec![record("B1")], Some("feb.xlsx")),
+            ],
+            ProcessingMetadata::default(),
+        );
+
+        let options = OutputOptions { flatten: true, ..OutputOptions::default() };
+        let json = OutputFormatter::format_output_with_options(&result, OutputFormat::Json, &options).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+
+        let data = value["data"].as_array().unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!((&data[0]["sheet"], &data[0]["main_value"]), (&json!("Sheet1"), &json!("A1")));
+        assert_eq!((&data[1]["sheet"], &data[1]["main_value"]), (&json!("Sheet1"), &json!("A2")));
+        assert!(data[0].get("file").is_none());
+        assert_eq!(data[2]["sheet"], "Sheet2");
+        assert_eq!(data[2]["file"], "feb.xlsx");
+        assert!(data[0].get("metadata").is_none());
+
+        // Generic rows are tagged the same way, replacing a `sheet` column
+        let generic = SheetData {
+            sheet: "Raw".to_string(),
+            rows: Vec::new(),
+            generic_rows: Some(vec![GenericRecord(IndexMap::from([
+                ("sheet".to_string(), json!("column")),
+                ("Name".to_string(), json!("x")),
+            ]))]),
+            metadata: None,
+            file: None,
+        };
+        let result = ProcessingResult::success_multi_sheet(vec![generic], ProcessingMetadata::default());
+        let yaml = OutputFormatter::format_output_with_options(&result, OutputFormat::Yaml, &options).unwrap();
+        assert!(yaml.contains("sheet: Raw"), "got: {}", yaml);
+        assert!(!yaml.contains("column"));
+    }
+
+    #[test]
+    fn test_to_value_matches_parsed_output() {
+        let error = ProcessingResult::error("File not found".to_string(), None, ProcessingMetadata::default());
+        let options = OutputOptions { compact: true, root_key: "records".to_string(), ..OutputOptions::default() };
+        for result in [sample_result(), error] {
+            for format in [OutputFormat::Json, OutputFormat::NestedJson, OutputFormat::PhpArray] {
+                let text = OutputFormatter::format_output_with_options(&result, format, &options).unwrap();
+                let parsed: Value = serde_json::from_str(&text).unwrap();
+                assert_eq!(OutputFormatter::to_value_with_options(&result, format, &options).unwrap(), parsed, "{:?}", format);
+            }
+        }
+
+        let result = sample_result();
+        assert_eq!(
+            OutputFormatter::to_value(&result, OutputFormat::Yaml).unwrap(),
+            OutputFormatter::to_value(&result, OutputFormat::Json).unwrap(),
+        );
+        let csv = OutputFormatter::format_output(&result, OutputFormat::Csv).unwrap();
+        assert_eq!(OutputFormatter::to_value(&result, OutputFormat::Csv).unwrap(), Value::String(csv));
+        assert!(OutputFormatter::to_value(&result, OutputFormat::Parquet).is_err());
+    }
+
+    #[test]
+    fn test_summary_mentions_row_window() {
+        let mut result = sample_result();
+        assert!(!OutputFormatter::create_summary(&result).contains("Limited to"));
+
+        result.metadata.row_window = Some(crate::models::RowWindow { offset: 0, limit: Some(10) });
+        assert!(OutputFormatter::create_summary(&result).contains("Limited to rows 1-10 of each sheet"));
+    }
+
+    #[test]
+    fn test_summary_lists_each_sheet_and_total() {
+        let records = sample_result().records.unwrap();
+        let sheet = |name: &str, valid_records, invalid_records, processing_time_ms| SheetData {
+            sheet: name.to_string(),
+            rows: records.clone(),
+            generic_rows: None,
+            metadata: Some(ProcessingMetadata { valid_records, invalid_records, processing_time_ms, ..ProcessingMetadata::default() }),
+            file: None,
+        };
+        let total = ProcessingMetadata { valid_records: 5, invalid_records: 1, processing_time_ms: 20, ..ProcessingMetadata::default() };
+        let result = ProcessingResult::success_multi_sheet(
+            vec![sheet("Cascade Fields", 3, 1, 12), sheet("Types", 2, 0, 5)],
+            total.clone(),
+        );
+
+        let summary = OutputFormatter::create_summary(&result);
+        assert!(summary.contains(concat!(
+            "\nSheets:\n",
+            "  Cascade Fields  3 valid, 1 invalid, 12ms\n",
+            "  Types           2 valid, 0 invalid, 5ms\n",
+            "  Total           5 valid, 1 invalid, 20ms\n",
+        )), "got: {}", summary);
+
+        // A single sheet has no breakdown
+        let single = ProcessingResult::success_multi_sheet(vec![sheet("Types", 2, 0, 5)], total);
+        assert!(!OutputFormatter::create_summary(&single).contains("Sheets:"));
+    }
+
+    #[test]
+    fn test_sql_batches_escape_and_comment_errors() {
+        let record = |value: &str| {
+            let mut row = vec![None; 12];
+            row[0] = Some("O'Brien".to_string());
+            row[1] = Some(value.to_string());
+            CascadeField::from_row(row).unwrap()
+        };
+        let result = ProcessingResult::success(
+            vec![record("A1"), record("A2"), record("A3")],
+            ProcessingMetadata::default(),
+        );
+        let options = OutputOptions {
+            table: "app.cascade fields".to_string(),
+            batch_size: 2,
+            ..OutputOptions::default()
+        };
+
+        let sql = OutputFormatter::format_output_with_options(&result, OutputFormat::Sql, &options).unwrap();
+        let statements: Vec<&str> = sql.split_inclusive(";\n").collect();
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].starts_with("INSERT INTO app.\"cascade fields\" (main_label, main_value,"));
+        assert!(statements[0].contains("VALUES\n  ('O''Brien', 'A1', NULL,"));
+        assert!(statements[0].contains(",\n  ('O''Brien', 'A2', NULL,"));
+        assert!(statements[1].contains(" VALUES ('O''Brien', 'A3', NULL,"));
+
+        let error = ProcessingResult::error("Sheet 'X' not found\nTry -a".to_string(), None, ProcessingMetadata::default());
+        let sql = OutputFormatter::format_output(&error, OutputFormat::Sql).unwrap();
+        assert_eq!(sql, "-- Error: Sheet 'X' not found\n-- Try -a\n");
+    }
+
+    #[test]
+    fn test_xml_wraps_sheets_escapes_text_and_handles_nulls() {
+        let mut row = vec![None; 12];
+        row[0] = Some("<Tops> & \"Tees\" 'n' more".to_string());
+        row[1] = Some("CAT001".to_string());
+        let sheet = SheetData {
+            sheet: "Q1 & Q2".to_string(),
+            rows: vec![CascadeField::from_row(row).unwrap()],
+            generic_rows: None,
+            metadata: None,
+            file: None,
+        };
+        let result = ProcessingResult::success_multi_sheet(vec![sheet], ProcessingMetadata::default());
+
+        let xml = OutputFormatter::format_output(&result, OutputFormat::Xml).unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<records>\n  <sheet name=\"Q1 &amp; Q2\">\n    <record>\n"));
+        assert!(xml.contains("      <main_label>&lt;Tops&gt; &amp; &quot;Tees&quot; &apos;n&apos; more</main_label>\n"));
+        assert!(xml.contains("      <minor_description/>\n"));
+        assert!(xml.ends_with("    </record>\n  </sheet>\n</records>\n"));
+
+        let options = OutputOptions { xml_omit_null: true, ..OutputOptions::default() };
+        let xml = OutputFormatter::format_output_with_options(&result, OutputFormat::Xml, &options).unwrap();
+        assert!(xml.contains("<main_value>CAT001</main_value>"));
+        assert!(!xml.contains("minor_description"));
+
+        // Header texts are made into valid element names
+        let mut generic = IndexMap::new();
+        generic.insert("Item Code".to_string(), json!("A-1"));
+        generic.insert("2024 Qty".to_string(), json!(5));
+        let result = ProcessingResult::success_generic(vec![GenericRecord(generic)], ProcessingMetadata::default());
+        let xml = OutputFormatter::format_output(&result, OutputFormat::Xml).unwrap();
+        assert!(xml.contains("<Item_Code>A-1</Item_Code>"));
+        assert!(xml.contains("<_2024_Qty>5</_2024_Qty>"));
+
+        let error = ProcessingResult::error("Sheet <X> not found".to_string(), None, ProcessingMetadata::default());
+        let xml = OutputFormatter::format_output(&error, OutputFormat::Xml).unwrap();
+        assert!(xml.ends_with("<error>Sheet &lt;X&gt; not found</error>\n"));
+    }
+
+    #[test]
+    fn test_metrics_exposition_format() {
+        let metrics = OutputFormatter::format_metrics(&sample_result(), "C:\\data\\\"q\".xlsx");
+
+        let label = r#"{file="C:\\data\\\"q\".xlsx"}"#;
+        for (name, value) in [
+            ("success", 1),
+            ("total_rows_processed", 1),
+            ("valid_records", 1),
+            ("invalid_records", 0),
+            ("malformed_rows", 0),
+            ("skipped_records", 0),
+            ("duplicate_records", 0),
+            ("filtered_records", 0),
+            ("processing_time_ms", 1),
+            ("warnings", 0),
+            ("diagnostics", 0),
+        ] {
+            assert!(metrics.contains(&format!("# TYPE excel_to_json_{} gauge\n", name)), "missing TYPE for {}", name);
+            assert!(metrics.contains(&format!("excel_to_json_{}{} {}\n", name, label, value)), "missing sample for {}:\n{}", name, metrics);
+        }
+    }
+
+    #[test]
+    fn test_line_per_record_json() {
+        use crate::models::SheetData;
+
+        let record = |value: &str| {
+            let mut row = vec![None; 12];
+            row[1] = Some(value.to_string());
+            CascadeField::from_row(row).unwrap()
+        };
+        let result = ProcessingResult::success_multi_sheet(
+            vec![SheetData {
+                sheet: "Sheet1".to_string(),
+                rows: vec![record("A1"), record("A2"), record("A3")],
+                generic_rows: None,
+                metadata: None,
+                file: None,
+            }],
+            ProcessingMetadata::default(),
+        );
+
+        let options = OutputOptions { line_per_record: true, ..OutputOptions::default() };
+        let json = OutputFormatter::format_output_with_options(&result, OutputFormat::Json, &options)
+            .expect("Should format JSON");
+
+        let record_lines: Vec<&str> = json.lines().filter(|line| line.contains("main_value")).collect();
+        assert_eq!(record_lines.len(), 3, "Each record should occupy exactly one line:\n{}", json);
+        for (line, value) in record_lines.iter().zip(["A1", "A2", "A3"]) {
+            let line = line.trim().trim_end_matches(',');
+            let parsed: Value = serde_json::from_str(line).expect("Record line should be a complete object");
+            assert_eq!(parsed["main_value"], value);
+        }
+
+        let parsed: Value = serde_json::from_str(&json).expect("Output should be valid JSON");
+        let pretty = OutputFormatter::format_output(&result, OutputFormat::Json).unwrap();
+        assert_eq!(parsed, serde_json::from_str::<Value>(&pretty).unwrap());
+    }
+
+    #[test]
+    fn test_row_numbers_in_json_and_csv() {
+        let mut result = sample_result();
+        result.records.as_mut().unwrap()[0].row_number = Some(5);
+        let numbered = OutputOptions { with_row_numbers: true, ..OutputOptions::default() };
+
+        let json: Value = serde_json::from_str(&OutputFormatter::format_output_with_options(&result, OutputFormat::Json, &numbered).unwrap()).unwrap();
+        assert_eq!(json["data"][0]["source_row"], json!(5));
+        let plain: Value = serde_json::from_str(&OutputFormatter::format_output(&result, OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(plain["data"][0].get("source_row"), None);
+
+        let csv = OutputFormatter::format_output_with_options(&result, OutputFormat::Csv, &numbered).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("source_row,main_label,main_value,"));
+        assert!(lines[1].starts_with("5,Main; primary,M1,"));
+
+        // PHP arrays keep the fixed columns
+        let php = OutputFormatter::format_output_with_options(&result, OutputFormat::PhpArray, &numbered).unwrap();
+        assert!(!php.contains("source_row"));
+    }
+
+    #[test]
+    fn test_columns_project_json_records_in_given_order() {
+        let mut result = sample_result();
+        result.records.as_mut().unwrap()[0].row_number = Some(5);
+        let options = OutputOptions {
+            columns: Some(vec!["main_value".to_string(), "main_label".to_string()]),
+            with_row_numbers: true,
+            compact: true,
+            ..OutputOptions::default()
+        };
+
+        let json = OutputFormatter::format_output_with_options(&result, OutputFormat::Json, &options).unwrap();
+        assert!(json.contains(r#""data":[{"main_value":"M1","main_label":"Main; primary","source_row":5}]"#), "{}", json);
+        let yaml = OutputFormatter::format_output_with_options(&result, OutputFormat::Yaml, &options).unwrap();
+        assert!(yaml.contains("data:\n  - main_value: M1\n    main_label: \"Main; primary\"\n    source_row: 5\n"), "{}", yaml);
+    }
+
+    #[test]
+    fn test_columns_project_csv_in_given_order() {
+        let result = sample_result();
+        let options = OutputOptions {
+            columns: Some(vec!["main_description".to_string(), "main_value".to_string()]),
+            ..OutputOptions::default()
+        };
+
+        let csv = OutputFormatter::format_output_with_options(&result, OutputFormat::Csv, &options).unwrap();
+        assert_eq!(csv, "main_description,main_value\nPlain,M1\n");
+        let tsv = OutputFormatter::format_output_with_options(&result, OutputFormat::Tsv, &options).unwrap();
+        assert_eq!(tsv, "main_description\tmain_value\nPlain\tM1\n");
+    }
+
+    #[test]
+    fn test_compact_json_and_php_have_no_newlines() {
+        let result = sample_result();
+        let compact = OutputOptions { compact: true, ..OutputOptions::default() };
+
+        for format in [OutputFormat::Json, OutputFormat::PhpArray] {
+            let pretty = OutputFormatter::format_output(&result, format).unwrap();
+            let minified = OutputFormatter::format_output_with_options(&result, format, &compact).unwrap();
+
+            assert!(!minified.contains('\n'), "{:?} output should be one line", format);
+            assert!(minified.len() < pretty.len());
+            let pretty: Value = serde_json::from_str(&pretty).unwrap();
+            let minified: Value = serde_json::from_str(&minified).unwrap();
+            assert_eq!(minified, pretty);
+        }
+    }
+
+    #[test]
+    fn test_php_keep_null_distinguishes_blank_fields() {
+        let mut result = sample_result();
+        let record = &mut result.records.as_mut().unwrap()[0];
+        record.extra.insert("Weight".to_string(), Value::Null);
+        let php = |php_keep_null| {
+            let options = OutputOptions { php_keep_null, ..OutputOptions::default() };
+            let php = OutputFormatter::format_output_with_options(&result, OutputFormat::PhpArray, &options).unwrap();
+            serde_json::from_str::<Value>(&php).unwrap()
+        };
+
+        let default = php(false);
+        assert_eq!(default[0]["sub_label"], json!(""));
+        assert_eq!(default[0]["Weight"], json!(""));
+        assert_eq!(default[0]["main_value"], json!("M1"));
+
+        let kept = php(true);
+        assert_eq!(kept[0]["sub_label"], Value::Null);
+        assert_eq!(kept[0]["Weight"], Value::Null);
+        assert_eq!(kept[0]["main_value"], json!("M1"));
+    }
+
+    #[test]
+    fn test_markdown_heading_and_table_per_sheet() {
+        let record = |main: &str, description: Option<&str>| {
+            let mut row = vec![None; 12];
+            row[1] = Some(main.to_string());
+            row[2] = description.map(str::to_string);
+            CascadeField::from_row(row).unwrap()
+        };
+        let sheet = |name: &str, rows| SheetData { sheet: name.to_string(), rows, generic_rows: None, metadata: None, file: None };
+        let result = ProcessingResult::success_multi_sheet(
+            vec![
+                sheet("First", vec![record("A|B", Some("line one\nline two")), record("C", None)]),
+                sheet("Second", vec![]),
+            ],
+            ProcessingMetadata::default(),
+        );
+
+        let markdown = OutputFormatter::format_output(&result, OutputFormat::Markdown).unwrap();
+        let lines: Vec<&str> = markdown.lines().collect();
+        let empty_fields = "  |".repeat(9);
+        assert_eq!(lines[0], "## First");
+        assert_eq!(lines[1], "");
+        assert_eq!(lines[2], format!("| {} |", CascadeField::FIELD_NAMES.join(" | ")));
+        assert_eq!(lines[3], format!("|{}", " --- |".repeat(12)));
+        assert_eq!(lines[4], format!("|  | A\\|B | line one<br>line two |{}", empty_fields));
+        assert_eq!(lines[5], format!("|  | C |  |{}", empty_fields));
+        assert_eq!(lines[6], "");
+        assert_eq!(lines[7], "## Second");
+        assert_eq!(lines.len(), 11);
+
+        let error = ProcessingResult::error("Sheet 'X' not found".to_string(), None, ProcessingMetadata::default());
+        assert_eq!(OutputFormatter::format_output(&error, OutputFormat::Markdown).unwrap(), "**Error:** Sheet 'X' not found\n");
+    }
+
+    #[test]
+    fn test_write_zip_has_entry_per_sheet() {
+        use crate::models::SheetData;
+        use std::io::Read;
+
+        let record = |value: &str| {
+            let mut row = vec![None; 12];
+            row[1] = Some(value.to_string());
+            CascadeField::from_row(row).unwrap()
+        };
+        let sheet = |name: &str, rows| SheetData { sheet: name.to_string(), rows, generic_rows: None, metadata: None, file: None };
+        let result = ProcessingResult::success_multi_sheet(
+            vec![
+                sheet("Sheet1", vec![record("A1"), record("A2")]),
+                sheet("Q1/Q2", vec![record("B1")]),
+            ],
+            ProcessingMetadata::default(),
+        );
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("export.zip");
+        let path = path.to_str().unwrap();
+
+        OutputFormatter::write_zip(&result, OutputFormat::Csv, &OutputOptions::default(), path)
+            .expect("Should write zip");
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(|n| n.to_string()).collect();
+        names.sort();
+        assert_eq!(names, vec!["Q1_Q2.csv", "Sheet1.csv"]);
+
+        for (name, expected_rows) in [("Sheet1.csv", 2), ("Q1_Q2.csv", 1)] {
+            let mut content = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut content).unwrap();
+            let lines: Vec<&str> = content.lines().collect();
+            assert_eq!(lines[0], CascadeField::FIELD_NAMES.join(","), "{} should start with the header", name);
+            assert_eq!(lines.len(), expected_rows + 1);
+        }
+
+        OutputFormatter::write_zip(&result, OutputFormat::Json, &OutputOptions::default(), path)
+            .expect("Should write zip");
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        let mut content = String::new();
+        archive.by_name("Sheet1.json").unwrap().read_to_string(&mut content).unwrap();
+        let json: Value = serde_json::from_str(&content).expect("Entry should be valid JSON");
+        assert_eq!(json["data"].as_array().unwrap().len(), 2);
+    }
 }
@@ -0,0 +1,206 @@
+//! Parquet output.
+//!
+//! Writes cascade records as a Parquet file with one nullable UTF-8 column per
+//! field, for loading into data lakes without re-serializing JSON. Multi-sheet
+//! results get a leading `sheet` column, as in CSV output. Empty fields become
+//! Parquet nulls, and the processing metadata is stored as JSON under the
+//! [`METADATA_KEY`] key-value entry of the file metadata.
+//!
+//! The records are built as an Arrow record batch and written by the `parquet`
+//! crate's Arrow writer, uncompressed, in a single row group. This module is
+//! only built with the `parquet` cargo feature, as the Arrow and Parquet
+//! libraries add considerably to the build.
+//!
+//! Parquet is binary, so it is written to a file (see [`write_parquet`])
+//! rather than returned by [`OutputFormatter::format_output`](crate::output::OutputFormatter::format_output).
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::models::{CascadeField, ProcessingMetadata, ProcessingResult};
+//! use excel_to_json::parquet::to_parquet;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut row = vec![None; 12];
+//! row[1] = Some("CAT001".to_string());
+//! let result = ProcessingResult::success(vec![CascadeField::from_row(row).unwrap()], ProcessingMetadata::default());
+//!
+//! let bytes = to_parquet(&result)?;
+//! assert!(bytes.starts_with(b"PAR1") && bytes.ends_with(b"PAR1"));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::models::{CascadeField, ProcessingResult, SheetData};
+use anyhow::{Context, Result};
+use arrow_array::{ArrayRef, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use std::fs;
+use std::sync::Arc;
+
+/// Key of the file metadata entry holding the processing metadata as JSON.
+pub const METADATA_KEY: &str = "excel_to_json.metadata";
+
+/// Encodes a successful result as a Parquet file.
+///
+/// # Arguments
+///
+/// * `result` - A successful result with cascade records
+///
+/// # Returns
+///
+/// The bytes of the Parquet file
+///
+/// # Errors
+///
+/// Returns an error for a failed result, and for `--generic` records, which
+/// have no fixed columns.
+pub fn to_parquet(result: &ProcessingResult) -> Result<Vec<u8>> {
+    if !result.success {
+        anyhow::bail!(result.error.clone().unwrap_or_else(|| "Unknown error".to_string()));
+    }
+    let sheets = result.sheet_data.as_deref().unwrap_or_default();
+    if result.generic_records.is_some() || sheets.iter().any(|sheet| sheet.generic_rows.is_some()) {
+        anyhow::bail!("Parquet output needs the cascade field columns; it is not available with --generic");
+    }
+
+    let keys: Vec<String> = sheets.iter().map(SheetData::key).collect();
+    let rows: Vec<(Option<&str>, &CascadeField)> = match &result.sheet_data {
+        Some(sheet_data) => sheet_data.iter()
+            .zip(&keys)
+            .flat_map(|(sheet, key)| sheet.rows.iter().map(move |record| (Some(key.as_str()), record)))
+            .collect(),
+        None => result.records.iter().flatten().map(|record| (None, record)).collect(),
+    };
+    let values: Vec<[Option<&str>; 12]> = rows.iter().map(|(_, record)| record.field_values()).collect();
+
+    let mut fields = Vec::new();
+    let mut columns: Vec<ArrayRef> = Vec::new();
+    if result.sheet_data.is_some() {
+        fields.push(Field::new("sheet", DataType::Utf8, false));
+        columns.push(Arc::new(rows.iter().map(|(sheet, _)| *sheet).collect::<StringArray>()));
+    }
+    for (idx, name) in CascadeField::FIELD_NAMES.iter().enumerate() {
+        fields.push(Field::new(*name, DataType::Utf8, true));
+        columns.push(Arc::new(values.iter().map(|record| record[idx]).collect::<StringArray>()));
+    }
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+
+    let properties = WriterProperties::builder()
+        .set_created_by(concat!("excel-to-json version ", env!("CARGO_PKG_VERSION")).to_string())
+        .set_key_value_metadata(Some(vec![KeyValue::new(METADATA_KEY.to_string(), serde_json::to_string(&result.metadata)?)]))
+        .build();
+    let mut file = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut file, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(file)
+}
+
+/// Writes a successful result to a Parquet file.
+///
+/// # Arguments
+///
+/// * `result` - A successful result with cascade records
+/// * `file_path` - Path of the file to create or overwrite
+///
+/// # Errors
+///
+/// Returns an error if the result cannot be encoded (see [`to_parquet`]) or
+/// the file cannot be written.
+pub fn write_parquet(result: &ProcessingResult, file_path: &str) -> Result<()> {
+    let bytes = to_parquet(result)?;
+    fs::write(file_path, bytes).with_context(|| format!("Failed to write Parquet file {}", file_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ProcessingMetadata;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field as ParquetField;
+
+    /// A column read back: its name and a value per row.
+    type ColumnValues = (String, Vec<Option<String>>);
+
+    /// Reads a Parquet file back with the `parquet` crate's reader, returning
+    /// its row count, stored processing metadata and column values.
+    fn read_parquet(path: &std::path::Path) -> (i64, String, Vec<ColumnValues>) {
+        let reader = SerializedFileReader::new(fs::File::open(path).unwrap()).unwrap();
+        let file_metadata = reader.metadata().file_metadata();
+        let stored = file_metadata.key_value_metadata().unwrap().iter()
+            .find(|entry| entry.key == METADATA_KEY)
+            .and_then(|entry| entry.value.clone())
+            .unwrap();
+
+        let mut columns: Vec<ColumnValues> = file_metadata.schema_descr().columns().iter()
+            .map(|column| (column.name().to_string(), Vec::new()))
+            .collect();
+        for row in reader.get_row_iter(None).unwrap() {
+            for (idx, (_, field)) in row.unwrap().get_column_iter().enumerate() {
+                columns[idx].1.push(match field {
+                    ParquetField::Str(value) => Some(value.clone()),
+                    ParquetField::Null => None,
+                    other => panic!("unexpected value {:?}", other),
+                });
+            }
+        }
+        (file_metadata.num_rows(), stored, columns)
+    }
+
+    #[test]
+    fn test_parquet_reads_back_columns_nulls_and_metadata() {
+        let record = |main: Option<&str>, minor: &str| {
+            let mut row = vec![None; 12];
+            row[1] = main.map(str::to_string);
+            row[10] = Some(minor.to_string());
+            CascadeField::from_row(row).unwrap()
+        };
+        let mut rows: Vec<CascadeField> = (0..9).map(|idx| record(Some(&format!("M{}", idx)), "Ärger")).collect();
+        rows[7] = record(None, "N7");
+        let sheet = |name: &str, rows| SheetData { sheet: name.to_string(), rows, generic_rows: None, metadata: None, file: None };
+        let metadata = ProcessingMetadata { valid_records: 9, ..ProcessingMetadata::default() };
+        let result = ProcessingResult::success_multi_sheet(vec![sheet("First", rows), sheet("Empty", vec![])], metadata);
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("export.parquet");
+        write_parquet(&result, path.to_str().unwrap()).unwrap();
+
+        let (num_rows, stored, columns) = read_parquet(&path);
+
+        assert_eq!(num_rows, 9);
+        assert_eq!(columns.len(), 13);
+        assert_eq!(columns[0], ("sheet".to_string(), vec![Some("First".to_string()); 9]));
+        assert_eq!(columns[2].0, "main_value");
+        assert_eq!(columns[2].1[6].as_deref(), Some("M6"));
+        assert_eq!(columns[2].1[7], None);
+        assert_eq!(columns[2].1[8].as_deref(), Some("M8"));
+        assert_eq!(columns[11].1[7].as_deref(), Some("N7"));
+        assert_eq!(columns[11].1[0].as_deref(), Some("Ärger"));
+        assert!(columns[1].1.iter().all(Option::is_none));
+
+        let stored: ProcessingMetadata = serde_json::from_str(&stored).unwrap();
+        assert_eq!(stored.valid_records, 9);
+
+        let failed = ProcessingResult::error("File not found".to_string(), None, ProcessingMetadata::default());
+        assert_eq!(to_parquet(&failed).unwrap_err().to_string(), "File not found");
+    }
+
+    #[test]
+    fn test_single_sheet_result_has_no_sheet_column() {
+        let mut row = vec![None; 12];
+        row[1] = Some("CAT001".to_string());
+        let result = ProcessingResult::success(vec![CascadeField::from_row(row).unwrap()], ProcessingMetadata::default());
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("export.parquet");
+        write_parquet(&result, path.to_str().unwrap()).unwrap();
+
+        let (num_rows, _, columns) = read_parquet(&path);
+        assert_eq!(num_rows, 1);
+        let names: Vec<&str> = columns.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, CascadeField::FIELD_NAMES);
+        assert_eq!(columns[1].1, vec![Some("CAT001".to_string())]);
+    }
+}
@@ -0,0 +1,955 @@
+//! Workbook-level processing.
+//!
+//! This module ties the reader and the processor together: it selects the
+//! sheets of a workbook, reads each of them and turns their rows into
+//! records. [`process_file`] is the entry point for programs that want the
+//! same result as the command-line tool without shelling out to it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::{process_file, ProcessOptions};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let options = ProcessOptions {
+//!     sheets: vec!["Cascade Fields".to_string()],
+//!     ..ProcessOptions::default()
+//! };
+//! let result = process_file("resources/Cascade Sample.xlsx", &options)?;
+//!
+//! assert!(result.success);
+//! let sheets = result.sheet_data.unwrap();
+//! assert_eq!(sheets[0].rows.len(), 2);
+//! assert_eq!(result.metadata.invalid_records, 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::excel_reader::{closest_sheet_name, resolve_sheet_name, ExcelReader, ReaderError, ReaderOptions, SheetRowIter, SheetRows, WorkbookSource};
+use crate::models::{CascadeField, ProcessingMetadata, ProcessingResult, SheetData};
+use crate::output::OutputFormatter;
+use crate::processor::{DataProcessor, ProcessorOptions, PROGRESS_INTERVAL};
+use crate::progress::{Progress, ProgressUpdate};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde_json::{json, Value};
+use tracing::{debug, info, warn};
+
+/// Settings for [`process_file`].
+///
+/// The defaults process the first sheet of the workbook with the same
+/// settings as the command-line tool run without options.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessOptions {
+    /// Sheets to process, in order. Empty selects the first sheet, or every
+    /// sheet with `all_sheets`.
+    pub sheets: Vec<String>,
+    /// Glob patterns selecting further sheets, e.g. `Data_*`; see
+    /// [`compile_sheet_glob`].
+    pub sheet_globs: Vec<String>,
+    /// Process every sheet of the workbook when `sheets` is empty.
+    pub all_sheets: bool,
+    /// Process only the sheet at this 0-based position in the workbook,
+    /// whatever its name; takes the place of `sheets` and `all_sheets`.
+    pub sheet_index: Option<usize>,
+    /// Replace a requested sheet that does not exist with the sheet its name
+    /// is a near miss of; see [`closest_sheet_name`].
+    pub fuzzy_sheets: bool,
+    /// Sheets whose statistics count toward the aggregate metadata, matched
+    /// ignoring case and surrounding spaces; empty counts every processed
+    /// sheet.
+    pub metadata_sheets: Vec<String>,
+    /// Cell conversion settings applied to every sheet
+    pub reader: ReaderOptions,
+    /// Cleaning and validation settings applied to every sheet
+    pub processor: ProcessorOptions,
+}
+
+/// Settings for [`stream_excel_file_multiple_sheets`] on top of the reader
+/// and processor options.
+#[derive(Debug, Clone, Default)]
+pub struct StreamOptions<'a> {
+    /// Add each cascade record's spreadsheet row as `source_row`
+    pub with_row_numbers: bool,
+    /// Add the record's sheet as `sheet`, so that lines of several sheets
+    /// can be told apart
+    pub tag_sheets: bool,
+    /// Add this input file name as `file`
+    pub file: Option<String>,
+    /// Destination of periodic progress updates and of a `N/M sheets`
+    /// indicator
+    pub progress: Option<&'a Progress>,
+}
+
+/// Processes the selected sheets of a workbook into a [`ProcessingResult`].
+///
+/// The result has one [`SheetData`] per sheet and aggregate metadata, as
+/// produced by the command-line tool for a single input file. It can be
+/// passed to [`OutputFormatter`] to render any output format.
+///
+/// # Arguments
+///
+/// * `source` - The workbook, as a path or an in-memory [`WorkbookSource`]
+/// * `options` - Sheet selection and processing settings
+///
+/// # Returns
+///
+/// * `Ok(result)` - A successful result holding the records of every sheet
+/// * `Err` - If the workbook cannot be opened, a sheet is missing, or
+///   processing a sheet fails
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::output::{OutputFormat, OutputFormatter};
+/// use excel_to_json::{process_file, ProcessOptions};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let result = process_file("resources/Cascade Sample.xlsx", &ProcessOptions::default())?;
+/// let csv = OutputFormatter::format_output(&result, OutputFormat::Csv)?;
+/// assert!(csv.starts_with("sheet,"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn process_file(source: impl Into<WorkbookSource>, options: &ProcessOptions) -> Result<ProcessingResult> {
+    let source = source.into();
+    let sheets = select_sheets(&source, &options.sheets, &options.sheet_globs, options.all_sheets, options.sheet_index, options.fuzzy_sheets)?;
+    
+    let (sheet_data, mut metadata) = process_excel_file_multiple_sheets(
+        &source,
+        sheets,
+        &options.metadata_sheets,
+        &options.reader,
+        &options.processor,
+        None,
+    )?;
+    metadata.row_window = options.reader.row_window();
+    
+    Ok(ProcessingResult::success_multi_sheet(sheet_data, metadata))
+}
+
+/// Determines which sheets of a workbook to process.
+///
+/// # Arguments
+///
+/// * `source` - The workbook
+/// * `sheets` - Explicitly requested sheets; returned as they are when not empty
+/// * `sheet_globs` - Glob patterns adding the sheets they match, in workbook
+///   order, after `sheets` (see [`compile_sheet_glob`])
+/// * `all_sheets` - Select every sheet instead of the first when `sheets` and
+///   `sheet_globs` are empty
+/// * `sheet_index` - Select only the sheet at this 0-based position, resolved
+///   against the workbook's sheet names; `sheets` should then be empty
+/// * `fuzzy` - Replace requested sheets missing from the workbook with the
+///   sheet their name is a near miss of, if any (see [`closest_sheet_name`])
+///
+/// # Errors
+///
+/// Returns an error if the workbook cannot be opened or has no sheets, and
+/// [`ReaderError::NoSheetMatches`] for a pattern matching no sheet and
+/// [`ReaderError::SheetIndexOutOfRange`] for an index past the last sheet.
+pub fn select_sheets(
+    source: &WorkbookSource,
+    sheets: &[String],
+    sheet_globs: &[String],
+    all_sheets: bool,
+    sheet_index: Option<usize>,
+    fuzzy: bool,
+) -> Result<Vec<String>> {
+    if !sheets.is_empty() && sheet_globs.is_empty() && !fuzzy {
+        info!("Processing sheets of {}: {:?}", source, sheets);
+        return Ok(sheets.to_vec());
+    }
+    
+    let reader = ExcelReader::from_source(source.clone(), String::new())
+        .context("Failed to open Excel file")?;
+    let available = reader.get_sheet_names();
+    if let Some(index) = sheet_index {
+        let sheet = available.get(index)
+            .cloned()
+            .ok_or_else(|| ReaderError::SheetIndexOutOfRange { index, available: available.clone() })?;
+        info!("Processing sheet {} of {}: {}", index, source, sheet);
+        return Ok(vec![sheet]);
+    }
+    if !sheets.is_empty() || !sheet_globs.is_empty() {
+        let mut selected: Vec<String> = sheets.iter()
+            .map(|sheet| match closest_sheet_name(sheet, &available) {
+                Some(closest) if fuzzy && !available.contains(sheet) => {
+                    warn!("Sheet '{}' not found; using '{}'", sheet, closest);
+                    closest.to_string()
+                },
+                // Missing sheets without a near miss are reported when read
+                _ => sheet.clone(),
+            })
+            .collect();
+        for pattern in sheet_globs {
+            let glob = compile_sheet_glob(pattern)?;
+            let matches: Vec<&String> = available.iter().filter(|name| glob.is_match(name)).collect();
+            if matches.is_empty() {
+                return Err(ReaderError::NoSheetMatches { pattern: pattern.clone(), available: available.clone() }.into());
+            }
+            for name in matches {
+                if !selected.contains(name) {
+                    selected.push(name.clone());
+                }
+            }
+        }
+        info!("Processing sheets of {}: {:?}", source, selected);
+        return Ok(selected);
+    }
+    let sheets = available;
+    if all_sheets {
+        info!("Processing all sheets of {}", source);
+        return Ok(sheets);
+    }
+    
+    // Default to first sheet
+    let first_sheet = sheets.first()
+        .ok_or_else(|| anyhow::anyhow!("No sheets found in Excel file"))?
+        .clone();
+    info!("Processing default sheet of {}: {}", source, first_sheet);
+    Ok(vec![first_sheet])
+}
+
+/// Compiles a sheet name glob pattern into a regex matching whole names.
+///
+/// `*` matches any run of characters, `?` any single character and `[...]`
+/// one character of a set or range (`[!...]` negates it); everything else
+/// matches itself. Matching is case-sensitive.
+///
+/// # Errors
+///
+/// Returns an error for an unclosed `[` or an invalid range.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::pipeline::compile_sheet_glob;
+///
+/// let glob = compile_sheet_glob("Data_20[2-3]?")?;
+/// assert!(glob.is_match("Data_2023"));
+/// assert!(!glob.is_match("Data_2019"));
+/// assert!(!glob.is_match("Old Data_2023"));
+/// assert!(compile_sheet_glob("Data_[").is_err());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn compile_sheet_glob(pattern: &str) -> Result<Regex> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                regex.push('[');
+                if chars.next_if_eq(&'!').is_some() {
+                    regex.push('^');
+                }
+                // A ']' first in the set is a literal
+                if chars.next_if_eq(&']').is_some() {
+                    regex.push_str("\\]");
+                }
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c @ ('\\' | '[' | '^' | '&' | '~')) => {
+                            regex.push('\\');
+                            regex.push(c);
+                        },
+                        Some(c) => regex.push(c),
+                        None => anyhow::bail!("Unclosed '[' in sheet pattern '{}'", pattern),
+                    }
+                }
+                regex.push(']');
+            },
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).with_context(|| format!("Invalid sheet pattern '{}'", pattern))
+}
+
+/// Processes an Excel file and extracts records from multiple sheets.
+///
+/// This function handles the core Excel processing workflow for multiple sheets:
+/// reading the file, extracting data with formula evaluation,
+/// and transforming rows into structured records.
+///
+/// # Arguments
+///
+/// * `source` - Excel file to process, on disk or read from stdin
+/// * `sheet_names` - List of worksheet names to process
+/// * `metadata_sheets` - Sheets whose statistics count toward the aggregate
+///   metadata, matched ignoring case and surrounding spaces; an empty slice
+///   means every processed sheet counts
+/// * `reader_options` - Cell conversion settings applied to every sheet
+/// * `options` - Cleaning and validation settings applied to every sheet
+/// * `progress` - Optional destination of periodic progress updates and of a
+///   `N/M sheets` indicator
+///
+/// # Returns
+///
+/// * `Ok((sheet_data, metadata))` - Successfully processed sheet data and statistics
+/// * `Err` - If file reading or processing fails, or a metadata sheet is not
+///   among `sheet_names`
+///
+/// Each `SheetData` carries its own metadata regardless of `metadata_sheets`,
+/// so lookup/reference sheets can be output without inflating the totals.
+pub fn process_excel_file_multiple_sheets(
+    source: &WorkbookSource,
+    sheet_names: Vec<String>,
+    metadata_sheets: &[String],
+    reader_options: &ReaderOptions,
+    options: &ProcessorOptions,
+    progress: Option<&Progress>,
+) -> Result<(Vec<SheetData>, ProcessingMetadata)> {
+    let metadata_sheets = resolve_metadata_sheets(source, &sheet_names, metadata_sheets)?;
+    let mut all_sheet_data = Vec::new();
+    let mut total_metadata = ProcessingMetadata::default();
+    let mut all_warnings = Vec::new();
+    let sheet_count = sheet_names.len();
+    
+    for (sheet_idx, sheet_name) in sheet_names.into_iter().enumerate() {
+        // Create Excel reader for this sheet
+        let mut reader = ExcelReader::from_source(source.clone(), sheet_name.clone())
+            .context("Failed to create Excel reader")?;
+        reader.set_options(reader_options.clone());
+        let sheet_name = reader.sheet_name().to_string();
+        
+        info!("Processing sheet: {}", sheet_name);
+        if let Some(progress) = progress {
+            progress.sheet_started(sheet_idx + 1, sheet_count, &sheet_name);
+        }
+        
+        // Read and process the Excel data
+        let sheet = reader.read_sheet()
+            .context(format!("Failed to read Excel data from sheet '{}'", sheet_name))?;
+        let sheet_data = process_sheet_rows(sheet_name, sheet, options, progress)?;
+        
+        // Aggregate metadata, skipping sheets excluded from the totals
+        let counted = metadata_sheets.is_empty() || metadata_sheets.contains(&sheet_data.sheet);
+        match &sheet_data.metadata {
+            Some(metadata) if counted => add_sheet_metadata(&mut total_metadata, &mut all_warnings, metadata),
+            _ => debug!("Excluding sheet '{}' from aggregate metadata", sheet_data.sheet),
+        }
+        
+        all_sheet_data.push(sheet_data);
+    }
+    
+    if !all_warnings.is_empty() {
+        total_metadata.warnings = Some(all_warnings);
+    }
+    
+    Ok((all_sheet_data, total_metadata))
+}
+
+/// Prefixes a sheet's warnings with its name, so they stay clear once
+/// gathered into the aggregate metadata.
+fn name_sheet_in_warnings(metadata: &mut ProcessingMetadata, sheet_name: &str) {
+    for warning in metadata.warnings.iter_mut().flatten() {
+        *warning = format!("Sheet '{}': {}", sheet_name, warning);
+    }
+}
+
+/// Warns that a sheet has no cells at all, which would otherwise look like a
+/// sheet whose header has no data below it.
+fn warn_empty_sheet(metadata: &mut ProcessingMetadata, sheet_name: &str) {
+    let message = format!("Sheet '{}' is empty", sheet_name);
+    warn!("{}", message);
+    metadata.warnings.get_or_insert_with(Vec::new).push(message);
+}
+
+/// Adds a sheet's statistics, diagnostics and warnings to the aggregate metadata.
+pub fn add_sheet_metadata(total: &mut ProcessingMetadata, warnings: &mut Vec<String>, metadata: &ProcessingMetadata) {
+    total.total_rows_processed += metadata.total_rows_processed;
+    total.valid_records += metadata.valid_records;
+    total.invalid_records += metadata.invalid_records;
+    total.malformed_rows += metadata.malformed_rows;
+    total.skipped_records += metadata.skipped_records;
+    total.duplicate_records += metadata.duplicate_records;
+    total.filtered_records += metadata.filtered_records;
+    total.processing_time_ms += metadata.processing_time_ms;
+    total.diagnostics.extend(metadata.diagnostics.iter().cloned());
+    
+    if let Some(sheet_warnings) = &metadata.warnings {
+        warnings.extend(sheet_warnings.iter().cloned());
+    }
+}
+
+/// Processes a sheet that is already in memory as a calamine range.
+///
+/// Runs the same header handling, cleaning and validation as
+/// [`process_file`] does for each sheet it reads, for callers that hold
+/// their workbooks open or build the cells themselves. The cells are
+/// converted as described in [`SheetRowIter::from_range`].
+///
+/// # Arguments
+///
+/// * `sheet_name` - Name given to the sheet in the result and in messages
+/// * `range` - The sheet's cells, header row included
+/// * `reader_options` - Cell conversion settings
+/// * `options` - Cleaning and validation settings
+///
+/// # Returns
+///
+/// * `Ok(sheet)` - The sheet's records, with its metadata
+/// * `Err` - If the header row is beyond the range or processing fails
+///
+/// # Example
+///
+/// ```rust
+/// use calamine::{Data, Range};
+/// use excel_to_json::excel_reader::ReaderOptions;
+/// use excel_to_json::pipeline::process_range;
+/// use excel_to_json::processor::ProcessorOptions;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut range = Range::new((0, 0), (2, 11));
+/// range.set_value((0, 1), Data::String("main_value".to_string()));
+/// range.set_value((1, 1), Data::String("CAT001".to_string()));
+/// range.set_value((2, 0), Data::String("No value".to_string()));
+///
+/// let sheet = process_range("Data", range, &ReaderOptions::default(), &ProcessorOptions::default())?;
+/// assert_eq!(sheet.rows[0].main_value.as_deref(), Some("CAT001"));
+/// assert_eq!(sheet.metadata.unwrap().invalid_records, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub fn process_range(
+    sheet_name: &str,
+    range: calamine::Range<calamine::Data>,
+    reader_options: &ReaderOptions,
+    options: &ProcessorOptions,
+) -> Result<SheetData> {
+    let rows = SheetRowIter::from_range(sheet_name, range, reader_options)
+        .context(format!("Failed to read Excel data from sheet '{}'", sheet_name))?;
+    let rows = SheetRows::try_from(rows)
+        .context(format!("Failed to read Excel data from sheet '{}'", sheet_name))?;
+    process_sheet_rows(sheet_name.to_string(), rows, options, None)
+}
+
+/// Turns the rows read from one sheet into its records and metadata.
+///
+/// Notes from the reader, such as the detected header row, come first in
+/// the sheet's diagnostics and count toward `--max-warnings`.
+fn process_sheet_rows(
+    sheet_name: String,
+    sheet: SheetRows,
+    options: &ProcessorOptions,
+    progress: Option<&Progress>,
+) -> Result<SheetData> {
+    let mut processor = DataProcessor::with_options(options.clone());
+    processor.set_cell_types(sheet.types);
+    processor.set_row_numbers(sheet.row_numbers);
+    processor.set_untrimmed_width(sheet.untrimmed_width);
+    processor.prepend_diagnostics(sheet.diagnostics);
+    if let Some(progress) = progress.filter(|progress| progress.is_enabled()) {
+        let progress = progress.clone();
+        let sheet = sheet_name.clone();
+        processor.set_progress_callback(move |rows_done, total| {
+            progress.update(&ProgressUpdate { rows_done, total, sheet: sheet.clone() });
+        });
+    }
+    let (records, generic_rows, mut metadata) = if options.generic {
+        let (rows, metadata) = processor.process_generic_rows(&sheet.headers, sheet.rows)
+            .context(format!("Failed to process rows from sheet '{}'", sheet_name))?;
+        (Vec::new(), Some(rows), metadata)
+    } else {
+        let (records, metadata) = processor.process_rows_with_headers(&sheet.headers, sheet.rows)
+            .context(format!("Failed to process rows from sheet '{}'", sheet_name))?;
+        (records, None, metadata)
+    };
+    name_sheet_in_warnings(&mut metadata, &sheet_name);
+    if sheet.empty {
+        warn_empty_sheet(&mut metadata, &sheet_name);
+    }
+
+    Ok(SheetData {
+        sheet: sheet_name,
+        rows: records,
+        generic_rows,
+        metadata: Some(metadata),
+        file: None,
+    })
+}
+
+/// Streams records from multiple sheets as newline-delimited JSON.
+///
+/// Each row is read, processed and written to `out` before the next one is
+/// read, so neither the converted rows nor the records are held in memory.
+/// Each line is one record, shaped as in the buffered JSON output; records of
+/// several sheets follow each other in sheet order. With `stream.tag_sheets`
+/// each record also names its `sheet`, and `stream.file` its input file, as
+/// in the flattened output; both replace a column of the same name.
+///
+/// # Arguments
+///
+/// * `source` - Excel file to process, on disk or read from stdin
+/// * `sheet_names` - List of worksheet names to process
+/// * `metadata_sheets` - Sheets whose statistics count toward the returned
+///   metadata, matched ignoring case and surrounding spaces; an empty slice
+///   means every processed sheet counts
+/// * `reader_options` - Cell conversion settings applied to every sheet
+/// * `options` - Cleaning and validation settings applied to every sheet
+/// * `stream` - Row numbers and the sheet and file tags added to each record,
+///   and where progress goes
+/// * `out` - Destination of the NDJSON lines
+///
+/// # Returns
+///
+/// * `Ok(metadata)` - Aggregate statistics of the streamed sheets
+/// * `Err` - If reading, processing or writing fails; lines already written
+///   stay in the output. A metadata sheet not among `sheet_names` fails
+///   before any line is written
+pub fn stream_excel_file_multiple_sheets(
+    source: &WorkbookSource,
+    sheet_names: Vec<String>,
+    metadata_sheets: &[String],
+    reader_options: &ReaderOptions,
+    options: &ProcessorOptions,
+    stream: &StreamOptions,
+    out: &mut dyn std::io::Write,
+) -> Result<ProcessingMetadata> {
+    let metadata_sheets = resolve_metadata_sheets(source, &sheet_names, metadata_sheets)?;
+    let mut total_metadata = ProcessingMetadata::default();
+    let mut all_warnings = Vec::new();
+    let sheet_count = sheet_names.len();
+    let progress = stream.progress.filter(|progress| progress.is_enabled());
+    
+    for (sheet_idx, sheet_name) in sheet_names.into_iter().enumerate() {
+        let mut reader = ExcelReader::from_source(source.clone(), sheet_name.clone())
+            .context("Failed to create Excel reader")?;
+        reader.set_options(reader_options.clone());
+        let sheet_name = reader.sheet_name().to_string();
+        
+        info!("Streaming sheet: {}", sheet_name);
+        if let Some(progress) = progress {
+            progress.sheet_started(sheet_idx + 1, sheet_count, &sheet_name);
+        }
+        
+        let rows = reader.rows_iter()
+            .context(format!("Failed to read Excel data from sheet '{}'", sheet_name))?;
+        // Rows are counted as they are read, so the total is the rows left
+        // in the sheet, blank ones included
+        let total = rows.size_hint().1.unwrap_or_default();
+        let mut reader_diagnostics = rows.diagnostics().to_vec();
+        let empty = rows.is_sheet_empty();
+        let mut processor = DataProcessor::with_options(options.clone());
+        processor.set_untrimmed_width(rows.untrimmed_width());
+        processor.begin_rows(rows.headers())
+            .context(format!("Failed to process rows from sheet '{}'", sheet_name))?;
+        
+        let mut rows_done: usize = 0;
+        for row in rows {
+            if let Some(progress) = progress.filter(|_| rows_done > 0 && rows_done.is_multiple_of(PROGRESS_INTERVAL)) {
+                progress.update(&ProgressUpdate { rows_done, total, sheet: sheet_name.clone() });
+            }
+            rows_done += 1;
+            if let (true, Some(error)) = (reader_options.fail_on_cell_error, row.cell_errors.first()) {
+                return Err(anyhow::Error::new(error.clone()))
+                    .context(format!("Failed to read Excel data from sheet '{}'", sheet_name));
+            }
+            reader_diagnostics.extend(row.cell_diagnostics());
+            let record = if options.generic {
+                processor.process_generic_row(row.cells, row.row_number, &row.types)
+                    .map(|record| record.to_value())
+            } else {
+                processor.process_row(row.cells, row.row_number, &row.types)
+                    .map(|record| if stream.with_row_numbers { record.to_numbered_array() } else { record.to_typed_array() })
+            };
+            if let Some(mut record) = record {
+                if let (true, Value::Object(map)) = (stream.tag_sheets, &mut record) {
+                    map.insert("sheet".to_string(), json!(sheet_name));
+                }
+                if let (Some(file), Value::Object(map)) = (&stream.file, &mut record) {
+                    map.insert("file".to_string(), json!(file));
+                }
+                OutputFormatter::write_ndjson_line(&mut *out, &record)?;
+            }
+            if processor.has_failed() {
+                break;
+            }
+        }
+        
+        processor.prepend_diagnostics(reader_diagnostics);
+        let mut metadata = processor.finish_rows()
+            .context(format!("Failed to process rows from sheet '{}'", sheet_name))?;
+        if let Some(progress) = progress {
+            progress.update(&ProgressUpdate { rows_done, total: rows_done, sheet: sheet_name.clone() });
+        }
+        name_sheet_in_warnings(&mut metadata, &sheet_name);
+        if empty {
+            warn_empty_sheet(&mut metadata, &sheet_name);
+        }
+        if metadata_sheets.is_empty() || metadata_sheets.contains(&sheet_name) {
+            add_sheet_metadata(&mut total_metadata, &mut all_warnings, &metadata);
+        }
+    }
+    
+    if !all_warnings.is_empty() {
+        total_metadata.warnings = Some(all_warnings);
+    }
+    
+    Ok(total_metadata)
+}
+
+/// Resolves the names of `metadata_sheets` to the sheets processed, matching
+/// them as [`resolve_sheet_name`] matches requested sheets: ignoring case and
+/// surrounding spaces when there is no exact match.
+///
+/// # Errors
+///
+/// Returns [`ReaderError::SheetNotFound`] for a name matching none of the
+/// processed sheets, and an error if the workbook cannot be opened.
+fn resolve_metadata_sheets(source: &WorkbookSource, sheet_names: &[String], metadata_sheets: &[String]) -> Result<Vec<String>> {
+    if metadata_sheets.is_empty() {
+        return Ok(Vec::new());
+    }
+    let reader = ExcelReader::from_source(source.clone(), String::new())
+        .context("Failed to open Excel file")?;
+    let available = reader.get_sheet_names();
+    // Processed sheets under the names they are output with
+    let processed: Vec<String> = sheet_names.iter()
+        .map(|name| resolve_sheet_name(name, &available).unwrap_or(name).to_string())
+        .collect();
+    metadata_sheets.iter()
+        .map(|name| match resolve_sheet_name(name, &processed) {
+            Some(sheet) => Ok(sheet.to_string()),
+            None => {
+                let suggestion = closest_sheet_name(name, &processed).map(str::to_string);
+                Err(anyhow::Error::new(ReaderError::SheetNotFound { requested: name.clone(), available: processed.clone(), suggestion })
+                    .context(format!("Metadata sheet '{}' is not one of the processed sheets", name)))
+            },
+        })
+        .collect()
+}
+
+/// Estimates the record set of the given sheets before processing them.
+///
+/// Used to check the `--max-memory-mb` budget before anything is read; see
+/// [`ExcelReader::estimate_record_bytes`].
+pub fn estimate_input_bytes(
+    source: &WorkbookSource,
+    sheet_names: &[String],
+) -> Result<usize> {
+    let mut total = 0;
+    for sheet_name in sheet_names {
+        let reader = ExcelReader::from_source(source.clone(), sheet_name.clone())
+            .context("Failed to open Excel file")?;
+        total += reader.estimate_record_bytes()?;
+    }
+    Ok(total)
+}
+
+/// Processes an Excel file and extracts records.
+///
+/// This function handles the core Excel processing workflow:
+/// reading the file, extracting data with formula evaluation,
+/// and transforming rows into structured records.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the Excel file to process
+/// * `sheet_name` - Optional name of the worksheet to read (uses first sheet if None)
+///
+/// # Returns
+///
+/// * `Ok((records, metadata))` - Successfully processed records and statistics
+/// * `Err` - If file reading or processing fails
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::pipeline::process_excel_file;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let (records, metadata) = process_excel_file(
+///     "resources/Cascade Sample.xlsx",
+///     Some("Cascade Fields")
+/// )?;
+///
+/// println!("Processed {} records", records.len());
+/// println!("Processing time: {}ms", metadata.processing_time_ms);
+///
+/// if let Some(warnings) = &metadata.warnings {
+///     for warning in warnings {
+///         println!("Warning: {}", warning);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn process_excel_file(
+    file_path: &str,
+    sheet_name: Option<&str>,
+) -> Result<(Vec<CascadeField>, ProcessingMetadata)> {
+    // Get sheet name - use provided name or first sheet
+    let sheet = if let Some(name) = sheet_name {
+        name.to_string()
+    } else {
+        // Get the first sheet name
+        let reader = ExcelReader::new(file_path, String::new())
+            .context("Failed to open Excel file")?;
+        let sheets = reader.get_sheet_names();
+        sheets.first()
+            .ok_or_else(|| anyhow::anyhow!("No sheets found in Excel file"))?
+            .clone()
+    };
+    
+    // Create Excel reader with the determined sheet
+    let mut reader = ExcelReader::new(file_path, sheet.clone())
+        .context("Failed to create Excel reader")?;
+    
+    info!("Processing sheet: {}", sheet);
+    
+    // Read and process the Excel data
+    let raw_rows = reader.read_with_formulas()
+        .context("Failed to read Excel data")?;
+    
+    // Process the rows into records
+    let mut processor = DataProcessor::new();
+    let (records, metadata) = processor.process_rows(raw_rows)
+        .context("Failed to process rows")?;
+    
+    Ok((records, metadata))
+}
+
+/// Retrieves the list of available sheet names from an Excel file.
+///
+/// This helper function is used primarily for error reporting when
+/// a requested sheet is not found, providing users with the list of
+/// available sheets they can choose from.
+///
+/// # Arguments
+///
+/// * `source` - The Excel file, as a path or an in-memory workbook
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` - List of sheet names in the workbook
+/// * `Err` - If the file cannot be opened or read
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::pipeline::get_available_sheets;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let sheets = get_available_sheets("resources/Cascade Sample.xlsx")?;
+///
+/// // Check if desired sheet exists
+/// if !sheets.contains(&"Cascade Fields".to_string()) {
+///     eprintln!("Sheet 'Cascade Fields' not found.");
+///     eprintln!("Available sheets: {:?}", sheets);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_available_sheets(source: impl Into<WorkbookSource>) -> Result<Vec<String>> {
+    let reader = ExcelReader::from_source(source.into(), String::new())
+        .context("Failed to open Excel file")?;
+    Ok(reader.get_sheet_names())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_file_selects_sheets_and_reports_row_window() {
+        let bytes = std::fs::read("resources/Cascade Sample.xlsx").unwrap();
+        let options = ProcessOptions {
+            all_sheets: true,
+            reader: ReaderOptions { limit: Some(1), ..ReaderOptions::default() },
+            ..ProcessOptions::default()
+        };
+
+        let result = process_file(WorkbookSource::Bytes(bytes.into()), &options).unwrap();
+
+        let sheets = result.sheet_data.unwrap();
+        assert_eq!(sheets.iter().map(|sheet| sheet.sheet.as_str()).collect::<Vec<_>>(), vec!["Cascade Fields", "Line Type"]);
+        assert_eq!(sheets[0].rows.len(), 1);
+        assert_eq!(result.metadata.row_window.unwrap().limit, Some(1));
+
+        let missing = ProcessOptions { sheets: vec!["Nope".to_string()], ..ProcessOptions::default() };
+        let error = process_file("resources/Cascade Sample.xlsx", &missing).unwrap_err();
+        assert!(format!("{:#}", error).contains("Sheet 'Nope' not found"));
+    }
+
+    #[test]
+    fn test_empty_sheet_is_told_from_sheet_without_data() {
+        use rust_xlsxwriter::Workbook;
+
+        let mut workbook = Workbook::new();
+        workbook.add_worksheet().set_name("Empty").unwrap();
+        let headers_only = workbook.add_worksheet().set_name("Headers").unwrap();
+        for (col, name) in CascadeField::FIELD_NAMES.iter().enumerate() {
+            headers_only.write_string(0, col as u16, *name).unwrap();
+        }
+        let source = WorkbookSource::Bytes(workbook.save_to_buffer().unwrap().into());
+        let options = ProcessOptions { all_sheets: true, ..ProcessOptions::default() };
+
+        let result = process_file(source.clone(), &options).unwrap();
+        assert!(result.success);
+        assert_eq!(result.metadata.warnings, Some(vec!["Sheet 'Empty' is empty".to_string()]));
+        let sheets = result.sheet_data.unwrap();
+        assert_eq!(sheets[1].metadata.as_ref().unwrap().warnings, None);
+
+        let mut out = Vec::new();
+        let sheets = vec!["Empty".to_string(), "Headers".to_string()];
+        let streamed = stream_excel_file_multiple_sheets(&source, sheets, &[], &ReaderOptions::default(), &ProcessorOptions::default(), &StreamOptions::default(), &mut out).unwrap();
+        assert_eq!(streamed.warnings, Some(vec!["Sheet 'Empty' is empty".to_string()]));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_sheet_globs_select_matching_sheets_in_workbook_order() {
+        use rust_xlsxwriter::Workbook;
+
+        let mut workbook = Workbook::new();
+        for name in ["Data_2021", "Summary", "Data_2023", "Data_2022", "data_old"] {
+            workbook.add_worksheet().set_name(name).unwrap();
+        }
+        let source = WorkbookSource::Bytes(workbook.save_to_buffer().unwrap().into());
+        let select = |sheets: &[&str], globs: &[&str]| {
+            let sheets: Vec<String> = sheets.iter().map(|s| s.to_string()).collect();
+            let globs: Vec<String> = globs.iter().map(|s| s.to_string()).collect();
+            select_sheets(&source, &sheets, &globs, false, None, false)
+        };
+
+        assert_eq!(select(&[], &["Data_*"]).unwrap(), vec!["Data_2021", "Data_2023", "Data_2022"]);
+        // Explicit sheets come first and are not repeated
+        assert_eq!(select(&["Data_2022"], &["Data_202[!1]"]).unwrap(), vec!["Data_2022", "Data_2023"]);
+
+        let error = select(&[], &["Sales_*"]).unwrap_err();
+        assert_eq!(error.to_string(), "No sheet matches 'Sales_*'. Available sheets: [\"Data_2021\", \"Summary\", \"Data_2023\", \"Data_2022\", \"data_old\"]");
+        assert!(matches!(error.downcast_ref::<ReaderError>(), Some(ReaderError::NoSheetMatches { .. })));
+    }
+
+    #[test]
+    fn test_process_range_matches_process_file() {
+        use calamine::{open_workbook, Data, Range, Reader, Xlsx};
+
+        let mut workbook: Xlsx<_> = open_workbook("resources/Cascade Sample.xlsx").unwrap();
+        let range = workbook.worksheet_range("Cascade Fields").unwrap();
+        let sheet = process_range("Cascade Fields", range, &ReaderOptions::default(), &ProcessorOptions::default()).unwrap();
+
+        let result = process_file("resources/Cascade Sample.xlsx", &ProcessOptions::default()).unwrap();
+        let expected = &result.sheet_data.unwrap()[0];
+        assert_eq!(sheet.sheet, expected.sheet);
+        assert_eq!(
+            sheet.rows.iter().map(CascadeField::to_numbered_array).collect::<Vec<_>>(),
+            expected.rows.iter().map(CascadeField::to_numbered_array).collect::<Vec<_>>()
+        );
+        assert_eq!(sheet.metadata.unwrap().invalid_records, expected.metadata.as_ref().unwrap().invalid_records);
+
+        // Ranges built in memory keep their position on the sheet
+        let mut range = Range::new((4, 0), (5, 11));
+        range.set_value((4, 1), Data::String("main_value".to_string()));
+        range.set_value((5, 1), Data::Int(42));
+        let sheet = process_range("Built", range, &ReaderOptions::default(), &ProcessorOptions::default()).unwrap();
+        assert_eq!(sheet.rows[0].main_value.as_deref(), Some("42"));
+        assert_eq!(sheet.rows[0].row_number, Some(6));
+    }
+
+    #[test]
+    fn test_error_cells_are_reported_with_their_coordinate() {
+        use crate::excel_reader::CellError;
+        use crate::models::RowDiagnostic;
+        use calamine::{CellErrorType, Data, Range};
+
+        let mut range = Range::new((0, 0), (2, 11));
+        for (col, field) in CascadeField::FIELD_NAMES.iter().enumerate() {
+            range.set_value((0, col as u32), Data::String(field.to_string()));
+        }
+        range.set_value((1, 1), Data::String("CAT001".to_string()));
+        range.set_value((1, 2), Data::Error(CellErrorType::Div0));
+        range.set_value((2, 1), Data::Error(CellErrorType::Ref));
+        range.set_value((2, 2), Data::String("Orphan".to_string()));
+
+        let sheet = process_range("Data", range.clone(), &ReaderOptions::default(), &ProcessorOptions::default()).unwrap();
+        assert_eq!(sheet.rows.len(), 1);
+        let diagnostics = sheet.metadata.unwrap().diagnostics;
+        assert_eq!(diagnostics[0], RowDiagnostic::warning(2, None, "Cell C2 holds the error #DIV/0!"));
+        assert_eq!(diagnostics[1], RowDiagnostic::warning(3, None, "Cell B3 holds the error #REF!"));
+
+        let reader_options = ReaderOptions { fail_on_cell_error: true, ..ReaderOptions::default() };
+        let error = process_range("Data", range, &reader_options, &ProcessorOptions::default()).unwrap_err();
+        assert_eq!(format!("{:#}", error), "Failed to read Excel data from sheet 'Data': Cell C2 holds the error #DIV/0!");
+        let cell_error = error.chain().find_map(|cause| cause.downcast_ref::<CellError>()).unwrap();
+        assert_eq!((cell_error.cell.to_string(), &cell_error.error), ("C2".to_string(), &CellErrorType::Div0));
+    }
+
+    #[test]
+    fn test_max_warnings_caps_reader_diagnostics() {
+        use rust_xlsxwriter::{Formula, Workbook};
+        use std::io::{Read, Write};
+
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Data").unwrap();
+        for (col, name) in CascadeField::FIELD_NAMES.iter().enumerate() {
+            sheet.write_string(0, col as u16, *name).unwrap();
+        }
+        for row in 1..=10u32 {
+            sheet.write_string(row, 1, format!("M{}", row)).unwrap();
+            sheet.write_formula(row, 2, Formula::new("=NA()").set_result("#N/A")).unwrap();
+        }
+
+        // The writer stores formula results as text; typing them as errors
+        // makes every C cell a #N/A
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(workbook.save_to_buffer().unwrap())).unwrap();
+        let mut bytes = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for idx in 0..archive.len() {
+            let mut entry = archive.by_index(idx).unwrap();
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+            if entry.name() == "xl/worksheets/sheet1.xml" {
+                contents = contents.replace(r#"t="str""#, r#"t="e""#);
+            }
+            bytes.start_file(entry.name(), zip::write::SimpleFileOptions::default()).unwrap();
+            bytes.write_all(contents.as_bytes()).unwrap();
+        }
+        let source = WorkbookSource::Bytes(bytes.finish().unwrap().into_inner().into());
+
+        // The footer note and nine #N/A cells, against a limit of three
+        let reader_options = ReaderOptions { footer_rows: 1, ..ReaderOptions::default() };
+        let options = ProcessorOptions { max_warnings: Some(3), ..ProcessorOptions::default() };
+        let (sheets, buffered) = process_excel_file_multiple_sheets(&source, vec!["Data".to_string()], &[], &reader_options, &options, None).unwrap();
+        let streamed = stream_excel_file_multiple_sheets(&source, vec!["Data".to_string()], &[], &reader_options, &options, &StreamOptions::default(), &mut std::io::sink()).unwrap();
+
+        assert_eq!(sheets[0].rows.len(), 9);
+        for metadata in [buffered, streamed] {
+            assert_eq!(metadata.diagnostics.len(), 3);
+            assert_eq!(metadata.diagnostics[0].to_string(), "Row 11: Ignored row 11 as a footer row");
+            assert_eq!(metadata.diagnostics[2].to_string(), "Row 3: Cell C3 holds the error #N/A");
+            assert!(metadata.warnings.unwrap().iter().any(|warning| warning.ends_with("... and 7 more warnings suppressed (limit 3)")));
+        }
+    }
+
+    #[test]
+    fn test_sheet_names_match_ignoring_case_and_surrounding_spaces() {
+        use rust_xlsxwriter::Workbook;
+
+        let mut workbook = Workbook::new();
+        for name in ["Data ", "Summary"] {
+            let sheet = workbook.add_worksheet().set_name(name).unwrap();
+            for (col, field) in CascadeField::FIELD_NAMES.iter().enumerate() {
+                sheet.write_string(0, col as u16, *field).unwrap();
+            }
+            sheet.write_string(1, 1, name.trim()).unwrap();
+        }
+        let bytes = workbook.save_to_buffer().unwrap();
+
+        let options = ProcessOptions {
+            sheets: vec!["data".to_string(), "SUMMARY".to_string()],
+            ..ProcessOptions::default()
+        };
+        let result = process_file(WorkbookSource::Bytes(bytes.into()), &options).unwrap();
+
+        // Sheets are reported under their names in the workbook
+        let sheets = result.sheet_data.unwrap();
+        assert_eq!(sheets.iter().map(|sheet| sheet.sheet.as_str()).collect::<Vec<_>>(), vec!["Data ", "Summary"]);
+        assert_eq!(sheets[0].rows[0].main_value.as_deref(), Some("Data"));
+        assert_eq!(sheets[1].rows[0].main_value.as_deref(), Some("Summary"));
+    }
+}
@@ -32,10 +32,509 @@
 //! # }
 //! ```
 
-use crate::models::{CascadeField, ProcessingMetadata};
-use anyhow::Result;
+use crate::headers::{DuplicateHeaderPolicy, HeaderLayout};
+use crate::models::{CascadeField, CellType, ColumnMap, GenericRecord, ProcessingMetadata, RowDiagnostic};
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
 use tracing::{debug, info, warn};
 
+/// Number of rows between progress callback invocations.
+pub const PROGRESS_INTERVAL: usize = 1000;
+
+/// Per-field value normalization tables: field name → (variant → canonical).
+pub type ValueMap = HashMap<String, HashMap<String, String>>;
+
+/// Callback receiving `(rows_done, total_rows)` progress updates.
+pub type ProgressCallback = Box<dyn FnMut(usize, usize)>;
+
+/// Settings that adjust how [`DataProcessor`] cleans and validates rows.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::{DataProcessor, ProcessorOptions};
+///
+/// let options = ProcessorOptions {
+///     defaults: vec![("main_description".to_string(), "N/A".to_string())],
+///     ..ProcessorOptions::default()
+/// };
+/// let processor = DataProcessor::with_options(options);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProcessorOptions {
+    /// Values assigned to fields that are `None` after cleaning, as
+    /// `(field_name, value)` pairs. Defaults are applied before validation,
+    /// so a default `main_value` makes otherwise-invalid rows valid.
+    /// Header-keyed records only take defaults for keys of their header row;
+    /// the others are listed in a warning.
+    pub defaults: Vec<(String, String)>,
+    /// Hybrid schema mode: map this many leading columns positionally onto the
+    /// cascade fields and capture the remaining columns by header name.
+    /// `None` uses the fixed 12-column cascade layout.
+    pub positional_columns: Option<usize>,
+    /// Number of label/value/description triples per row. `None` uses the
+    /// four cascade levels (12 columns); see [`CascadeField::from_row_with_arity`].
+    pub levels: Option<usize>,
+    /// Read each cascade field from an explicit column instead of the
+    /// canonical positions; see [`CascadeField::from_row_mapped`]. Takes
+    /// precedence over `positional_columns` and `levels`.
+    pub column_map: Option<ColumnMap>,
+    /// Ensure every output value is pure ASCII, either by failing or by
+    /// transliterating. `None` leaves values untouched.
+    pub ascii_only: Option<AsciiMode>,
+    /// How header-keyed columns resolve repeated header names.
+    pub duplicate_headers: DuplicateHeaderPolicy,
+    /// Canonical spellings applied during cleaning, keyed by field name.
+    /// Header-keyed columns in hybrid mode are matched by their output key.
+    pub value_map: ValueMap,
+    /// Rows whose field matches the pattern are dropped and counted as
+    /// skipped rather than invalid (e.g. `Total`/`Subtotal` rows).
+    pub skip_rows_matching: Vec<(String, Regex)>,
+    /// Fields every valid record must have a value in, e.g. `minor_value`.
+    /// Empty uses [`CascadeField::is_valid`], which requires `main_value`.
+    /// Checked after defaults are applied.
+    pub required_fields: Vec<String>,
+    /// Only rows matching every filter are kept; the others are counted as
+    /// `filtered_records` rather than invalid.
+    pub filters: Vec<RowFilter>,
+    /// Transforms applied to cascade fields right after cleaning, in order,
+    /// as `(field_name, transform)` pairs; see [`Transform`]. Defaults are
+    /// not transformed.
+    pub transforms: Vec<(String, Transform)>,
+    /// Fields holding dates; used by [`text_date_format`](Self::text_date_format).
+    pub date_columns: Vec<String>,
+    /// chrono format (e.g. `%m/%d/%Y`) for dates stored as text in
+    /// `date_columns`. Matching values are rewritten as ISO-8601; values that
+    /// do not parse are kept and reported as warning diagnostics.
+    pub text_date_format: Option<String>,
+    /// Generic mode: key every column by its header text instead of mapping
+    /// onto the cascade schema. See [`DataProcessor::process_generic_rows`].
+    pub generic: bool,
+    /// Keep numeric and boolean cells as JSON numbers and booleans, using the
+    /// types supplied through [`DataProcessor::set_cell_types`]. Columns merged
+    /// under a duplicate header keep text values.
+    pub preserve_types: bool,
+    /// Drop records repeating an earlier record's composite key; see
+    /// [`DataProcessor::dedupe_records`].
+    pub dedupe: bool,
+    /// Keep only records with all four of `main_value`, `sub_value`,
+    /// `major_value` and `minor_value`; see
+    /// [`DataProcessor::filter_complete_records`]. Dropped records count as
+    /// filtered, not invalid.
+    pub complete_only: bool,
+    /// Right-pad rows shorter than the layout's width with empty cells, so a
+    /// trailing blank column that Excel left out does not make a row
+    /// malformed. Rows that end before the last required field (see
+    /// `required_fields`) are still malformed.
+    pub pad_rows: bool,
+    /// Field to sort the valid records by; `None` keeps the sheet order.
+    /// See [`sort_records`].
+    pub sort_by: Option<String>,
+    /// Sort in descending order with [`sort_by`](Self::sort_by).
+    pub sort_desc: bool,
+    /// Treat a repeated composite key (`main_value`, `sub_value`,
+    /// `major_value`, `minor_value`) as an error: the first repeat makes
+    /// [`DataProcessor::finish_rows`] return a [`DuplicateKeyError`].
+    pub strict_unique: bool,
+    /// Stop at the first row that is invalid or malformed (including blank
+    /// rows kept by the reader): no further rows are processed and
+    /// [`DataProcessor::finish_rows`] returns an [`InvalidRowError`] naming
+    /// it. See [`DataProcessor::has_failed`].
+    pub fail_fast: bool,
+    /// Report the header row passed to [`DataProcessor::begin_rows`] (or the
+    /// `process_*` methods) as the metadata's `source_headers`, so the
+    /// columns can be checked against the fields they are read into.
+    pub echo_headers: bool,
+    /// Skip data rows identical to the header row, such as the header
+    /// repeated between sheets pasted one below another. Only rows whose
+    /// every cell equals the header cell above it are skipped; they are
+    /// counted as skipped and listed in a warning.
+    pub dedupe_headers: bool,
+    /// Keep at most this many row diagnostics per sheet; further ones are
+    /// only counted, and a warning reports how many were suppressed, so a
+    /// sheet of a million bad rows does not hold a million messages. `None`
+    /// keeps every diagnostic.
+    pub max_warnings: Option<usize>,
+}
+
+/// How [`DataProcessor`] enforces ASCII-only output.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::AsciiMode;
+/// use std::str::FromStr;
+///
+/// assert_eq!(AsciiMode::from_str("fail"), Ok(AsciiMode::Fail));
+/// assert_eq!(AsciiMode::from_str("transliterate"), Ok(AsciiMode::Transliterate));
+/// assert!(AsciiMode::from_str("ignore").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsciiMode {
+    /// Abort processing, listing every row and field containing non-ASCII text
+    Fail,
+    /// Replace non-ASCII characters with their closest ASCII equivalent
+    Transliterate,
+}
+
+impl std::str::FromStr for AsciiMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fail" => Ok(AsciiMode::Fail),
+            "transliterate" => Ok(AsciiMode::Transliterate),
+            _ => Err(format!("Unknown ASCII mode: {}. Use 'fail' or 'transliterate'.", s)),
+        }
+    }
+}
+
+/// Parses a `field=value` assignment for one of the known CascadeField fields.
+///
+/// # Arguments
+///
+/// * `spec` - Assignment string such as `main_description=N/A`
+///
+/// # Returns
+///
+/// * `Ok((field, value))` - The validated field name and its value
+/// * `Err` - If the `=` is missing or the field name is unknown
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::parse_field_assignment;
+///
+/// let (field, value) = parse_field_assignment("main_description=N/A").unwrap();
+/// assert_eq!(field, "main_description");
+/// assert_eq!(value, "N/A");
+///
+/// assert!(parse_field_assignment("bogus=1").is_err());
+/// assert!(parse_field_assignment("main_value").is_err());
+/// ```
+pub fn parse_field_assignment(spec: &str) -> Result<(String, String)> {
+    let (field, value) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected FIELD=VALUE, got '{}'", spec))?;
+    let field = field.trim();
+
+    if !CascadeField::FIELD_NAMES.contains(&field) {
+        anyhow::bail!(
+            "Unknown field '{}'. Valid fields: {}",
+            field,
+            CascadeField::FIELD_NAMES.join(", ")
+        );
+    }
+
+    Ok((field.to_string(), value.to_string()))
+}
+
+/// Parses a `field=regex` skip rule for `--skip-rows-matching`.
+///
+/// # Arguments
+///
+/// * `spec` - Rule such as `main_label=^(Sub)?[Tt]otal`
+///
+/// # Returns
+///
+/// * `Ok((field, regex))` - The validated field name and compiled pattern
+/// * `Err` - If the field is unknown or the pattern does not compile
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::parse_skip_rule;
+///
+/// let (field, pattern) = parse_skip_rule("main_label=^Total").unwrap();
+/// assert_eq!(field, "main_label");
+/// assert!(pattern.is_match("Total 2024"));
+///
+/// assert!(parse_skip_rule("main_label=(").is_err());
+/// ```
+pub fn parse_skip_rule(spec: &str) -> Result<(String, Regex)> {
+    let (field, pattern) = parse_field_assignment(spec)?;
+    let regex = Regex::new(&pattern)
+        .with_context(|| format!("Invalid pattern for {}: '{}'", field, pattern))?;
+    Ok((field, regex))
+}
+
+/// A `--filter` predicate comparing one cascade field with a value.
+///
+/// Fields are compared after cleaning and defaults, as whole trimmed text;
+/// an empty field compares equal to an empty value.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::models::CascadeField;
+/// use excel_to_json::processor::parse_row_filter;
+///
+/// let mut row = vec![None; 12];
+/// row[1] = Some("CAT001".to_string());
+/// let record = CascadeField::from_row(row).unwrap();
+///
+/// assert!(parse_row_filter("main_value=CAT001").unwrap().matches(&record));
+/// assert!(parse_row_filter("sub_value!=SUB001").unwrap().matches(&record));
+/// // `field!=` keeps records where the field is non-empty
+/// assert!(!parse_row_filter("sub_value!=").unwrap().matches(&record));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowFilter {
+    pub field: String,
+    pub value: String,
+    /// `field!=value`: the field must differ from the value
+    pub negated: bool,
+}
+
+impl RowFilter {
+    /// Returns whether a record satisfies the predicate.
+    pub fn matches(&self, record: &CascadeField) -> bool {
+        let actual = CascadeField::FIELD_NAMES
+            .iter()
+            .position(|name| *name == self.field)
+            .and_then(|idx| record.field_values()[idx])
+            .unwrap_or("");
+        (actual == self.value) != self.negated
+    }
+}
+
+/// Parses a `field=value` or `field!=value` predicate for `--filter`.
+///
+/// # Arguments
+///
+/// * `spec` - Predicate such as `main_value=CAT001` or `sub_value!=`
+///
+/// # Returns
+///
+/// * `Ok(filter)` - The predicate, with its value trimmed
+/// * `Err` - If the `=` is missing or the field name is unknown
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::parse_row_filter;
+///
+/// let filter = parse_row_filter("main_value != CAT001").unwrap();
+/// assert_eq!((filter.field.as_str(), filter.value.as_str(), filter.negated), ("main_value", "CAT001", true));
+///
+/// assert!(parse_row_filter("colour=red").is_err());
+/// assert!(parse_row_filter("main_value").is_err());
+/// ```
+pub fn parse_row_filter(spec: &str) -> Result<RowFilter> {
+    let (field, value) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected FIELD=VALUE or FIELD!=VALUE, got '{}'", spec))?;
+    let (field, negated) = match field.trim_end().strip_suffix('!') {
+        Some(field) => (field, true),
+        None => (field, false),
+    };
+    let (field, value) = parse_field_assignment(&format!("{}={}", field, value))?;
+
+    Ok(RowFilter { field, value: value.trim().to_string(), negated })
+}
+
+/// A change of case or spacing applied to a field's value by `--transform`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    /// `CAT001`
+    Upper,
+    /// `cat001`
+    Lower,
+    /// Each word capitalized, the rest lowercase: `Outdoor Gear`
+    Title,
+    /// Leading and trailing whitespace removed
+    Trim,
+}
+
+impl Transform {
+    /// Returns `value` transformed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::processor::Transform;
+    ///
+    /// assert_eq!(Transform::Upper.apply("cat-001"), "CAT-001");
+    /// assert_eq!(Transform::Title.apply("oUTDOOR  gear"), "Outdoor  Gear");
+    /// ```
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Transform::Upper => value.to_uppercase(),
+            Transform::Lower => value.to_lowercase(),
+            Transform::Title => {
+                let mut title = String::with_capacity(value.len());
+                let mut word_start = true;
+                for c in value.chars() {
+                    if word_start {
+                        title.extend(c.to_uppercase());
+                    } else {
+                        title.extend(c.to_lowercase());
+                    }
+                    word_start = c.is_whitespace();
+                }
+                title
+            },
+            Transform::Trim => value.trim().to_string(),
+        }
+    }
+}
+
+impl std::str::FromStr for Transform {
+    type Err = anyhow::Error;
+
+    /// Parses "upper", "lower", "title" or "trim"; case-insensitive.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "upper" => Ok(Transform::Upper),
+            "lower" => Ok(Transform::Lower),
+            "title" => Ok(Transform::Title),
+            "trim" => Ok(Transform::Trim),
+            _ => anyhow::bail!("Unknown transform '{}'. Supported transforms: upper, lower, title, trim", s.trim()),
+        }
+    }
+}
+
+/// Parses a `field=transform` pair for `--transform`.
+///
+/// # Arguments
+///
+/// * `spec` - Pair such as `main_value=upper`
+///
+/// # Returns
+///
+/// * `Ok((field, transform))` - The validated field name and transform
+/// * `Err` - If the `=` is missing or the field or transform is unknown
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::{parse_field_transform, Transform};
+///
+/// assert_eq!(parse_field_transform("main_value=upper").unwrap(), ("main_value".to_string(), Transform::Upper));
+/// assert!(parse_field_transform("main_value=shout").is_err());
+/// assert!(parse_field_transform("colour=upper").is_err());
+/// ```
+pub fn parse_field_transform(spec: &str) -> Result<(String, Transform)> {
+    let (field, transform) = parse_field_assignment(spec)?;
+    Ok((field, transform.parse()?))
+}
+
+/// Sorts records by one of the cascade fields.
+///
+/// Numeric values compare numerically and sort before text values, which
+/// compare as text. Records without a value for the field sort last in
+/// either direction. The
+/// sort is stable, so records with equal values keep their sheet order.
+///
+/// # Arguments
+///
+/// * `records` - Records to sort in place
+/// * `field` - One of [`CascadeField::FIELD_NAMES`]; an unknown name leaves
+///   the order unchanged
+/// * `descending` - Sort from the largest value to the smallest
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::models::CascadeField;
+/// use excel_to_json::processor::sort_records;
+///
+/// let record = |value: Option<&str>| {
+///     let mut row = vec![None; 12];
+///     row[7] = value.map(str::to_string);
+///     CascadeField::from_row(row).unwrap()
+/// };
+/// let mut records = vec![record(Some("10")), record(None), record(Some("9")), record(Some("B"))];
+///
+/// sort_records(&mut records, "major_value", false);
+/// let values: Vec<_> = records.iter().map(|r| r.major_value.as_deref()).collect();
+/// assert_eq!(values, vec![Some("9"), Some("10"), Some("B"), None]);
+/// ```
+pub fn sort_records(records: &mut [CascadeField], field: &str, descending: bool) {
+    let Some(idx) = CascadeField::FIELD_NAMES.iter().position(|name| *name == field) else {
+        return;
+    };
+
+    records.sort_by(|a, b| {
+        match (a.field_values()[idx], b.field_values()[idx]) {
+            (Some(a), Some(b)) => {
+                let order = match (a.parse::<f64>(), b.parse::<f64>()) {
+                    (Ok(x), Ok(y)) => x.total_cmp(&y),
+                    (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+                    (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+                    (Err(_), Err(_)) => a.cmp(b),
+                };
+                if descending { order.reverse() } else { order }
+            },
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+    });
+}
+
+/// Parses a date stored as text and returns it in ISO-8601 form.
+///
+/// Formats containing a time component produce `YYYY-MM-DDTHH:MM:SS`;
+/// date-only formats produce `YYYY-MM-DD`.
+///
+/// # Arguments
+///
+/// * `value` - The text to parse, e.g. `01/02/2024`
+/// * `format` - A chrono format string, e.g. `%m/%d/%Y`
+///
+/// # Returns
+///
+/// * `Some(String)` - The normalized date
+/// * `None` - If the value does not match the format
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::parse_text_date;
+///
+/// assert_eq!(parse_text_date("01/02/2024", "%m/%d/%Y"), Some("2024-01-02".to_string()));
+/// assert_eq!(parse_text_date("2024-01-02 13:45", "%Y-%m-%d %H:%M"), Some("2024-01-02T13:45:00".to_string()));
+/// assert_eq!(parse_text_date("soon", "%m/%d/%Y"), None);
+/// ```
+pub fn parse_text_date(value: &str, format: &str) -> Option<String> {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    let value = value.trim();
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+        return Some(datetime.format("%Y-%m-%dT%H:%M:%S").to_string());
+    }
+    NaiveDate::parse_from_str(value, format)
+        .ok()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Loads value normalization tables from a JSON file.
+///
+/// The file maps each field name to an object of `variant: canonical` pairs:
+///
+/// ```json
+/// { "main_label": { "USA": "United States", "U.S.A.": "United States" } }
+/// ```
+///
+/// # Arguments
+///
+/// * `path` - Path to the JSON value map
+///
+/// # Returns
+///
+/// * `Ok(ValueMap)` - The parsed tables
+/// * `Err` - If the file cannot be read or is not in the expected shape
+pub fn load_value_map(path: &str) -> Result<ValueMap> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read value map: {}", path))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Invalid value map {}: expected {{\"field\": {{\"variant\": \"canonical\"}}}}", path))
+}
+
 /// Processes raw Excel data into validated CascadeField records.
 ///
 /// The `DataProcessor` handles the transformation of raw Excel rows into
@@ -62,23 +561,187 @@ use tracing::{debug, info, warn};
 /// println!("Valid records: {}", metadata.valid_records);
 /// println!("Invalid records: {}", metadata.invalid_records);
 ///
-/// if let Some(warnings) = &metadata.warnings {
-///     for warning in warnings {
-///         println!("Warning: {}", warning);
-///     }
+/// for diagnostic in &metadata.diagnostics {
+///     println!("{:?}: {}", diagnostic.severity, diagnostic);
 /// }
 /// # Ok(())
 /// # }
 /// ```
 pub struct DataProcessor {
-    warnings: Vec<String>,
+    diagnostics: Vec<RowDiagnostic>,
+    /// Diagnostics not kept because of [`ProcessorOptions::max_warnings`]
+    suppressed_diagnostics: usize,
+    /// The note on `suppressed_diagnostics` last added to the warnings, so
+    /// deduping can update its count
+    suppressed_note: Option<String>,
+    options: ProcessorOptions,
+    progress: Option<ProgressCallback>,
+    cell_types: Vec<Vec<CellType>>,
+    row_numbers: Vec<usize>,
+    layout: HeaderLayout,
+    stats: RowStats,
+    /// Width of the sheet before its blank trailing columns were trimmed
+    untrimmed_width: Option<usize>,
+}
+
+/// The values of `main_value`, `sub_value`, `major_value` and `minor_value`.
+type CompositeKey = [Option<String>; 4];
+
+/// Returns the composite key of a record.
+fn composite_key(record: &CascadeField) -> CompositeKey {
+    [
+        record.main_value.clone(),
+        record.sub_value.clone(),
+        record.major_value.clone(),
+        record.minor_value.clone(),
+    ]
+}
+
+/// A record repeating the composite key of an earlier record, reported by
+/// [`DataProcessor::finish_rows`] with [`ProcessorOptions::strict_unique`].
+///
+/// Keys missing from both records compare equal, as with
+/// [`DataProcessor::dedupe_records`].
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::{DataProcessor, DuplicateKeyError, ProcessorOptions};
+///
+/// let mut processor = DataProcessor::with_options(ProcessorOptions {
+///     strict_unique: true,
+///     ..ProcessorOptions::default()
+/// });
+/// let mut row = vec![None; 12];
+/// row[1] = Some("CAT001".to_string());
+///
+/// let error = processor.process_rows(vec![row.clone(), row]).unwrap_err();
+/// let duplicate = error.downcast_ref::<DuplicateKeyError>().unwrap();
+/// assert_eq!((duplicate.row_number, duplicate.first_row), (3, 2));
+/// assert_eq!(error.to_string(), "Row 3: duplicate composite key (main_value=CAT001), first seen at row 2");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError {
+    /// Spreadsheet row repeating the key
+    pub row_number: usize,
+    /// Spreadsheet row where the key first appeared
+    pub first_row: usize,
+    /// The key fields present, as `(field, value)` pairs
+    pub key: Vec<(String, String)>,
+}
+
+impl std::fmt::Display for DuplicateKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let key: Vec<String> = self.key.iter().map(|(field, value)| format!("{}={}", field, value)).collect();
+        write!(
+            f,
+            "Row {}: duplicate composite key ({}), first seen at row {}",
+            self.row_number,
+            key.join(", "),
+            self.first_row
+        )
+    }
+}
+
+impl std::error::Error for DuplicateKeyError {}
+
+/// The first invalid row, reported by [`DataProcessor::finish_rows`] with
+/// [`ProcessorOptions::fail_fast`].
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::processor::{DataProcessor, InvalidRowError, ProcessorOptions};
+///
+/// let mut processor = DataProcessor::with_options(ProcessorOptions {
+///     fail_fast: true,
+///     ..ProcessorOptions::default()
+/// });
+/// let mut row = vec![None; 12];
+/// row[1] = Some("CAT001".to_string());
+///
+/// let error = processor.process_rows(vec![row.clone(), vec![Some("Short".to_string())], row]).unwrap_err();
+/// assert_eq!(error.downcast_ref::<InvalidRowError>().unwrap().row_number, 3);
+/// assert_eq!(error.to_string(), "Row 3: Insufficient columns (expected 12, found 1)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRowError {
+    /// Spreadsheet row that failed
+    pub row_number: usize,
+    /// Why it failed, as in its diagnostic
+    pub message: String,
+}
+
+impl std::fmt::Display for InvalidRowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}: {}", self.row_number, self.message)
+    }
+}
+
+impl std::error::Error for InvalidRowError {}
+
+/// Running counts for the rows processed since [`DataProcessor::begin_rows`].
+#[derive(Debug)]
+struct RowStats {
+    started: std::time::Instant,
+    total: usize,
+    valid: usize,
+    invalid: usize,
+    malformed: usize,
+    skipped: usize,
+    filtered: usize,
+    /// Number of rows of each width, for [`ProcessingMetadata::detected_columns`]
+    column_counts: HashMap<usize, usize>,
+    non_ascii: Vec<String>,
+    /// Row of the first record with each composite key, for `strict_unique`
+    first_rows: HashMap<CompositeKey, usize>,
+    duplicate_key: Option<DuplicateKeyError>,
+    /// The first invalid row, kept for `fail_fast`
+    invalid_row: Option<InvalidRowError>,
+    /// The header row, kept for `echo_headers`
+    source_headers: Option<Vec<String>>,
+    /// The header row, kept for `dedupe_headers`
+    header_row: Option<Vec<String>>,
+    /// Rows skipped as repeats of the header row
+    repeated_headers: Vec<usize>,
+    /// Fields with a default that are not keys of header-keyed records
+    unmatched_defaults: Vec<String>,
+}
+
+impl Default for RowStats {
+    fn default() -> Self {
+        RowStats {
+            started: std::time::Instant::now(),
+            total: 0,
+            valid: 0,
+            invalid: 0,
+            malformed: 0,
+            skipped: 0,
+            filtered: 0,
+            column_counts: HashMap::new(),
+            non_ascii: Vec::new(),
+            first_rows: HashMap::new(),
+            duplicate_key: None,
+            invalid_row: None,
+            source_headers: None,
+            header_row: None,
+            unmatched_defaults: Vec::new(),
+            repeated_headers: Vec::new(),
+        }
+    }
+}
+
+impl Default for DataProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DataProcessor {
     /// Creates a new DataProcessor instance.
     ///
-    /// Initializes a processor with an empty warnings vector that will
-    /// collect any issues encountered during processing.
+    /// Initializes a processor with an empty diagnostics vector that will
+    /// collect any row issues encountered during processing.
     ///
     /// # Example
     ///
@@ -89,11 +752,199 @@ impl DataProcessor {
     /// // Processor is ready to process Excel rows
     /// ```
     pub fn new() -> Self {
+        Self::with_options(ProcessorOptions::default())
+    }
+
+    /// Creates a DataProcessor with custom processing options.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Cleaning and validation settings
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::processor::{DataProcessor, ProcessorOptions};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut processor = DataProcessor::with_options(ProcessorOptions {
+    ///     defaults: vec![("main_value".to_string(), "UNKNOWN".to_string())],
+    ///     ..ProcessorOptions::default()
+    /// });
+    ///
+    /// // Without the default this row would be invalid (no main_value)
+    /// let rows = vec![vec![
+    ///     Some("Label".to_string()),
+    ///     None,  // main_value is missing
+    ///     None, None, None, None, None, None, None, None, None, None,
+    /// ]];
+    ///
+    /// let (records, _) = processor.process_rows(rows)?;
+    /// assert_eq!(records[0].main_value, Some("UNKNOWN".to_string()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_options(options: ProcessorOptions) -> Self {
         DataProcessor {
-            warnings: Vec::new(),
+            diagnostics: Vec::new(),
+            suppressed_diagnostics: 0,
+            suppressed_note: None,
+            options,
+            progress: None,
+            cell_types: Vec::new(),
+            row_numbers: Vec::new(),
+            layout: HeaderLayout::default(),
+            stats: RowStats::default(),
+            untrimmed_width: None,
         }
     }
 
+    /// Registers a callback that receives progress updates while processing.
+    ///
+    /// The callback is invoked with `(rows_done, total_rows)` every
+    /// [`PROGRESS_INTERVAL`] rows and once more when processing completes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::processor::DataProcessor;
+    ///
+    /// let mut processor = DataProcessor::new();
+    /// processor.set_progress_callback(|done, total| {
+    ///     eprintln!("{}/{} rows", done, total);
+    /// });
+    /// ```
+    pub fn set_progress_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(usize, usize) + 'static,
+    {
+        self.progress = Some(Box::new(callback));
+    }
+
+    /// Supplies the spreadsheet type of every cell for the next rows processed.
+    ///
+    /// `types[r][c]` describes column `c` of row `r`, as returned by
+    /// [`ExcelReader::read_with_types`](crate::excel_reader::ExcelReader::read_with_types).
+    /// The types are only used when [`ProcessorOptions::preserve_types`] is set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::CellType;
+    /// use excel_to_json::processor::{DataProcessor, ProcessorOptions};
+    /// use serde_json::json;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut processor = DataProcessor::with_options(ProcessorOptions {
+    ///     preserve_types: true,
+    ///     ..ProcessorOptions::default()
+    /// });
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("42".to_string());
+    /// let mut types = vec![CellType::Text; 12];
+    /// types[1] = CellType::Number;
+    /// processor.set_cell_types(vec![types]);
+    ///
+    /// let (records, _) = processor.process_rows(vec![row])?;
+    /// assert_eq!(records[0].to_typed_array()["main_value"], json!(42));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_cell_types(&mut self, types: Vec<Vec<CellType>>) {
+        self.cell_types = types;
+    }
+
+    /// Supplies the spreadsheet row number of each row for the next rows processed.
+    ///
+    /// Warnings and logs refer to these numbers. Without them, rows are assumed
+    /// to follow a single header row, so the first row is reported as row 2.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::processor::DataProcessor;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut processor = DataProcessor::new();
+    /// // Data starts below a banner and a header row
+    /// processor.set_row_numbers(vec![4]);
+    ///
+    /// let (_, metadata) = processor.process_rows(vec![vec![Some("short".to_string())]])?;
+    /// assert_eq!(
+    ///     metadata.diagnostics[0].to_string(),
+    ///     "Row 4: Insufficient columns (expected 12, found 1)"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_row_numbers(&mut self, row_numbers: Vec<usize>) {
+        self.row_numbers = row_numbers;
+    }
+
+    /// Sets the width the rows had before the reader trimmed the columns that
+    /// are blank in every row (see [`ReaderOptions::keep_blank_columns`]).
+    ///
+    /// Cascade rows are padded back with empty cells up to the layout's
+    /// width, but not beyond this one, so trimming never makes a row
+    /// malformed that would have been read as a record.
+    ///
+    /// [`ReaderOptions::keep_blank_columns`]: crate::excel_reader::ReaderOptions::keep_blank_columns
+    pub fn set_untrimmed_width(&mut self, width: Option<usize>) {
+        self.untrimmed_width = width;
+    }
+
+    /// Puts diagnostics from the reader, such as cell errors and footer
+    /// notes, before the row diagnostics recorded so far.
+    ///
+    /// They count toward [`ProcessorOptions::max_warnings`] like the
+    /// processor's own: past the limit, the last diagnostics are only counted.
+    /// Call it before [`finish_rows`](Self::finish_rows), or before the rows
+    /// are processed, for the suppressed ones to be noted.
+    pub fn prepend_diagnostics(&mut self, diagnostics: Vec<RowDiagnostic>) {
+        let recorded = std::mem::replace(&mut self.diagnostics, diagnostics);
+        self.diagnostics.extend(recorded);
+        if let Some(max) = self.options.max_warnings.filter(|&max| self.diagnostics.len() > max) {
+            self.suppressed_diagnostics += self.diagnostics.len() - max;
+            self.diagnostics.truncate(max);
+        }
+    }
+
+    /// Records a row diagnostic, or only counts it once
+    /// [`ProcessorOptions::max_warnings`] are kept.
+    fn push_diagnostic(&mut self, diagnostic: RowDiagnostic) {
+        match self.options.max_warnings {
+            Some(max) if self.diagnostics.len() >= max => self.suppressed_diagnostics += 1,
+            _ => self.diagnostics.push(diagnostic),
+        }
+    }
+
+    /// Notes the diagnostics dropped by [`ProcessorOptions::max_warnings`],
+    /// if any were, at the front of `warnings`, replacing the note added
+    /// there before.
+    fn note_suppressed(&mut self, warnings: &mut Vec<String>) {
+        if self.suppressed_diagnostics == 0 {
+            return;
+        }
+        let note = format!(
+            "... and {} more warnings suppressed (limit {})",
+            self.suppressed_diagnostics,
+            self.diagnostics.len()
+        );
+        warn!("{}", note);
+        let previous = self.suppressed_note.as_ref()
+            .and_then(|previous| warnings.iter().position(|warning| warning == previous));
+        match previous {
+            Some(idx) => warnings[idx] = note.clone(),
+            None => warnings.insert(0, note.clone()),
+        }
+        self.suppressed_note = Some(note);
+    }
+
+    /// Returns the spreadsheet row number reported for a row index.
+    fn row_number(&self, row_idx: usize) -> usize {
+        self.row_numbers.get(row_idx).copied().unwrap_or(row_idx + 2)
+    }
+
     /// Processes raw Excel rows into validated CascadeField records.
     ///
     /// This is the main processing method that transforms raw Excel data into
@@ -101,16 +952,319 @@ impl DataProcessor {
     /// - Converts each row to a CascadeField
     /// - Cleans and normalizes field data
     /// - Validates records for required fields
-    /// - Collects processing warnings
+    /// - Collects a diagnostic for each problem row
     ///
     /// # Arguments
     ///
-    /// * `raw_rows` - Vector of raw Excel rows, each containing optional string values
+    /// * `raw_rows` - Vector of raw Excel rows, each containing optional string values
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((records, metadata))` - Successfully processed records and statistics
+    /// * `Err` - If a critical processing error occurs
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::processor::DataProcessor;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut processor = DataProcessor::new();
+    ///
+    /// // Sample data with valid and invalid rows
+    /// let raw_rows = vec![
+    ///     // Valid row with all required fields
+    ///     vec![
+    ///         Some("Main Label".to_string()),
+    ///         Some("MAIN001".to_string()),  // Required main_value
+    ///         Some("Main Description".to_string()),
+    ///         Some("Sub Label".to_string()),
+    ///         Some("SUB001".to_string()),
+    ///         Some("Sub Description".to_string()),
+    ///         Some("Major Label".to_string()),
+    ///         Some("MAJ001".to_string()),
+    ///         Some("Major Description".to_string()),
+    ///         Some("Minor Label".to_string()),
+    ///         Some("MIN001".to_string()),
+    ///         Some("Minor Description".to_string()),
+    ///     ],
+    ///     // Invalid row (missing main_value)
+    ///     vec![
+    ///         Some("Label".to_string()),
+    ///         None,  // Missing required main_value
+    ///         None, None, None, None, None, None, None, None, None, None,
+    ///     ],
+    /// ];
+    ///
+    /// let (records, metadata) = processor.process_rows(raw_rows)?;
+    ///
+    /// assert_eq!(records.len(), 1);  // Only the valid record
+    /// assert_eq!(metadata.total_rows_processed, 2);
+    /// assert_eq!(metadata.valid_records, 1);
+    /// assert_eq!(metadata.invalid_records, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn process_rows(&mut self, raw_rows: Vec<Vec<Option<String>>>) -> Result<(Vec<CascadeField>, ProcessingMetadata)> {
+        self.process_rows_with_headers(&[], raw_rows)
+    }
+
+    /// Processes raw Excel rows using the sheet's header row.
+    ///
+    /// Behaves like [`process_rows`](Self::process_rows); the headers are used
+    /// to name the tail columns when hybrid schema mode
+    /// ([`ProcessorOptions::positional_columns`]) is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the headers contain duplicates and
+    /// [`ProcessorOptions::duplicate_headers`] is `Error`.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - Header texts, one per column
+    /// * `raw_rows` - Vector of raw Excel rows
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::processor::{DataProcessor, ProcessorOptions};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut processor = DataProcessor::with_options(ProcessorOptions {
+    ///     positional_columns: Some(2),
+    ///     ..ProcessorOptions::default()
+    /// });
+    ///
+    /// let headers = vec!["Label".to_string(), "Value".to_string(), "Notes".to_string()];
+    /// let rows = vec![vec![
+    ///     Some("Category".to_string()),
+    ///     Some("CAT001".to_string()),
+    ///     Some("Seasonal".to_string()),
+    /// ]];
+    ///
+    /// let (records, _) = processor.process_rows_with_headers(&headers, rows)?;
+    /// assert_eq!(records[0].main_value, Some("CAT001".to_string()));
+    /// assert_eq!(records[0].extra["Notes"], "Seasonal");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn process_rows_with_headers(
+        &mut self,
+        headers: &[String],
+        raw_rows: Vec<Vec<Option<String>>>,
+    ) -> Result<(Vec<CascadeField>, ProcessingMetadata)> {
+        let total_rows = raw_rows.len();
+        
+        info!("Processing {} rows", total_rows);
+        
+        self.start_rows(headers, false)?;
+        let cell_types = std::mem::take(&mut self.cell_types);
+        let mut valid_records = Vec::new();
+        
+        for (row_idx, row) in raw_rows.into_iter().enumerate() {
+            self.report_progress(row_idx, total_rows);
+            
+            let row_number = self.row_number(row_idx);
+            let types = cell_types.get(row_idx).map_or(&[][..], Vec::as_slice);
+            if let Some(field) = self.process_row(row, row_number, types) {
+                valid_records.push(field);
+            }
+            if self.has_failed() {
+                break;
+            }
+        }
+        
+        self.cell_types = cell_types;
+        let mut metadata = self.finish_rows()?;
+        
+        if self.options.dedupe {
+            valid_records = self.dedupe_records(valid_records, &mut metadata);
+        }
+        
+        if self.options.complete_only {
+            let before = valid_records.len();
+            valid_records = Self::filter_complete_records(valid_records);
+            let incomplete = before - valid_records.len();
+            if incomplete > 0 {
+                info!("Dropped {} records with incomplete keys", incomplete);
+                metadata.valid_records -= incomplete;
+                metadata.filtered_records += incomplete;
+            }
+        }
+        
+        if let Some(field) = &self.options.sort_by {
+            sort_records(&mut valid_records, field, self.options.sort_desc);
+        }
+        
+        if let Some(progress) = self.progress.as_mut() {
+            progress(total_rows, total_rows);
+        }
+        
+        Ok((valid_records, metadata))
+    }
+    
+    /// Drops records whose composite key repeats an earlier record.
+    ///
+    /// Records are compared on `main_value`, `sub_value`, `major_value` and
+    /// `minor_value`. Keys missing from a record take part as missing, so a
+    /// record with partial keys only matches records with the same keys
+    /// present and equal. The first occurrence is kept; each dropped record
+    /// adds a warning diagnostic naming both rows and moves from `valid_records` to
+    /// `duplicate_records` in `metadata`.
+    ///
+    /// Runs automatically after [`process_rows_with_headers`](Self::process_rows_with_headers)
+    /// when [`ProcessorOptions::dedupe`] is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - Valid records in sheet order
+    /// * `metadata` - Statistics of the run that produced `records`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::processor::DataProcessor;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut processor = DataProcessor::new();
+    /// let row = |description: &str| {
+    ///     let mut row = vec![None; 12];
+    ///     row[1] = Some("CAT001".to_string());
+    ///     row[2] = Some(description.to_string());
+    ///     row
+    /// };
+    ///
+    /// let (records, mut metadata) = processor.process_rows(vec![row("First"), row("Second")])?;
+    /// let records = processor.dedupe_records(records, &mut metadata);
+    ///
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].main_description, Some("First".to_string()));
+    /// assert_eq!(metadata.duplicate_records, 1);
+    /// assert_eq!(metadata.diagnostics[0].to_string(), "Row 3: duplicate of row 2, skipped");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn dedupe_records(&mut self, records: Vec<CascadeField>, metadata: &mut ProcessingMetadata) -> Vec<CascadeField> {
+        let mut first_rows: HashMap<CompositeKey, usize> = HashMap::new();
+        let mut unique = Vec::with_capacity(records.len());
+        let mut duplicates = 0;
+        
+        for (idx, record) in records.into_iter().enumerate() {
+            let row_number = record.row_number.unwrap_or(idx + 2);
+            let key = composite_key(&record);
+            match first_rows.get(&key) {
+                Some(first_row) => {
+                    debug!("Dropping row {} as a duplicate of row {}", row_number, first_row);
+                    let message = format!("duplicate of row {}, skipped", first_row);
+                    self.push_diagnostic(RowDiagnostic::warning(row_number, None, message));
+                    duplicates += 1;
+                },
+                None => {
+                    first_rows.insert(key, row_number);
+                    unique.push(record);
+                },
+            }
+        }
+        
+        if duplicates > 0 {
+            info!("Dropped {} duplicate records", duplicates);
+            metadata.valid_records -= duplicates;
+            metadata.duplicate_records += duplicates;
+            metadata.diagnostics = self.diagnostics.clone();
+            let mut warnings = metadata.warnings.take().unwrap_or_default();
+            self.note_suppressed(&mut warnings);
+            metadata.warnings = (!warnings.is_empty()).then_some(warnings);
+        }
+        
+        unique
+    }
+    
+    /// Processes raw Excel rows into header-keyed generic records.
+    ///
+    /// Every column becomes a key named after its header (blank and duplicate
+    /// headers are resolved as in hybrid mode). Values are trimmed, and value
+    /// maps, text dates, skip rules, defaults and ASCII handling are applied by
+    /// header name. Rows without any value are counted as invalid, whatever
+    /// defaults are configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - Header texts, one per column
+    /// * `raw_rows` - Vector of raw Excel rows
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the headers contain duplicates and
+    /// [`ProcessorOptions::duplicate_headers`] is `Error`, or if non-ASCII
+    /// values are found with [`AsciiMode::Fail`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::processor::DataProcessor;
+    /// use serde_json::json;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut processor = DataProcessor::new();
+    /// let headers = vec!["Header A".to_string(), "Header B".to_string()];
+    /// let rows = vec![vec![Some(" a ".to_string()), None]];
+    ///
+    /// let (records, metadata) = processor.process_generic_rows(&headers, rows)?;
+    /// assert_eq!(records[0].to_value(), json!({"Header A": "a", "Header B": null}));
+    /// assert_eq!(metadata.valid_records, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn process_generic_rows(
+        &mut self,
+        headers: &[String],
+        raw_rows: Vec<Vec<Option<String>>>,
+    ) -> Result<(Vec<GenericRecord>, ProcessingMetadata)> {
+        let total_rows = raw_rows.len();
+        
+        info!("Processing {} rows in generic mode", total_rows);
+        
+        self.start_rows(headers, true)?;
+        let cell_types = std::mem::take(&mut self.cell_types);
+        let mut valid_records = Vec::new();
+        
+        for (row_idx, row) in raw_rows.into_iter().enumerate() {
+            self.report_progress(row_idx, total_rows);
+            
+            let row_number = self.row_number(row_idx);
+            let types = cell_types.get(row_idx).map_or(&[][..], Vec::as_slice);
+            if let Some(record) = self.process_generic_row(row, row_number, types) {
+                valid_records.push(record);
+            }
+            if self.has_failed() {
+                break;
+            }
+        }
+        
+        self.cell_types = cell_types;
+        let metadata = self.finish_rows()?;
+        
+        if let Some(progress) = self.progress.as_mut() {
+            progress(total_rows, total_rows);
+        }
+        
+        Ok((valid_records, metadata))
+    }
+
+    /// Prepares the processor for a sheet processed one row at a time.
+    ///
+    /// Resets the row counts and resolves the header layout used by
+    /// [`process_row`](Self::process_row) and
+    /// [`process_generic_row`](Self::process_generic_row); the layout keys
+    /// every column by header when [`ProcessorOptions::generic`] is set. Together with
+    /// [`finish_rows`](Self::finish_rows) this lets a caller process a sheet
+    /// without holding all of its rows or records in memory.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// * `Ok((records, metadata))` - Successfully processed records and statistics
-    /// * `Err` - If a critical processing error occurs
+    /// Returns an error if the headers contain duplicates and
+    /// [`ProcessorOptions::duplicate_headers`] is `Error`.
     ///
     /// # Example
     ///
@@ -119,109 +1273,585 @@ impl DataProcessor {
     ///
     /// # fn main() -> anyhow::Result<()> {
     /// let mut processor = DataProcessor::new();
+    /// processor.begin_rows(&[])?;
     ///
-    /// // Sample data with valid and invalid rows
-    /// let raw_rows = vec![
-    ///     // Valid row with all required fields
-    ///     vec![
-    ///         Some("Main Label".to_string()),
-    ///         Some("MAIN001".to_string()),  // Required main_value
-    ///         Some("Main Description".to_string()),
-    ///         Some("Sub Label".to_string()),
-    ///         Some("SUB001".to_string()),
-    ///         Some("Sub Description".to_string()),
-    ///         Some("Major Label".to_string()),
-    ///         Some("MAJ001".to_string()),
-    ///         Some("Major Description".to_string()),
-    ///         Some("Minor Label".to_string()),
-    ///         Some("MIN001".to_string()),
-    ///         Some("Minor Description".to_string()),
-    ///     ],
-    ///     // Invalid row (missing main_value)
-    ///     vec![
-    ///         Some("Label".to_string()),
-    ///         None,  // Missing required main_value
-    ///         None, None, None, None, None, None, None, None, None, None,
-    ///     ],
-    /// ];
-    ///
-    /// let (records, metadata) = processor.process_rows(raw_rows)?;
+    /// let mut row = vec![None; 12];
+    /// row[1] = Some("CAT001".to_string());
+    /// if let Some(record) = processor.process_row(row, 2, &[]) {
+    ///     println!("{}", record.to_typed_array());
+    /// }
     ///
-    /// assert_eq!(records.len(), 1);  // Only the valid record
-    /// assert_eq!(metadata.total_rows_processed, 2);
+    /// let metadata = processor.finish_rows()?;
     /// assert_eq!(metadata.valid_records, 1);
-    /// assert_eq!(metadata.invalid_records, 1);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn process_rows(&mut self, raw_rows: Vec<Vec<Option<String>>>) -> Result<(Vec<CascadeField>, ProcessingMetadata)> {
-        let start_time = std::time::Instant::now();
-        let total_rows = raw_rows.len();
+    pub fn begin_rows(&mut self, headers: &[String]) -> Result<()> {
+        self.start_rows(headers, self.options.generic)
+    }
+
+    /// Resets the row counts and builds the header layout, keying every
+    /// column by header when `generic` is set.
+    ///
+    /// With a [`ColumnMap`], checks that the mapped columns exist in the
+    /// header row (when the sheet has one).
+    fn start_rows(&mut self, headers: &[String], generic: bool) -> Result<()> {
+        if let (Some(map), false, false) = (&self.options.column_map, generic, headers.is_empty()) {
+            map.check_columns(headers.len())?;
+        }
+        self.layout = match (generic, self.options.positional_columns) {
+            (true, _) => HeaderLayout::build(headers, 0, self.options.duplicate_headers)?,
+            (false, Some(positional)) => HeaderLayout::build(headers, positional, self.options.duplicate_headers)?,
+            (false, None) => HeaderLayout::default(),
+        };
+        // Header-keyed records only take defaults for their own keys
+        let keys = self.layout.keys();
+        let unmatched_defaults = self.options.defaults.iter()
+            .map(|(name, _)| name)
+            .filter(|name| generic && !keys.contains(&name.as_str()))
+            .cloned()
+            .collect();
+        self.stats = RowStats {
+            unmatched_defaults,
+            source_headers: self.options.echo_headers.then(|| headers.to_vec()),
+            header_row: (self.options.dedupe_headers && headers.iter().any(|header| !header.is_empty()))
+                .then(|| headers.to_vec()),
+            ..RowStats::default()
+        };
+        Ok(())
+    }
+
+    /// Processes a single row into a cascade record.
+    ///
+    /// Applies the same cleaning, skip rules, defaults and validation as
+    /// [`process_rows_with_headers`](Self::process_rows_with_headers) and
+    /// updates the counts reported by [`finish_rows`](Self::finish_rows).
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The raw row
+    /// * `row_number` - Spreadsheet row number used in diagnostics
+    /// * `types` - Cell types of the row, used with [`ProcessorOptions::preserve_types`]
+    ///
+    /// # Returns
+    ///
+    /// The record if the row is valid; `None` for invalid and skipped rows.
+    pub fn process_row(&mut self, row: Vec<Option<String>>, row_number: usize, types: &[CellType]) -> Option<CascadeField> {
+        self.stats.total += 1;
         
-        info!("Processing {} rows", total_rows);
+        // Blank rows only get here when the reader keeps them
+        if row.iter().all(Option::is_none) {
+            debug!("Invalid record at row {} - empty row", row_number);
+            self.stats.invalid += 1;
+            self.push_diagnostic(RowDiagnostic::error(row_number, None, "Empty row"));
+            self.fail_at(row_number, "Empty row");
+            return None;
+        }
         
-        let mut valid_records = Vec::new();
-        let mut invalid_count = 0;
+        if self.skip_repeated_header(&row, row_number) {
+            return None;
+        }
         
-        for (row_idx, row) in raw_rows.into_iter().enumerate() {
-            // Convert row to CascadeField
-            match CascadeField::from_row(row.clone()) {
-                Some(mut field) => {
-                    // Trim whitespace from all string fields
-                    self.clean_field(&mut field);
-                    
-                    // Validate the field
-                    if field.is_valid() {
-                        debug!("Valid record at row {}", row_idx + 2);
-                        valid_records.push(field);
-                    } else {
-                        debug!("Invalid record at row {} - missing required fields", row_idx + 2);
-                        invalid_count += 1;
-                        
-                        // Add warning for incomplete keys if applicable
-                        if !field.has_complete_keys() {
-                            self.warnings.push(format!(
-                                "Row {}: Incomplete composite keys",
-                                row_idx + 2
-                            ));
-                        }
+        // Convert row to CascadeField
+        let row = self.restore_trimmed_columns(row);
+        let found_columns = row.len();
+        *self.stats.column_counts.entry(found_columns).or_default() += 1;
+        let row = self.pad_row(row, row_number);
+        let (parsed, expected_columns) = match (&self.options.column_map, self.options.positional_columns, self.options.levels) {
+            (Some(map), _, _) => (CascadeField::from_row_mapped(row, map), map.width()),
+            (None, Some(positional), _) => (CascadeField::from_row_hybrid(row, positional, &self.layout), positional),
+            (None, None, Some(levels)) => (CascadeField::from_row_with_arity(row, levels), levels * 3),
+            (None, None, None) => (CascadeField::from_row(row), CascadeField::FIELD_NAMES.len()),
+        };
+        
+        let Some(mut field) = parsed else {
+            debug!("Malformed row {} - insufficient columns", row_number);
+            self.stats.malformed += 1;
+            let message = format!("Insufficient columns (expected {}, found {})", expected_columns, found_columns);
+            self.fail_at(row_number, &message);
+            self.push_diagnostic(RowDiagnostic::error(row_number, None, message));
+            return None;
+        };
+        
+        field.row_number = Some(row_number);
+        
+        // Trim whitespace from all string fields
+        self.clean_field(&mut field);
+        self.apply_transforms(&mut field);
+        
+        // Canonicalize variant spellings and text dates
+        self.apply_value_map(&mut field, row_number);
+        self.parse_text_dates(&mut field, row_number);
+        
+        // Drop rows such as totals before defaults can mask them
+        if let Some(field_name) = self.matching_skip_rule(&field) {
+            debug!("Skipping row {} - {} matches skip rule", row_number, field_name);
+            self.stats.skipped += 1;
+            return None;
+        }
+        
+        // Fill missing fields before validation
+        self.apply_defaults(&mut field);
+        
+        if let Some(filter) = self.options.filters.iter().find(|filter| !filter.matches(&field)) {
+            debug!("Filtering out row {} - {} does not match", row_number, filter.field);
+            self.stats.filtered += 1;
+            return None;
+        }
+        
+        // Enforce ASCII-only output if requested
+        match self.options.ascii_only {
+            Some(AsciiMode::Transliterate) => Self::transliterate_field(&mut field),
+            Some(AsciiMode::Fail) => {
+                for name in Self::non_ascii_fields(&field) {
+                    self.stats.non_ascii.push(format!("row {} ({})", row_number, name));
+                }
+            },
+            None => {},
+        }
+        
+        // Validate the field
+        let missing = self.missing_required_fields(&field);
+        if missing.is_empty() {
+            debug!("Valid record at row {}", row_number);
+            if self.options.preserve_types {
+                self.apply_cell_types(&mut field, types);
+            }
+            if self.options.strict_unique {
+                self.check_unique(&field, row_number);
+            }
+            self.stats.valid += 1;
+            Some(field)
+        } else {
+            debug!("Invalid record at row {} - missing {}", row_number, missing.join(", "));
+            self.stats.invalid += 1;
+            
+            if self.options.required_fields.is_empty() {
+                self.fail_at(row_number, "Incomplete composite keys");
+                self.push_diagnostic(RowDiagnostic::error(row_number, Some("main_value"), "Incomplete composite keys"));
+            } else {
+                self.fail_at(row_number, &format!("Missing required field(s) {}", missing.join(", ")));
+                for name in missing {
+                    let message = format!("Missing required field {}", name);
+                    self.push_diagnostic(RowDiagnostic::error(row_number, Some(&name), message));
+                }
+            }
+            None
+        }
+    }
+
+    /// Returns the required fields that `field` has no value for, in the
+    /// order they were configured. See [`ProcessorOptions::required_fields`].
+    fn missing_required_fields(&self, field: &CascadeField) -> Vec<String> {
+        if self.options.required_fields.is_empty() {
+            return if field.is_valid() { Vec::new() } else { vec!["main_value".to_string()] };
+        }
+        let values = field.field_values();
+        self.options.required_fields.iter()
+            .filter(|name| {
+                CascadeField::FIELD_NAMES.iter()
+                    .position(|field_name| field_name == name)
+                    .and_then(|idx| values[idx])
+                    .is_none()
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Processes a single row into a header-keyed generic record.
+    ///
+    /// The row-at-a-time form of [`process_generic_rows`](Self::process_generic_rows);
+    /// call [`begin_rows`](Self::begin_rows) with the sheet's headers first.
+    ///
+    /// # Returns
+    ///
+    /// The record if the row has any value; `None` for blank and skipped rows.
+    pub fn process_generic_row(&mut self, row: Vec<Option<String>>, row_number: usize, types: &[CellType]) -> Option<GenericRecord> {
+        self.stats.total += 1;
+        
+        if self.skip_repeated_header(&row, row_number) {
+            return None;
+        }
+        
+        let mut record = GenericRecord(self.layout.extract(&row));
+        record.0.values_mut().for_each(Self::clean_value);
+        self.apply_generic_text_rules(&mut record, row_number);
+        
+        if let Some(name) = self.generic_skip_rule(&record) {
+            debug!("Skipping row {} - {} matches skip rule", row_number, name);
+            self.stats.skipped += 1;
+            return None;
+        }
+        
+        // Rows of blank cells stay invalid even when defaults would fill them
+        if !record.has_values() {
+            debug!("Invalid record at row {} - no values", row_number);
+            self.stats.invalid += 1;
+            self.fail_at(row_number, "Empty row");
+            return None;
+        }
+        
+        for (name, value) in &self.options.defaults {
+            if let Some(slot) = record.0.get_mut(name).filter(|slot| slot.is_null()) {
+                *slot = Value::String(value.clone());
+            }
+        }
+        
+        match self.options.ascii_only {
+            Some(AsciiMode::Transliterate) => {
+                for value in record.0.values_mut().flat_map(Self::value_strings) {
+                    if !value.is_ascii() {
+                        *value = deunicode::deunicode(value);
                     }
-                },
-                None => {
-                    debug!("Failed to parse row {}", row_idx + 2);
-                    invalid_count += 1;
-                    self.warnings.push(format!("Row {}: Insufficient columns", row_idx + 2));
+                }
+            },
+            Some(AsciiMode::Fail) => {
+                for (name, value) in &record.0 {
+                    if !Self::value_is_ascii(value) {
+                        self.stats.non_ascii.push(format!("row {} ({})", row_number, name));
+                    }
+                }
+            },
+            None => {},
+        }
+        
+        if self.options.preserve_types {
+            for (col, cell_type) in Self::typed_columns(types) {
+                let Some(key) = self.layout.key_for_column(col) else {
+                    continue;
+                };
+                if let Some(Value::String(text)) = record.0.get(&key) {
+                    let typed = cell_type.to_value(text);
+                    record.0.insert(key, typed);
                 }
             }
         }
         
-        let processing_time = start_time.elapsed().as_millis();
+        self.stats.valid += 1;
+        Some(record)
+    }
+
+    /// Records the first invalid row for [`ProcessorOptions::fail_fast`].
+    fn fail_at(&mut self, row_number: usize, message: &str) {
+        if self.options.fail_fast && self.stats.invalid_row.is_none() {
+            debug!("Stopping at invalid row {}", row_number);
+            self.stats.invalid_row = Some(InvalidRowError { row_number, message: message.to_string() });
+        }
+    }
+
+    /// Returns whether a row failed with [`ProcessorOptions::fail_fast`], so
+    /// callers feeding rows one at a time can stop; [`finish_rows`](Self::finish_rows)
+    /// then returns the [`InvalidRowError`].
+    pub fn has_failed(&self) -> bool {
+        self.stats.invalid_row.is_some()
+    }
+
+    /// Records the first repeated composite key for
+    /// [`ProcessorOptions::strict_unique`].
+    fn check_unique(&mut self, field: &CascadeField, row_number: usize) {
+        let key = composite_key(field);
+        match self.stats.first_rows.get(&key) {
+            Some(&first_row) if self.stats.duplicate_key.is_none() => {
+                debug!("Row {} repeats the composite key of row {}", row_number, first_row);
+                let present = ["main_value", "sub_value", "major_value", "minor_value"]
+                    .iter()
+                    .zip(key)
+                    .filter_map(|(name, value)| value.map(|value| (name.to_string(), value)))
+                    .collect();
+                self.stats.duplicate_key = Some(DuplicateKeyError { row_number, first_row, key: present });
+            },
+            Some(_) => {},
+            None => {
+                self.stats.first_rows.insert(key, row_number);
+            },
+        }
+    }
+
+    /// Completes a sheet started with [`begin_rows`](Self::begin_rows).
+    ///
+    /// # Returns
+    ///
+    /// Statistics for the rows processed since `begin_rows`, with every
+    /// diagnostic collected so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing the offending rows if non-ASCII values were
+    /// found with [`AsciiMode::Fail`], or a [`DuplicateKeyError`] for the
+    /// first repeated composite key with [`ProcessorOptions::strict_unique`],
+    /// or an [`InvalidRowError`] for the first invalid row with
+    /// [`ProcessorOptions::fail_fast`]; whichever came first.
+    pub fn finish_rows(&mut self) -> Result<ProcessingMetadata> {
+        let stats = std::mem::take(&mut self.stats);
+        
+        match (stats.duplicate_key, stats.invalid_row) {
+            (Some(duplicate), Some(invalid)) if invalid.row_number < duplicate.row_number => return Err(invalid.into()),
+            (Some(duplicate), _) => return Err(duplicate.into()),
+            (None, Some(invalid)) => return Err(invalid.into()),
+            (None, None) => {},
+        }
+        
+        if !stats.non_ascii.is_empty() {
+            anyhow::bail!(
+                "Non-ASCII characters found in {} field(s): {}",
+                stats.non_ascii.len(),
+                stats.non_ascii.join(", ")
+            );
+        }
+        
+        let processing_time = stats.started.elapsed().as_millis();
         
         info!(
-            "Processing complete: {} valid records, {} invalid records in {}ms",
-            valid_records.len(),
-            invalid_count,
+            "Processing complete: {} valid records, {} invalid records, {} skipped in {}ms",
+            stats.valid,
+            stats.invalid,
+            stats.skipped,
             processing_time
         );
         
-        // Log warnings if any
-        if !self.warnings.is_empty() {
-            warn!("Processing warnings: {:?}", self.warnings);
+        for diagnostic in &self.diagnostics {
+            warn!("{}", diagnostic);
         }
         
-        let metadata = ProcessingMetadata {
-            total_rows_processed: total_rows,
-            valid_records: valid_records.len(),
-            invalid_records: invalid_count,
-            processing_time_ms: processing_time,
-            warnings: if self.warnings.is_empty() {
-                None
+        // Most rows having another width than the layout's points at a
+        // shifted or missing column, which would otherwise go unexplained
+        let detected_columns = stats.column_counts.iter()
+            .max_by_key(|&(width, rows)| (rows, width))
+            .map(|(&width, _)| width)
+            .filter(|&width| self.expected_columns().is_some_and(|expected| width != expected));
+        let mut warnings = Vec::new();
+        self.note_suppressed(&mut warnings);
+        if !stats.repeated_headers.is_empty() {
+            let rows: Vec<String> = stats.repeated_headers.iter().map(usize::to_string).collect();
+            let message = format!("Skipped {} repeated header row(s): {}", rows.len(), rows.join(", "));
+            warn!("{}", message);
+            warnings.push(message);
+        }
+        if !stats.unmatched_defaults.is_empty() {
+            let message = format!(
+                "Defaults not applied to fields missing from the header row: {}",
+                stats.unmatched_defaults.join(", ")
+            );
+            warn!("{}", message);
+            warnings.push(message);
+        }
+        // Repeated headers keep their columns under new keys, which consumers
+        // looking the header up would not expect
+        if let Some(message) = self.layout.renamed_warning() {
+            warn!("{}", message);
+            warnings.push(message);
+        }
+        if let (Some(detected), Some(expected)) = (detected_columns, self.expected_columns()) {
+            let consequence = if detected > expected {
+                "extra columns are ignored"
+            } else if self.options.pad_rows && detected >= self.required_width() {
+                "short rows are padded"
             } else {
-                Some(self.warnings.clone())
-            },
+                "rows are reported as malformed"
+            };
+            let message = format!("{} columns found but {} expected; {}", detected, expected, consequence);
+            warn!("{}", message);
+            warnings.push(message);
+        }
+        
+        Ok(ProcessingMetadata {
+            total_rows_processed: stats.total,
+            valid_records: stats.valid,
+            invalid_records: stats.invalid,
+            malformed_rows: stats.malformed,
+            skipped_records: stats.skipped,
+            duplicate_records: 0,
+            filtered_records: stats.filtered,
+            row_window: None,
+            source_headers: stats.source_headers,
+            detected_columns,
+            processing_time_ms: processing_time,
+            diagnostics: self.diagnostics.clone(),
+            warnings: (!warnings.is_empty()).then_some(warnings),
+        })
+    }
+
+    /// Counts a row as skipped when [`ProcessorOptions::dedupe_headers`] is
+    /// set and each of its cells equals the header cell of its column.
+    fn skip_repeated_header(&mut self, row: &[Option<String>], row_number: usize) -> bool {
+        let Some(header_row) = &self.stats.header_row else {
+            return false;
+        };
+        let width = row.len().max(header_row.len());
+        let repeated = (0..width).all(|col| {
+            let cell = row.get(col).and_then(Option::as_deref).unwrap_or("");
+            cell == header_row.get(col).map_or("", String::as_str)
+        });
+        if repeated {
+            debug!("Skipping row {} - repeats the header row", row_number);
+            self.stats.skipped += 1;
+            self.stats.repeated_headers.push(row_number);
+        }
+        repeated
+    }
+
+    /// Puts back the empty cells of trimmed blank columns that the layout
+    /// reads (see [`set_untrimmed_width`](Self::set_untrimmed_width)).
+    fn restore_trimmed_columns(&self, mut row: Vec<Option<String>>) -> Vec<Option<String>> {
+        if let Some(untrimmed) = self.untrimmed_width {
+            let width = self.layout_width().min(untrimmed);
+            if row.len() < width {
+                row.resize(width, None);
+            }
+        }
+        row
+    }
+
+    /// Returns the number of columns the cascade layout reads.
+    fn layout_width(&self) -> usize {
+        match (&self.options.column_map, self.options.positional_columns, self.options.levels) {
+            (Some(map), _, _) => map.width(),
+            (None, Some(positional), _) => positional,
+            (None, None, Some(levels)) => levels * 3,
+            (None, None, None) => CascadeField::FIELD_NAMES.len(),
+        }
+    }
+
+    /// Pads a short row with empty cells up to the layout's width when
+    /// [`ProcessorOptions::pad_rows`] is set and the row reaches the last
+    /// required field; other rows are returned unchanged.
+    fn pad_row(&self, mut row: Vec<Option<String>>, row_number: usize) -> Vec<Option<String>> {
+        let width = self.layout_width();
+        if self.options.pad_rows && row.len() < width && row.len() >= self.required_width() {
+            debug!("Padding row {} from {} to {} columns", row_number, row.len(), width);
+            row.resize(width, None);
+        }
+        row
+    }
+
+    /// Returns the number of columns a row needs to hold every required
+    /// field: the column of the last one plus one.
+    fn required_width(&self) -> usize {
+        let required: Vec<&str> = if self.options.required_fields.is_empty() {
+            vec!["main_value"]
+        } else {
+            self.options.required_fields.iter().map(String::as_str).collect()
         };
+        required.into_iter()
+            .filter_map(|name| match &self.options.column_map {
+                Some(map) => map.column(name),
+                None => CascadeField::FIELD_NAMES.iter().position(|field_name| *field_name == name),
+            })
+            .map(|col| col + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of columns a cascade row is expected to have; `None`
+    /// when a column map or header-keyed columns make any width valid.
+    fn expected_columns(&self) -> Option<usize> {
+        match (&self.options.column_map, self.options.positional_columns, self.options.levels) {
+            (Some(_), _, _) | (None, Some(_), _) => None,
+            (None, None, Some(levels)) => Some(levels * 3),
+            (None, None, None) => Some(CascadeField::FIELD_NAMES.len()),
+        }
+    }
+
+    /// Invokes the progress callback every [`PROGRESS_INTERVAL`] rows.
+    fn report_progress(&mut self, row_idx: usize, total_rows: usize) {
+        if row_idx > 0 && row_idx.is_multiple_of(PROGRESS_INTERVAL) {
+            if let Some(progress) = self.progress.as_mut() {
+                progress(row_idx, total_rows);
+            }
+        }
+    }
+
+    /// Returns the non-text cell types of a row as `(column, type)` pairs.
+    fn typed_columns(types: &[CellType]) -> Vec<(usize, CellType)> {
+        types.iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, cell_type)| *cell_type != CellType::Text)
+            .collect()
+    }
+
+    /// Returns the cascade field or header key a column is parsed into.
+    fn column_key(&self, col: usize) -> Option<String> {
+        let fixed = || CascadeField::FIELD_NAMES.get(col).map(|name| name.to_string());
+        match (self.options.positional_columns, self.options.levels) {
+            (Some(positional), _) if col < positional => fixed(),
+            (Some(_), _) => self.layout.key_for_column(col),
+            (None, Some(levels)) if col >= levels * 3 => None,
+            (None, Some(_)) if col >= CascadeField::FIELD_NAMES.len() => {
+                let part = ["label", "value", "description"][col % 3];
+                Some(format!("level{}_{}", col / 3 + 1, part))
+            },
+            _ => fixed(),
+        }
+    }
+
+    /// Records numeric and boolean cells of a row on a parsed field.
+    ///
+    /// Cascade fields are listed in [`CascadeField::cell_types`]; header-keyed
+    /// values are converted in place.
+    fn apply_cell_types(&self, field: &mut CascadeField, types: &[CellType]) {
+        if let Some(map) = &self.options.column_map {
+            for (name, col) in map.fields() {
+                if let Some(&cell_type @ (CellType::Number | CellType::Bool)) = types.get(col) {
+                    field.cell_types.insert(name.to_string(), cell_type);
+                }
+            }
+            return;
+        }
         
-        Ok((valid_records, metadata))
+        for (col, cell_type) in Self::typed_columns(types) {
+            let Some(key) = self.column_key(col) else {
+                continue;
+            };
+            if CascadeField::FIELD_NAMES.contains(&key.as_str()) {
+                field.cell_types.insert(key, cell_type);
+            } else if let Some(Value::String(text)) = field.extra.get(&key) {
+                let typed = cell_type.to_value(text);
+                field.extra.insert(key, typed);
+            }
+        }
+    }
+
+    /// Applies value maps and text date parsing to a generic record by header name.
+    fn apply_generic_text_rules(&mut self, record: &mut GenericRecord, row_number: usize) {
+        for (name, table) in &self.options.value_map {
+            if let Some(Value::String(slot)) = record.0.get_mut(name) {
+                if let Some(canonical) = table.get(slot.as_str()) {
+                    debug!("Row {}: {} '{}' -> '{}'", row_number, name, slot, canonical);
+                    *slot = canonical.clone();
+                }
+            }
+        }
+
+        let Some(format) = &self.options.text_date_format else {
+            return;
+        };
+        let mut failures = Vec::new();
+        for name in &self.options.date_columns {
+            let Some(Value::String(slot)) = record.0.get_mut(name) else {
+                continue;
+            };
+            match parse_text_date(slot, format) {
+                Some(iso) => *slot = iso,
+                None => failures.push(RowDiagnostic::warning(row_number, Some(name), format!(
+                    "Could not parse '{}' in {} as a date ({})",
+                    slot, name, format
+                ))),
+            }
+        }
+        for diagnostic in failures {
+            self.push_diagnostic(diagnostic);
+        }
+    }
+
+    /// Returns the name of the first skip rule column whose value matches.
+    fn generic_skip_rule(&self, record: &GenericRecord) -> Option<String> {
+        self.options.skip_rows_matching
+            .iter()
+            .find(|(name, pattern)| {
+                matches!(record.0.get(name), Some(Value::String(value)) if pattern.is_match(value))
+            })
+            .map(|(name, _)| name.clone())
     }
     
     /// Cleans a CascadeField by trimming whitespace and normalizing empty strings.
@@ -252,30 +1882,189 @@ impl DataProcessor {
     ///     None, None, None, None, None, None, None, None,
     /// ];
     ///
-    /// let mut field = CascadeField::from_row(row).unwrap();
-    /// // After cleaning (done internally in process_rows):
-    /// // - "  Main Label  " becomes "Main Label"
-    /// // - "" becomes None
-    /// // - "   " becomes None
-    /// ```
-    fn clean_field(&self, field: &mut CascadeField) {
-        field.main_label = field.main_label.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        field.main_value = field.main_value.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        field.main_description = field.main_description.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        
-        field.sub_label = field.sub_label.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        field.sub_value = field.sub_value.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        field.sub_description = field.sub_description.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        
-        field.major_label = field.major_label.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        field.major_value = field.major_value.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        field.major_description = field.major_description.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        
-        field.minor_label = field.minor_label.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        field.minor_value = field.minor_value.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-        field.minor_description = field.minor_description.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    /// let mut field = CascadeField::from_row(row).unwrap();
+    /// // After cleaning (done internally in process_rows):
+    /// // - "  Main Label  " becomes "Main Label"
+    /// // - "" becomes None
+    /// // - "   " becomes None
+    /// ```
+    fn clean_field(&self, field: &mut CascadeField) {
+        field.main_label = field.main_label.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        field.main_value = field.main_value.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        field.main_description = field.main_description.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        
+        field.sub_label = field.sub_label.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        field.sub_value = field.sub_value.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        field.sub_description = field.sub_description.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        
+        field.major_label = field.major_label.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        field.major_value = field.major_value.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        field.major_description = field.major_description.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        
+        field.minor_label = field.minor_label.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        field.minor_value = field.minor_value.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        field.minor_description = field.minor_description.as_ref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        
+        for value in field.extra.values_mut() {
+            Self::clean_value(value);
+        }
+    }
+
+    /// Applies the configured transforms to the cleaned cascade fields.
+    ///
+    /// A value a transform leaves blank, such as one of spaces only, becomes
+    /// `None` as in cleaning.
+    fn apply_transforms(&self, field: &mut CascadeField) {
+        for (name, transform) in &self.options.transforms {
+            if let Some(slot) = field.field_mut(name) {
+                *slot = slot.as_deref().map(|value| transform.apply(value)).filter(|value| !value.trim().is_empty());
+            }
+        }
+    }
+
+    /// Trims a header-keyed value in place, turning blank strings into `null`.
+    fn clean_value(value: &mut Value) {
+        match value {
+            Value::String(s) if s.trim().is_empty() => *value = Value::Null,
+            Value::String(s) => *s = s.trim().to_string(),
+            Value::Array(items) => items.iter_mut().for_each(Self::clean_value),
+            _ => {},
+        }
+    }
+
+    /// Returns mutable references to every string inside a header-keyed value.
+    fn value_strings(value: &mut Value) -> Vec<&mut String> {
+        match value {
+            Value::String(s) => vec![s],
+            Value::Array(items) => items.iter_mut().flat_map(Self::value_strings).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Replaces mapped variant values with their canonical spelling.
+    ///
+    /// Runs after [`clean_field`](Self::clean_field), so variants are matched
+    /// against trimmed values.
+    fn apply_value_map(&self, field: &mut CascadeField, row_number: usize) {
+        for (name, table) in &self.options.value_map {
+            let Some(slot) = Self::text_slot(field, name) else {
+                continue;
+            };
+            if let Some(canonical) = table.get(slot.as_str()) {
+                debug!("Row {}: {} '{}' -> '{}'", row_number, name, slot, canonical);
+                *slot = canonical.clone();
+            }
+        }
+    }
+
+    /// Rewrites text dates in the configured date columns as ISO-8601.
+    ///
+    /// Values that do not match the format are left unchanged and recorded
+    /// as warning diagnostics.
+    fn parse_text_dates(&mut self, field: &mut CascadeField, row_number: usize) {
+        let Some(format) = &self.options.text_date_format else {
+            return;
+        };
+        let mut failures = Vec::new();
+        for name in &self.options.date_columns {
+            let Some(slot) = Self::text_slot(field, name) else {
+                continue;
+            };
+            match parse_text_date(slot, format) {
+                Some(iso) => *slot = iso,
+                None => failures.push(RowDiagnostic::warning(row_number, Some(name), format!(
+                    "Could not parse '{}' in {} as a date ({})",
+                    slot, name, format
+                ))),
+            }
+        }
+        for diagnostic in failures {
+            self.push_diagnostic(diagnostic);
+        }
+    }
+
+    /// Returns the text value of a cascade field or header-keyed column, if set.
+    fn text_slot<'a>(field: &'a mut CascadeField, name: &str) -> Option<&'a mut String> {
+        if CascadeField::FIELD_NAMES.contains(&name) {
+            return field.field_mut(name)?.as_mut();
+        }
+        match field.extra.get_mut(name) {
+            Some(Value::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the first skip rule field whose value matches.
+    fn matching_skip_rule(&self, field: &CascadeField) -> Option<String> {
+        let values = field.field_values();
+        self.options.skip_rows_matching
+            .iter()
+            .find(|(name, pattern)| {
+                CascadeField::FIELD_NAMES.iter()
+                    .position(|field_name| field_name == name)
+                    .and_then(|idx| values[idx])
+                    .is_some_and(|value| pattern.is_match(value))
+            })
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Fills fields that are `None` with the configured default values.
+    ///
+    /// Runs after [`clean_field`](Self::clean_field), so whitespace-only cells
+    /// also receive the default.
+    fn apply_defaults(&self, field: &mut CascadeField) {
+        for (name, value) in &self.options.defaults {
+            if let Some(slot) = field.field_mut(name) {
+                if slot.is_none() {
+                    *slot = Some(value.clone());
+                }
+            }
+        }
+    }
+
+    /// Replaces non-ASCII characters in every field with ASCII equivalents.
+    ///
+    /// Smart quotes become straight quotes and accented letters lose their
+    /// accents (e.g. `“Café”` becomes `"Cafe"`).
+    fn transliterate_field(field: &mut CascadeField) {
+        for name in CascadeField::FIELD_NAMES {
+            if let Some(Some(value)) = field.field_mut(name) {
+                if !value.is_ascii() {
+                    *value = deunicode::deunicode(value);
+                }
+            }
+        }
+        for value in field.extra.values_mut().flat_map(Self::value_strings) {
+            if !value.is_ascii() {
+                *value = deunicode::deunicode(value);
+            }
+        }
+    }
+
+    /// Returns true if every string inside a header-keyed value is ASCII.
+    fn value_is_ascii(value: &Value) -> bool {
+        match value {
+            Value::String(s) => s.is_ascii(),
+            Value::Array(items) => items.iter().all(Self::value_is_ascii),
+            _ => true,
+        }
+    }
+
+    /// Returns the names of fields whose values contain non-ASCII characters.
+    fn non_ascii_fields(field: &CascadeField) -> Vec<String> {
+        let mut names: Vec<String> = CascadeField::FIELD_NAMES
+            .iter()
+            .zip(field.field_values())
+            .filter(|(_, value)| value.is_some_and(|v| !v.is_ascii()))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        names.extend(
+            field.extra.iter()
+                .filter(|(_, value)| !Self::value_is_ascii(value))
+                .map(|(name, _)| name.clone()),
+        );
+        names
     }
-    
     
     /// Filters records by completeness of composite keys.
     ///
@@ -328,7 +2117,6 @@ impl DataProcessor {
     /// assert_eq!(complete.len(), 1);
     /// assert_eq!(complete[0].main_value, Some("M001".to_string()));
     /// ```
-    #[allow(dead_code)]
     pub fn filter_complete_records(records: Vec<CascadeField>) -> Vec<CascadeField> {
         records.into_iter()
             .filter(|record| record.has_complete_keys())
@@ -386,18 +2174,54 @@ impl DataProcessor {
     ///     println!("{}: {} records", category, items.len());
     /// }
     /// ```
-    #[allow(dead_code)]
     pub fn group_by_main_value(records: &[CascadeField]) -> std::collections::HashMap<String, Vec<&CascadeField>> {
-        use std::collections::HashMap;
-        
-        let mut grouped = HashMap::new();
+        Self::group_by_field(records, "main_value")
+            .into_iter()
+            .filter_map(|(value, group)| Some((value?.to_string(), group)))
+            .collect()
+    }
+    
+    /// Groups records by the value of any cascade field.
+    ///
+    /// Unlike [`group_by_main_value`](Self::group_by_main_value), records
+    /// without a value for the field are kept, grouped under `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - Records to group
+    /// * `field` - One of [`CascadeField::FIELD_NAMES`]; an unknown name puts
+    ///   every record under `None`
+    ///
+    /// # Returns
+    ///
+    /// The groups in order of their first record; each group keeps its
+    /// records in sheet order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use excel_to_json::models::CascadeField;
+    /// use excel_to_json::processor::DataProcessor;
+    ///
+    /// let record = |sub: Option<&str>| {
+    ///     let mut row = vec![None; 12];
+    ///     row[1] = Some("CAT001".to_string());
+    ///     row[4] = sub.map(str::to_string);
+    ///     CascadeField::from_row(row).unwrap()
+    /// };
+    /// let records = vec![record(Some("SUB2")), record(None), record(Some("SUB1")), record(Some("SUB2"))];
+    ///
+    /// let grouped = DataProcessor::group_by_field(&records, "sub_value");
+    /// let sizes: Vec<_> = grouped.iter().map(|(value, group)| (*value, group.len())).collect();
+    /// assert_eq!(sizes, vec![(Some("SUB2"), 2), (None, 1), (Some("SUB1"), 1)]);
+    /// ```
+    pub fn group_by_field<'a>(records: &'a [CascadeField], field: &str) -> IndexMap<Option<&'a str>, Vec<&'a CascadeField>> {
+        let idx = CascadeField::FIELD_NAMES.iter().position(|name| *name == field);
+        let mut grouped: IndexMap<Option<&str>, Vec<&CascadeField>> = IndexMap::new();
         
         for record in records {
-            if let Some(main_value) = &record.main_value {
-                grouped.entry(main_value.clone())
-                    .or_insert_with(Vec::new)
-                    .push(record);
-            }
+            let value = idx.and_then(|idx| record.field_values()[idx]);
+            grouped.entry(value).or_default().push(record);
         }
         
         grouped
@@ -494,4 +2318,609 @@ mod tests {
         assert_eq!(metadata.invalid_records, 0);
         assert_eq!(metadata.total_rows_processed, 2);
     }
+
+    #[test]
+    fn test_defaults_fill_missing_fields() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            defaults: vec![
+                ("main_description".to_string(), "No description".to_string()),
+                ("main_value".to_string(), "UNASSIGNED".to_string()),
+            ],
+            ..ProcessorOptions::default()
+        });
+
+        let rows = vec![
+            vec![
+                Some("Main Label".to_string()),
+                Some("MAIN1".to_string()),
+                Some("   ".to_string()), // Whitespace-only, cleaned to None
+                None, None, None, None, None, None, None, None, None,
+            ],
+            vec![
+                Some("Main Label 2".to_string()),
+                None, // Would be invalid without a main_value default
+                Some("Has description".to_string()),
+                None, None, None, None, None, None, None, None, None,
+            ],
+        ];
+
+        let (records, metadata) = processor.process_rows(rows).expect("Should process rows");
+
+        assert_eq!(metadata.valid_records, 2);
+        assert_eq!(metadata.invalid_records, 0);
+        assert_eq!(records[0].main_description, Some("No description".to_string()));
+        assert_eq!(records[0].main_value, Some("MAIN1".to_string()));
+        assert_eq!(records[1].main_value, Some("UNASSIGNED".to_string()));
+        assert_eq!(records[1].main_description, Some("Has description".to_string()));
+    }
+
+    #[test]
+    fn test_pad_rows_repairs_rows_missing_trailing_columns() {
+        let row = |width: usize| {
+            let mut row = vec![None; width];
+            row[1] = Some("CAT001".to_string());
+            if width > 10 {
+                row[10] = Some("MIN001".to_string());
+            }
+            row
+        };
+        let process = |options: ProcessorOptions, width: usize| {
+            DataProcessor::with_options(options).process_rows(vec![row(width)]).unwrap()
+        };
+
+        let (records, metadata) = process(ProcessorOptions::default(), 11);
+        assert_eq!((records.len(), metadata.malformed_rows), (0, 1));
+
+        let padded = ProcessorOptions { pad_rows: true, ..ProcessorOptions::default() };
+        let (records, metadata) = process(padded.clone(), 11);
+        assert_eq!((records.len(), metadata.malformed_rows), (1, 0));
+        assert_eq!(records[0].minor_value, Some("MIN001".to_string()));
+        assert_eq!(records[0].minor_description, None);
+        assert_eq!(metadata.warnings, Some(vec!["11 columns found but 12 expected; short rows are padded".to_string()]));
+
+        // A row ending before a required field stays malformed
+        let require_minor = ProcessorOptions { required_fields: vec!["minor_value".to_string()], ..padded };
+        assert_eq!(process(require_minor.clone(), 10).1.malformed_rows, 1);
+        assert_eq!(process(require_minor, 11).1.valid_records, 1);
+    }
+
+    #[test]
+    fn test_max_warnings_counts_diagnostics_past_the_limit() {
+        let rows: Vec<Vec<Option<String>>> = (0..10)
+            .map(|i| vec![None, Some(format!("M{}", i))])
+            .collect();
+
+        let options = ProcessorOptions { max_warnings: Some(3), ..ProcessorOptions::default() };
+        let (_records, metadata) = DataProcessor::with_options(options).process_rows(rows.clone()).unwrap();
+        assert_eq!(metadata.malformed_rows, 10);
+        assert_eq!(metadata.diagnostics.len(), 3);
+        assert_eq!(metadata.diagnostics[2].row_number, 4);
+        assert!(metadata.warnings.unwrap().contains(&"... and 7 more warnings suppressed (limit 3)".to_string()));
+
+        let (_records, metadata) = DataProcessor::new().process_rows(rows).unwrap();
+        assert_eq!(metadata.diagnostics.len(), 10);
+        assert!(!metadata.warnings.unwrap().iter().any(|warning| warning.contains("suppressed")));
+
+        // Duplicates dropped by dedupe update the note rather than adding one
+        let mut rows: Vec<Vec<Option<String>>> = (0..4).map(|i| vec![None, Some(format!("M{}", i))]).collect();
+        let mut duplicate = vec![None; 12];
+        duplicate[1] = Some("CAT001".to_string());
+        rows.extend(std::iter::repeat_n(duplicate, 5));
+        let options = ProcessorOptions { max_warnings: Some(3), dedupe: true, ..ProcessorOptions::default() };
+        let (records, metadata) = DataProcessor::with_options(options).process_rows(rows).unwrap();
+        assert_eq!((records.len(), metadata.duplicate_records), (1, 4));
+        let notes: Vec<&String> = metadata.warnings.iter().flatten()
+            .filter(|warning| warning.contains("suppressed"))
+            .collect();
+        assert_eq!(notes, vec!["... and 5 more warnings suppressed (limit 3)"]);
+    }
+
+    #[test]
+    fn test_column_count_mismatch_is_reported() {
+        let rows = |width: usize, count: usize| -> Vec<Vec<Option<String>>> {
+            (0..count).map(|_| {
+                let mut row = vec![None; width];
+                row[1] = Some("CAT001".to_string());
+                row
+            }).collect()
+        };
+        let process = |rows| DataProcessor::new().process_rows(rows).unwrap().1;
+
+        let wide = process(rows(13, 2));
+        assert_eq!((wide.valid_records, wide.detected_columns), (2, Some(13)));
+        assert_eq!(wide.warnings, Some(vec!["13 columns found but 12 expected; extra columns are ignored".to_string()]));
+
+        // The most common width counts, not the odd short row
+        let mut mixed = rows(11, 2);
+        mixed.extend(rows(12, 1));
+        let narrow = process(mixed);
+        assert_eq!((narrow.malformed_rows, narrow.detected_columns), (2, Some(11)));
+        assert_eq!(narrow.warnings, Some(vec!["11 columns found but 12 expected; rows are reported as malformed".to_string()]));
+
+        let exact = process(rows(12, 2));
+        assert_eq!((exact.detected_columns, exact.warnings), (None, None));
+
+        // Header-keyed columns after the positional ones can be any number
+        let mut hybrid = DataProcessor::with_options(ProcessorOptions { positional_columns: Some(3), ..ProcessorOptions::default() });
+        assert_eq!(hybrid.process_rows(rows(5, 1)).unwrap().1.detected_columns, None);
+    }
+
+    #[test]
+    fn test_hybrid_schema_processing() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            positional_columns: Some(3),
+            ..ProcessorOptions::default()
+        });
+
+        let headers: Vec<String> = ["Main Label", "Main Value", "Main Description", "Region", "Owner"]
+            .iter().map(|h| h.to_string()).collect();
+        let rows = vec![
+            vec![
+                Some("Category".to_string()),
+                Some("CAT001".to_string()),
+                Some("Main category".to_string()),
+                Some("  EMEA ".to_string()),
+                Some("alice".to_string()),
+            ],
+            vec![
+                Some("Category".to_string()),
+                Some("CAT002".to_string()),
+                None,
+                Some("APAC".to_string()),
+            ],
+            vec![Some("Too short".to_string())],
+        ];
+
+        let (records, metadata) = processor.process_rows_with_headers(&headers, rows)
+            .expect("Should process rows");
+
+        assert_eq!(metadata.valid_records, 2);
+        assert_eq!((metadata.invalid_records, metadata.malformed_rows), (0, 1));
+
+        // Positional prefix maps onto the cascade fields
+        assert_eq!(records[0].main_label, Some("Category".to_string()));
+        assert_eq!(records[0].main_value, Some("CAT001".to_string()));
+        assert_eq!(records[0].sub_label, None);
+
+        // Tail columns are named by header and cleaned
+        assert_eq!(records[0].extra["Region"], "EMEA");
+        assert_eq!(records[0].extra["Owner"], "alice");
+        assert_eq!(records[1].extra["Region"], "APAC");
+        assert!(!records[1].extra.contains_key("Owner"));
+    }
+
+    fn duplicate_value_sheet(policy: DuplicateHeaderPolicy) -> Result<Value> {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            positional_columns: Some(2),
+            duplicate_headers: policy,
+            ..ProcessorOptions::default()
+        });
+
+        let headers: Vec<String> = ["Main Label", "Main Value", "Value", "Value"]
+            .iter().map(|h| h.to_string()).collect();
+        let rows = vec![vec![
+            Some("Category".to_string()),
+            Some("CAT001".to_string()),
+            Some("10".to_string()),
+            Some(" 20 ".to_string()),
+        ]];
+
+        let (records, _) = processor.process_rows_with_headers(&headers, rows)?;
+        Ok(serde_json::to_value(&records[0])?)
+    }
+
+    #[test]
+    fn test_transforms_apply_to_named_fields_only() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            transforms: vec![
+                parse_field_transform("main_value=upper").unwrap(),
+                parse_field_transform("sub_value=upper").unwrap(),
+                parse_field_transform("sub_description=title").unwrap(),
+            ],
+            ..ProcessorOptions::default()
+        });
+        let mut row = vec![None; 12];
+        row[0] = Some("  Category label ".to_string());
+        row[1] = Some(" cat-001 ".to_string());
+        row[4] = Some("sub1".to_string());
+        row[5] = Some("outdoor GEAR".to_string());
+
+        let (records, _) = processor.process_rows(vec![row]).expect("Should process rows");
+        assert_eq!(records[0].main_value.as_deref(), Some("CAT-001"));
+        assert_eq!(records[0].sub_value.as_deref(), Some("SUB1"));
+        assert_eq!(records[0].sub_description.as_deref(), Some("Outdoor Gear"));
+        // Fields without a transform are only trimmed
+        assert_eq!(records[0].main_label.as_deref(), Some("Category label"));
+
+        let unknown = parse_field_transform("main_value=shout").unwrap_err();
+        assert_eq!(unknown.to_string(), "Unknown transform 'shout'. Supported transforms: upper, lower, title, trim");
+        assert!(parse_field_transform("main_valeu=upper").unwrap_err().to_string().starts_with("Unknown field 'main_valeu'"));
+    }
+
+    #[test]
+    fn test_duplicate_headers_suffix() {
+        let record = duplicate_value_sheet(DuplicateHeaderPolicy::Suffix).expect("Should process rows");
+
+        assert_eq!(record["Value"], "10");
+        assert_eq!(record["Value_2"], "20");
+    }
+
+    #[test]
+    fn test_duplicate_headers_merge() {
+        let record = duplicate_value_sheet(DuplicateHeaderPolicy::Merge).expect("Should process rows");
+
+        assert_eq!(record["Value"], serde_json::json!(["10", "20"]));
+        assert!(record.get("Value_2").is_none());
+    }
+
+    #[test]
+    fn test_duplicate_headers_error() {
+        let err = duplicate_value_sheet(DuplicateHeaderPolicy::Error).expect_err("Should reject duplicates");
+
+        assert!(err.to_string().contains("Duplicate header(s) found: Value"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_value_map_canonicalizes_variants() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let map_path = temp_dir.path().join("values.json");
+        std::fs::write(
+            &map_path,
+            r#"{"main_label": {"USA": "United States", "U.S.A.": "United States"}}"#,
+        ).unwrap();
+
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            value_map: load_value_map(map_path.to_str().unwrap()).expect("Should load map"),
+            ..ProcessorOptions::default()
+        });
+
+        let row = |label: &str, value: &str| {
+            let mut row = vec![None; 12];
+            row[0] = Some(label.to_string());
+            row[1] = Some(value.to_string());
+            row
+        };
+        let rows = vec![row("USA", "M1"), row(" U.S.A. ", "M2"), row("Canada", "M3")];
+
+        let (records, _) = processor.process_rows(rows).expect("Should process rows");
+
+        assert_eq!(records[0].main_label, Some("United States".to_string()));
+        assert_eq!(records[1].main_label, Some("United States".to_string()));
+        assert_eq!(records[2].main_label, Some("Canada".to_string()));
+    }
+
+    #[test]
+    fn test_skip_rows_matching_total_row() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            skip_rows_matching: vec![parse_skip_rule("main_label=^(Sub)?[Tt]otal").unwrap()],
+            ..ProcessorOptions::default()
+        });
+
+        let row = |label: &str, value: Option<&str>| {
+            let mut row = vec![None; 12];
+            row[0] = Some(label.to_string());
+            row[1] = value.map(|v| v.to_string());
+            row
+        };
+        let rows = vec![
+            row("Widgets", Some("W1")),
+            row("Total", Some("1500")),
+            row("Gadgets", None),
+        ];
+
+        let (records, metadata) = processor.process_rows(rows).expect("Should process rows");
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].main_value, Some("W1".to_string()));
+        assert_eq!(metadata.total_rows_processed, 3);
+        assert_eq!(metadata.skipped_records, 1);
+        assert_eq!(metadata.invalid_records, 1, "Only the row missing main_value is invalid");
+    }
+
+    #[test]
+    fn test_required_fields_replace_default_validation() {
+        let row = |main: Option<&str>, minor: Option<&str>| {
+            let mut row = vec![None; 12];
+            row[1] = main.map(str::to_string);
+            row[10] = minor.map(str::to_string);
+            row[11] = Some("Desc".to_string());
+            row
+        };
+        let rows = || vec![row(Some("M1"), Some("N1")), row(Some("M2"), None), row(None, None), row(None, Some("N4"))];
+
+        let (records, metadata) = DataProcessor::new().process_rows(rows()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(metadata.diagnostics[0], RowDiagnostic::error(4, Some("main_value"), "Incomplete composite keys"));
+
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            required_fields: vec!["minor_value".to_string(), "main_value".to_string()],
+            ..ProcessorOptions::default()
+        });
+        let (records, metadata) = processor.process_rows(rows()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(metadata.invalid_records, 3);
+        assert_eq!(metadata.diagnostics, vec![
+            RowDiagnostic::error(3, Some("minor_value"), "Missing required field minor_value"),
+            RowDiagnostic::error(4, Some("minor_value"), "Missing required field minor_value"),
+            RowDiagnostic::error(4, Some("main_value"), "Missing required field main_value"),
+            RowDiagnostic::error(5, Some("main_value"), "Missing required field main_value"),
+        ]);
+    }
+
+    #[test]
+    fn test_filters_are_anded_and_counted_separately() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            filters: vec![
+                parse_row_filter("main_value=CAT001").unwrap(),
+                parse_row_filter("sub_value!=").unwrap(),
+            ],
+            ..ProcessorOptions::default()
+        });
+
+        let row = |main: Option<&str>, sub: Option<&str>| {
+            let mut row = vec![None; 12];
+            row[1] = main.map(|v| format!(" {} ", v));
+            row[4] = sub.map(|v| v.to_string());
+            row
+        };
+        let rows = vec![
+            row(Some("CAT001"), Some("S1")),
+            row(Some("CAT001"), Some("   ")),
+            row(Some("CAT002"), Some("S2")),
+            row(None, Some("S3")),
+        ];
+
+        let (records, metadata) = processor.process_rows(rows).expect("Should process rows");
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].sub_value, Some("S1".to_string()));
+        assert_eq!(metadata.filtered_records, 3, "Rows failing a filter are not invalid");
+        assert_eq!(metadata.invalid_records, 0);
+
+        let err = parse_row_filter("colour!=red").unwrap_err();
+        assert!(err.to_string().contains("Unknown field 'colour'"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_sort_records_keeps_ties_in_order_and_none_last() {
+        let row = |main: &str, minor: Option<&str>| {
+            let mut row = vec![None; 12];
+            row[1] = Some(main.to_string());
+            row[10] = minor.map(str::to_string);
+            row
+        };
+        let rows = vec![
+            row("A", Some("2")),
+            row("B", None),
+            row("C", Some("10")),
+            row("D", Some("2")),
+            row("E", Some("x")),
+        ];
+        let sorted = |sort_desc: bool| {
+            let mut processor = DataProcessor::with_options(ProcessorOptions {
+                sort_by: Some("minor_value".to_string()),
+                sort_desc,
+                ..ProcessorOptions::default()
+            });
+            let (records, _) = processor.process_rows(rows.clone()).expect("Should process rows");
+            records.iter().map(|r| r.main_value.clone().unwrap()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(sorted(false), vec!["A", "D", "C", "E", "B"]);
+        assert_eq!(sorted(true), vec!["E", "C", "A", "D", "B"]);
+    }
+
+    #[test]
+    fn test_dedupe_compares_present_keys() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            dedupe: true,
+            ..ProcessorOptions::default()
+        });
+
+        let row = |main: &str, sub: Option<&str>, description: &str| {
+            let mut row = vec![None; 12];
+            row[1] = Some(main.to_string());
+            row[2] = Some(description.to_string());
+            row[4] = sub.map(|v| v.to_string());
+            row
+        };
+        let rows = vec![
+            row("M1", None, "partial"),
+            row("M1", Some("S1"), "full"),
+            row("M1", None, "partial again"),
+            row("M1", Some("S1"), "full again"),
+            row("M2", None, "other"),
+        ];
+
+        let (records, metadata) = processor.process_rows(rows).expect("Should process rows");
+
+        let descriptions: Vec<_> = records.iter().map(|r| r.main_description.as_deref().unwrap()).collect();
+        assert_eq!(descriptions, vec!["partial", "full", "other"]);
+        assert_eq!(metadata.valid_records, 3);
+        assert_eq!(metadata.duplicate_records, 2);
+        assert_eq!(metadata.diagnostics, vec![
+            RowDiagnostic::warning(4, None, "duplicate of row 2, skipped"),
+            RowDiagnostic::warning(5, None, "duplicate of row 3, skipped"),
+        ]);
+    }
+
+    #[test]
+    fn test_text_dates_normalized_to_iso() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            date_columns: vec!["main_description".to_string()],
+            text_date_format: Some("%m/%d/%Y".to_string()),
+            ..ProcessorOptions::default()
+        });
+
+        let row = |value: &str, date: &str| {
+            let mut row = vec![None; 12];
+            row[1] = Some(value.to_string());
+            row[2] = Some(date.to_string());
+            row
+        };
+        let rows = vec![row("M1", "01/02/2024"), row("M2", "12/31/2023 "), row("M3", "TBD")];
+
+        let (records, metadata) = processor.process_rows(rows).expect("Should process rows");
+
+        assert_eq!(records[0].main_description, Some("2024-01-02".to_string()));
+        assert_eq!(records[1].main_description, Some("2023-12-31".to_string()));
+        assert_eq!(records[2].main_description, Some("TBD".to_string()));
+        assert_eq!(metadata.diagnostics, vec![RowDiagnostic::warning(
+            4,
+            Some("main_description"),
+            "Could not parse 'TBD' in main_description as a date (%m/%d/%Y)",
+        )]);
+    }
+
+    #[test]
+    fn test_levels_option_reads_two_level_rows() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            levels: Some(2),
+            ..ProcessorOptions::default()
+        });
+
+        let rows = vec![
+            ["Cat", "C1", "Category", "Sub", "S1", "Subcategory"].iter().map(|v| Some(v.to_string())).collect(),
+            vec![Some("Cat".to_string()), Some("C2".to_string()), None, Some("Sub".to_string())],
+        ];
+
+        let (records, metadata) = processor.process_rows(rows).expect("Should process rows");
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].sub_value, Some("S1".to_string()));
+        assert_eq!((metadata.invalid_records, metadata.malformed_rows), (0, 1));
+        assert_eq!(
+            metadata.diagnostics,
+            vec![RowDiagnostic::error(3, None, "Insufficient columns (expected 6, found 4)")]
+        );
+    }
+
+    fn smart_quote_rows() -> Vec<Vec<Option<String>>> {
+        vec![vec![
+            Some("Main".to_string()),
+            Some("M1".to_string()),
+            Some("The \u{201C}best\u{201D} caf\u{E9}".to_string()),
+            None, None, None, None, None, None, None, None, None,
+        ]]
+    }
+
+    #[test]
+    fn test_ascii_only_transliterates() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            ascii_only: Some(AsciiMode::Transliterate),
+            ..ProcessorOptions::default()
+        });
+
+        let (records, _) = processor.process_rows(smart_quote_rows()).expect("Should process rows");
+
+        assert_eq!(records[0].main_description, Some("The \"best\" cafe".to_string()));
+    }
+
+    #[test]
+    fn test_ascii_only_fail_lists_offending_fields() {
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            ascii_only: Some(AsciiMode::Fail),
+            ..ProcessorOptions::default()
+        });
+
+        let err = processor.process_rows(smart_quote_rows()).expect_err("Should reject non-ASCII");
+
+        assert!(err.to_string().contains("row 2 (main_description)"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_generic_rows_keyed_by_header() {
+        let headers: Vec<String> = ["Code", "Name", "Name", ""].iter().map(|h| h.to_string()).collect();
+        let mut processor = DataProcessor::with_options(ProcessorOptions {
+            skip_rows_matching: vec![("Code".to_string(), Regex::new("^Total$").unwrap())],
+            defaults: vec![("Name_2".to_string(), "n/a".to_string()), ("main_value".to_string(), "EU".to_string())],
+            ..ProcessorOptions::default()
+        });
+        let rows = vec![
+            vec![Some("A1".to_string()), Some(" First ".to_string()), None, Some("x".to_string())],
+            vec![Some("Total".to_string()), None, None, None],
+            vec![None, Some("  ".to_string())],
+        ];
+
+        let (records, metadata) = processor.process_generic_rows(&headers, rows).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].0.keys().collect::<Vec<_>>(),
+            vec!["Code", "Name", "Name_2", "column_4"]
+        );
+        assert_eq!(records[0].0["Name"], "First");
+        assert_eq!(records[0].0["Name_2"], "n/a");
+        assert_eq!(metadata.skipped_records, 1);
+        assert_eq!(metadata.invalid_records, 1);
+        assert_eq!(metadata.warnings, Some(vec![
+            "Defaults not applied to fields missing from the header row: main_value".to_string(),
+            "Renamed duplicate headers: Name in column 3 to Name_2".to_string(),
+        ]));
+    }
+
+    #[test]
+    fn test_preserve_types_in_generic_and_hybrid_columns() {
+        let headers: Vec<String> = ["Code", "Qty", "Active"].iter().map(|h| h.to_string()).collect();
+        let row = vec![Some("007".to_string()), Some("2.5".to_string()), Some("true".to_string())];
+        let types = vec![CellType::Text, CellType::Number, CellType::Bool];
+
+        let mut generic = DataProcessor::with_options(ProcessorOptions {
+            generic: true,
+            preserve_types: true,
+            ..ProcessorOptions::default()
+        });
+        generic.set_cell_types(vec![types.clone()]);
+        let (records, _) = generic.process_generic_rows(&headers, vec![row.clone()]).unwrap();
+        assert_eq!(records[0].to_value(), serde_json::json!({"Code": "007", "Qty": 2.5, "Active": true}));
+
+        let mut hybrid = DataProcessor::with_options(ProcessorOptions {
+            positional_columns: Some(2),
+            preserve_types: true,
+            ..ProcessorOptions::default()
+        });
+        hybrid.set_cell_types(vec![types]);
+        let (records, _) = hybrid.process_rows_with_headers(&headers, vec![row]).unwrap();
+        let typed = records[0].to_typed_array();
+        assert_eq!(typed["main_value"], serde_json::json!(2.5));
+        assert_eq!(typed["Active"], serde_json::json!(true));
+        assert_eq!(records[0].to_php_array()["Active"], "true");
+    }
+
+    #[test]
+    fn test_echo_headers_reports_header_row_in_metadata() {
+        let headers: Vec<String> = ["Main Label", "Main Value", ""].iter().map(|h| h.to_string()).collect();
+        let mut row = vec![None; 12];
+        row[1] = Some("CAT001".to_string());
+
+        let mut processor = DataProcessor::with_options(ProcessorOptions { echo_headers: true, ..ProcessorOptions::default() });
+        let (_records, metadata) = processor.process_rows_with_headers(&headers, vec![row.clone()]).unwrap();
+        assert_eq!(metadata.source_headers, Some(headers.clone()));
+
+        let (_records, metadata) = DataProcessor::new().process_rows_with_headers(&headers, vec![row]).unwrap();
+        assert_eq!(metadata.source_headers, None);
+    }
+
+    #[test]
+    fn test_progress_callback_reports_increasing_counts() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let updates = Rc::new(RefCell::new(Vec::new()));
+        let recorded = Rc::clone(&updates);
+
+        let mut processor = DataProcessor::new();
+        processor.set_progress_callback(move |done, total| recorded.borrow_mut().push((done, total)));
+
+        let rows: Vec<Vec<Option<String>>> = (0..2500)
+            .map(|i| {
+                let mut row = vec![None; 12];
+                row[1] = Some(format!("M{}", i));
+                row
+            })
+            .collect();
+        processor.process_rows(rows).expect("Should process rows");
+
+        assert_eq!(*updates.borrow(), vec![(1000, 2500), (2000, 2500), (2500, 2500)]);
+    }
 }
@@ -0,0 +1,224 @@
+//! Column profiles of processed records.
+//!
+//! A profile summarizes each of the twelve cascade fields of a sheet: how many
+//! records have a value, how many distinct values there are, the shortest and
+//! longest value and a few samples. It is meant for a first look at an
+//! unfamiliar sheet before importing it, and is computed from the records the
+//! import would produce, after cleaning and validation.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::models::CascadeField;
+//! use excel_to_json::profile::profile_records;
+//!
+//! let record = |main: &str| {
+//!     let mut row = vec![None; 12];
+//!     row[1] = Some(main.to_string());
+//!     CascadeField::from_row(row).unwrap()
+//! };
+//! let fields = profile_records(&[record("CAT001"), record("CAT2"), record("CAT001")]);
+//!
+//! let main_value = &fields[1];
+//! assert_eq!(main_value.field, "main_value");
+//! assert_eq!((main_value.non_null, main_value.unique), (3, 2));
+//! assert_eq!((main_value.min_length, main_value.max_length), (Some(4), Some(6)));
+//! assert_eq!(main_value.samples, vec!["CAT001", "CAT2"]);
+//! assert_eq!(fields[0].non_null, 0);
+//! ```
+
+use crate::models::{CascadeField, SheetData};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Number of distinct sample values kept per field.
+pub const SAMPLE_COUNT: usize = 3;
+
+/// How `--profile` prints the profiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// An aligned text table per sheet
+    Table,
+    /// A JSON array of [`SheetProfile`]s
+    Json,
+}
+
+impl std::str::FromStr for ProfileFormat {
+    type Err = String;
+
+    /// Parses "table" or "json"; case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(ProfileFormat::Table),
+            "json" => Ok(ProfileFormat::Json),
+            _ => Err(format!("Unknown profile format: {}. Supported formats: table, json.", s)),
+        }
+    }
+}
+
+/// Statistics of one cascade field over a sheet's records.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldProfile {
+    /// Field name, e.g. `main_value`
+    pub field: String,
+    /// Records with a value in the field
+    pub non_null: usize,
+    /// Distinct values
+    pub unique: usize,
+    /// Length in characters of the shortest value; `None` without values
+    pub min_length: Option<usize>,
+    /// Length in characters of the longest value; `None` without values
+    pub max_length: Option<usize>,
+    /// The first [`SAMPLE_COUNT`] distinct values, in sheet order
+    pub samples: Vec<String>,
+}
+
+/// The field profiles of one sheet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SheetProfile {
+    /// Sheet name, prefixed with its file in multi-file runs (see [`SheetData::key`])
+    pub sheet: String,
+    /// Number of records profiled
+    pub records: usize,
+    pub fields: Vec<FieldProfile>,
+}
+
+/// Profiles each of the twelve cascade fields over `records`.
+///
+/// # Returns
+///
+/// One [`FieldProfile`] per field, in [`CascadeField::FIELD_NAMES`] order.
+pub fn profile_records(records: &[CascadeField]) -> Vec<FieldProfile> {
+    let values: Vec<[Option<&str>; 12]> = records.iter().map(CascadeField::field_values).collect();
+
+    CascadeField::FIELD_NAMES.iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            let mut seen = HashSet::new();
+            let mut profile = FieldProfile {
+                field: name.to_string(),
+                non_null: 0,
+                unique: 0,
+                min_length: None,
+                max_length: None,
+                samples: Vec::new(),
+            };
+            for value in values.iter().filter_map(|record| record[idx]) {
+                let length = value.chars().count();
+                profile.non_null += 1;
+                profile.min_length = Some(profile.min_length.map_or(length, |min| min.min(length)));
+                profile.max_length = Some(profile.max_length.map_or(length, |max| max.max(length)));
+                if seen.insert(value) && profile.samples.len() < SAMPLE_COUNT {
+                    profile.samples.push(value.to_string());
+                }
+            }
+            profile.unique = seen.len();
+            profile
+        })
+        .collect()
+}
+
+/// Profiles every sheet of a multi-sheet result.
+///
+/// Generic (`--generic`) sheets have no cascade fields; they are profiled
+/// with no records.
+pub fn profile_sheets(sheets: &[SheetData]) -> Vec<SheetProfile> {
+    sheets.iter()
+        .map(|sheet| SheetProfile {
+            sheet: sheet.key(),
+            records: sheet.rows.len(),
+            fields: profile_records(&sheet.rows),
+        })
+        .collect()
+}
+
+/// Renders profiles as one aligned table per sheet.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::profile::{format_table, SheetProfile};
+///
+/// let table = format_table(&[SheetProfile { sheet: "Data".to_string(), records: 0, fields: vec![] }]);
+/// assert!(table.starts_with("Sheet: Data (0 records)\n"));
+/// ```
+pub fn format_table(profiles: &[SheetProfile]) -> String {
+    let mut out = String::new();
+    for (idx, profile) in profiles.iter().enumerate() {
+        if idx > 0 {
+            out.push('\n');
+        }
+        let noun = if profile.records == 1 { "record" } else { "records" };
+        out.push_str(&format!("Sheet: {} ({} {})\n", profile.sheet, profile.records, noun));
+
+        let length = |length: Option<usize>| length.map_or("-".to_string(), |length| length.to_string());
+        let rows: Vec<[String; 6]> = profile.fields.iter()
+            .map(|field| [
+                field.field.clone(),
+                field.non_null.to_string(),
+                field.unique.to_string(),
+                length(field.min_length),
+                length(field.max_length),
+                field.samples.join(", "),
+            ])
+            .collect();
+        let header = ["field", "non-null", "unique", "min len", "max len", "samples"].map(str::to_string);
+        let mut widths = header.clone().map(|cell| cell.chars().count());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        for row in std::iter::once(&header).chain(&rows) {
+            let cells: Vec<String> = row.iter()
+                .zip(widths)
+                .enumerate()
+                .map(|(col, (cell, width))| match col {
+                    // Counts are right-aligned, text left-aligned
+                    1..=4 => format!("{:>width$}", cell, width = width),
+                    _ => format!("{:<width$}", cell, width = width),
+                })
+                .collect();
+            out.push_str(cells.join("  ").trim_end());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_table_per_sheet() {
+        let record = |main: &str, description: Option<&str>| {
+            let mut row = vec![None; 12];
+            row[1] = Some(main.to_string());
+            row[2] = description.map(str::to_string);
+            CascadeField::from_row(row).unwrap()
+        };
+        let sheet = |name: &str, rows| SheetData { sheet: name.to_string(), rows, generic_rows: None, metadata: None, file: None };
+        let sheets = vec![
+            sheet("First", vec![record("A", Some("Ärger")), record("BB", None), record("A", Some("x"))]),
+            sheet("Second", vec![]),
+        ];
+
+        let profiles = profile_sheets(&sheets);
+        assert_eq!(profiles[0].records, 3);
+        let description = &profiles[0].fields[2];
+        assert_eq!((description.non_null, description.unique), (2, 2));
+        // Lengths count characters, not bytes
+        assert_eq!((description.min_length, description.max_length), (Some(1), Some(5)));
+        assert_eq!(profiles[1].fields[1].samples, Vec::<String>::new());
+
+        let table = format_table(&profiles);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "Sheet: First (3 records)");
+        assert_eq!(lines[1], "field              non-null  unique  min len  max len  samples");
+        assert_eq!(lines[3], "main_value                3       2        1        2  A, BB");
+        assert_eq!(lines[4], "main_description          2       2        1        5  Ärger, x");
+        assert!(table.contains("\nSheet: Second (0 records)\n"));
+    }
+}
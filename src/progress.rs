@@ -0,0 +1,232 @@
+//! Progress reporting.
+//!
+//! This module writes a small JSON document describing how far processing has
+//! got, so that an external process can poll the file instead of parsing logs.
+//! For people watching a long run, [`Progress`] can also print row counts and
+//! a `N/M sheets` indicator to stderr, leaving stdout to the output.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use excel_to_json::progress::{ProgressFile, ProgressUpdate};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let progress = ProgressFile::new("progress.json");
+//! progress.write(&ProgressUpdate {
+//!     rows_done: 1000,
+//!     total: 5000,
+//!     sheet: "Cascade Fields".to_string(),
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Rows between two progress lines on stderr.
+pub const STDERR_INTERVAL: usize = 10_000;
+
+/// A snapshot of processing progress for a single sheet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressUpdate {
+    pub rows_done: usize,
+    pub total: usize,
+    pub sheet: String,
+}
+
+/// Writes progress snapshots to a file.
+///
+/// Each write goes to a temporary sibling file which is then renamed over the
+/// target, so readers never observe a partially written document.
+#[derive(Debug, Clone)]
+pub struct ProgressFile {
+    path: PathBuf,
+}
+
+impl ProgressFile {
+    /// Creates a progress writer targeting the given path.
+    ///
+    /// The file is not created until the first [`write`](Self::write).
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        ProgressFile {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Atomically replaces the progress file with the given snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temporary file cannot be written or renamed.
+    pub fn write(&self, update: &ProgressUpdate) -> Result<()> {
+        let mut tmp_name = self.path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = self.path.with_file_name(tmp_name);
+
+        std::fs::write(&tmp_path, serde_json::to_string(update)?)
+            .with_context(|| format!("Failed to write progress file: {:?}", tmp_path))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to update progress file: {:?}", self.path))?;
+        Ok(())
+    }
+}
+
+/// When `--progress` prints to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Only when stderr is a terminal, so redirected logs stay clean
+    Auto,
+    /// Even when stderr is redirected
+    Always,
+}
+
+impl std::str::FromStr for ProgressMode {
+    type Err = String;
+
+    /// Parses "auto" or "always"; case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ProgressMode::Auto),
+            "always" => Ok(ProgressMode::Always),
+            _ => Err(format!("Unknown progress mode: {}. Use 'auto' or 'always'.", s)),
+        }
+    }
+}
+
+/// Callback receiving every progress update, for programs that embed the
+/// pipeline and report progress their own way.
+pub type ProgressObserver = Arc<dyn Fn(&ProgressUpdate) + Send + Sync>;
+
+/// Where progress is reported while sheets are processed: a progress file,
+/// stderr, an observer, or any of them together.
+///
+/// # Example
+///
+/// ```rust
+/// use excel_to_json::progress::{Progress, ProgressMode, ProgressUpdate};
+///
+/// let progress = Progress::default().with_stderr(ProgressMode::Always);
+/// progress.sheet_started(1, 2, "Cascade Fields");
+/// progress.update(&ProgressUpdate { rows_done: 10_000, total: 25_000, sheet: "Cascade Fields".to_string() });
+/// ```
+#[derive(Clone, Default)]
+pub struct Progress {
+    /// File receiving a JSON snapshot with every update
+    pub file: Option<ProgressFile>,
+    /// Print row counts and sheet indicators to stderr
+    pub stderr: bool,
+    /// Called with every update, after the file is written
+    pub observer: Option<ProgressObserver>,
+}
+
+impl std::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("file", &self.file)
+            .field("stderr", &self.stderr)
+            .field("observer", &self.observer.as_ref().map(|_| "Fn(&ProgressUpdate)"))
+            .finish()
+    }
+}
+
+impl Progress {
+    /// Enables stderr output for `mode`; [`ProgressMode::Auto`] only
+    /// enables it when stderr is a terminal.
+    pub fn with_stderr(mut self, mode: ProgressMode) -> Self {
+        self.stderr = match mode {
+            ProgressMode::Auto => std::io::stderr().is_terminal(),
+            ProgressMode::Always => true,
+        };
+        self
+    }
+
+    /// Returns true if updates go anywhere.
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some() || self.stderr || self.observer.is_some()
+    }
+
+    /// Announces the `index`-th (1-based) of `count` sheets.
+    pub fn sheet_started(&self, index: usize, count: usize, sheet: &str) {
+        if self.stderr {
+            eprintln!("{}", sheet_line(index, count, sheet));
+        }
+    }
+
+    /// Reports rows processed so far. The file and the observer get every
+    /// update; stderr gets one every [`STDERR_INTERVAL`] rows and when the
+    /// sheet is done.
+    /// Failures to write the file are logged, not returned, so progress
+    /// never interrupts processing.
+    pub fn update(&self, update: &ProgressUpdate) {
+        if let Some(file) = &self.file {
+            if let Err(e) = file.write(update) {
+                warn!("Failed to write progress: {:#}", e);
+            }
+        }
+        if let Some(line) = self.stderr.then(|| rows_line(update)).flatten() {
+            eprintln!("{}", line);
+        }
+        if let Some(observer) = &self.observer {
+            observer(update);
+        }
+    }
+}
+
+/// The stderr line announcing a sheet, e.g. `[2/3 sheets] Line Type`.
+fn sheet_line(index: usize, count: usize, sheet: &str) -> String {
+    format!("[{}/{} sheets] {}", index, count, sheet)
+}
+
+/// The stderr line for a row count update, if it is due.
+fn rows_line(update: &ProgressUpdate) -> Option<String> {
+    let due = update.rows_done.is_multiple_of(STDERR_INTERVAL) || update.rows_done == update.total;
+    (due && update.rows_done > 0).then(|| format!("  {}: {}/{} rows", update.sheet, update.rows_done, update.total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_progress_file_is_replaced_with_latest_update() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("progress.json");
+        let progress = ProgressFile::new(&path);
+
+        let mut seen = Vec::new();
+        for rows_done in [1000, 2000, 2500] {
+            progress.write(&ProgressUpdate {
+                rows_done,
+                total: 2500,
+                sheet: "Data".to_string(),
+            }).expect("Should write progress");
+
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let update: ProgressUpdate = serde_json::from_str(&contents).expect("Should be valid JSON");
+            seen.push(update.rows_done);
+        }
+
+        assert_eq!(seen, vec![1000, 2000, 2500]);
+        assert!(!temp_dir.path().join("progress.json.tmp").exists(), "Temporary file should be renamed away");
+    }
+
+    #[test]
+    fn test_stderr_lines_every_interval_and_at_the_end() {
+        let update = |rows_done| ProgressUpdate { rows_done, total: 25_000, sheet: "Data".to_string() };
+
+        assert_eq!(rows_line(&update(1_000)), None);
+        assert_eq!(rows_line(&update(10_000)).as_deref(), Some("  Data: 10000/25000 rows"));
+        assert_eq!(rows_line(&update(25_000)).as_deref(), Some("  Data: 25000/25000 rows"));
+        assert_eq!(rows_line(&ProgressUpdate { rows_done: 0, total: 0, sheet: "Empty".to_string() }), None);
+        assert_eq!(sheet_line(2, 3, "Line Type"), "[2/3 sheets] Line Type");
+
+        assert!(!Progress::default().is_enabled());
+        assert!(Progress::default().with_stderr(ProgressMode::Always).is_enabled());
+    }
+}
@@ -0,0 +1,479 @@
+//! JSON Schema and TypeScript descriptions of the output for `--emit-schema`.
+//!
+//! The schema is not written by hand. Example results are built from the
+//! model types ([`CascadeField`], [`GenericRecord`], [`ProcessingMetadata`],
+//! ...), rendered by the same [`OutputFormatter`] that writes real output, and
+//! the schema is inferred from the JSON that comes out. A field added to the
+//! metadata, or a change in how records are serialized, shows up in the schema
+//! without touching this module.
+//!
+//! # Inference Rules
+//!
+//! - A key present in some examples but not others is optional.
+//! - Values seen with different types become a union (`string | null`).
+//! - Objects keyed by data rather than by the types, such as sheet names with
+//!   `--sheets-as-object` or header texts with `--generic`, are built with the
+//!   placeholder key `*` and become maps (`additionalProperties` /
+//!   `Record<string, T>`).
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::output::{OutputFormat, OutputOptions};
+//! use excel_to_json::schema::{output_schema, RecordShape};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let shape = output_schema(OutputFormat::Json, &OutputOptions::default(), RecordShape::default())?;
+//! let schema = shape.to_json_schema("excel-to-json output");
+//! assert_eq!(schema["properties"]["metadata"]["properties"]["valid_records"]["type"], "integer");
+//!
+//! let typescript = shape.to_typescript("ExcelToJsonOutput");
+//! assert!(typescript.contains("main_value: string;"));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::models::{
+    CascadeField, CellType, ErrorDetails, FailureKind, FileError, GenericRecord, ProcessingMetadata, ProcessingResult, RowDiagnostic, RowWindow,
+    SheetData,
+};
+use crate::output::{OutputFormat, OutputFormatter, OutputOptions};
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+
+/// Placeholder key standing for keys that come from the data.
+const ANY_KEY: &str = "*";
+
+/// Language a schema is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaFormat {
+    /// JSON Schema (draft 2020-12)
+    JsonSchema,
+    /// A TypeScript declaration
+    TypeScript,
+}
+
+impl std::str::FromStr for SchemaFormat {
+    type Err = String;
+
+    /// Parses "json-schema" (also "jsonschema") or "typescript" (also "ts");
+    /// case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json-schema" | "jsonschema" => Ok(SchemaFormat::JsonSchema),
+            "typescript" | "ts" => Ok(SchemaFormat::TypeScript),
+            _ => Err(format!("Unknown schema format: {}. Supported formats: json-schema, typescript.", s)),
+        }
+    }
+}
+
+/// Processing settings that change the shape of each record.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecordShape {
+    /// Records are keyed by the sheet's header texts (`--generic`)
+    pub generic: bool,
+    /// Numeric and boolean cells keep their type (`--preserve-types`)
+    pub preserve_types: bool,
+}
+
+/// The inferred type of a JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    /// An array; `None` when only empty arrays were seen
+    Array(Option<Box<Shape>>),
+    /// An object with known keys, each with its shape and whether it is required
+    Object(IndexMap<String, (Shape, bool)>),
+    /// An object keyed by data, with values of one shape
+    Map(Box<Shape>),
+    /// One of several shapes
+    Union(Vec<Shape>),
+}
+
+impl Shape {
+    /// Infers the shape of a JSON value.
+    pub fn infer(value: &Value) -> Shape {
+        match value {
+            Value::Null => Shape::Null,
+            Value::Bool(_) => Shape::Boolean,
+            Value::Number(n) if n.is_f64() => Shape::Number,
+            Value::Number(_) => Shape::Integer,
+            Value::String(_) => Shape::String,
+            Value::Array(items) => Shape::Array(
+                items.iter().map(Shape::infer).reduce(Shape::merge).map(Box::new),
+            ),
+            Value::Object(map) if map.len() == 1 && map.contains_key(ANY_KEY) => {
+                Shape::Map(Box::new(Shape::infer(&map[ANY_KEY])))
+            },
+            Value::Object(map) => Shape::Object(
+                map.iter().map(|(key, value)| (key.clone(), (Shape::infer(value), true))).collect(),
+            ),
+        }
+    }
+
+    /// Combines two shapes into one that accepts values of either.
+    pub fn merge(self, other: Shape) -> Shape {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Shape::Integer, Shape::Number) | (Shape::Number, Shape::Integer) => Shape::Number,
+            (Shape::Array(a), Shape::Array(b)) => Shape::Array(match (a, b) {
+                (Some(a), Some(b)) => Some(Box::new(a.merge(*b))),
+                (a, b) => a.or(b),
+            }),
+            (Shape::Map(a), Shape::Map(b)) => Shape::Map(Box::new(a.merge(*b))),
+            // An empty object is a map without entries
+            (Shape::Map(a), Shape::Object(b)) | (Shape::Object(b), Shape::Map(a)) if b.is_empty() => Shape::Map(a),
+            (Shape::Object(mut a), Shape::Object(mut b)) => {
+                for (key, (shape, required)) in a.iter_mut() {
+                    match b.shift_remove(key) {
+                        Some((other, other_required)) => {
+                            *shape = std::mem::replace(shape, Shape::Null).merge(other);
+                            *required &= other_required;
+                        },
+                        None => *required = false,
+                    }
+                }
+                a.extend(b.into_iter().map(|(key, (shape, _))| (key, (shape, false))));
+                Shape::Object(a)
+            },
+            (Shape::Union(members), other) | (other, Shape::Union(members)) => {
+                members.into_iter().fold(Shape::Union(Vec::new()), Shape::add_member).add_member(other)
+            },
+            (a, b) => Shape::Union(vec![a]).add_member(b),
+        }
+    }
+
+    /// Adds a member to a union, merging it into a member of the same kind.
+    fn add_member(self, shape: Shape) -> Shape {
+        let Shape::Union(mut members) = self else {
+            return self.merge(shape);
+        };
+        let members_to_add = match shape {
+            Shape::Union(inner) => inner,
+            shape => vec![shape],
+        };
+        for shape in members_to_add {
+            match members.iter().position(|member| member.same_kind(&shape)) {
+                Some(idx) => {
+                    let member = members.remove(idx);
+                    members.insert(idx, member.merge(shape));
+                },
+                None => members.push(shape),
+            }
+        }
+        if members.len() == 1 {
+            members.remove(0)
+        } else {
+            Shape::Union(members)
+        }
+    }
+
+    /// Returns true when two shapes merge into one rather than forming a union.
+    fn same_kind(&self, other: &Shape) -> bool {
+        matches!(
+            (self, other),
+            (Shape::Null, Shape::Null)
+                | (Shape::Boolean, Shape::Boolean)
+                | (Shape::Integer | Shape::Number, Shape::Integer | Shape::Number)
+                | (Shape::String, Shape::String)
+                | (Shape::Array(_), Shape::Array(_))
+                | (Shape::Object(_), Shape::Object(_))
+                | (Shape::Map(_), Shape::Map(_))
+        )
+    }
+
+    /// Renders the shape as a JSON Schema document with the given title.
+    pub fn to_json_schema(&self, title: &str) -> Value {
+        let mut schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": title,
+        });
+        if let (Value::Object(root), Value::Object(body)) = (&mut schema, self.json_schema_body()) {
+            root.extend(body);
+        }
+        schema
+    }
+
+    fn json_schema_body(&self) -> Value {
+        match self {
+            Shape::Null => json!({"type": "null"}),
+            Shape::Boolean => json!({"type": "boolean"}),
+            Shape::Integer => json!({"type": "integer"}),
+            Shape::Number => json!({"type": "number"}),
+            Shape::String => json!({"type": "string"}),
+            Shape::Array(None) => json!({"type": "array"}),
+            Shape::Array(Some(items)) => json!({"type": "array", "items": items.json_schema_body()}),
+            Shape::Object(fields) => json!({
+                "type": "object",
+                "properties": fields.iter()
+                    .map(|(key, (shape, _))| (key.clone(), shape.json_schema_body()))
+                    .collect::<serde_json::Map<_, _>>(),
+                "required": fields.iter()
+                    .filter(|(_, (_, required))| *required)
+                    .map(|(key, _)| key.as_str())
+                    .collect::<Vec<_>>(),
+                "additionalProperties": false,
+            }),
+            Shape::Map(values) => json!({"type": "object", "additionalProperties": values.json_schema_body()}),
+            Shape::Union(members) => {
+                let bodies: Vec<Value> = members.iter().map(Shape::json_schema_body).collect();
+                // Unions of plain types collapse to a type list
+                let plain_types: Option<Vec<Value>> = bodies.iter()
+                    .map(|body| body.as_object().filter(|body| body.len() == 1).and_then(|body| body.get("type")).cloned())
+                    .collect();
+                match plain_types {
+                    Some(types) => json!({"type": types}),
+                    None => json!({"anyOf": bodies}),
+                }
+            },
+        }
+    }
+
+    /// Renders the shape as an exported TypeScript declaration named `name`:
+    /// an `interface` for objects, a `type` alias otherwise.
+    pub fn to_typescript(&self, name: &str) -> String {
+        match self {
+            Shape::Object(_) => format!("export interface {} {}\n", name, self.typescript_type(0)),
+            _ => format!("export type {} = {};\n", name, self.typescript_type(0)),
+        }
+    }
+
+    fn typescript_type(&self, indent: usize) -> String {
+        match self {
+            Shape::Null => "null".to_string(),
+            Shape::Boolean => "boolean".to_string(),
+            Shape::Integer | Shape::Number => "number".to_string(),
+            Shape::String => "string".to_string(),
+            Shape::Array(None) => "unknown[]".to_string(),
+            Shape::Array(Some(items)) => match **items {
+                Shape::Union(_) => format!("({})[]", items.typescript_type(indent)),
+                _ => format!("{}[]", items.typescript_type(indent)),
+            },
+            Shape::Object(fields) => {
+                let padding = "  ".repeat(indent + 1);
+                let mut body = String::from("{\n");
+                for (key, (shape, required)) in fields {
+                    let plain = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+                        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+                    let key = if plain { key.clone() } else { Value::from(key.as_str()).to_string() };
+                    let optional = if *required { "" } else { "?" };
+                    body.push_str(&format!("{}{}{}: {};\n", padding, key, optional, shape.typescript_type(indent + 1)));
+                }
+                body.push_str(&"  ".repeat(indent));
+                body.push('}');
+                body
+            },
+            Shape::Map(values) => format!("Record<string, {}>", values.typescript_type(indent)),
+            Shape::Union(members) => members.iter()
+                .map(|member| member.typescript_type(indent))
+                .collect::<Vec<_>>()
+                .join(" | "),
+        }
+    }
+}
+
+/// Describes the output written for `format` with the given settings.
+///
+/// The shape covers both successful and failed runs. It describes the
+/// multi-sheet layout the command-line tool writes, where `data` lists each
+/// sheet's records (or maps sheet names to them).
+///
+/// # Errors
+///
+/// Returns an error for formats that are not JSON (CSV, SQL and XML), or for
+/// combinations the formatter rejects, such as nested output of generic
+/// records.
+pub fn output_schema(format: OutputFormat, options: &OutputOptions, records: RecordShape) -> Result<Shape> {
+    if !matches!(format, OutputFormat::Json | OutputFormat::NestedJson | OutputFormat::PhpArray) {
+        anyhow::bail!("--emit-schema describes JSON output; use it with --format json, nested or php");
+    }
+
+    let keyed_by_sheet = options.sheets_as_object || matches!(format, OutputFormat::PhpArray);
+    // Grouping on no field puts every record under the null key, which
+    // stands for any group
+    let options = &OutputOptions {
+        group_by: options.group_by.as_ref().map(|_| String::new()),
+        group_null_key: ANY_KEY.to_string(),
+        ..options.clone()
+    };
+    let mut shape: Option<Shape> = None;
+    for result in example_results(records, !keyed_by_sheet) {
+        let value = OutputFormatter::to_value_with_options(&result, format, options)?;
+        let inferred = Shape::infer(&value);
+        shape = Some(match shape {
+            Some(shape) => shape.merge(inferred),
+            None => inferred,
+        });
+    }
+    Ok(shape.unwrap_or(Shape::Null))
+}
+
+/// Builds results that together exercise every optional part of the output.
+///
+/// Each successful result holds a single sheet, so that output keyed by sheet
+/// name has the placeholder key alone. Sheets are tagged with a file only
+/// when `tag_files` is set, as the file becomes part of such keys.
+fn example_results(records: RecordShape, tag_files: bool) -> Vec<ProcessingResult> {
+    let full_metadata = ProcessingMetadata {
+        row_window: Some(RowWindow { offset: 1, limit: Some(1) }),
+        source_headers: Some(vec![String::new()]),
+        detected_columns: Some(1),
+        diagnostics: vec![RowDiagnostic::error(1, Some(""), ""), RowDiagnostic::warning(1, None, "")],
+        warnings: Some(vec![String::new()]),
+        ..ProcessingMetadata::default()
+    };
+    let open_window_metadata = ProcessingMetadata {
+        row_window: Some(RowWindow { offset: 1, limit: None }),
+        ..ProcessingMetadata::default()
+    };
+    let file_error = FileError {
+        file: String::new(),
+        error: String::new(),
+        available_sheets: Some(vec![String::new()]),
+        suggested_sheet: Some(String::new()),
+        row_number: Some(1),
+        kind: FailureKind::Other,
+    };
+
+    let result = |filled: bool, metadata: ProcessingMetadata| {
+        let (rows, generic_rows) = match (records.generic, filled) {
+            (true, true) => (Vec::new(), Some(example_generic_records(records.preserve_types))),
+            (true, false) => (Vec::new(), Some(Vec::new())),
+            (false, true) => (example_cascade_records(records.preserve_types), None),
+            (false, false) => (Vec::new(), None),
+        };
+        let sheet = SheetData {
+            sheet: ANY_KEY.to_string(),
+            rows,
+            generic_rows,
+            metadata: Some(metadata.clone()),
+            file: (filled && tag_files).then(String::new),
+        };
+        ProcessingResult::success_multi_sheet(vec![sheet], metadata)
+    };
+
+    let mut full = result(true, full_metadata);
+    full.file_errors = Some(vec![file_error.clone()]);
+    let details = ErrorDetails {
+        file: String::new(),
+        available_sheets: None,
+        suggested_sheet: None,
+        row_number: None,
+        column: None,
+    };
+    let mut error = ProcessingResult::error(String::new(), Some(details), ProcessingMetadata::default());
+    error.file_errors = Some(vec![FileError { available_sheets: None, suggested_sheet: None, row_number: None, ..file_error }]);
+    let full_details = ErrorDetails {
+        file: String::new(),
+        available_sheets: Some(vec![String::new()]),
+        suggested_sheet: Some(String::new()),
+        row_number: Some(1),
+        column: Some(String::new()),
+    };
+    let detailed_error = ProcessingResult::error(String::new(), Some(full_details), ProcessingMetadata::default());
+
+    vec![full, result(false, open_window_metadata), result(false, ProcessingMetadata::default()), error, detailed_error]
+}
+
+/// Cascade records with every level filled and with only the first filled,
+/// typed when `preserve_types` is set.
+fn example_cascade_records(preserve_types: bool) -> Vec<CascadeField> {
+    let full = CascadeField::from_row(vec![Some("1.5".to_string()); 12]);
+    let mut partial_row = vec![None; 12];
+    partial_row[1] = Some("true".to_string());
+    let partial = CascadeField::from_row(partial_row);
+
+    let mut records: Vec<CascadeField> = full.into_iter().chain(partial).collect();
+    // Written as `source_row` with --with-row-numbers
+    for record in records.iter_mut() {
+        record.row_number = Some(2);
+    }
+    if preserve_types {
+        for (record, cell_type) in records.iter_mut().zip([CellType::Number, CellType::Bool]) {
+            record.cell_types = CascadeField::FIELD_NAMES.iter()
+                .map(|name| (name.to_string(), cell_type))
+                .collect();
+        }
+    }
+    records
+}
+
+/// Header-keyed records holding text and empty cells, plus numbers and
+/// booleans when `preserve_types` is set.
+fn example_generic_records(preserve_types: bool) -> Vec<GenericRecord> {
+    let mut values = vec![Value::String(String::new()), Value::Null];
+    if preserve_types {
+        values.extend([json!(1.5), json!(true)]);
+    }
+    values.into_iter()
+        .map(|value| GenericRecord(IndexMap::from([(ANY_KEY.to_string(), value)])))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_tracks_record_shape_and_optional_fields() {
+        let shape = output_schema(OutputFormat::Json, &OutputOptions::default(), RecordShape::default()).unwrap();
+        let schema = shape.to_json_schema("output");
+
+        let sheet = &schema["properties"]["data"]["items"];
+        let record = &sheet["properties"]["rows"]["items"];
+        assert_eq!(record["properties"].as_object().unwrap().len(), 12);
+        assert_eq!(record["properties"]["minor_value"]["type"], "string");
+        assert_eq!(record["additionalProperties"], false);
+        let numbered = OutputOptions { with_row_numbers: true, ..OutputOptions::default() };
+        let numbered = output_schema(OutputFormat::Json, &numbered, RecordShape::default()).unwrap().to_json_schema("output");
+        assert_eq!(numbered["properties"]["data"]["items"]["properties"]["rows"]["items"]["properties"]["source_row"]["type"], "integer");
+
+        // Optional parts are present in some runs only
+        let required = |schema: &Value| -> Vec<String> {
+            serde_json::from_value(schema["required"].clone()).unwrap()
+        };
+        assert_eq!(required(&schema), vec!["success", "data"]);
+        assert!(!required(sheet).contains(&"file".to_string()));
+        let metadata = &schema["properties"]["metadata"];
+        assert_eq!(metadata["properties"]["warnings"]["anyOf"][1]["type"], "null");
+        let diagnostic = &metadata["properties"]["diagnostics"]["items"];
+        assert_eq!(diagnostic["properties"]["field"]["type"], json!(["string", "null"]));
+        assert_eq!(diagnostic["properties"]["row_number"]["type"], "integer");
+        assert_eq!(metadata["properties"]["row_window"]["properties"]["limit"]["type"], json!(["integer", "null"]));
+        assert!(!required(metadata).contains(&"row_window".to_string()));
+
+        // Generic records are keyed by header text
+        let records = RecordShape { generic: true, preserve_types: true };
+        let shape = output_schema(OutputFormat::Json, &OutputOptions::default(), records).unwrap();
+        let schema = shape.to_json_schema("output");
+        let record = &schema["properties"]["data"]["items"]["properties"]["rows"]["items"];
+        assert_eq!(record["additionalProperties"]["type"], json!(["string", "null", "number", "boolean"]));
+
+        assert!(output_schema(OutputFormat::Csv, &OutputOptions::default(), RecordShape::default()).is_err());
+    }
+
+    #[test]
+    fn test_typescript_declarations() {
+        let options = OutputOptions { sheets_as_object: true, ..OutputOptions::default() };
+        let shape = output_schema(OutputFormat::Json, &options, RecordShape::default()).unwrap();
+        let typescript = shape.to_typescript("Output");
+        assert!(typescript.starts_with("export interface Output {\n  success: boolean;\n  data: Record<string, {\n"), "{}", typescript);
+        assert!(typescript.contains("    main_value: string;\n"));
+        assert!(typescript.contains("  file_errors?: {\n    file: string;\n    error: string;\n    available_sheets?: string[];\n"));
+        assert!(typescript.contains("    warnings: string[] | null;\n"));
+
+        // Grouped records map each group key to a list of records
+        let options = OutputOptions { group_by: Some("main_value".to_string()), ..options };
+        let typescript = output_schema(OutputFormat::Json, &options, RecordShape::default()).unwrap().to_typescript("Output");
+        assert!(typescript.starts_with("export interface Output {\n  success: boolean;\n  data: Record<string, Record<string, {\n"), "{}", typescript);
+
+        // PHP output is the bare records on success and an error object on failure
+        let shape = output_schema(OutputFormat::PhpArray, &OutputOptions::default(), RecordShape::default()).unwrap();
+        assert!(shape.to_typescript("Output").starts_with("export type Output = Record<string, {\n"));
+    }
+}
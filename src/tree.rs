@@ -0,0 +1,169 @@
+//! Nested representation of the cascade hierarchy.
+//!
+//! Cascade records are flat: every row repeats the labels of its main, sub
+//! and major levels. This module groups them into a tree (main → sub → major
+//! → minor) for consumers such as front-end tree widgets.
+//!
+//! # Missing Intermediate Keys
+//!
+//! A row's path stops at its last level with a value. When an intermediate
+//! level has no value but a deeper one does (a sub with no major, followed by
+//! a minor), the deeper levels are kept under a placeholder node whose
+//! `value` is `null`, so no data is lost and rows sharing the same gap are
+//! grouped together. Rows without a `main_value` group under a `null` main node.
+//!
+//! Only the twelve cascade fields take part; header-keyed columns from hybrid
+//! mode and levels past the fourth are not included.
+//!
+//! # Example
+//!
+//! ```rust
+//! use excel_to_json::models::CascadeField;
+//! use excel_to_json::tree::build_tree;
+//!
+//! let row = |main: &str, sub: &str| {
+//!     let mut row = vec![None; 12];
+//!     row[1] = Some(main.to_string());
+//!     row[4] = Some(sub.to_string());
+//!     CascadeField::from_row(row).unwrap()
+//! };
+//! let records = vec![row("CAT001", "SUB001"), row("CAT001", "SUB002"), row("CAT002", "SUB001")];
+//!
+//! let tree = build_tree(&records);
+//! assert_eq!(tree.len(), 2);
+//! assert_eq!(tree[0].children.len(), 2);
+//! assert_eq!(tree[0].children[1].value.as_deref(), Some("SUB002"));
+//! ```
+
+use crate::models::CascadeField;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Names of the cascade levels, from the root down.
+pub const LEVEL_NAMES: [&str; 4] = ["main", "sub", "major", "minor"];
+
+/// One node of the cascade tree.
+///
+/// Serialized as `{"level", "value", "label", "description", "children"}`;
+/// `children` is omitted on leaves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CascadeNode {
+    /// Level name: `main`, `sub`, `major` or `minor`
+    pub level: String,
+    /// The level's value; `None` for a placeholder filling a missing key
+    pub value: Option<String>,
+    /// First non-empty label seen for this node
+    pub label: Option<String>,
+    /// First non-empty description seen for this node
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<CascadeNode>,
+}
+
+/// A node under construction, with children keyed by value.
+#[derive(Default)]
+struct NodeBuilder {
+    label: Option<String>,
+    description: Option<String>,
+    children: IndexMap<Option<String>, NodeBuilder>,
+}
+
+impl NodeBuilder {
+    fn build(self, depth: usize, value: Option<String>) -> CascadeNode {
+        CascadeNode {
+            level: LEVEL_NAMES[depth].to_string(),
+            value,
+            label: self.label,
+            description: self.description,
+            children: Self::build_children(self.children, depth + 1),
+        }
+    }
+
+    fn build_children(children: IndexMap<Option<String>, NodeBuilder>, depth: usize) -> Vec<CascadeNode> {
+        children.into_iter()
+            .map(|(value, child)| child.build(depth, value))
+            .collect()
+    }
+}
+
+/// Groups flat cascade records into a tree.
+///
+/// Nodes keep the order in which their values first appear. Labels and
+/// descriptions come from the first record that has them for the node. See
+/// the [module documentation](self) for rows with missing intermediate keys.
+///
+/// # Arguments
+///
+/// * `records` - Cascade records in sheet order
+///
+/// # Returns
+///
+/// The `main` level nodes, each holding its `sub`, `major` and `minor` descendants.
+pub fn build_tree(records: &[CascadeField]) -> Vec<CascadeNode> {
+    let mut roots: IndexMap<Option<String>, NodeBuilder> = IndexMap::new();
+
+    for record in records {
+        let fields = record.field_values();
+        let levels: Vec<[Option<&str>; 3]> = fields.chunks(3)
+            .map(|level| [level[0], level[1], level[2]])
+            .collect();
+        // Levels past the last one with a value are not part of the path
+        let depth = levels.iter().rposition(|[_, value, _]| value.is_some()).map_or(1, |last| last + 1);
+
+        let mut children = &mut roots;
+        for [label, value, description] in &levels[..depth] {
+            let node = children.entry(value.map(str::to_string)).or_default();
+            if node.label.is_none() {
+                node.label = label.map(str::to_string);
+            }
+            if node.description.is_none() {
+                node.description = description.map(str::to_string);
+            }
+            children = &mut node.children;
+        }
+    }
+
+    NodeBuilder::build_children(roots, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(values: [Option<&str>; 4], label: &str) -> CascadeField {
+        let mut row = vec![None; 12];
+        for (level, value) in values.iter().enumerate() {
+            row[level * 3] = Some(format!("{} {}", label, LEVEL_NAMES[level]));
+            row[level * 3 + 1] = value.map(str::to_string);
+        }
+        CascadeField::from_row(row).unwrap()
+    }
+
+    #[test]
+    fn test_missing_intermediate_key_gets_placeholder() {
+        let records = vec![
+            record([Some("M1"), Some("S1"), Some("J1"), Some("N1")], "First"),
+            record([Some("M1"), Some("S1"), Some("J1"), Some("N2")], "Second"),
+            record([Some("M1"), Some("S2"), None, Some("N3")], "Gap"),
+            record([Some("M1"), Some("S3"), None, None], "Short"),
+        ];
+
+        let tree = build_tree(&records);
+
+        assert_eq!(tree.len(), 1);
+        let main = &tree[0];
+        assert_eq!(main.label.as_deref(), Some("First main"));
+        assert_eq!(main.children.len(), 3);
+
+        let minors = &main.children[0].children[0].children;
+        assert_eq!(minors.iter().map(|n| n.value.as_deref().unwrap()).collect::<Vec<_>>(), vec!["N1", "N2"]);
+        assert_eq!(minors[1].level, "minor");
+
+        let placeholder = &main.children[1].children[0];
+        assert_eq!((placeholder.level.as_str(), placeholder.value.as_deref()), ("major", None));
+        assert_eq!(placeholder.children[0].value.as_deref(), Some("N3"));
+
+        // Trailing missing levels simply end the branch
+        assert!(main.children[2].children.is_empty());
+    }
+}